@@ -3,7 +3,7 @@ use std::cmp;
 use std::ops;
 use std::str;
 use std::borrow::Cow;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use take_mut::take;
 
 use kailua_env::{Span, Spanned, WithLoc};
@@ -11,15 +11,19 @@ use kailua_diag::{self, Result, Report, Reporter};
 use kailua_syntax::{Str, Name};
 use kailua_syntax::ast::{self, NameRef, Var, TypeSpec, Kind, Sig, Ex, Exp, UnOp, BinOp, Table};
 use kailua_syntax::ast::{SelfParam, TypeScope, Args, St, Stmt, Block, K, Attr, M, MM, Varargs};
-use kailua_types::diag::{TypeReport, TypeReportHint, TypeReportMore};
-use kailua_types::ty::{Displayed, Display, TypeContext, TypeResolver};
+use kailua_types::diag::{TypeReport, TypeResult, TypeReportHint, TypeReportMore};
+use kailua_types::ty::{Displayed, Display, TypeContext, TypeResolver, TypeParamResolver};
 use kailua_types::ty::{Dyn, Nil, T, Ty, TySeq, SpannedTySeq, Lattice, Union, Dummy};
-use kailua_types::ty::{Key, Tables, Function, Functions};
-use kailua_types::ty::{F, Slot, SlotSeq, SpannedSlotSeq, Tag, Class, ClassId};
+use kailua_types::ty::{Key, Tables, RVar, Function, Functions, Thread, Threads, Interface};
+use kailua_types::ty::{F, Slot, SlotSeq, SpannedSlotSeq, Tag, Modifiers, Class, ClassId};
 use kailua_types::ty::flags::*;
 use kailua_types::env::Types;
-use env::{Env, Returns, Frame, Scope, Module, Context, SlotSpec};
-use class_system::make_predefined_class_system;
+use env::{Env, Returns, Frame, CoroutineFrame, Scope, Module, Context, SlotSpec, NameDef, NameSlot};
+use class_system::{ClassSystem, Visibility};
+use class_system::{is_builtin_class_system, make_predefined_class_system};
+use class_system::declarative::DeclarativeClassSystem;
+use options::{ClassSystemRules, ClassSystemRuleError};
+use strlib::{self, Capture, PatternError, FormatArg, FormatError};
 use message as m;
 
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
@@ -150,12 +154,136 @@ impl<'chk, 'inp, 'envr, 'env, R: Report> Drop for ScopedChecker<'chk, 'inp, 'env
 // conditions out of boolean expression, used for assertion and branch typing
 #[derive(Clone, Debug)]
 enum Cond {
-    Flags(Spanned<Slot>, Flags),
+    // the name is present when the slot is a local variable that can be narrowed
+    Flags(Spanned<Slot>, Option<Spanned<NameRef>>, Flags),
+    // a local variable is equal to given string literal
+    Str(Spanned<NameRef>, Str),
+    // a local variable is an instance of given class (or its subclass), or a class prototype
+    Class(Spanned<NameRef>, Class),
     And(Box<Cond>, Box<Cond>),
     Or(Box<Cond>, Box<Cond>),
     Not(Box<Cond>),
 }
 
+impl Cond {
+    fn negate(self) -> Cond {
+        match self {
+            Cond::Flags(info, var, flags) => Cond::Flags(info, var, !flags),
+            Cond::Not(cond) => *cond,
+            cond => Cond::Not(Box::new(cond)),
+        }
+    }
+
+    // collects local variables compared against string literals
+    fn collect_str_vars<'a>(&'a self, vars: &mut Vec<&'a Spanned<NameRef>>) {
+        match *self {
+            Cond::Flags(..) | Cond::Class(..) => {}
+            Cond::Str(ref var, _) => vars.push(var),
+            Cond::And(ref lcond, ref rcond) | Cond::Or(ref lcond, ref rcond) => {
+                lcond.collect_str_vars(vars);
+                rcond.collect_str_vars(vars);
+            }
+            Cond::Not(ref cond) => cond.collect_str_vars(vars),
+        }
+    }
+}
+
+// returns true if the statement is a sole function or method call
+fn is_call_stmt(stmt: &Stmt) -> bool {
+    if let St::Void(ref exp) = **stmt {
+        match *exp.base {
+            Ex::FuncCall(..) | Ex::MethodCall(..) => return true,
+            _ => {}
+        }
+    }
+    false
+}
+
+// returns a name of the type declaration that can refer to other types declared alongside,
+// and a span for reporting a bad recursive type
+fn recursive_type_decl(stmt: &Spanned<Stmt>) -> Option<(&Spanned<Name>, Span)> {
+    match *stmt.base {
+        St::KailuaType(_, ref name, ref tparams, ref kind) if tparams.is_empty() => {
+            // self-redefinition is not a type declaration, see `St::KailuaType` in `visit_stmt`
+            if let K::Named(ref name_, ref args) = *kind.base {
+                if name.base == name_.base && args.is_empty() {
+                    return None;
+                }
+            }
+            Some((name, kind.span))
+        }
+        St::KailuaInterface(_, ref name, _) => Some((name, stmt.span)),
+        _ => None,
+    }
+}
+
+// returns the number of consecutive type declarations with distinct names from the beginning
+fn count_recursive_type_decls(stmts: &[Spanned<Stmt>]) -> usize {
+    let mut names = HashSet::new();
+    stmts.iter().take_while(|stmt| {
+        recursive_type_decl(stmt).map_or(false, |(name, _)| names.insert(&name.base))
+    }).count()
+}
+
+// returns a local variable directly referred by the expression if any
+fn narrowable_var(exp: &Spanned<Exp>) -> Option<Spanned<NameRef>> {
+    match *exp.base {
+        Ex::Var(ref name @ Spanned { base: NameRef::Local(_), .. }) => Some(name.clone()),
+        _ => None,
+    }
+}
+
+// returns a local variable tested by the call expression if any,
+// i.e. `self` for method calls and the first argument for function calls
+fn class_test_var(exp: &Spanned<Exp>) -> Option<Spanned<NameRef>> {
+    match *exp.base {
+        Ex::FuncCall(_, Spanned { base: Args::List(ref args), .. }) => {
+            args.first().and_then(narrowable_var)
+        }
+        Ex::MethodCall(Spanned { base: (ref e, _), .. }, _) => narrowable_var(e),
+        _ => None,
+    }
+}
+
+// true if the block can exit the current loop without evaluating the loop condition.
+// `goto` is conservatively assumed to do so.
+fn block_may_break(block: &Block) -> bool {
+    block.iter().any(|stmt| {
+        match *stmt.base {
+            St::Break | St::Goto(_) => true,
+            St::Do(ref block) => block_may_break(block),
+            St::If(ref conds, ref lastblock) => {
+                conds.iter().any(|cond| block_may_break(&cond.base.1)) ||
+                    lastblock.as_ref().map_or(false, |block| block_may_break(block))
+            }
+            _ => false,
+        }
+    })
+}
+
+// true if the block (excluding nested functions) can assign to given variable
+fn block_assigns_to(block: &Block, name: &NameRef) -> bool {
+    block.iter().any(|stmt| {
+        match *stmt.base {
+            St::Assign(ref vars, _) => vars.iter().any(|var| match var.base.base {
+                Var::Name(ref nameref) => nameref.base == *name,
+                _ => false,
+            }),
+            St::FuncDecl(ref nameref, ..) => nameref.base == *name,
+            St::Do(ref block) |
+            St::While(_, ref block) |
+            St::Repeat(ref block, _) |
+            St::For(.., ref block) |
+            St::ForIn(.., ref block) => block_assigns_to(block, name),
+            St::If(ref conds, ref lastblock) => {
+                conds.iter().any(|cond| block_assigns_to(&cond.base.1, name)) ||
+                    lastblock.as_ref().map_or(false, |block| block_assigns_to(block, name))
+            }
+            _ => false,
+        }
+    })
+}
+
 #[derive(Copy, Clone, Debug)]
 enum Bool {
     Unknown,
@@ -176,6 +304,60 @@ impl Index {
     }
 }
 
+// returns argument types of the function to be used as hints for actual arguments.
+//
+// for overloaded functions, each argument is hinted by the first overload
+// having a function type in that position (so that anonymous functions can be inferred),
+// or by the first overload having that argument otherwise.
+fn callable_arg_hints(functy: &Ty, ctx: &mut TypeContext) -> Option<TySeq> {
+    match **functy {
+        T::Intersection(ref parts) => {
+            let argss: Vec<TySeq> = parts.iter().filter_map(|part| {
+                match part.get_functions() {
+                    Some(&Functions::Simple(ref f)) => Some(function_arg_hints(f, ctx)),
+                    _ => None,
+                }
+            }).collect();
+            if argss.is_empty() {
+                return None;
+            }
+
+            let nargs = argss.iter().map(|args| args.head.len()).max().unwrap_or(0);
+            let head = (0..nargs).map(|i| {
+                let tys: Vec<&Ty> = argss.iter().filter_map(|args| args.head.get(i)).collect();
+                let ty = tys.iter().find(|ty| ty.get_functions().is_some()).unwrap_or(&tys[0]);
+                (*ty).clone()
+            }).collect();
+            let tail = argss.iter().filter_map(|args| args.tail.clone()).next();
+            Some(TySeq { head: head, tail: tail })
+        }
+
+        _ => match functy.get_functions() {
+            Some(&Functions::Simple(ref f)) => Some(function_arg_hints(f, ctx)),
+            _ => None,
+        },
+    }
+}
+
+// returns argument types of the function to be used as hints.
+//
+// for generic functions, type parameters are instantiated only for function types
+// (so that anonymous functions can be inferred) and other arguments are not hinted,
+// as they would constrain type parameters before the actual check.
+fn function_arg_hints(f: &Function, ctx: &mut TypeContext) -> TySeq {
+    if !f.is_generic() {
+        return f.args.clone();
+    }
+    let (f, _) = f.instantiate(ctx);
+    let erase = |ty: Ty| if ty.get_functions().is_some() { ty } else { Ty::new(T::All) };
+    TySeq { head: f.args.head.into_iter().map(&erase).collect(), tail: f.args.tail.map(&erase) }
+}
+
+// LuaJIT cdata objects are represented as a tagged userdata
+fn cdata_ty() -> Ty {
+    Ty::new(T::UserData).with_tag(Tag::CData)
+}
+
 #[derive(Copy, Clone, PartialEq, Eq, Debug)]
 enum NoCheck {
     User, // user-requested
@@ -191,10 +373,12 @@ struct Lvalue {
 #[derive(Clone, Debug)]
 struct PendingFuncBody<'inp> {
     tag: Option<Tag>,
+    modifiers: Modifiers,
     selfparam: Option<(&'inp Spanned<SelfParam>, Slot)>,
     sig: &'inp Sig,
     block: &'inp Spanned<Vec<Spanned<Stmt>>>,
     declspan: Span,
+    class: Option<ClassId>,
 }
 
 // represents all delay-checked function bodies and associated module slots in a single scope
@@ -224,12 +408,24 @@ impl<'inp> PendingModules<'inp> {
 pub struct Checker<'inp, 'envr, 'env: 'envr, R: 'env> {
     env: &'envr mut Env<'env, R>,
     pending_modules: Vec<PendingModules<'inp>>,
+
+    // yield types collected from the last coroutine body, see `visit_func_call`
+    coroutine_yields: Option<TySeq>,
+
+    // a class tested by the last call with `IsInstanceOf` or `IsSubclassOf` tags,
+    // see `collect_conds_from_exp`
+    tested_class: Option<Class>,
+
+    // a class whose method is being declared by `function A:method()`, see `St::MethodDecl`.
+    // the method itself is looked up before its body is checked, so `Frame::class` is not enough
+    declaring_class: Option<ClassId>,
 }
 
 impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
     /// Creates a new checker from the per-file context.
     pub fn new(env: &'envr mut Env<'env, R>) -> Checker<'inp, 'envr, 'env, R> {
-        Checker { env: env, pending_modules: Vec::new() }
+        Checker { env: env, pending_modules: Vec::new(), coroutine_yields: None,
+                  tested_class: None, declaring_class: None }
     }
 
     fn types(&mut self) -> &mut Types {
@@ -289,6 +485,11 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             }
         }
 
+        // LuaJIT cdata overloads the negation
+        if op == UnOp::Neg && info.tag() == Some(Tag::CData) {
+            return Ok(Slot::just(cdata_ty()));
+        }
+
         match op {
             UnOp::Neg => {
                 assert_sub!(&info, &T::Number);
@@ -312,6 +513,65 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 assert_sub!(&info, &(T::table() | T::String));
                 Ok(Slot::just(Ty::new(T::Integer)))
             }
+
+            UnOp::BNot => {
+                // floats with an exact integer representation are also accepted at runtime,
+                // so we only require a number but the result is always an integer
+                assert_sub!(&info, &T::Number);
+                Ok(Slot::just(Ty::new(T::Integer)))
+            }
+        }
+    }
+
+    // returns None if no operand has a corresponding metamethod
+    fn check_bin_op_metamethod(&mut self, lhs: &Spanned<Slot>, op: BinOp, rhs: &Spanned<Slot>,
+                               expspan: Span) -> Result<Option<Slot>> {
+        // `a > b` and `a >= b` are translated to `b < a` and `b <= a` respectively
+        let (name, swapped): (&[u8], bool) = match op {
+            BinOp::Add => (b"__add", false),
+            BinOp::Sub => (b"__sub", false),
+            BinOp::Mul => (b"__mul", false),
+            BinOp::Div => (b"__div", false),
+            BinOp::Mod => (b"__mod", false),
+            BinOp::Pow => (b"__pow", false),
+            BinOp::IDiv => (b"__idiv", false),
+            BinOp::BAnd => (b"__band", false),
+            BinOp::BOr => (b"__bor", false),
+            BinOp::BXor => (b"__bxor", false),
+            BinOp::Shl => (b"__shl", false),
+            BinOp::Shr => (b"__shr", false),
+            BinOp::Cat => (b"__concat", false),
+            BinOp::Eq | BinOp::Ne => (b"__eq", false),
+            BinOp::Lt => (b"__lt", false),
+            BinOp::Le => (b"__le", false),
+            BinOp::Gt => (b"__lt", true),
+            BinOp::Ge => (b"__le", true),
+            BinOp::And | BinOp::Or => return Ok(None),
+        };
+        let (lhs, rhs) = if swapped { (rhs, lhs) } else { (lhs, rhs) };
+
+        // the metamethod from the left operand has a precedence
+        let method = match self.get_metatable_field(&lhs.unlift(), name, expspan)? {
+            Some(method) => method,
+            None => match self.get_metatable_field(&rhs.unlift(), name, expspan)? {
+                Some(method) => method,
+                None => return Ok(None),
+            },
+        };
+
+        let args = SpannedTySeq {
+            head: vec![lhs.unlift().clone().with_loc(lhs), rhs.unlift().clone().with_loc(rhs)],
+            tail: None,
+            span: expspan,
+        };
+        let method = method.unlift().clone().with_loc(expspan);
+        let returns = self.check_metamethod_call(&method, &args)?;
+
+        // comparison operators always return a boolean
+        match op {
+            BinOp::Eq | BinOp::Ne | BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge =>
+                Ok(Some(Slot::just(Ty::new(T::Boolean)))),
+            _ => Ok(Some(Slot::just(returns.into_first()))),
         }
     }
 
@@ -359,8 +619,39 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             }
         }
 
+        // tables with a known metatable may overload operators
+        if let Some(slot) = self.check_bin_op_metamethod(lhs, op, rhs, expspan)? {
+            return Ok(slot);
+        }
+
+        // LuaJIT cdata overloads arithmetic and comparison operators,
+        // which should be applied to another cdata or a number
+        let lcdata = lhs.tag() == Some(Tag::CData);
+        let rcdata = rhs.tag() == Some(Tag::CData);
+        if lcdata || rcdata {
+            let restype = match op {
+                BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Div | BinOp::Pow |
+                BinOp::Mod | BinOp::IDiv => Some(cdata_ty()),
+                BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge => Some(Ty::new(T::Boolean)),
+                _ => None,
+            };
+            if let Some(restype) = restype {
+                if !lcdata {
+                    if let Err(r) = lhs.assert_sub(&T::Number, self.types()) {
+                        finalize(r, self)?;
+                    }
+                }
+                if !rcdata {
+                    if let Err(r) = rhs.assert_sub(&T::Number, self.types()) {
+                        finalize(r, self)?;
+                    }
+                }
+                return Ok(Slot::just(restype));
+            }
+        }
+
         match op {
-            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Mod => {
+            BinOp::Add | BinOp::Sub | BinOp::Mul | BinOp::Mod | BinOp::IDiv => {
                 // ? + integer = integer, ? + number = ? + ? = number, number + integer = number
                 // see UnOp::Neg comment for the rationale
                 let lflags = self.env.get_type_bounds(&lhs.unlift()).1;
@@ -384,6 +675,12 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 Ok(Slot::just(Ty::new(T::Number)))
             }
 
+            BinOp::BAnd | BinOp::BOr | BinOp::BXor | BinOp::Shl | BinOp::Shr => {
+                // see UnOp::BNot comment for the rationale
+                assert_sub_both!(lhs, rhs, &T::Number);
+                Ok(Slot::just(Ty::new(T::Integer)))
+            }
+
             BinOp::Cat => {
                 assert_sub_both!(lhs, rhs, &(T::Number | T::String));
 
@@ -509,6 +806,106 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         }
     }
 
+    // coerces literal types in the argument and fields of the argument record (if any),
+    // so that each literal does not constrain type parameters separately.
+    // the record is copied, so the original argument is not affected.
+    fn coerce_generic_arg(&mut self, ty: &Ty) -> Ty {
+        let ty = ty.clone().coerce();
+        let resolved = match self.env.resolve_exact_type(&ty) {
+            Some(resolved) => resolved,
+            None => return ty,
+        };
+        if resolved.tag().is_some() {
+            return ty;
+        }
+        let rvar = match *resolved {
+            T::Tables(ref tab) => match **tab {
+                Tables::Fields(ref rvar) => rvar.clone(),
+                _ => return ty,
+            },
+            _ => return ty,
+        };
+
+        let mut fields = Vec::new();
+        let last = self.types().list_rvar_fields(rvar, &mut |k, v| {
+            fields.push((k.clone(), Slot::new(v.flex(), v.unlift().clone().coerce())));
+            Ok(())
+        }).expect("list_rvar_fields exited early while we haven't break");
+
+        let copied = self.types().gen_rvar();
+        self.types().assert_rvar_includes(copied.clone(), &fields).expect(
+            "cannot insert coerced fields into a fresh row variable"
+        );
+        if last == RVar::empty() {
+            self.types().assert_rvar_closed(copied.clone()).expect(
+                "cannot close a fresh row variable"
+            );
+        }
+        Ty::new(T::Tables(Cow::Owned(Tables::Fields(copied)))).or_nil(resolved.nil())
+    }
+
+    // checks if generalize(f.args) :> args and returns generalize(f.returns),
+    // or `None` if the function never returns
+    fn check_function_args(&mut self, f: &Function, func: &Spanned<Ty>,
+                           args: &SpannedTySeq) -> TypeResult<Option<TySeq>> {
+        let generalize_tyseq = |seq: &TySeq, ctx: &mut TypeContext| {
+            let head = seq.head.iter().map(|t| t.clone().generalize(ctx)).collect();
+            let tail = seq.tail.as_ref().map(|t| t.clone().generalize(ctx));
+            TySeq { head: head, tail: tail }
+        };
+
+        // generic functions get fresh type variables for type parameters in each call,
+        // which should be shared by arguments and returns (so no generalization).
+        // they are resolved to their bounds after arguments have been checked
+        // (literal types in lower bounds are coerced, just like new variables);
+        // type parameters without any bound (e.g. from `WHATEVER`) become `WHATEVER`.
+        let generic = f.is_generic();
+        let (f, tvars) = if generic {
+            let (f, tvars) = f.instantiate(self.types());
+            (Cow::Owned(f), tvars)
+        } else {
+            (Cow::Borrowed(f), Vec::new())
+        };
+
+        let funcargs = if generic {
+            f.args.clone()
+        } else {
+            generalize_tyseq(&f.args, self.types())
+        };
+        let funcargs = funcargs.all_with_loc(func);
+        if generic {
+            // lower bounds of type parameters cannot be widened once set,
+            // so `{1, 2}` should be seen as `{integer, integer}` to match `vector<T>`
+            let head = args.head.iter().map(|t| {
+                self.coerce_generic_arg(&t.base).with_loc(t)
+            }).collect();
+            let tail = args.tail.as_ref().map(|t| self.coerce_generic_arg(&t.base).with_loc(t));
+            let args = SpannedTySeq { head: head, tail: tail, span: args.span };
+            args.assert_sub(&funcargs, self.types())?;
+        } else {
+            args.assert_sub(&funcargs, self.types())?;
+        }
+
+        if let Some(ref returns) = f.returns {
+            if generic {
+                let subst: HashMap<_, _> = tvars.into_iter().map(|tvar| {
+                    let ctx = self.types();
+                    let ty = ctx.get_tvar_exact_type(tvar)
+                                .or_else(|| {
+                                    ctx.get_tvar_lower_bound(tvar).map(|t| t.coerce())
+                                })
+                                .unwrap_or_else(|| Ty::new(T::Dynamic(Dyn::User)));
+                    (tvar, ty)
+                }).collect();
+                Ok(Some(returns.substitute(&subst, self.types())))
+            } else {
+                Ok(Some(generalize_tyseq(returns, self.types())))
+            }
+        } else {
+            Ok(None)
+        }
+    }
+
     fn check_callable(&mut self, func: &Spanned<Ty>, args: &SpannedTySeq,
                       methodcall: bool) -> Result<Exitable<TySeq>> {
         debug!("checking if {:?} can be called with {:?} ({})",
@@ -522,32 +919,52 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             return Ok(Exitable::dummy());
         };
 
-        // check if generalize(func.args) :> args and gather generalize(func.returns)
-        let mut returns = match *functy.get_functions().unwrap() {
-            Functions::Simple(ref f) => {
-                let generalize_tyseq = |seq: &TySeq, ctx: &mut TypeContext| {
-                    let head = seq.head.iter().map(|t| t.clone().generalize(ctx)).collect();
-                    let tail = seq.tail.as_ref().map(|t| t.clone().generalize(ctx));
-                    TySeq { head: head, tail: tail }
-                };
+        let hint = if methodcall {
+            TypeReportHint::MethodArgs
+        } else {
+            TypeReportHint::FuncArgs
+        };
 
-                let funcargs = generalize_tyseq(&f.args, self.types()).all_with_loc(func);
-                if let Err(r) = args.assert_sub(&funcargs, self.types()) {
-                    let hint = if methodcall {
-                        TypeReportHint::MethodArgs
-                    } else {
-                        TypeReportHint::FuncArgs
-                    };
-                    self.env.error(func, m::CallToWrongType { func: self.display(func) })
-                            .report_types(r, hint)
-                            .done()?;
-                    return Ok(Exitable::dummy());
+        // overloaded functions are tried in order, and the first matching one is used
+        if let T::Intersection(ref parts) = *functy {
+            let mut candidates = Vec::new();
+            for part in parts.iter() {
+                let f = match part.get_functions() {
+                    Some(&Functions::Simple(ref f)) => f,
+                    _ => continue, // non-function parts cannot be called
+                };
+                // a failed overload should not leave any constraint to type variables in args
+                let snapshot = self.types().snapshot();
+                match self.check_function_args(f, func, args) {
+                    Ok(Some(returns)) => return Ok(Exitable::new(returns)),
+                    Ok(None) => return Ok(Exitable::diverging()),
+                    Err(r) => {
+                        self.types().restore(snapshot);
+                        candidates.push((part, r));
+                    }
                 }
+            }
 
-                if let Some(ref returns) = f.returns {
-                    generalize_tyseq(returns, self.types())
-                } else {
-                    return Ok(Exitable::diverging());
+            let mut more = self.env.error(func, m::CallToNoOverload { func: self.display(func) });
+            for (part, r) in candidates {
+                more = more.note(func, m::OverloadNotMatched { func: self.display(part) })
+                           .report_types(r, hint);
+            }
+            more.done()?;
+            return Ok(Exitable::dummy());
+        }
+
+        let mut returns = match *functy.get_functions().unwrap() {
+            Functions::Simple(ref f) => {
+                match self.check_function_args(f, func, args) {
+                    Ok(Some(returns)) => returns,
+                    Ok(None) => return Ok(Exitable::diverging()),
+                    Err(r) => {
+                        self.env.error(func, m::CallToWrongType { func: self.display(func) })
+                                .report_types(r, hint)
+                                .done()?;
+                        return Ok(Exitable::dummy());
+                    }
                 }
             },
 
@@ -557,7 +974,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             },
         };
 
-        // XXX hack to allow generics for some significant functions
+        // XXX hack to allow `pairs`, which cannot be typed with type parameters
         if functy.tag() == Some(Tag::GenericPairs) {
             (|| {
                 let mut args = args.to_owned();
@@ -601,7 +1018,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 };
 
                 // replace the key with the third return type if it's not any
-                // (this is primarily to detect ipairs)
+                // (this is primarily to detect ipairs-like functions)
                 {
                     let third = returns.ensure_at(2);
                     match **third {
@@ -614,6 +1031,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 let knil = k.clone().with_nil();
                 let v = v.unlift().clone().without_nil();
                 *returns.ensure_at_mut(0) = Ty::new(T::func(Function {
+                    tparams: Vec::new(),
                     args: TySeq { head: vec![tab.clone(), k.clone()], tail: None },
                     argnames: Vec::new(),
                     returns: Some(TySeq { head: vec![knil, v], tail: None }),
@@ -626,6 +1044,58 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         Ok(Exitable::new(returns))
     }
 
+    // returns a field from the metatable of `ty` (if known) with given name.
+    // class instances and prototypes delegate this to the class system.
+    fn get_metatable_field(&mut self, ty: &Ty, name: &[u8],
+                           expspan: Span) -> Result<Option<Slot>> {
+        if let T::Class(cls) = **ty {
+            return self.context().index_class_metamethod(cls, name, expspan);
+        }
+
+        let mtid = if let Some(Tag::Metatable(mtid)) = ty.tag() { mtid } else { return Ok(None); };
+        let mt = self.types().get_metatable(mtid);
+        let mt = if let Some(mt) = self.env.resolve_exact_type(&mt) {
+            mt
+        } else {
+            return Ok(None);
+        };
+
+        let mut field = None;
+        if let Some(&Tables::Fields(ref rvar)) = mt.get_tables() {
+            let _ = self.env.context().list_rvar_fields(rvar.clone(), &mut |k, v| {
+                if *k == name {
+                    field = Some(v.clone());
+                    Err(())
+                } else {
+                    Ok(())
+                }
+            });
+        }
+        Ok(field)
+    }
+
+    // calls a metamethod (which may not be a function) with given arguments
+    fn check_metamethod_call(&mut self, method: &Spanned<Ty>,
+                             args: &SpannedTySeq) -> Result<TySeq> {
+        let functy = if let Some(func) = self.env.resolve_exact_type(method) {
+            func.with_loc(method)
+        } else {
+            self.env.error(method, m::CallToInexactType { func: self.display(method) }).done()?;
+            return Ok(TySeq::dummy());
+        };
+
+        if !self.env.get_type_bounds(&functy).1.is_callable() {
+            self.env.error(&functy, m::CallToNonFunc { func: self.display(&functy) }).done()?;
+            return Ok(TySeq::dummy());
+        }
+        if let Some(dyn) = functy.get_dynamic() {
+            return Ok(TySeq::from(T::Dynamic(dyn)));
+        }
+
+        let Exitable(_, returns) = self.check_callable(&functy, args, false)?;
+        Ok(returns)
+    }
+
     fn cannot_index(&self, span: Span, tab: &Slot, key: &Slot) -> Result<()> {
         // use a special message when the table is a record and key is a string literal
         match (tab.unlift().get_tables(), key.unlift().as_string()) {
@@ -642,6 +1112,39 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         }
     }
 
+    // private and protected fields can be only accessed from methods of the owning class
+    // (and its subclasses for protected fields), including functions nested in them.
+    // the visibility is determined from both the field type and the class system.
+    fn check_class_field_access(&mut self, cls: Class, key: Spanned<&Key>, slot: &Slot,
+                                expspan: Span) -> Result<()> {
+        // methods defined with a function body are only known after the type variable is resolved
+        let modifiers = self.env.resolve_exact_type(&slot.unlift())
+                                .map_or_else(|| slot.unlift().modifiers(), |ty| ty.modifiers());
+        let visibility = cmp::max(modifiers.visibility,
+                                  self.context().class_field_visibility(cls, &key));
+        if visibility == Visibility::Public {
+            return Ok(());
+        }
+
+        let owner = self.context().class_field_owner(cls, &key);
+        let accessible = match self.declaring_class.or(self.env.get_frame().class) {
+            Some(cid) if cid == owner => true,
+            Some(cid) => visibility == Visibility::Protected &&
+                         self.types().is_subclass_of(cid, owner),
+            None => false,
+        };
+        if !accessible {
+            let ownerty = Ty::new(T::Class(Class::Instance(owner)));
+            let cls = self.display(&ownerty);
+            if visibility == Visibility::Private {
+                self.env.error(expspan, m::PrivateFieldAccess { key: &key, cls: cls }).done()?;
+            } else {
+                self.env.error(expspan, m::ProtectedFieldAccess { key: &key, cls: cls }).done()?;
+            }
+        }
+        Ok(())
+    }
+
     // common routine for check_{l,r}val_index
     // when lval is true, the field is created as needed (otherwise it's an error)
     // when lval is false, the missing field is returned as Index::Missing
@@ -654,6 +1157,12 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         let ety = ety0.unlift().clone();
         let kty = kty0.unlift().clone();
 
+        // LuaJIT cdata fields are declared by `ffi.cdef` which we don't parse
+        if ety.tag() == Some(Tag::CData) {
+            return Ok(Index::Found(Slot::new(F::Dynamic(Dyn::User),
+                                             Ty::new(T::Dynamic(Dyn::User)))));
+        }
+
         let (_, flags) = self.env.get_type_bounds(&ety);
         if !flags.is_tabular() {
             self.env.error(&*ety0, m::IndexToNonTable { tab: self.display(&*ety0) }).done()?;
@@ -705,6 +1214,14 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 if lval {
                     if let Some((new, vslot)) = self.context().index_class_lval(cls, litkey,
                                                                                 expspan, None)? {
+                        // defining a new class field is a part of the class definition
+                        let defining = match cls {
+                            Class::Prototype(_) => new,
+                            Class::Instance(_) => false,
+                        };
+                        if !defining {
+                            self.check_class_field_access(cls, litkey, &vslot, expspan)?;
+                        }
                         vslot.adapt(ety0.flex(), self.types());
                         if new {
                             return Ok(Index::Created(vslot));
@@ -716,6 +1233,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                     }
                 } else {
                     if let Some(info) = self.context().index_class_rval(cls, litkey, expspan)? {
+                        self.check_class_field_access(cls, litkey, &info, expspan)?;
                         return Ok(Index::Found(info));
                     } else {
                         return Ok(Index::Missing);
@@ -731,6 +1249,31 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 return Ok(Index::dummy());
             }
 
+            T::Intersection(ref parts) => {
+                // each tabular part is tried in order, and the first part with the key is used.
+                // parts are looked up as r-values first so that no part gets a new field.
+                for part in parts.iter() {
+                    if !self.env.get_type_bounds(part).1.is_tabular() {
+                        continue;
+                    }
+                    let partslot = Slot::new(ety0.flex(), part.clone()).with_loc(&*ety0);
+                    match self.check_index_common(&partslot, kty0, expspan, false)? {
+                        Index::Missing => {}
+                        Index::Found(_) if lval => {
+                            return self.check_index_common(&partslot, kty0, expspan, true);
+                        }
+                        index => return Ok(index),
+                    }
+                }
+
+                if lval {
+                    self.cannot_index(expspan, &ety0, kty0)?;
+                    return Ok(Index::dummy());
+                } else {
+                    return Ok(Index::Missing);
+                }
+            }
+
             _ => {}
         }
 
@@ -839,6 +1382,22 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                     }
                 }
 
+                Some(&Tables::Interface(ref iface)) => {
+                    // methods receive the interface type itself as `self`
+                    match iface.member_slot(&litkey, &ety) {
+                        Some(vslot) => {
+                            vslot.adapt(ety0.flex(), self.types());
+                            return Ok(Index::Found(vslot));
+                        }
+                        None if lval => {
+                            // interfaces cannot be extended
+                            self.cannot_index(expspan, &ety0, kty0)?;
+                            return Ok(Index::dummy());
+                        }
+                        None => return Ok(Index::Missing),
+                    }
+                }
+
                 Some(&Tables::ArrayN(ref value)) => {
                     // special case `n`, otherwise use the general case (rejects non-int keys)
                     if let Key::Str(ref s) = litkey {
@@ -866,6 +1425,13 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
 
             Some(&Tables::Fields(..)) => {
                 assert!(!had_litkey);
+
+                // the `__index` metamethod can handle such keys, see `check_rval_index`
+                if !lval && self.get_metatable_field(&ety0.unlift(), b"__index",
+                                                     expspan)?.is_some() {
+                    return Ok(Index::Missing);
+                }
+
                 self.env.error(expspan,
                                m::IndexToRecWithUnknownStr { tab: self.display(&*ety0),
                                                              key: self.display(&kty) })
@@ -873,6 +1439,15 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 Ok(Index::dummy())
             },
 
+            Some(&Tables::Interface(..)) => {
+                assert!(!had_litkey);
+                self.env.error(expspan,
+                               m::IndexToInterfaceWithUnknown { iface: self.display(&*ety0),
+                                                                key: self.display(&kty) })
+                        .done()?;
+                Ok(Index::dummy())
+            },
+
             Some(&Tables::Array(ref value)) | Some(&Tables::ArrayN(ref value)) if intkey => {
                 if lval { value.adapt(ety0.flex(), self.types()); }
                 Ok(Index::Found((*value).clone().with_nil()))
@@ -887,7 +1462,9 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             },
 
             Some(&Tables::Map(ref key, ref value)) => {
-                check!(kty.assert_sub(&**key, self.types()));
+                // the key type may have a nominal tag that should be retained
+                let key = key.clone().or_nil(Nil::Absent);
+                check!(kty.assert_sub(&key, self.types()));
                 if lval { value.adapt(ety0.flex(), self.types()); }
                 Ok(Index::Found((*value).clone().with_nil()))
             },
@@ -902,8 +1479,38 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
 
     fn check_rval_index(&mut self, ety: &Spanned<Slot>, kty: &Spanned<Slot>,
                         expspan: Span) -> Result<Slot> {
+        self.check_rval_index_with_depth(ety, kty, expspan, 0)
+    }
+
+    fn check_rval_index_with_depth(&mut self, ety: &Spanned<Slot>, kty: &Spanned<Slot>,
+                                   expspan: Span, depth: usize) -> Result<Slot> {
+        // Lua also limits the length of `__index` chain
+        const MAX_INDEX_DEPTH: usize = 100;
+
         match self.check_index_common(ety, kty, expspan, false)? {
             Index::Missing => {
+                // missing fields are looked up through the `__index` metamethod if any
+                if depth < MAX_INDEX_DEPTH {
+                    if let Some(index) = self.get_metatable_field(&ety.unlift(), b"__index",
+                                                                  expspan)? {
+                        let indexflags = self.env.get_type_bounds(&index.unlift()).1;
+                        if indexflags.is_callable() && !indexflags.is_tabular() {
+                            let args = SpannedTySeq {
+                                head: vec![ety.unlift().clone().with_loc(ety),
+                                           kty.unlift().clone().with_loc(kty)],
+                                tail: None,
+                                span: expspan,
+                            };
+                            let index = index.unlift().clone().with_loc(ety);
+                            let returns = self.check_metamethod_call(&index, &args)?;
+                            return Ok(Slot::just(returns.into_first()));
+                        } else {
+                            return self.check_rval_index_with_depth(&index.with_loc(ety), kty,
+                                                                    expspan, depth + 1);
+                        }
+                    }
+                }
+
                 self.cannot_index(expspan, ety, kty)?;
                 Ok(Slot::dummy())
             },
@@ -915,6 +1522,18 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
     // this should be followed by assign_to_lval_index
     fn check_lval_index(&mut self, ety: &Spanned<Slot>, kty: &Spanned<Slot>,
                         expspan: Span) -> Result<Lvalue> {
+        // `readonly` tables reject any write, whether the field exists or not
+        let readonly = {
+            let ety = ety.unlift();
+            let resolved = self.env.resolve_exact_type(&ety);
+            ety.tag() == Some(Tag::Readonly) ||
+                resolved.map_or(false, |ty| ty.tag() == Some(Tag::Readonly))
+        };
+        if readonly {
+            self.env.error(expspan, m::CannotUpdateReadonly { tab: self.display(ety) }).done()?;
+            return Ok(Lvalue { found: true, slot: Slot::dummy().with_loc(expspan) });
+        }
+
         let (found, slot) = match self.check_index_common(ety, kty, expspan, true)? {
             Index::Missing => unreachable!(),
             Index::Created(slot) => (false, slot),
@@ -1188,7 +1807,8 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         };
 
         // unlike St::Local, do not tolerate the uninitialized variables
-        for (var, (varref, specinfo)) in vars.iter().zip(varrefspecs.into_iter()) {
+        for (i, (var, (varref, specinfo))) in vars.iter().zip(varrefspecs.into_iter())
+                                                  .enumerate() {
             // ideally should be done via zip, but then concrete types will collide
             // just ignore the assignment when info is None instead
             let info = infos.as_mut().and_then(|it| it.next());
@@ -1203,7 +1823,14 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                     } else {
                         // variable assignment
                         if let Some(info) = info {
-                            Some(self.env.assign_to_var(nameref, info)?)
+                            let nonnil = info.unlift().nil() != Nil::Noisy;
+                            let varslot = self.env.assign_to_var(nameref, info)?;
+                            if nonnil {
+                                if let Some(exp) = exps.and_then(|exps| exps.base.get(i)) {
+                                    self.narrow_default_assign(nameref, exp);
+                                }
+                            }
+                            Some(varslot)
                         } else {
                             None
                         }
@@ -1237,6 +1864,28 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         exprexit.to_stmt(stmtspan, self.env)
     }
 
+    // `x = x or <default>` with a non-nil default makes the local variable `x` non-nil
+    // until the current scope ends or the variable gets assigned again.
+    fn narrow_default_assign(&mut self, nameref: &Spanned<NameRef>, exp: &Spanned<Exp>) {
+        let is_default = match *exp.base {
+            Ex::Bin(ref l, Spanned { base: BinOp::Or, .. }, _) =>
+                narrowable_var(l).map_or(false, |var| var.base == nameref.base),
+            _ => false,
+        };
+        if !is_default {
+            return;
+        }
+
+        let slot = match self.env.get_var(nameref) {
+            Some(&NameDef { slot: NameSlot::Set(ref slot), .. }) => slot.clone(),
+            _ => return,
+        };
+        let narrowed = Slot::new(slot.flex(), slot.unlift().clone());
+        if narrowed.filter_by_flags(T_TRUTHY | T_FALSE, self.types()).is_ok() {
+            self.env.narrow_var(nameref, narrowed);
+        }
+    }
+
     #[cfg(feature = "no_implicit_func_sig")]
     fn error_on_implicit_sig(&mut self, sig: &Sig) -> Result<()> {
         if sig.args.head.iter().any(|spec| spec.kind.is_none()) {
@@ -1272,6 +1921,16 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         Ok(exit)
     }
 
+    // visits a block with local variables narrowed by the condition (or its negation)
+    fn visit_narrowed_block(&mut self, block: &'inp Spanned<Block>,
+                            cond: Option<&Cond>, negated: bool) -> Result<Exit> {
+        let mut scope = self.scoped(Scope::new());
+        if let Some(cond) = cond {
+            scope.narrow_cond(cond, negated, None);
+        }
+        scope.visit_block(block)
+    }
+
     fn check_pending_modules(&mut self) -> Result<()> {
         // we cannot remove the list of pending modules until we are done,
         // because pending type checking may refer (or even add) to them.
@@ -1292,8 +1951,8 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             for body in bodies {
                 // we can discard the output type, because it should be same to the previous type
                 // as long as the signature is explicit and identical
-                self.visit_func_body(body.tag, None, body.selfparam, body.sig,
-                                     body.block, body.declspan, None)?;
+                self.visit_func_body(body.tag, body.modifiers, None, body.selfparam, body.sig,
+                                     body.block, body.declspan, body.class, None)?;
             }
         }
 
@@ -1310,23 +1969,79 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
     fn visit_block_(&mut self, block: &'inp Spanned<Block>) -> Result<Exit> {
         let mut exit = Exit::None;
         let mut ignored_stmts: Option<Span> = None;
-        for stmt in &block.base {
+        let mut diverging_call = false;
+        let mut stmts = &block.base[..];
+        while !stmts.is_empty() {
+            // consecutive type declarations are checked together to allow mutual references
+            let ntypes = count_recursive_type_decls(stmts);
+            if ntypes > 1 {
+                let (types, rest) = stmts.split_at(ntypes);
+                stmts = rest;
+                if exit != Exit::None {
+                    let span = types.iter().fold(Span::dummy(), |span, stmt| span | stmt.span);
+                    ignored_stmts = Some(ignored_stmts.unwrap_or(Span::dummy()) | span);
+                }
+                self.visit_recursive_type_decls(types)?;
+                continue;
+            }
+
+            let stmt = &stmts[0];
+            stmts = &stmts[1..];
             if exit != Exit::None {
                 ignored_stmts = Some(ignored_stmts.unwrap_or(Span::dummy()) | stmt.span);
                 // the exit return can no longer affect this block's return
                 self.visit_stmt(stmt)?;
             } else {
                 exit = self.visit_stmt(stmt)?;
+                diverging_call = exit == Exit::Stop && is_call_stmt(stmt);
             }
         }
-        #[cfg(feature = "warn_on_dead_code")] {
-            if let Some(span) = ignored_stmts {
+        if let Some(span) = ignored_stmts {
+            // the code after calling a function that never returns (e.g. `error`)
+            // is almost surely a mistake, so it is always reported
+            if diverging_call {
                 self.env.warn(span, m::DeadCode {}).done()?;
+            } else {
+                #[cfg(feature = "warn_on_dead_code")] {
+                    self.env.warn(span, m::DeadCode {}).done()?;
+                }
             }
         }
         Ok(exit)
     }
 
+    // visits consecutive type declarations (see `count_recursive_type_decls`) at once,
+    // so that they can refer to each other
+    fn visit_recursive_type_decls(&mut self, stmts: &'inp [Spanned<Stmt>]) -> Result<()> {
+        let names: Vec<_> = stmts.iter().map(|stmt| {
+            recursive_type_decl(stmt).expect("not a recursive type declaration")
+        }).collect();
+        let tys = self.env.resolve_named_types(&names, |env, i| {
+            match *stmts[i].base {
+                St::KailuaType(_, _, _, ref kind) => Ty::from_kind(kind, env),
+                St::KailuaInterface(_, _, ref members) => {
+                    let iface = Interface::from_kind(members, env)?;
+                    Ok(Ty::new(T::Tables(Cow::Owned(Tables::Interface(iface)))))
+                }
+                _ => unreachable!(),
+            }
+        })?;
+
+        for (stmt, ty) in stmts.iter().zip(tys) {
+            let (scope, name) = match *stmt.base {
+                St::KailuaType(scope, ref name, ..) |
+                St::KailuaInterface(scope, ref name, _) => (scope, name),
+                _ => unreachable!(),
+            };
+            match scope {
+                TypeScope::Local => self.env.define_local_type(name, ty, Vec::new())?,
+                TypeScope::Global => self.env.define_global_type(name, ty, Vec::new())?,
+                TypeScope::Exported => self.env.define_and_export_type(name, ty, Vec::new())?,
+            }
+        }
+        Ok(())
+    }
+
     fn visit_stmt(&mut self, stmt: &'inp Spanned<Stmt>) -> Result<Exit> {
         debug!("visiting stmt {:?}", *stmt);
 
@@ -1346,7 +2061,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             St::Do(ref block) => self.visit_block(block),
 
             St::While(ref cond, ref block) => {
-                let (mut exit, ty) = self.visit_exp_from_stmt(cond, None)?;
+                let (mut exit, cond_, ty) = self.visit_cond_from_stmt(cond)?;
                 let boolean = self.check_bool(ty.unspan().unlift());
 
                 // the "normal" exit when the loop body doesn't do anything special
@@ -1364,25 +2079,40 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 }
 
                 if let Some(normal_exit) = normal_exit {
-                    exit &= self.visit_block(block)?;
+                    exit &= self.visit_narrowed_block(block, cond_.as_ref(), false)?;
                     exit = exit.loop_boundary(normal_exit);
                 }
+
+                // the condition should be false when the loop ends normally
+                if exit == Exit::None && !block_may_break(block) {
+                    if let Some(cond) = cond_ {
+                        self.narrow_cond(&cond, true, None);
+                    }
+                }
                 Ok(exit)
             }
 
             St::Repeat(ref block, ref cond) => {
                 let mut exit = self.visit_block(block)?;
-                let (exit_, ty) = self.visit_exp_from_stmt(cond, None)?;
+                let (exit_, cond_, ty) = self.visit_cond_from_stmt(cond)?;
                 exit &= exit_;
-                if exit == Exit::None {
+                let exit = if exit == Exit::None {
                     match self.check_bool(ty.unspan().unlift()) {
-                        Bool::Truthy => Ok(Exit::Stop),
-                        Bool::Falsy => Ok(exit),
-                        Bool::Unknown => Ok(Exit::None),
+                        Bool::Truthy => Exit::Stop,
+                        Bool::Falsy => exit,
+                        Bool::Unknown => Exit::None,
                     }
                 } else {
-                    Ok(exit.loop_boundary(Exit::None))
+                    exit.loop_boundary(Exit::None)
+                };
+
+                // the condition should be true when the loop ends normally
+                if exit == Exit::None && !block_may_break(block) {
+                    if let Some(cond) = cond_ {
+                        self.narrow_cond(&cond, false, None);
+                    }
                 }
+                Ok(exit)
             }
 
             St::If(ref conds, ref lastblock) => {
@@ -1393,45 +2123,94 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 let mut exit = Exit::Stop; // (C1 & B1) | ... | (C1 & ... & Ck & Bk)
                 let mut condexit = Exit::None; // C1 & ... & Ck
 
+                // each block is checked with all preceding conditions being false
+                // and its own condition being true. if only one block (including the implicit
+                // `else`) continues past the `if`, the same holds for the rest of the block.
+                let mut falsyconds = Vec::new(); // [(Ck, negated)]
+                let mut reaching = Vec::new(); // [(conditions known to hold, block)]
+
+                // enum variables compared in conditions, checked for exhaustiveness
+                let mut enumvars = Vec::new(); // [(variable, slot before the `if` statement)]
+
                 let mut ignored_blocks = None; // or Some((first truthy cond span, blocks span))
-                for &Spanned { base: (ref cond, ref block), span } in conds {
-                    // do not update the exit, as blocks after a truthy condition will be ignored
-                    if let Some((_, ref mut blocks_span)) = ignored_blocks {
-                        *blocks_span |= span;
-                        continue;
-                    }
+                {
+                    // falsy conditions accumulate to this scope
+                    let mut scope = self.scoped(Scope::new());
+
+                    for &Spanned { base: (ref cond, ref block), span } in conds {
+                        // do not update the exit, as blocks after a truthy condition
+                        // will be ignored
+                        if let Some((_, ref mut blocks_span)) = ignored_blocks {
+                            *blocks_span |= span;
+                            continue;
+                        }
 
-                    let (condexit_, ty) = self.visit_exp_from_stmt(cond, None)?;
-                    condexit &= condexit_;
-                    let boolean = self.check_bool(ty.unspan().unlift());
-                    match boolean {
-                        Bool::Truthy => {
-                            ignored_blocks = Some((cond.span, Span::dummy()));
-                            exit |= condexit & self.visit_block(block)?;
+                        let (condexit_, cond_, ty) = scope.visit_cond_from_stmt(cond)?;
+                        condexit &= condexit_;
+                        if let Some(ref cond) = cond_ {
+                            scope.collect_enum_vars(cond, &mut enumvars);
                         }
-                        Bool::Falsy => {
-                            #[cfg(feature = "warn_on_useless_conds")] {
-                                self.env.warn(span, m::IgnoredIfCase {})
-                                        .note(cond, m::IfCaseWithFalsyCond {})
-                                        .done()?;
+                        let boolean = scope.check_bool(ty.unspan().unlift());
+                        let blockexit = match boolean {
+                            Bool::Truthy => {
+                                ignored_blocks = Some((cond.span, Span::dummy()));
+                                Some(scope.visit_narrowed_block(block, cond_.as_ref(), false)?)
                             }
-                            exit |= condexit;
+                            Bool::Falsy => {
+                                #[cfg(feature = "warn_on_useless_conds")] {
+                                    scope.env.warn(span, m::IgnoredIfCase {})
+                                             .note(cond, m::IfCaseWithFalsyCond {})
+                                             .done()?;
+                                }
+                                None
+                            }
+                            Bool::Unknown => {
+                                Some(scope.visit_narrowed_block(block, cond_.as_ref(), false)?)
+                            }
+                        };
+
+                        if let Some(blockexit) = blockexit {
+                            exit |= condexit & blockexit;
+                            if condexit & blockexit == Exit::None {
+                                let mut known = falsyconds.clone();
+                                known.extend(cond_.clone().map(|cond| (cond, false)));
+                                reaching.push((known, Some(block)));
+                            }
+                        } else {
+                            exit |= condexit;
                         }
-                        Bool::Unknown => {
-                            exit |= condexit & self.visit_block(block)?;
+
+                        if let Some(cond) = cond_ {
+                            scope.narrow_cond(&cond, true, None);
+                            falsyconds.push((cond, true));
                         }
                     }
-                }
 
-                if let &Some(ref block) = lastblock {
-                    if let Some((_, ref mut blocks_span)) = ignored_blocks {
-                        *blocks_span |= block.span;
+                    if let &Some(ref block) = lastblock {
+                        if let Some((_, ref mut blocks_span)) = ignored_blocks {
+                            *blocks_span |= block.span;
+                        } else {
+                            let blockexit = scope.visit_block(block)?;
+                            exit |= condexit & blockexit;
+                            if condexit & blockexit == Exit::None {
+                                reaching.push((falsyconds, Some(block)));
+                            }
+                        }
                     } else {
-                        exit |= condexit & self.visit_block(block)?;
+                        if ignored_blocks.is_none() {
+                            scope.check_exhaustive_if(stmt.span, &enumvars)?;
+                            exit |= condexit;
+                            if condexit == Exit::None {
+                                reaching.push((falsyconds, None));
+                            }
+                        }
                     }
-                } else {
-                    if ignored_blocks.is_none() {
-                        exit |= condexit;
+                }
+
+                if reaching.len() == 1 {
+                    let (known, block) = reaching.pop().unwrap();
+                    for (cond, negated) in known {
+                        self.narrow_cond(&cond, negated, block.map(|block| &block.base));
                     }
                 }
 
@@ -1616,9 +2395,9 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                     self.register_module_if_needed(&varslot);
                 }
 
-                let (tag, no_check) = self.visit_sig_attrs(&sig.attrs)?;
-                let functy = self.visit_func_body(tag, no_check, None, sig, block,
-                                                  stmt.span, None)?;
+                let (tag, modifiers, no_check) = self.visit_sig_attrs(&sig.attrs)?;
+                let functy = self.visit_func_body(tag, modifiers, no_check, None, sig, block,
+                                                  stmt.span, None, None)?;
                 if let Err(r) = Ty::new(T::TVar(funcv)).assert_eq(&*functy.unlift(), self.types()) {
                     self.env.error(stmt, m::BadRecursiveCall {})
                         .report_types(r, TypeReportHint::None)
@@ -1656,10 +2435,14 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 let subspan = info.span | method.span;
                 let keystr = Str::from(method.base[..].to_owned());
                 let kty = Slot::just(Ty::new(T::Str(Cow::Owned(keystr)))).with_loc(method);
-                let lvalue = self.check_lval_index(&info, &kty, subspan)?;
+                let class = self.class_of_prototype(&info);
+                self.declaring_class = class;
+                let lvalue = self.check_lval_index(&info, &kty, subspan);
+                self.declaring_class = None;
+                let lvalue = lvalue?;
 
                 // now prepare the right-hand side (i.e. method decl)
-                let (tag, mut no_check) = self.visit_sig_attrs(&sig.attrs)?;
+                let (tag, modifiers, mut no_check) = self.visit_sig_attrs(&sig.attrs)?;
                 if no_check.is_none() && info.flex() == F::Module {
                     // module indexing causes the rhs not to be checked right now (like NO_CHECK)
                     no_check = Some(NoCheck::Module);
@@ -1670,8 +2453,8 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 } else {
                     None
                 };
-                let methinfo = self.visit_func_body(tag, no_check, selfinfo.clone(), sig, block,
-                                                    stmt.span, None)?;
+                let methinfo = self.visit_func_body(tag, modifiers, no_check, selfinfo.clone(),
+                                                    sig, block, stmt.span, class, None)?;
 
                 // if this is a module indexing (that is, an assignment to the module field slot
                 // and the declaration was not already [NO_CHECK]), we will keep the arguments to
@@ -1688,7 +2471,8 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                         "slots with F::Module not registered in the current checker"
                     );
                     modules.func_bodies.push(PendingFuncBody {
-                        tag: tag, selfparam: selfinfo, sig: sig, block: block, declspan: stmt.span,
+                        tag: tag, modifiers: modifiers, selfparam: selfinfo, sig: sig,
+                        block: block, declspan: stmt.span, class: class,
                     });
                 }
 
@@ -1700,11 +2484,11 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 // collect specified types first (required for hints)
                 let nameinfos = names.iter().map(|namespec| {
                     let info = self.visit_type_spec(namespec)?;
-                    Ok((&namespec.base, info))
+                    Ok((namespec, info))
                 }).collect::<Result<Vec<_>>>()?;
 
                 let hint = SpannedSlotSeq {
-                    head: nameinfos.iter().map(|&(name, ref info)| {
+                    head: nameinfos.iter().map(|&(&TypeSpec { base: ref name, .. }, ref info)| {
                         if let Some(ref spec) = *info {
                             spec.slot().clone()
                         } else {
@@ -1716,8 +2500,12 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 };
                 let (exit, infos) = self.visit_explist_from_stmt(exps, Some(hint))?;
 
-                for ((localname, specinfo), info) in nameinfos.into_iter()
-                                                              .zip(infos.into_iter_with_none()) {
+                for ((namespec, specinfo), info) in nameinfos.into_iter()
+                                                             .zip(infos.into_iter_with_none()) {
+                    if namespec.modf == MM::Close {
+                        self.check_closable(&namespec.base, info.as_ref())?;
+                    }
+                    let localname = &namespec.base;
                     let nameref = NameRef::Local(localname.base.clone()).with_loc(localname);
                     if let Some(varslot) = self.env.add_var(&nameref, specinfo, info)? {
                         self.register_module_if_needed(&varslot);
@@ -1739,36 +2527,104 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
 
             St::Break => Ok(Exit::Break),
 
+            // `goto` may jump backward, so we cannot tell if the following code is dead
+            St::Goto(_) | St::Label(_) => Ok(Exit::None),
+
             St::KailuaOpen(ref name) => {
                 let opts = self.env.opts().clone();
                 self.env.context().open_library(name.as_ref().map(|n| &n[..]), opts)?;
                 Ok(Exit::None)
             }
 
-            St::KailuaType(scope, ref name, ref kind) => {
+            St::KailuaType(scope, ref name, ref tparams, ref kind) => {
                 // self-redefinition is handled separately, as we cannot distingiush
                 // `--# type local A = <some type> / --# type A = A` from `--# type A = <some type>`
-                if let K::Named(ref name_) = *kind.base {
-                    if name.base == name_.base {
-                        match scope {
-                            TypeScope::Local => {} // invalid, will error below
-                            TypeScope::Global => {
+                if let K::Named(ref name_, ref args) = *kind.base {
+                    if name.base == name_.base && tparams.is_empty() && args.is_empty() {
+                        if scope != TypeScope::Local && self.env.get_named_type(name).is_some() {
+                            if scope == TypeScope::Global {
                                 self.env.redefine_global_type(name, kind.span)?;
-                                return Ok(Exit::None);
-                            }
-                            TypeScope::Exported => {
+                            } else {
                                 self.env.reexport_local_type(name, kind.span)?;
-                                return Ok(Exit::None);
                             }
+                            return Ok(Exit::None);
                         }
+
+                        // otherwise this is a self-referencing definition, which is not
+                        // a valid recursive type (reported in the same way to `--# type A = A?`)
+                        self.env.error(kind, m::BadRecursiveType { name: &name.base }).done()?;
+                        let ty = Ty::dummy();
+                        match scope {
+                            TypeScope::Local => self.env.define_local_type(name, ty, Vec::new())?,
+                            TypeScope::Global => self.env.define_global_type(name, ty, Vec::new())?,
+                            TypeScope::Exported =>
+                                self.env.define_and_export_type(name, ty, Vec::new())?,
+                        }
+                        return Ok(Exit::None);
                     }
                 }
 
-                let ty = Ty::from_kind(kind, &mut self.env)?;
+                // type parameters are resolved to type variables that are never bound,
+                // and get substituted whenever the type is used (see `Ty::from_kind`).
+                // non-generic types can refer to themselves, generic types cannot.
+                let tparams: Vec<_> = tparams.iter().map(|name| {
+                    (name.clone(), self.env.types().gen_tvar())
+                }).collect();
+                let ty = if tparams.is_empty() {
+                    self.env.resolve_named_type(name, kind.span, |env| {
+                        Ty::from_kind(kind, env)
+                    })?
+                } else {
+                    let tvars = tparams.iter().map(|&(_, tvar)| tvar).collect();
+                    self.env.resolve_generic_type(name, tvars, |env| {
+                        let mut resolv = TypeParamResolver::new(env, &tparams);
+                        Ty::from_kind(kind, &mut resolv)
+                    })?
+                };
+                let tvars = tparams.into_iter().map(|(_, tvar)| tvar).collect();
                 match scope {
-                    TypeScope::Local => self.env.define_local_type(name, ty)?,
-                    TypeScope::Global => self.env.define_global_type(name, ty)?,
-                    TypeScope::Exported => self.env.define_and_export_type(name, ty)?,
+                    TypeScope::Local => self.env.define_local_type(name, ty, tvars)?,
+                    TypeScope::Global => self.env.define_global_type(name, ty, tvars)?,
+                    TypeScope::Exported => self.env.define_and_export_type(name, ty, tvars)?,
+                }
+                Ok(Exit::None)
+            }
+
+            St::KailuaNewtype(scope, ref name, ref kind) => {
+                let base = Ty::from_kind(kind, &mut self.env)?;
+
+                // only primitive types without any tag can be a base of newtypes
+                let flags = base.flags();
+                let ty = if base.tag().is_none() && !flags.is_empty() && !flags.is_dynamic() &&
+                            (flags & !(T_BOOLEAN | T_NUMBER | T_STRING)).is_empty() {
+                    // the named type retains the original type and also identifies the newtype
+                    let types = self.env.types();
+                    let id = types.gen_named_type(name);
+                    types.set_named_type(id, base.clone());
+                    base.with_tag(Tag::Newtype(id))
+                } else {
+                    self.env.error(kind, m::NonPrimitiveNewtype { ty: self.display(&base) })
+                            .done()?;
+                    Ty::dummy()
+                };
+
+                match scope {
+                    TypeScope::Local => self.env.define_local_type(name, ty, Vec::new())?,
+                    TypeScope::Global => self.env.define_global_type(name, ty, Vec::new())?,
+                    TypeScope::Exported => self.env.define_and_export_type(name, ty, Vec::new())?,
+                }
+                Ok(Exit::None)
+            }
+
+            St::KailuaInterface(scope, ref name, ref members) => {
+                let ty = self.env.resolve_named_type(name, stmt.span, |env| {
+                    let iface = Interface::from_kind(members, env)?;
+                    Ok(Ty::new(T::Tables(Cow::Owned(Tables::Interface(iface)))))
+                })?;
+                match scope {
+                    TypeScope::Local => self.env.define_local_type(name, ty, Vec::new())?,
+                    TypeScope::Global => self.env.define_global_type(name, ty, Vec::new())?,
+                    TypeScope::Exported => self.env.define_and_export_type(name, ty, Vec::new())?,
                 }
                 Ok(Exit::None)
             }
@@ -1797,24 +2653,50 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             }
 
             St::KailuaAssumeMethod(Spanned { base: (ref rootname, ref names), span },
-                                   kindm, ref funckind) => {
+                                   kindm, ref attrs, ref funckinds) => {
                 assert!(!names.is_empty());
+                assert!(!funckinds.is_empty());
 
                 if self.env.get_var(rootname).is_some() {
                     let flex = F::from(kindm);
-                    let mut func = Function::from_kind(funckind, &mut self.env)?;
+                    let mut funcs = Vec::new();
+                    for funckind in funckinds {
+                        funcs.push(Function::from_kind(funckind, &mut self.env)?);
+                    }
 
                     let rootslot = self.env.ensure_var(rootname)?.with_loc(rootname);
 
                     // convert `method(...) --> ...` to `function(self: Self, ...) --> ...`
                     // where `Self` is an inferred type from `rootslot`
                     let selfinfo = self.visit_self_param(stmt.span, &rootslot, None, &names[0])?;
-                    func.args.head.insert(0, selfinfo.unlift().clone());
-                    func.argnames.insert(0, Some(Name::from(&b"self"[..]).without_loc()));
+                    let mut tys: Vec<Ty> = funcs.into_iter().map(|mut func| {
+                        func.args.head.insert(0, selfinfo.unlift().clone());
+                        func.argnames.insert(0, Some(Name::from(&b"self"[..]).without_loc()));
+                        Ty::new(T::Functions(Cow::Owned(Functions::Simple(func))))
+                    }).collect();
+
+                    // multiple methods are overloads
+                    let ty = if tys.len() == 1 {
+                        tys.pop().unwrap()
+                    } else {
+                        Ty::new(T::Intersection(Cow::Owned(tys)))
+                    };
 
-                    let slot = Slot::new(
-                        flex, Ty::new(T::Functions(Cow::Owned(Functions::Simple(func)))),
-                    );
+                    let mut tag = None;
+                    let mut modifiers = Modifiers::none();
+                    for attr in attrs {
+                        if Modifiers::is_modifier_attr(attr) {
+                            modifiers.add_attr(attr, self.env)?;
+                        } else if let Some(tag_) = Tag::from(attr, self.env)? {
+                            // None is simply ignored, `Tag::from` has already reported the error
+                            if tag.is_some() {
+                                self.env.warn(attr, m::DuplicateAttrInSig {}).done()?;
+                            } else {
+                                tag = Some(tag_);
+                            }
+                        }
+                    }
+                    let slot = Slot::new(flex, ty.with_tag(tag).with_modifiers(modifiers));
 
                     // the final slot should be static
                     let newslot = self.assume_field_slot(true, rootslot, names, span, slot)?;
@@ -1828,9 +2710,30 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 Ok(Exit::None)
             }
 
-            St::KailuaClassSystem(ref name) => {
-                if let Some(system) =
-                        str::from_utf8(&name.base).ok().and_then(make_predefined_class_system) {
+            St::KailuaClassSystem(ref name, ref attrs) => {
+                let namestr = str::from_utf8(&name.base).ok();
+                let builtin = namestr.map_or(false, is_builtin_class_system);
+                if builtin && !attrs.is_empty() {
+                    self.env.error(&attrs[0], m::RulesToBuiltinClassSystem { name: name })
+                            .done()?;
+                }
+
+                let system = if attrs.is_empty() || builtin {
+                    let opts = self.env.opts().clone();
+                    let opts = opts.borrow();
+                    namestr.and_then(|name| make_predefined_class_system(name, &*opts))
+                } else {
+                    // rules in the declaration are applied on top of rules from the options
+                    let rules = {
+                        let opts = self.env.opts().clone();
+                        let opts = opts.borrow();
+                        namestr.and_then(|name| opts.class_system_rules(name)).unwrap_or_default()
+                    };
+                    let rules = self.class_system_rules_from_attrs(rules, attrs)?;
+                    Some(Box::new(DeclarativeClassSystem::new(rules)) as Box<ClassSystem>)
+                };
+
+                if let Some(system) = system {
                     self.env.define_class_system(name, system)?;
                 } else {
                     self.env.error(name, m::NoSuchPredefinedClassSystem { name: name }).done()?;
@@ -1839,14 +2742,15 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 Ok(Exit::None)
             }
 
-            St::KailuaAssumeClass(ref system, ref name, ref parent, _scope) => {
+            St::KailuaAssumeClass(ref system, ref name, ref parents, _scope) => {
                 let csid = if let Some(ref system) = *system {
                     self.env.class_system_from_name(system)?
                 } else {
                     None
                 };
 
-                let parent = if let Some(ref parent) = *parent {
+                let mut parentcids = Vec::new();
+                for parent in parents {
                     // the parent class should be a defined type...
                     if let Some(def) = self.env.get_named_type(parent) {
                         // and should be a plain class instance (no `?` or `!` as well).
@@ -1855,23 +2759,19 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                         // such variable is impossible with the type definition.
                         if let (Nil::Silent, &T::Class(Class::Instance(cid))) = (def.ty.nil(),
                                                                                  &*def.ty) {
-                            Some(cid.with_loc(parent))
+                            parentcids.push(cid.with_loc(parent));
                         } else {
                             self.env.error(parent, m::BadClassParent { ty: self.display(&def.ty) })
                                     .done()?;
-                            None
                         }
                     } else {
                         self.env.error(parent, m::NoType { name: &parent.base }).done()?;
-                        None
                     }
-                } else {
-                    None
-                };
+                }
 
                 // defaults to the (publicly invisible) "dumb" class system
                 let csid = csid.unwrap_or_else(|| self.env.dumb_class_system());
-                if let Some(cid) = self.context().assume_class(csid, parent, stmt.span)? {
+                if let Some(cid) = self.context().assume_class(csid, parentcids, stmt.span)? {
                     let name = name.after.clone().with_loc(name);
 
                     // immediately name the class.
@@ -1938,9 +2838,11 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         Ok(())
     }
 
-    fn visit_sig_attrs(&mut self,
-                       attrs: &[Spanned<Attr>]) -> Result<(Option<Tag>, Option<NoCheck>)> {
+    fn visit_sig_attrs(&mut self, attrs: &[Spanned<Attr>])
+        -> Result<(Option<Tag>, Modifiers, Option<NoCheck>)>
+    {
         let mut tag = None;
+        let mut modifiers = Modifiers::none();
         let mut no_check = None;
 
         for attr in attrs {
@@ -1951,6 +2853,8 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 } else {
                     no_check = Some(NoCheck::User);
                 }
+            } else if Modifiers::is_modifier_attr(attr) {
+                modifiers.add_attr(attr, self.env)?;
             } else {
                 // None is simply ignored, `Tag::from` has already reported the error
                 if let Some(tag_) = Tag::from(attr, self.env)? {
@@ -1963,7 +2867,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             }
         }
 
-        Ok((tag, no_check))
+        Ok((tag, modifiers, no_check))
     }
 
     fn visit_self_param(&mut self, selfparamspan: Span, tableinfo: &Spanned<Slot>,
@@ -2005,10 +2909,22 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         }
     }
 
-    fn visit_func_body(&mut self, tag: Option<Tag>, no_check: Option<NoCheck>,
+    // returns a class if `tableinfo` is a class prototype, so that methods declared to it
+    // can access private and protected members of the class.
+    fn class_of_prototype(&self, tableinfo: &Spanned<Slot>) -> Option<ClassId> {
+        if let Some(tableinfo) = self.env.resolve_exact_type(&tableinfo.unlift()) {
+            if let T::Class(Class::Prototype(cid)) = *tableinfo {
+                return Some(cid);
+            }
+        }
+        None
+    }
+
+    fn visit_func_body(&mut self, tag: Option<Tag>, modifiers: Modifiers,
+                       no_check: Option<NoCheck>,
                        selfparam: Option<(&Spanned<SelfParam>, Slot)>, sig: &Sig,
                        block: &'inp Spanned<Vec<Spanned<Stmt>>>, declspan: Span,
-                       hint: Option<Spanned<Slot>>) -> Result<Slot> {
+                       class: Option<ClassId>, hint: Option<Spanned<Slot>>) -> Result<Slot> {
         // if the hint exists and has a functional portion,
         // collect first `sig.args.head.len()` types for missing argument types,
         // and a repeating part of remaining type sequence for a missing variadic argument type.
@@ -2019,6 +2935,13 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         // then when `n < m` the variadic argument would get `n - m` non-repeating types!
         // since this is forbidden from the signature we treat this as an error case
         // and drop the type hint for the variadic argument altogether.
+        //
+        // a function hinted with a thread type is a coroutine body (see `visit_func_call`),
+        // where `coroutine.yield` calls are tracked.
+        let coroutine = hint.as_ref().map_or(false, |hint| {
+            let ty = self.env.resolve_exact_type(&hint.unlift());
+            ty.map_or(false, |ty| ty.get_threads().is_some())
+        });
         let hint: Option<(Vec<Ty>, Option<Ty>, Option<TySeq>)> = hint.and_then(|hint| {
             self.env.resolve_exact_type(&hint.unlift()).and_then(|ty| {
                 if let Some(&Functions::Simple(ref f)) = ty.get_functions() {
@@ -2106,7 +3029,9 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         } else {
             Returns::None
         };
-        let frame = Frame { vararg: vainfo, returns: returns };
+        // nested functions inherit the class from the enclosing method
+        let class = class.or(self.env.get_frame().class);
+        let frame = Frame { vararg: vainfo, returns: returns, coroutine: None, class: class };
 
         let mut argshead = Vec::new();
         let mut argnames = Vec::new();
@@ -2185,6 +3110,11 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         }
 
         let args = TySeq { head: argshead, tail: vatype };
+        if coroutine {
+            // `coroutine.yield` returns what `coroutine.resume` has received
+            scope.env.get_frame_mut().coroutine =
+                Some(CoroutineFrame { args: args.clone(), yields: None });
+        }
 
         if no_check.is_none() {
             if let Exit::None = scope.visit_block(block)? {
@@ -2192,28 +3122,99 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 let span = Span::from(block.span.end()); // conceptually at the end of block
                 scope.visit_return(SpannedSlotSeq::new(span), span)?;
             }
+
+            if coroutine {
+                let coroutine = scope.env.get_frame_mut().coroutine.take();
+                let yields = coroutine.and_then(|co| co.yields).unwrap_or_else(|| TySeq::new());
+                scope.coroutine_yields = Some(yields);
+            }
         }
 
         let returns = match scope.env.get_frame().returns {
             Returns::Implicit(ref ret) | Returns::Explicit(ref ret) => Some(ret.clone()),
             Returns::Never | Returns::None => None,
         };
-        let func = Function { args: args, argnames: argnames, returns: returns };
-        Ok(Slot::just(Ty::new(T::func(func)).with_tag(tag)))
+        let func = Function { tparams: Vec::new(), args: args, argnames: argnames,
+                              returns: returns };
+        Ok(Slot::just(Ty::new(T::func(func)).with_tag(tag).with_modifiers(modifiers)))
+    }
+
+    // returns the first overload of `functy` which accepts given arguments without any error,
+    // or None if `functy` is not overloaded or no overload matches.
+    // arguments are checked in a trial, so they should be visited again with the result.
+    fn select_overload(&mut self, functy: &Spanned<Ty>, selfinfo: Option<&Spanned<Slot>>,
+                       exps: &'inp [Spanned<Exp>], argspan: Span) -> Result<Option<Ty>> {
+        let parts = if let T::Intersection(ref parts) = *functy.base {
+            parts
+        } else {
+            return Ok(None);
+        };
+
+        for part in parts.iter() {
+            let f = match part.get_functions() {
+                Some(&Functions::Simple(ref f)) => f,
+                _ => continue,
+            };
+
+            let trial = self.context().begin_trial();
+            let matched = self.try_overload(f, functy, selfinfo, exps, argspan);
+            let failed = self.context().end_trial(trial);
+            if matched? && !failed {
+                return Ok(Some(part.clone()));
+            }
+        }
+
+        Ok(None)
     }
 
-    fn visit_func_call(&mut self, functy: &Spanned<Ty>, selfinfo: Option<Spanned<Slot>>,
+    fn try_overload(&mut self, f: &Function, functy: &Spanned<Ty>,
+                    selfinfo: Option<&Spanned<Slot>>, exps: &'inp [Spanned<Exp>],
+                    argspan: Span) -> Result<bool> {
+        let mut hint = function_arg_hints(f, self.types());
+        if selfinfo.is_some() && !hint.head.is_empty() {
+            hint.head.remove(0); // args do not contain self, so do hints
+        }
+        let hint = SlotSeq::from_seq(hint).all_with_loc(functy);
+
+        let Exitable(_, mut argtys) = self.visit_explist_with_span(exps, argspan, Some(hint))?;
+        if let Some(selfinfo) = selfinfo {
+            argtys.head.insert(0, selfinfo.clone());
+        }
+        Ok(self.check_function_args(f, functy, &argtys.unlift()).is_ok())
+    }
+
+    fn visit_func_call(&mut self, functy: &Spanned<Ty>, mut selfinfo: Option<Spanned<Slot>>,
                        args: &'inp Spanned<Args>, expspan: Span) -> Result<Exitable<SlotSeq>> {
-        let functy = if let Some(func) = self.env.resolve_exact_type(functy) {
+        let mut functy = if let Some(func) = self.env.resolve_exact_type(functy) {
             func.with_loc(functy)
         } else {
             self.env.error(functy, m::CallToInexactType { func: self.display(functy) }).done()?;
             return Ok(Exitable::dummy());
         };
 
-        // construct hints; they are given at the best effort basis
-        let hint = if let Some(&Functions::Simple(ref f)) = functy.get_functions() {
-            let mut args = f.args.clone();
+        // an anonymous function given to overloaded functions is typed by the first overload
+        // (having a function type in that position) by default, which can be wrong.
+        // in that case overloads are tentatively tried in order to select the matching one.
+        if let Args::List(ref ee) = args.base {
+            if ee.iter().any(|e| if let Ex::Func(..) = *e.base { true } else { false }) {
+                if let Some(part) = self.select_overload(&functy, selfinfo.as_ref(), ee,
+                                                         args.span)? {
+                    functy = part.with_loc(&functy);
+                }
+            }
+        }
+
+        // construct hints; they are given at the best effort basis.
+        // `setmetatable` returns the first argument as is, so it should not be coerced by hints
+        let hint = if functy.tag() == Some(Tag::SetMetatable) {
+            None
+        } else if functy.tag() == Some(Tag::CoroutineCreate) ||
+                  functy.tag() == Some(Tag::CoroutineWrap) {
+            // a function literal given is checked as a coroutine body,
+            // which yield types are collected into `self.coroutine_yields`
+            self.coroutine_yields = None;
+            Some(SlotSeq::from(T::thread()).all_with_loc(&functy))
+        } else if let Some(mut args) = callable_arg_hints(&functy, self.types()) {
             if selfinfo.is_some() && !args.head.is_empty() {
                 args.head.remove(0); // args do not contain self, so do hints
             }
@@ -2241,6 +3242,31 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             },
         };
 
+        // any class test in arguments is irrelevant to this call
+        self.tested_class = None;
+
+        // LuaJIT cdata may be a function pointer, which we cannot verify
+        if functy.tag() == Some(Tag::CData) {
+            return Ok(exit.with(SlotSeq::from(T::Dynamic(Dyn::User))));
+        }
+
+        // a table with the `__call` metamethod receives itself as the first argument
+        if let Some(call) = self.get_metatable_field(&functy, b"__call", expspan)? {
+            let call = call.unlift().clone().with_loc(&functy);
+            let call = if let Some(call) = self.env.resolve_exact_type(&call) {
+                call.with_loc(&functy)
+            } else {
+                self.env.error(&call, m::CallToInexactType { func: self.display(&call) })
+                        .done()?;
+                return Ok(exit.with_dummy());
+            };
+            if let Some(selfinfo) = selfinfo.take() {
+                argtys.head.insert(0, selfinfo);
+            }
+            selfinfo = Some(Slot::just(functy.base.clone()).with_loc(&functy));
+            functy = call;
+        }
+
         if !self.env.get_type_bounds(&functy).1.is_callable() {
             self.env.error(&functy, m::CallToNonFunc { func: self.display(&functy) }).done()?;
             return Ok(exit.with_dummy());
@@ -2250,6 +3276,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         }
 
         // handle tags, which may return different things from the function signature
+        let mut refined_returns = None;
         match functy.tag() {
             // require("foo")
             Some(Tag::Require) => {
@@ -2289,7 +3316,8 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
 
                 // non-list arguments have no usable conditions (always evaluate to true)
                 if let Args::List(ref args) = args.base {
-                    if let (Some(cond), _seq) = self.collect_conds_from_exp(&args[0])? {
+                    let Exitable(_, (cond, _seq)) = self.collect_conds_from_exp(&args[0])?;
+                    if let Some(cond) = cond {
                         self.assert_cond(cond, false)?;
                     }
                 }
@@ -2306,7 +3334,8 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 }
 
                 if let Args::List(ref args) = args.base {
-                    if let (Some(cond), _seq) = self.collect_conds_from_exp(&args[0])? {
+                    let Exitable(_, (cond, _seq)) = self.collect_conds_from_exp(&args[0])?;
+                    if let Some(cond) = cond {
                         self.assert_cond(cond, true)?;
                     }
                 }
@@ -2323,11 +3352,128 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 }
 
                 if let Some(flags) = self.ext_literal_ty_to_flags(argtys.ensure_at(1))? {
-                    let cond = Cond::Flags(argtys.ensure_at(0).clone(), flags);
+                    let cond = Cond::Flags(argtys.ensure_at(0).clone(), None, flags);
                     self.assert_cond(cond, false)?;
                 }
             }
 
+            // setmetatable(table, metatable)
+            Some(Tag::SetMetatable) => {
+                let tab = argtys.ensure_at(0).unlift().clone();
+                let meta = argtys.ensure_at(1).unlift().clone();
+
+                // check arguments against the signature first
+                let Exitable(retexit, _) = self.check_callable(&functy, &argtys.unlift(), false)?;
+                let exit = cmp::max(exit, retexit);
+                let tag = if self.env.get_type_bounds(&meta).1 == T_NONE {
+                    // setmetatable(table, nil) removes the metatable
+                    None
+                } else {
+                    Some(Tag::Metatable(self.types().gen_metatable(&meta)))
+                };
+                return Ok(exit.with(SlotSeq::from(tab.with_tag(tag))));
+            }
+
+            // coroutine.create(f), coroutine.wrap(f)
+            Some(tag @ Tag::CoroutineCreate) | Some(tag @ Tag::CoroutineWrap) => {
+                let yields = self.coroutine_yields.take();
+                let func = self.env.resolve_exact_type(&argtys.ensure_at(0).unlift());
+
+                // check arguments against the signature first
+                let Exitable(retexit, returns) =
+                    self.check_callable(&functy, &argtys.unlift(), false)?;
+                let exit = cmp::max(exit, retexit);
+
+                let func = match func.as_ref().and_then(|f| f.get_functions()) {
+                    Some(&Functions::Simple(ref f)) if f.is_generic() => {
+                        f.instantiate(self.types()).0
+                    },
+                    Some(&Functions::Simple(ref f)) => f.clone(),
+                    _ => return Ok(exit.with(SlotSeq::from_seq(returns))),
+                };
+
+                // yield types are unknown unless the function body has been checked
+                let yields = yields.unwrap_or_else(|| {
+                    TySeq { head: Vec::new(), tail: Some(Ty::new(T::Dynamic(Dyn::User))) }
+                });
+                let thread = Thread {
+                    args: func.args,
+                    yields: yields,
+                    returns: func.returns.unwrap_or_else(|| TySeq::new()),
+                };
+
+                let ty = if tag == Tag::CoroutineCreate {
+                    T::coroutine(thread)
+                } else {
+                    // coroutine.wrap propagates errors, so the function returns as is
+                    let returns = thread.resumed(self.types());
+                    T::func(Function { tparams: Vec::new(), args: thread.args,
+                                       argnames: func.argnames, returns: Some(returns) })
+                };
+                return Ok(exit.with(SlotSeq::from(ty)));
+            }
+
+            // coroutine.resume(co, ...)
+            Some(Tag::CoroutineResume) => {
+                let co = self.env.resolve_exact_type(&argtys.ensure_at(0).unlift());
+                let thread = match co.as_ref().and_then(|co| co.get_threads()) {
+                    Some(&Threads::Simple(ref th)) => Some(th.clone()),
+                    _ => None,
+                };
+                if let (Some(co), Some(thread)) = (co, thread) {
+                    // the coroutine itself is included for the correct argument index
+                    let mut args = thread.args.clone();
+                    args.head.insert(0, co.clone());
+                    let args = args.all_with_loc(&argtys.head[0]);
+                    if let Err(r) = argtys.unlift().assert_sub(&args, self.types()) {
+                        self.env.error(expspan, m::CannotResumeThread { thread: self.display(&co) })
+                                .report_types(r, TypeReportHint::FuncArgs)
+                                .done()?;
+                    }
+
+                    // the values may be an error message when the first return is false
+                    let resumed = thread.resumed(self.types());
+                    let mut returns = TySeq { head: vec![Ty::new(T::Boolean)], tail: resumed.tail };
+                    returns.head.extend(resumed.head);
+                    return Ok(exit.with(SlotSeq::from_seq(returns)));
+                }
+            }
+
+            // coroutine.yield(...)
+            Some(Tag::CoroutineYield) => {
+                if let Some(coroutine) = self.env.get_frame().coroutine.clone() {
+                    let seq = argtys.unlift();
+                    let yields = if let Some(yields) = coroutine.yields {
+                        let yields = yields.all_with_loc(expspan);
+                        match seq.union(&yields, false, self.types()) {
+                            Ok(yields) => yields.unspan(),
+                            Err(r) => {
+                                self.env.error(expspan, m::CannotExtendYieldType {})
+                                        .report_types(r, TypeReportHint::FuncArgs)
+                                        .done()?;
+                                yields.unspan()
+                            }
+                        }
+                    } else {
+                        seq.unspan()
+                    };
+
+                    let args = coroutine.args.clone();
+                    self.env.get_frame_mut().coroutine =
+                        Some(CoroutineFrame { args: coroutine.args, yields: Some(yields) });
+                    return Ok(exit.with(SlotSeq::from_seq(args)));
+                }
+            }
+
+            // string.find(s, pattern, init, plain), string.match(s, pattern, init),
+            // string.gmatch(s, pattern)
+            Some(tag @ Tag::StringFind) | Some(tag @ Tag::StringMatch) |
+            Some(tag @ Tag::StringGMatch) => {
+                // `s:find(pattern)` does not have `s` in the arguments yet
+                let patidx = if selfinfo.is_some() { 0 } else { 1 };
+                refined_returns = self.pattern_returns(tag, &argtys, patidx)?;
+            }
+
             // class([parent])
             Some(Tag::MakeClass(system)) => {
                 if let Some(cid) = self.context().make_class(system, argtys, expspan)? {
@@ -2340,6 +3486,40 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 }
             }
 
+            // obj:isInstanceOf(cls), cls:isSubclassOf(parent) and so on
+            Some(tag @ Tag::IsInstanceOf) | Some(tag @ Tag::IsSubclassOf) => {
+                // `obj:isInstanceOf(cls)` does not have `obj` in the arguments yet
+                let clsidx = if selfinfo.is_some() { 0 } else { 1 };
+                let cls = self.env.resolve_exact_type(&argtys.ensure_at(clsidx).unlift());
+                if let Some(cls) = cls {
+                    if let (Nil::Silent, &T::Class(Class::Prototype(cid))) = (cls.nil(), &*cls) {
+                        self.tested_class = Some(if tag == Tag::IsInstanceOf {
+                            Class::Instance(cid)
+                        } else {
+                            Class::Prototype(cid)
+                        });
+                    }
+                }
+            }
+
+            // cls:include(mixin, ...)
+            Some(Tag::IncludeMixin) => {
+                // `cls:include(mixin)` does not have `cls` in the arguments yet
+                let (cls, mixins) = if let Some(ref selfinfo) = selfinfo {
+                    (Some(selfinfo), &argtys.head[..])
+                } else {
+                    (argtys.head.first(), argtys.head.get(1..).unwrap_or(&[]))
+                };
+                let cls = cls.and_then(|cls| self.env.resolve_exact_type(&cls.unlift()));
+                if let Some(cls) = cls {
+                    if let (Nil::Silent, &T::Class(Class::Prototype(cid))) = (cls.nil(), &*cls) {
+                        for mixin in mixins {
+                            self.context().include_class_mixin(cid, mixin, expspan)?;
+                        }
+                    }
+                }
+            }
+
             // kailua_test.gen_tvar()
             Some(Tag::KailuaGenTvar) => {
                 return Ok(exit.with(SlotSeq::from(T::TVar(self.types().gen_tvar()))));
@@ -2374,14 +3554,186 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             false
         };
 
+        // string.format(fmt, ...) and functions sharing its signature
+        if functy.tag() == Some(Tag::StringFormat) {
+            self.check_format_args(&functy, &argtys, expspan)?;
+        }
+
         let Exitable(retexit, returns) =
             self.check_callable(&functy, &argtys.unlift(), methodcall)?;
+        let returns = refined_returns.unwrap_or(returns);
 
         // merge exits; do not use `ExprExit::then` as this is the only way to generate Stop.
         // TODO this should be Var instead of Just!!!!!
         Ok(Exitable(cmp::max(exit, retexit), SlotSeq::from_seq(returns)))
     }
 
+    // returns the refined return types of string functions with the `StringFind`,
+    // `StringMatch` or `StringGMatch` tag, if the pattern is known
+    fn pattern_returns(&mut self, tag: Tag, argtys: &SpannedSlotSeq,
+                       patidx: usize) -> Result<Option<TySeq>> {
+        let pat = match argtys.head.get(patidx) {
+            Some(pat) => pat,
+            None => return Ok(None),
+        };
+        let patstr = self.env.resolve_exact_type(&pat.unlift())
+                             .and_then(|t| t.as_string().map(|s| s.to_owned()));
+        let patstr = if let Some(patstr) = patstr { patstr } else { return Ok(None); };
+
+        // `string.find` with a truthy fourth argument does not use the pattern at all
+        if tag == Tag::StringFind {
+            let plain = match argtys.head.get(patidx + 2) {
+                Some(plain) => self.env.resolve_exact_type(&plain.unlift()).map(|t| {
+                    if t.is_truthy() {
+                        Some(true)
+                    } else if t.is_falsy() || t.flags() == T_NONE {
+                        Some(false)
+                    } else {
+                        None
+                    }
+                }).unwrap_or(None),
+                None => Some(false),
+            };
+            match plain {
+                Some(true) => {
+                    // nil is returned when the string is not found
+                    let head = vec![Ty::new(T::Integer).or_nil(Nil::Noisy), Ty::new(T::Integer)];
+                    return Ok(Some(TySeq { head: head, tail: None }));
+                }
+                Some(false) => {}
+                None => return Ok(None),
+            }
+        }
+
+        let captures = match strlib::parse_pattern(&patstr) {
+            Ok(captures) => captures,
+            Err(e) => {
+                let r = match e {
+                    PatternError::EndsWithPercent =>
+                        self.env.warn(pat, m::PatternEndsWithPercent {}),
+                    PatternError::MissingBracket =>
+                        self.env.warn(pat, m::PatternMissingBracket {}),
+                    PatternError::MissingBalanceArgs =>
+                        self.env.warn(pat, m::PatternMissingBalanceArgs {}),
+                    PatternError::MissingFrontierSet =>
+                        self.env.warn(pat, m::PatternMissingFrontierSet {}),
+                    PatternError::InvalidCaptureIndex(index) =>
+                        self.env.warn(pat, m::PatternInvalidCaptureIndex { index: index }),
+                    PatternError::InvalidCapture =>
+                        self.env.warn(pat, m::PatternInvalidCapture {}),
+                    PatternError::UnfinishedCapture =>
+                        self.env.warn(pat, m::PatternUnfinishedCapture {}),
+                    PatternError::TooManyCaptures =>
+                        self.env.warn(pat, m::PatternTooManyCaptures {}),
+                };
+                r.done()?;
+                return Ok(None);
+            }
+        };
+
+        let mut head: Vec<Ty> = captures.iter().map(|cap| match *cap {
+            Capture::String => Ty::new(T::String),
+            Capture::Position => Ty::new(T::Integer),
+        }).collect();
+        match tag {
+            Tag::StringFind => {
+                head.insert(0, Ty::new(T::Integer));
+                head.insert(0, Ty::new(T::Integer));
+            }
+            Tag::StringMatch => {
+                // the entire match is returned when there are no captures
+                if head.is_empty() {
+                    head.push(Ty::new(T::String));
+                }
+            }
+            Tag::StringGMatch => {
+                if head.is_empty() {
+                    head.push(Ty::new(T::String));
+                }
+                // the iterator ignores arguments (given by `for`) and returns nil at the end
+                head[0] = head[0].clone().or_nil(Nil::Noisy);
+                let args = TySeq { head: Vec::new(), tail: Some(Ty::new(T::All)) };
+                let returns = TySeq { head: head, tail: None };
+                let iter = Function { tparams: Vec::new(), args: args,
+                                      argnames: Vec::new(), returns: Some(returns) };
+                return Ok(Some(TySeq { head: vec![Ty::new(T::func(iter))], tail: None }));
+            }
+            _ => unreachable!(),
+        }
+        // `string.find` and `string.match` return nil when the pattern doesn't match
+        head[0] = head[0].clone().or_nil(Nil::Noisy);
+        Ok(Some(TySeq { head: head, tail: None }))
+    }
+
+    // checks arguments to functions with the `StringFormat` tag against the format string.
+    // `argtys` should include `self` for method calls.
+    fn check_format_args(&mut self, functy: &Ty, argtys: &SpannedSlotSeq,
+                         expspan: Span) -> Result<()> {
+        // the format string is the first argument declared as a `string`
+        let fmtidx = match functy.get_functions() {
+            Some(&Functions::Simple(ref f)) => {
+                f.args.head.iter().position(|t| if let T::String = **t { true } else { false })
+            }
+            _ => None,
+        };
+        let fmt = match fmtidx.and_then(|i| argtys.head.get(i)) {
+            Some(fmt) => fmt,
+            None => return Ok(()),
+        };
+        let fmtstr = self.env.resolve_exact_type(&fmt.unlift())
+                             .and_then(|t| t.as_string().map(|s| s.to_owned()));
+        let fmtstr = if let Some(fmtstr) = fmtstr { fmtstr } else { return Ok(()); };
+
+        let specs = match strlib::parse_format(&fmtstr) {
+            Ok(specs) => specs,
+            Err(e) => {
+                let r = match e {
+                    FormatError::RepeatedFlags(ref spec) =>
+                        self.env.error(fmt, m::FormatRepeatedFlags { spec: spec }),
+                    FormatError::WidthTooLong(ref spec) =>
+                        self.env.error(fmt, m::FormatWidthTooLong { spec: spec }),
+                    FormatError::InvalidConversion(ref spec) =>
+                        self.env.error(fmt, m::FormatInvalidConversion { spec: spec }),
+                };
+                r.done()?;
+                return Ok(());
+            }
+        };
+
+        let args = &argtys.head[fmtidx.unwrap() + 1..];
+        for (spec, arg) in specs.iter().zip(args.iter()) {
+            match spec.arg {
+                FormatArg::Number => {
+                    if let Err(r) = arg.assert_sub(&T::Number, self.types()) {
+                        self.env.error(arg, m::FormatArgNotNumber { spec: &spec.spec })
+                                .report_types(r, TypeReportHint::None)
+                                .done()?;
+                    }
+                }
+                FormatArg::String => {
+                    if let Err(r) = arg.assert_sub(&T::String, self.types()) {
+                        self.env.error(arg, m::FormatArgNotString { spec: &spec.spec })
+                                .report_types(r, TypeReportHint::None)
+                                .done()?;
+                    }
+                }
+                FormatArg::Any => {}
+            }
+        }
+
+        // variadic arguments may supply any number of remaining values
+        if argtys.tail.is_none() {
+            if let Some(spec) = specs.get(args.len()) {
+                self.env.error(expspan, m::FormatMissingArg { spec: &spec.spec })
+                        .note(fmt, m::FormatStringOrigin {})
+                        .done()?;
+            } else if let Some(arg) = args.get(specs.len()) {
+                self.env.warn(arg, m::FormatExtraArgs { count: specs.len() }).done()?;
+            }
+        }
+        Ok(())
+    }
+
     fn visit_table(&mut self, tab: &'inp Table, tabspan: Span,
                    hint: Option<Spanned<Slot>>) -> Result<Exitable<T<'static>>> {
         // the finally resolved type depends on the hint type
@@ -2410,6 +3762,8 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                         Some(Target::Map(k, v))
                     },
                     Some(&Tables::Fields(_)) => Some(Target::Fields(true, Vec::new())),
+                    // the resulting record will be checked against the interface later
+                    Some(&Tables::Interface(_)) => None,
                     None => None,
                 }
             })
@@ -2601,6 +3955,14 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         Ok((exit.to_stmt(exp.span, self.env)?, base))
     }
 
+    // similar to visit_exp_from_stmt but also collects conditions for narrowing
+    fn visit_cond_from_stmt(&mut self, exp: &'inp Spanned<Exp>)
+        -> Result<(Exit, Option<Cond>, SpannedSlotSeq)>
+    {
+        let Exitable(exit, (cond, seq)) = self.collect_conds_from_exp(exp)?;
+        Ok((exit.to_stmt(exp.span, self.env)?, cond, seq))
+    }
+
     // hint is used to drive the inference to the already known type.
     // this is mainly used for anonymous functions.
     fn visit_exp(&mut self, exp: &'inp Spanned<Exp>, hint: Option<SpannedSlotSeq>)
@@ -2641,6 +4003,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                     Exitable::new(SlotSeq::from(T::Integer))
                 },
             Ex::Num(_) => Exitable::new(SlotSeq::from(T::Number)),
+            Ex::SuffixedNum(..) => Exitable::new(SlotSeq::from(cdata_ty())),
             Ex::Str(ref s) => {
                 let str = Str::from(s[..].to_owned());
                 Exitable::new(SlotSeq::from(T::Str(Cow::Owned(str))))
@@ -2670,9 +4033,9 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
             },
             Ex::Func(ref sig, _scope, ref block) => {
                 let hint = hint.map(|seq| seq.into_first());
-                let (tag, no_check) = self.visit_sig_attrs(&sig.attrs)?;
-                let returns = self.visit_func_body(tag, no_check, None, sig, block,
-                                                   exp.span, hint)?;
+                let (tag, modifiers, no_check) = self.visit_sig_attrs(&sig.attrs)?;
+                let returns = self.visit_func_body(tag, modifiers, no_check, None, sig, block,
+                                                   exp.span, None, hint)?;
                 Exitable::new(SlotSeq::from(returns))
             },
             Ex::Table(ref tab) => {
@@ -2729,6 +4092,12 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 exit.with(SlotSeq::from(info))
             },
 
+            // `and` and `or` may conditionally diverge, which is handled with conditions
+            Ex::Bin(_, op, _) if op.base == BinOp::And || op.base == BinOp::Or => {
+                let Exitable(exit, (_, seq)) = self.collect_conds_from_exp(exp)?;
+                exit.with(seq.unspan())
+            },
+
             Ex::Bin(ref l, op, ref r) => {
                 let Exitable(exit1, lhs) = self.visit_exp(l, None)?;
                 let Exitable(exit2, rhs) = self.visit_exp(r, None)?;
@@ -2798,11 +4167,45 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         // and expand its result to the final sequence
         let Exitable(exit, last) = self.visit_exp(lastexp, hint)?;
         head.extend(last.head.into_iter());
-        exprexit = exprexit.collide(exit);
+        // a sole call to the diverging function (e.g. `return error(...)`) evaluates everything
+        let sole_call = exps.is_empty() && match *lastexp.base {
+            Ex::FuncCall(..) | Ex::MethodCall(..) => true,
+            _ => false,
+        };
+        exprexit = if sole_call { exit } else { exprexit.collide(exit) };
 
         Ok(exprexit.with(SpannedSlotSeq { head: head, tail: last.tail, span: expspan }))
     }
 
+    // a to-be-closed variable should be either nil, false or have a `__close` metamethod.
+    // only tables and userdata can have a per-value metatable, and the metamethod is
+    // looked up from the metatable known to the checker (see `get_metatable_field`).
+    fn check_closable<X>(&mut self, name: &Spanned<X>,
+                         info: Option<&Spanned<Slot>>) -> Result<()> {
+        let info = if let Some(info) = info { info } else { return Ok(()); }; // nil
+        if info.get_tvar().is_some() {
+            return Ok(()); // not yet known, do not risk the instantiation
+        }
+        let flags = self.env.get_type_bounds(&info.unlift()).1;
+        if flags.is_dynamic() || T_FALSY.contains(flags) {
+            return Ok(());
+        }
+
+        // tables and userdata should have a known `__close` metamethod
+        if (T_FALSY | T_TABLE | T_USERDATA).contains(flags) {
+            if let Some(ty) = self.env.resolve_exact_type(&info.unlift()) {
+                if self.get_metatable_field(&ty, b"__close", info.span)?.is_some() {
+                    return Ok(());
+                }
+            }
+        }
+
+        self.env.error(name, m::NonClosableLocal { ty: self.display(info) })
+                .note(info, m::NonClosableLocalValue {})
+                .done()?;
+        Ok(())
+    }
+
     fn visit_kind(&mut self, modf: M, kind: &Spanned<Kind>) -> Result<Spanned<Slot>> {
         let ty = Ty::from_kind(kind, &mut self.env)?;
         Ok(Slot::new(F::from(modf), ty).with_loc(kind))
@@ -2875,6 +4278,34 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         Ok(module)
     }
 
+    fn class_system_rules_from_attrs(&mut self, mut rules: ClassSystemRules,
+                                     attrs: &[Spanned<Attr>]) -> Result<ClassSystemRules> {
+        for attr in attrs {
+            let rule = str::from_utf8(&attr.name.base).unwrap_or("");
+            let value = match attr.values {
+                Some(ref values) if values.len() == 1 => {
+                    let ast::AttrValue::Name(ref value) = values[0].base;
+                    str::from_utf8(&value.base).unwrap_or("")
+                }
+                _ => "",
+            };
+
+            match rules.set(rule, value) {
+                Ok(()) => {}
+                Err(ClassSystemRuleError::UnknownRule) => {
+                    self.env.warn(&attr.name, m::UnknownClassSystemRule { name: &attr.name })
+                            .done()?;
+                }
+                Err(ClassSystemRuleError::BadValue) => {
+                    self.env.error(attr, m::BadClassSystemRuleValue { name: &attr.name })
+                            .done()?;
+                }
+            }
+        }
+
+        Ok(rules)
+    }
+
     fn register_module_if_needed(&mut self, slot: &Slot) {
         if slot.flex() == F::Module {
             debug!("registering {:?} to the current scope", slot);
@@ -2885,9 +4316,10 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
     }
 
     fn collect_type_from_exp(&mut self, exp: &'inp Spanned<Exp>)
-            -> Result<(Option<Spanned<Slot>>, SpannedSlotSeq)> {
+            -> Result<Exitable<(Option<(Spanned<Slot>, Option<Spanned<NameRef>>)>,
+                                SpannedSlotSeq)>> {
         if let Ex::FuncCall(ref func, ref args) = *exp.base {
-            let Exitable(_, funcseq) = self.visit_exp(func, None)?;
+            let Exitable(exit, funcseq) = self.visit_exp(func, None)?;
             let funcspan = funcseq.all_span();
             let funcinfo = funcseq.into_first();
             let funcinfo = funcinfo.unlift();
@@ -2896,7 +4328,7 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 match args.base {
                     Args::List(ref args) if args.len() >= 1 => {
                         let Exitable(_, info) = self.visit_exp(&args[0], None)?;
-                        Some(info.into_first())
+                        Some((info.into_first(), narrowable_var(&args[0])))
                     },
                     Args::List(_) => {
                         self.env.error(exp, m::BuiltinGivenLessArgs { name: "type", nargs: 1 })
@@ -2905,94 +4337,119 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                     },
                     Args::Str(ref s) => {
                         let argstr = Str::from(s[..].to_owned());
-                        Some(Slot::just(Ty::new(T::Str(Cow::Owned(argstr)))).with_loc(args))
+                        Some((Slot::just(Ty::new(T::Str(Cow::Owned(argstr)))).with_loc(args),
+                              None))
                     },
                     Args::Table(ref tab) => {
                         let Exitable(_, table) = self.visit_table(tab, args.span, None)?;
-                        Some(Slot::just(Ty::new(table)).with_loc(args))
+                        Some((Slot::just(Ty::new(table)).with_loc(args), None))
                     },
                 }
             } else {
                 None
             };
-            let Exitable(_, seq) = self.visit_func_call(&funcinfo.clone().with_loc(funcspan),
-                                                        None, args, exp.span)?;
-            Ok((typeofexp, seq.all_with_loc(exp)))
+            let seq = self.visit_func_call(&funcinfo.clone().with_loc(funcspan),
+                                           None, args, exp.span)?;
+            Ok(exit.then(seq.map(|seq| (typeofexp, seq.all_with_loc(exp)))))
         } else {
-            let Exitable(_, seq) = self.visit_exp(exp, None)?;
-            Ok((None, seq))
+            let Exitable(exit, seq) = self.visit_exp(exp, None)?;
+            Ok(exit.with((None, seq)))
         }
     }
 
     // similar to visit_exp but also tries to collect Cond
     fn collect_conds_from_exp(&mut self, exp: &'inp Spanned<Exp>)
-            -> Result<(Option<Cond>, SpannedSlotSeq)> {
+            -> Result<Exitable<(Option<Cond>, SpannedSlotSeq)>> {
         debug!("collecting conditions from exp {:?}", *exp);
 
         match *exp.base {
             Ex::Un(Spanned { base: UnOp::Not, .. }, ref e) => {
-                let (cond, seq) = self.collect_conds_from_exp(e)?;
-                let cond = match cond {
-                    Some(Cond::Not(cond)) => match *cond {
-                        Cond::Not(cond) => Some(*cond),
-                        cond => Some(Cond::Not(Box::new(cond))),
-                    },
-                    Some(Cond::Flags(info, flags)) => Some(Cond::Flags(info, !flags)),
-                    Some(cond) => Some(Cond::Not(Box::new(cond))),
-                    None => None,
-                };
+                let Exitable(exit, (cond, seq)) = self.collect_conds_from_exp(e)?;
+                let cond = cond.map(|cond| cond.negate());
                 let info = seq.into_first();
                 let info = self.check_un_op(UnOp::Not, &info, exp.span)?;
-                Ok((cond, SpannedSlotSeq::from(info.with_loc(exp))))
+                Ok(exit.with((cond, SpannedSlotSeq::from(info.with_loc(exp)))))
             }
 
-            Ex::Bin(ref l, Spanned { base: BinOp::Eq, .. }, ref r) => {
-                let (lty, linfo) = self.collect_type_from_exp(l)?;
-                let (rty, rinfo) = self.collect_type_from_exp(r)?;
+            Ex::Bin(ref l, op, ref r) if op.base == BinOp::Eq || op.base == BinOp::Ne => {
+                let Exitable(lexit, (lty, linfo)) = self.collect_type_from_exp(l)?;
+                let Exitable(rexit, (rty, rinfo)) = self.collect_type_from_exp(r)?;
 
                 let linfo = linfo.into_first();
                 let rinfo = rinfo.into_first();
 
-                // detect an expression of the form `type(x) == y`.
+                // detect an expression of the form `type(x) == y`, `x == nil` or `x == "lit"`.
                 // it is technically possible to detect `type(x) == type(y)` as well,
                 // but it is not common and results in a very subtle semi-equivalence condition
                 // that we cannot readily handle.
                 let cond = match (lty, rty) {
-                    (Some(ty), None) => {
+                    (Some((ty, var)), None) => {
                         if let Some(flags) = self.literal_ty_to_flags(&rinfo)? {
-                            Some(Cond::Flags(ty, flags))
+                            Some(Cond::Flags(ty, var, flags))
                         } else {
                             None // the rhs is not a literal, so we don't what it is
                         }
                     },
-                    (None, Some(ty)) => {
+                    (None, Some((ty, var))) => {
                         if let Some(flags) = self.literal_ty_to_flags(&linfo)? {
-                            Some(Cond::Flags(ty, flags))
+                            Some(Cond::Flags(ty, var, flags))
                         } else {
                             None
                         }
                     },
+                    (None, None) => match (&*l.base, &*r.base) {
+                        (_, &Ex::Nil) => Some(Cond::Flags(linfo, narrowable_var(l), T_NOISY_NIL)),
+                        (&Ex::Nil, _) => Some(Cond::Flags(rinfo, narrowable_var(r), T_NOISY_NIL)),
+                        (_, _) => {
+                            let lstr = linfo.unlift().as_string().cloned();
+                            let rstr = rinfo.unlift().as_string().cloned();
+                            match (narrowable_var(l), rstr, narrowable_var(r), lstr) {
+                                (Some(var), Some(s), _, _) => {
+                                    self.check_str_member(&linfo, &rinfo, &s)?;
+                                    Some(Cond::Str(var, s))
+                                }
+                                (_, _, Some(var), Some(s)) => {
+                                    self.check_str_member(&rinfo, &linfo, &s)?;
+                                    Some(Cond::Str(var, s))
+                                }
+                                (_, _, _, _) => None,
+                            }
+                        }
+                    },
                     (_, _) => None,
                 };
 
+                let cond = if op.base == BinOp::Ne { cond.map(|cond| cond.negate()) } else { cond };
+
                 // TODO when cond is None try to assert the type equivalence;
                 // it is currently not implemented due to bad interaction with sub-literal types
-                Ok((cond, SpannedSlotSeq::from(T::Boolean.with_loc(exp))))
+                let info = T::Boolean.with_loc(exp);
+                Ok(lexit.collide(rexit).with((cond, SpannedSlotSeq::from(info))))
             }
 
             Ex::Bin(ref l, Spanned { base: BinOp::And, .. }, ref r) => {
-                let (lcond, lseq) = self.collect_conds_from_exp(l)?;
-                let (rcond, rseq) = self.collect_conds_from_exp(r)?;
+                let Exitable(lexit, (lcond, lseq)) = self.collect_conds_from_exp(l)?;
+                let Exitable(rexit, (rcond, rseq)) = self.collect_conds_from_exp(r)?;
+
+                // `a and error(...)` only continues when `a` is falsy
+                if lexit == ExprExit::None && rexit != ExprExit::None {
+                    if let Some(ref lcond) = lcond {
+                        self.narrow_cond(lcond, true, None);
+                    }
+                    let info = Slot::just(lseq.into_first().unlift().clone().falsy());
+                    return Ok(lexit.with((lcond, SpannedSlotSeq::from(info.with_loc(exp)))));
+                }
 
                 let cond = match (lcond, rcond) {
                     (None, cond) | (cond, None) => cond,
-                    (Some(Cond::Flags(lty, lflags)), Some(Cond::Flags(rty, rflags))) => {
+                    (Some(Cond::Flags(lty, lvar, lflags)),
+                     Some(Cond::Flags(rty, rvar, rflags))) => {
                         let identical = &*lty.unlift() as *const _ == &*rty.unlift() as *const _;
                         if identical {
-                            Some(Cond::Flags(lty, lflags & rflags))
+                            Some(Cond::Flags(lty, lvar, lflags & rflags))
                         } else {
-                            Some(Cond::And(Box::new(Cond::Flags(lty, lflags)),
-                                           Box::new(Cond::Flags(rty, rflags))))
+                            Some(Cond::And(Box::new(Cond::Flags(lty, lvar, lflags)),
+                                           Box::new(Cond::Flags(rty, rvar, rflags))))
                         }
                     },
                     (Some(lcond), Some(rcond)) => {
@@ -3003,22 +4460,32 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 let linfo = lseq.into_first();
                 let rinfo = rseq.into_first();
                 let info = self.check_bin_op(&linfo, BinOp::And, &rinfo, exp.span)?;
-                Ok((cond, SpannedSlotSeq::from(info.with_loc(exp))))
+                Ok(lexit.collide(rexit).with((cond, SpannedSlotSeq::from(info.with_loc(exp)))))
             }
 
             Ex::Bin(ref l, Spanned { base: BinOp::Or, .. }, ref r) => {
-                let (lcond, lseq) = self.collect_conds_from_exp(l)?;
-                let (rcond, rseq) = self.collect_conds_from_exp(r)?;
+                let Exitable(lexit, (lcond, lseq)) = self.collect_conds_from_exp(l)?;
+                let Exitable(rexit, (rcond, rseq)) = self.collect_conds_from_exp(r)?;
+
+                // `a or error(...)` only continues when `a` is truthy
+                if lexit == ExprExit::None && rexit != ExprExit::None {
+                    if let Some(ref lcond) = lcond {
+                        self.narrow_cond(lcond, false, None);
+                    }
+                    let info = Slot::just(lseq.into_first().unlift().clone().truthy());
+                    return Ok(lexit.with((lcond, SpannedSlotSeq::from(info.with_loc(exp)))));
+                }
 
                 let cond = match (lcond, rcond) {
                     (None, cond) | (cond, None) => cond,
-                    (Some(Cond::Flags(lty, lflags)), Some(Cond::Flags(rty, rflags))) => {
+                    (Some(Cond::Flags(lty, lvar, lflags)),
+                     Some(Cond::Flags(rty, rvar, rflags))) => {
                         let identical = &*lty.unlift() as *const _ == &*rty.unlift() as *const _;
                         if identical {
-                            Some(Cond::Flags(lty, lflags | rflags))
+                            Some(Cond::Flags(lty, lvar, lflags | rflags))
                         } else {
-                            Some(Cond::Or(Box::new(Cond::Flags(lty, lflags)),
-                                          Box::new(Cond::Flags(rty, rflags))))
+                            Some(Cond::Or(Box::new(Cond::Flags(lty, lvar, lflags)),
+                                          Box::new(Cond::Flags(rty, rvar, rflags))))
                         }
                     },
                     (Some(lcond), Some(rcond)) => {
@@ -3029,15 +4496,26 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
                 let linfo = lseq.into_first();
                 let rinfo = rseq.into_first();
                 let info = self.check_bin_op(&linfo, BinOp::Or, &rinfo, exp.span)?;
-                Ok((cond, SpannedSlotSeq::from(info.with_loc(exp))))
+                Ok(lexit.collide(rexit).with((cond, SpannedSlotSeq::from(info.with_loc(exp)))))
             }
 
             _ => {
-                let Exitable(_, seq) = self.visit_exp(exp, None)?;
+                self.tested_class = None;
+                let Exitable(exit, seq) = self.visit_exp(exp, None)?;
                 let info = seq.into_first();
+
+                // the call to class test functions narrows the tested variable
+                if let Some(cls) = self.tested_class.take() {
+                    if let Some(var) = class_test_var(exp) {
+                        let cond = Cond::Class(var, cls);
+                        return Ok(exit.with((Some(cond), SpannedSlotSeq::from(info))));
+                    }
+                }
+
                 // XXX should detect non-local slots and reject them!
                 // probably we can do that via proper weakening, but who knows.
-                Ok((Some(Cond::Flags(info.clone(), T_TRUTHY)), SpannedSlotSeq::from(info)))
+                let cond = Cond::Flags(info.clone(), narrowable_var(exp), T_TRUTHY);
+                Ok(exit.with((Some(cond), SpannedSlotSeq::from(info))))
             }
         }
     }
@@ -3046,13 +4524,21 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         debug!("asserting condition {:?} (negated {:?})", cond, negated);
 
         match cond {
-            Cond::Flags(info, flags) => {
+            Cond::Flags(info, _, flags) => {
                 let flags = if negated { !flags } else { flags };
                 // XXX this is temporary, the entire condition assertion should be changed!
                 info.filter_by_flags(flags, self.types()).map_err(|_| kailua_diag::Stop)?;
                 debug!("resulted in {:?}", info);
             }
 
+            // string comparisons are only used for narrowing
+            Cond::Str(..) => {}
+
+            // class tests have no slot to filter, so the variable is narrowed instead
+            Cond::Class(..) => {
+                self.narrow_cond(&cond, negated, None);
+            }
+
             Cond::And(lcond, rcond) => {
                 if !negated {
                     self.assert_cond(*lcond, negated)?;
@@ -3075,6 +4561,142 @@ impl<'inp, 'envr, 'env, R: Report> Checker<'inp, 'envr, 'env, R> {
         Ok(())
     }
 
+    // narrows local variables in the condition until the current scope ends.
+    // unlike `assert_cond` this never fails; variables that cannot satisfy the condition
+    // (i.e. the branch is never taken) or are assigned in `assigned_in` are left as is.
+    fn narrow_cond(&mut self, cond: &Cond, negated: bool, assigned_in: Option<&Block>) {
+        debug!("narrowing by condition {:?} (negated {:?})", cond, negated);
+
+        match *cond {
+            Cond::Flags(_, Some(ref var), flags) => {
+                if assigned_in.map_or(false, |block| block_assigns_to(block, &var.base)) {
+                    return;
+                }
+
+                // the variable may have been narrowed by other parts of the condition
+                let slot = match self.env.get_var(&var.base) {
+                    Some(&NameDef { slot: NameSlot::Set(ref slot), .. }) => slot.clone(),
+                    _ => return,
+                };
+
+                let flags = if negated { !flags } else { flags };
+                let narrowed = Slot::new(slot.flex(), slot.unlift().clone());
+                if narrowed.filter_by_flags(flags, self.types()).is_ok() {
+                    self.env.narrow_var(var, narrowed);
+                }
+            }
+
+            Cond::Flags(_, None, _) => {}
+
+            Cond::Str(ref var, ref s) => {
+                if assigned_in.map_or(false, |block| block_assigns_to(block, &var.base)) {
+                    return;
+                }
+
+                let slot = match self.env.get_var(&var.base) {
+                    Some(&NameDef { slot: NameSlot::Set(ref slot), .. }) => slot.clone(),
+                    _ => return,
+                };
+
+                let ty = slot.unlift().clone();
+                if let Some(t) = ty.narrow_str(s, !negated) {
+                    // a value equal to the string cannot be nil
+                    let nil = if negated { ty.nil() } else { Nil::Silent };
+                    let ty = Ty::new(t).union_nil(nil).with_tag(ty.tag());
+                    self.env.narrow_var(var, Slot::new(slot.flex(), ty));
+                }
+            }
+
+            Cond::Class(ref var, cls) => {
+                // a failed class test says nothing about the variable
+                if negated {
+                    return;
+                }
+                if assigned_in.map_or(false, |block| block_assigns_to(block, &var.base)) {
+                    return;
+                }
+
+                let slot = match self.env.get_var(&var.base) {
+                    Some(&NameDef { slot: NameSlot::Set(ref slot), .. }) => slot.clone(),
+                    _ => return,
+                };
+
+                // only narrows to the class that is compatible to the current type
+                let ty = Ty::new(T::Class(cls));
+                if ty.assert_sub(&*slot.unlift(), self.types()).is_ok() {
+                    self.env.narrow_var(var, Slot::new(slot.flex(), ty));
+                }
+            }
+
+            Cond::And(ref lcond, ref rcond) => {
+                if !negated {
+                    self.narrow_cond(lcond, negated, assigned_in);
+                    self.narrow_cond(rcond, negated, assigned_in);
+                }
+            }
+
+            Cond::Or(ref lcond, ref rcond) => {
+                if negated {
+                    self.narrow_cond(lcond, negated, assigned_in);
+                    self.narrow_cond(rcond, negated, assigned_in);
+                }
+            }
+
+            Cond::Not(ref cond) => {
+                self.narrow_cond(cond, !negated, assigned_in);
+            }
+        }
+    }
+
+    // warns if a variable consisting of string literals (e.g. enums) is compared against
+    // a string literal not in that type, which is most likely a typo.
+    fn check_str_member(&self, info: &Spanned<Slot>, strinfo: &Spanned<Slot>,
+                        s: &Str) -> Result<()> {
+        if !info.unlift().may_equal_str(s) {
+            self.env.warn(strinfo, m::StrNotInType { s: s, ty: self.display(info) })
+                    .note(info, m::OtherTypeOrigin {})
+                    .done()?;
+        }
+        Ok(())
+    }
+
+    // records local variables with the `exhaustive` tag compared in the condition,
+    // along with their slots before any narrowing by the current `if` statement.
+    fn collect_enum_vars(&self, cond: &Cond, enumvars: &mut Vec<(Spanned<NameRef>, Slot)>) {
+        let mut vars = Vec::new();
+        cond.collect_str_vars(&mut vars);
+        for var in vars {
+            if enumvars.iter().any(|&(ref v, _)| v.base == var.base) {
+                continue;
+            }
+            if let Some(&NameDef { slot: NameSlot::Set(ref slot), .. }) = self.env.get_var(&var.base) {
+                if slot.unlift().tag() == Some(Tag::Exhaustive) {
+                    enumvars.push((var.clone(), slot.clone()));
+                }
+            }
+        }
+    }
+
+    // warns if any string literal of enum variables is left unhandled
+    // after all conditions of the `if` statement without `else` are known to be false.
+    fn check_exhaustive_if(&self, span: Span,
+                           enumvars: &[(Spanned<NameRef>, Slot)]) -> Result<()> {
+        for &(ref var, ref origslot) in enumvars {
+            let slot = match self.env.get_var(&var.base) {
+                Some(&NameDef { slot: NameSlot::Set(ref slot), .. }) => slot.clone(),
+                _ => continue,
+            };
+            let ty = slot.unlift();
+            if ty.flags().intersects(T_STRING) {
+                self.env.warn(span, m::NonExhaustiveIf { ty: self.display(origslot),
+                                                         missing: self.display(&**ty) })
+                        .note(var, m::NonExhaustiveIfVar {})
+                        .done()?;
+            }
+        }
+        Ok(())
+    }
+
     fn literal_ty_to_flags(&self, info: &Spanned<Slot>) -> Result<Option<Flags>> {
         if let Some(s) = info.unlift().as_string() {
             let tyname = &s[..];
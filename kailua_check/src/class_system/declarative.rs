@@ -0,0 +1,484 @@
+// notes on declarative class systems:
+//
+// many in-house class systems are variations of the Gideros class system, only differing in
+// the method names and small details. the declarative class system is parametrized by
+// `ClassSystemRules` given by the user (see `options` for the list of rules),
+// and otherwise works like the Gideros class system:
+//
+// - the constructor (`new` by default) is derived from the init method (`init` by default)
+//   and is called through the class (`Foo.new(...)`). the init method is required,
+//   cannot be accessed through instances and is exempted from subtyping constraints.
+// - fields assigned to the class are shared with instances if `prototype_fields` is `visible`.
+//   class fields starting with the static prefix are never visible through instances.
+// - fields assigned to instances are shared with parent classes, so that instance fields
+//   created through the parent class type are accessible through the child class type.
+// - fields starting with the private prefix are private (as if they have `[private]`).
+// - overriding class fields requires the strict subtyping against every parent,
+//   except for abstract methods which can be freely overriden. the constructor reports
+//   every abstract method left unimplemented.
+//
+// unlike Gideros there is no genesis class, so a class can freely have no parent.
+// if the multiple inheritance is allowed every argument to the `[make_class]` function is
+// a parent class, and fields are looked up in the method resolution order (see `linearize`).
+// for simple hierarchies this is same to the depth-first, left-to-right order of
+// most naive implementations of the multiple inheritance in Lua.
+
+use std::fmt;
+use std::collections::{HashSet, HashMap};
+use vec_map::{self, VecMap};
+use parking_lot::RwLock;
+
+use kailua_env::{Span, Spanned, WithLoc};
+use kailua_diag::{self, Report, Reporter};
+use kailua_syntax::{Str, Name};
+use kailua_types::ty::{TypeContext, ClassSystemId, ClassId, Class, Display, DisplayState, Lattice};
+use kailua_types::ty::{Slot, SpannedSlotSeq, Key, T, Ty, TySeq, F, Functions, Function, Nil};
+use kailua_types::diag::{TypeReportMore, TypeReportHint};
+use message as m;
+use options::{ClassSystemRules, Inheritance};
+use super::{ClassSystem, Visibility};
+
+#[derive(Clone, Debug)]
+struct ClassDef {
+    parents: Vec<usize>,
+    mro: Vec<usize>, // the class itself and all ancestors in the lookup order
+    new_ty: Option<Slot>, // a dummy slot after the first error on the constructor typing
+    class_fields: HashMap<Key, Spanned<Slot>>,
+    instance_fields: HashMap<Key, Spanned<Slot>>,
+}
+
+impl ClassDef {
+    fn fields(&self, proto: bool) -> &HashMap<Key, Spanned<Slot>> {
+        if proto { &self.class_fields } else { &self.instance_fields }
+    }
+
+    fn fields_mut(&mut self, proto: bool) -> &mut HashMap<Key, Spanned<Slot>> {
+        if proto { &mut self.class_fields } else { &mut self.instance_fields }
+    }
+}
+
+/// A class system defined from the user-given `ClassSystemRules`.
+#[derive(Debug)]
+pub struct DeclarativeClassSystem {
+    rules: ClassSystemRules,
+
+    classes: RwLock<Vec<ClassDef>>,
+
+    // frequently read even when classes are locked, so has to be separated
+    class_names: RwLock<VecMap<Spanned<Name>>>,
+}
+
+fn is_key(key: &Key, name: &str) -> bool {
+    match *key {
+        Key::Str(ref s) => &s[..] == name.as_bytes(),
+        _ => false,
+    }
+}
+
+impl DeclarativeClassSystem {
+    pub fn new(rules: ClassSystemRules) -> DeclarativeClassSystem {
+        DeclarativeClassSystem {
+            rules: rules,
+            classes: RwLock::new(Vec::new()),
+            class_names: RwLock::new(VecMap::new()),
+        }
+    }
+
+    fn is_static_key(&self, key: &Key) -> bool {
+        match (key, &self.rules.static_prefix) {
+            (&Key::Str(ref s), &Some(ref prefix)) => s.starts_with(prefix.as_bytes()),
+            (_, _) => false,
+        }
+    }
+
+    fn lookup<'a>(classes: &'a [ClassDef], idx: usize, proto: bool,
+                  key: &Key) -> Option<&'a Spanned<Slot>> {
+        classes[idx].mro.iter().filter_map(|&c| classes[c].fields(proto).get(key)).next()
+    }
+
+    fn define_class(&self, self_csid: ClassSystemId, parents: Vec<Spanned<ClassId>>,
+                    outerspan: Span, report: &Report) -> kailua_diag::Result<Option<ClassId>> {
+        let parents = match self.rules.inheritance {
+            Inheritance::Single => super::single_parent(parents, report)?.into_iter().collect(),
+            Inheritance::Multiple => parents,
+        };
+
+        let mut classes = self.classes.write();
+
+        let mut parentidxs = Vec::new();
+        for parent in parents {
+            if parent.0 == self_csid {
+                assert!((parent.1 as usize) < classes.len(), "invalid ClassId");
+                parentidxs.push((parent.1 as usize).with_loc(parent.span));
+            } else {
+                report.error(parent.span, m::ClassInheritFromDifferentClassSystem {}).done()?;
+            }
+        }
+
+        let idx = classes.len();
+        let mro = super::check_parents(idx, &parentidxs, outerspan,
+                                       |c| classes[c].mro.clone(), report)?;
+
+        let cid = ClassId(self_csid, idx as u32);
+        classes.push(ClassDef {
+            parents: parentidxs.into_iter().map(|p| p.base).collect(),
+            mro: mro,
+            new_ty: None,
+            class_fields: HashMap::new(),
+            instance_fields: HashMap::new(),
+        });
+        Ok(Some(cid))
+    }
+
+    fn new_method(&self, classes: &mut [ClassDef], cid: ClassId, key: &Spanned<&Key>,
+                  ctx: &mut TypeContext, report: &Report) -> kailua_diag::Result<Slot> {
+        let idx = cid.1 as usize;
+        if let Some(ref new) = classes[idx].new_ty {
+            return Ok(new.clone());
+        }
+
+        let init_key = Key::from(Str::from(self.rules.init.as_bytes()));
+        let init = Self::lookup(classes, idx, true, &init_key);
+        let slot = if let Some(init) = init.cloned() {
+            let slot = self.new_method_from_init(classes, cid, &init, ctx, report)?;
+            trace!("created a new method {:?} for {:?} from the constructor {:?}",
+                   slot, cid, init);
+            slot
+        } else {
+            report.error(key, m::NoCtor { new: &self.rules.constructor, init: &self.rules.init })
+                  .done()?;
+            Slot::dummy()
+        };
+
+        classes[idx].new_ty = Some(slot.clone());
+        Ok(slot)
+    }
+
+    fn check_abstract_methods(classes: &[ClassDef], cid: ClassId, expspan: Span,
+                              ctx: &mut TypeContext, report: &Report) -> kailua_diag::Result<()> {
+        let mut seen = HashSet::new();
+        let mut fields = Vec::new();
+        for &c in &classes[cid.1 as usize].mro {
+            for (key, slot) in &classes[c].class_fields {
+                if seen.insert(key) {
+                    fields.push((key, slot));
+                }
+            }
+        }
+        super::check_abstract_methods(fields, expspan, ctx, report)
+    }
+
+    fn new_method_from_init(&self, classes: &[ClassDef], cid: ClassId, init: &Spanned<Slot>,
+                            ctx: &mut TypeContext, report: &Report) -> kailua_diag::Result<Slot> {
+        let name = &self.rules.init[..];
+
+        // ensure that the type can be resolved...
+        let ty = if let Some(ty) = ctx.resolve_exact_type(&init.unlift()) {
+            ty
+        } else {
+            report.error(init, m::InexactInitMethod { name: name, init: init.base.display(ctx) })
+                  .done()?;
+            return Ok(Slot::dummy());
+        };
+
+        // ...and is a function.
+        let mut func = match *ty {
+            T::Functions(ref func) => match **func {
+                Functions::Simple(ref f) => f.to_owned(),
+                _ => {
+                    report.error(init, m::OverloadedFuncInitMethod { name: name,
+                                                                     init: init.base.display(ctx) })
+                          .done()?;
+                    return Ok(Slot::dummy());
+                }
+            },
+            _ => {
+                report.error(init, m::NonFuncInitMethod { name: name,
+                                                          init: init.base.display(ctx) })
+                      .done()?;
+                return Ok(Slot::dummy());
+            },
+        };
+
+        // strip the first argument which should be a fresh class instance
+        let mut selfarg_ok = false;
+        if !func.args.head.is_empty() {
+            let selfarg = func.args.head.remove(0);
+            if let Some(selfarg) = ctx.resolve_exact_type(&selfarg) {
+                if selfarg.nil() == Nil::Silent {
+                    if let T::Class(Class::Instance(cid_)) = *selfarg {
+                        // the constructor can be shared for multiple classes,
+                        // so any parent class that can accept the current class is fine
+                        selfarg_ok = cid.0 == cid_.0 &&
+                                     classes[cid.1 as usize].mro.contains(&(cid_.1 as usize));
+                    }
+                }
+            }
+        }
+        if !func.argnames.is_empty() {
+            func.argnames.remove(0);
+        }
+        if !selfarg_ok {
+            report.error(init, m::BadSelfInInitMethod { name: name,
+                                                        init: init.base.display(ctx) })
+                  .done()?;
+            return Ok(Slot::dummy());
+        }
+
+        // now the init method is: function(/* removed self */, ...) -> any
+        // fix the return type to make a signature for the constructor
+        let returns = T::Class(Class::Instance(cid));
+        let ctor = Function { tparams: func.tparams, args: func.args, argnames: func.argnames,
+                              returns: Some(TySeq::from(returns)) };
+        Ok(Slot::new(F::Const, Ty::new(T::func(ctor))))
+    }
+}
+
+impl ClassSystem for DeclarativeClassSystem {
+    fn make_class(&self, self_csid: ClassSystemId, argtys: SpannedSlotSeq, outerspan: Span,
+                  ctx: &mut TypeContext, report: &Report) -> kailua_diag::Result<Option<ClassId>> {
+        let parents = match self.rules.inheritance {
+            Inheritance::Single => {
+                super::extract_parent(argtys, ctx, report)?.map(|p| p.into_iter().collect())
+            }
+            Inheritance::Multiple => super::extract_parents(argtys, ctx, report)?,
+        };
+
+        if let Some(parents) = parents {
+            self.define_class(self_csid, parents, outerspan, report)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn assume_class(&self, self_csid: ClassSystemId, parents: Vec<Spanned<ClassId>>,
+                    outerspan: Span, _ctx: &mut TypeContext,
+                    report: &Report) -> kailua_diag::Result<Option<ClassId>> {
+        self.define_class(self_csid, parents, outerspan, report)
+    }
+
+    fn name_class(&self, cid: ClassId, name: Spanned<Name>) -> Result<(), Spanned<Name>> {
+        let mut names = self.class_names.write();
+        match names.entry(cid.1 as usize) {
+            vec_map::Entry::Occupied(e) => Err(e.get().clone()),
+            vec_map::Entry::Vacant(e) => {
+                info!("named {:?} as {:?}", cid, name);
+                e.insert(name);
+                Ok(())
+            },
+        }
+    }
+
+    fn is_subclass_of(&self, lhs: ClassId, rhs: ClassId) -> bool {
+        if lhs.0 != rhs.0 {
+            return false;
+        }
+        if lhs == rhs {
+            return true;
+        }
+
+        let classes = self.classes.read();
+
+        let lhs = lhs.1 as usize;
+        let rhs = rhs.1 as usize;
+
+        assert!(lhs < classes.len(), "invalid ClassId for lhs");
+        assert!(rhs < classes.len(), "invalid ClassId for rhs");
+
+        classes[lhs].mro.contains(&rhs)
+    }
+
+    fn index_rval(&self, cls: Class, key: Spanned<&Key>, expspan: Span, ctx: &mut TypeContext,
+                  report: &Report) -> kailua_diag::Result<Option<Slot>> {
+        let (proto, cid) = match cls {
+            Class::Prototype(cid) => (true, cid),
+            Class::Instance(cid) => (false, cid),
+        };
+
+        if !proto && is_key(&key, &self.rules.init) {
+            report.error(&key, m::CannotAccessCtorThruInstance { init: &self.rules.init })
+                  .done()?;
+            return Ok(None);
+        }
+        if !proto && self.is_static_key(&key) {
+            report.error(&key, m::CannotAccessStaticThruInstance { key: &key }).done()?;
+            return Ok(None);
+        }
+
+        let mut classes = self.classes.write();
+        if is_key(&key, &self.rules.constructor) {
+            Self::check_abstract_methods(&classes, cid, expspan, ctx, report)?;
+            return self.new_method(&mut classes, cid, &key, ctx, report).map(Some);
+        }
+
+        let idx = cid.1 as usize;
+        if !proto {
+            if let Some(info) = Self::lookup(&classes, idx, false, &key) {
+                return Ok(Some(info.base.clone()));
+            }
+        }
+        if proto || self.rules.prototype_fields_visible {
+            if let Some(info) = Self::lookup(&classes, idx, true, &key) {
+                return Ok(Some(info.base.clone()));
+            }
+        }
+        Ok(None)
+    }
+
+    fn field_owner(&self, cls: Class, key: &Key) -> ClassId {
+        let (proto, cid) = match cls {
+            Class::Prototype(cid) => (true, cid),
+            Class::Instance(cid) => (false, cid),
+        };
+
+        let classes = self.classes.read();
+        let mro = &classes[cid.1 as usize].mro;
+        let find = |proto| {
+            mro.iter().cloned().find(|&c| classes[c].fields(proto).contains_key(key))
+        };
+        let owner = if proto { find(true) } else { find(false).or_else(|| find(true)) };
+        ClassId(cid.0, owner.map_or(cid.1, |c| c as u32))
+    }
+
+    fn field_visibility(&self, _cls: Class, key: &Key) -> Visibility {
+        match (key, &self.rules.private_prefix) {
+            (&Key::Str(ref s), &Some(ref prefix)) if s.starts_with(prefix.as_bytes()) => {
+                Visibility::Private
+            }
+            (_, _) => Visibility::Public,
+        }
+    }
+
+    fn index_lval(&self, cls: Class, key: Spanned<&Key>, expspan: Span,
+                  hint: Option<&Slot>, ctx: &mut TypeContext,
+                  report: &Report) -> kailua_diag::Result<Option<(bool, Slot)>> {
+        let (proto, cid) = match cls {
+            Class::Prototype(cid) => (true, cid),
+            Class::Instance(cid) => (false, cid),
+        };
+
+        // the constructor is only accessible as an r-value due to its dependency to init
+        if is_key(&key, &self.rules.constructor) {
+            report.error(&key, m::ReservedClassMember { key: &key }).done()?;
+            return Ok(None);
+        }
+
+        let is_init = is_key(&key, &self.rules.init);
+        if !proto && is_init {
+            report.error(&key, m::CannotAccessCtorThruInstance { init: &self.rules.init })
+                  .done()?;
+            return Ok(None);
+        }
+        if !proto && self.is_static_key(&key) {
+            report.error(&key, m::CannotAccessStaticThruInstance { key: &key }).done()?;
+            return Ok(None);
+        }
+
+        let mut classes = self.classes.write();
+        let idx = cid.1 as usize;
+
+        if proto {
+            if let Some(info) = classes[idx].class_fields.get(&key) {
+                // the field exists in the current class
+                return Ok(Some((false, info.base.clone())));
+            }
+        } else {
+            // instance fields are shared with parent classes, and fields shadowing class fields
+            // should be compatible to them. in either case no new field is created.
+            let mut existing = Self::lookup(&classes, idx, false, &key);
+            if existing.is_none() && self.rules.prototype_fields_visible {
+                existing = Self::lookup(&classes, idx, true, &key);
+            }
+            if let Some(info) = existing {
+                return Ok(Some((false, info.base.clone())));
+            }
+        }
+
+        // this type will be a type of the new field
+        let slot = if let Some(hint) = hint {
+            let slot = hint.clone();
+            slot.adapt(F::Var, ctx); // always adapt to Var
+            slot
+        } else {
+            let tvar = T::TVar(ctx.gen_tvar());
+            Slot::new(F::Unknown, Ty::new(tvar))
+        };
+
+        // overriding class fields in any parent requires the subtyping, except for
+        // the init method which is only called through the constructor and abstract methods
+        if proto && !is_init {
+            for &parent in &classes[idx].mro[1..] {
+                if let Some(parent_slot) = classes[parent].class_fields.get(&key) {
+                    if super::is_abstract(parent_slot, ctx) {
+                        continue;
+                    }
+                    if let Err(r) = slot.assert_sub(&parent_slot.base, ctx) {
+                        report.error(expspan,
+                                     m::NotSubtypeOfParentField {
+                                         key: &key, sub: slot.display(ctx),
+                                         sup: parent_slot.base.display(ctx),
+                                     })
+                              .note_if(parent_slot, m::PreviousParentFieldType {})
+                              .report_types(r, TypeReportHint::None)
+                              .done()?;
+                    }
+                }
+            }
+        }
+
+        let fields = classes[idx].fields_mut(proto);
+        fields.insert(key.base.clone(), slot.clone().with_loc(&key));
+
+        Ok(Some((true, slot)))
+    }
+
+    fn fmt_class(&self, cid: ClassId, f: &mut fmt::Formatter, st: &DisplayState) -> fmt::Result {
+        let names = self.class_names.read();
+        match (&st.locale[..], &names.get(cid.1 as usize)) {
+            (_,    &Some(ref name)) => write!(f, "{:+}", name),
+            ("ko", &None) => write!(f, "<이름 없는 클래스 #{}.{}>", (cid.0).0, cid.1),
+            (_,    &None) => write!(f, "<unnamed class #{}.{}>", (cid.0).0, cid.1),
+        }
+    }
+
+    fn list_fields(&self, cls: Class,
+                   f: &mut FnMut(&Key, &Slot) -> Result<(), ()>) -> Result<(), ()> {
+        let (proto, cid) = match cls {
+            Class::Prototype(cid) => (true, cid),
+            Class::Instance(cid) => (false, cid),
+        };
+
+        let classes = self.classes.read();
+        let mut seen = HashSet::new();
+        let mut list = |class_fields| {
+            for &c in &classes[cid.1 as usize].mro {
+                for (key, slot) in classes[c].fields(class_fields) {
+                    if !proto && class_fields && self.is_static_key(key) {
+                        continue;
+                    }
+                    if seen.insert(key) {
+                        f(key, slot)?;
+                    }
+                }
+            }
+            Ok(())
+        };
+
+        if !proto {
+            list(false)?;
+        }
+        if proto || self.rules.prototype_fields_visible {
+            list(true)?;
+        }
+        Ok(())
+    }
+
+    fn list_parents(&self, cid: ClassId,
+                    f: &mut FnMut(ClassId) -> Result<(), ()>) -> Result<(), ()> {
+        let self_csid = cid.0;
+        for &parent in &self.classes.read()[cid.1 as usize].parents {
+            f(ClassId(self_csid, parent as u32))?;
+        }
+        Ok(())
+    }
+}
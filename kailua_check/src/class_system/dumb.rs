@@ -2,35 +2,42 @@
 
 use std::fmt;
 use std::collections::{HashSet, HashMap};
-use std::collections::hash_map::Entry;
 use vec_map::{self, VecMap};
 use parking_lot::RwLock;
 
-use kailua_env::{Span, Spanned};
+use kailua_env::{Span, Spanned, WithLoc};
 use kailua_diag::{self, Report, Reporter};
 use kailua_syntax::Name;
-use kailua_types::ty::{TypeContext, ClassSystemId, ClassId, Class, DisplayState};
+use kailua_types::ty::{TypeContext, ClassSystemId, ClassId, Class, Display, DisplayState, Lattice};
 use kailua_types::ty::{Slot, Key, T, Ty, F};
+use kailua_types::diag::{TypeReportMore, TypeReportHint};
 use message as m;
 use super::ClassSystem;
 
 #[derive(Clone, Debug)]
 struct ClassDef {
-    class_fields: HashMap<Key, Slot>,
-    instance_fields: HashMap<Key, Slot>,
+    parents: Vec<u32>,
+    mro: Vec<u32>, // the class itself and all ancestors in the lookup order
+    class_fields: HashMap<Key, Spanned<Slot>>,
+    instance_fields: HashMap<Key, Spanned<Slot>>,
 }
 
 impl ClassDef {
-    fn fields(&self, proto: bool) -> &HashMap<Key, Slot> {
+    fn fields(&self, proto: bool) -> &HashMap<Key, Spanned<Slot>> {
         if proto { &self.class_fields } else { &self.instance_fields }
     }
 
-    fn fields_mut(&mut self, proto: bool) -> &mut HashMap<Key, Slot> {
+    fn fields_mut(&mut self, proto: bool) -> &mut HashMap<Key, Spanned<Slot>> {
         if proto { &mut self.class_fields } else { &mut self.instance_fields }
     }
 }
 
-/// A "dumb" class system which does not support any special methods or inheritance.
+/// A "dumb" class system which does not support any special methods.
+///
+/// This also serves as a reference implementation for the multiple inheritance:
+/// fields are looked up in the method resolution order of the class (see `linearize`),
+/// instance fields are shared with all ancestors, and class fields can be overriden
+/// only when the new type is a subtype of the types in all ancestors.
 #[derive(Debug)]
 pub struct DumbClassSystem {
     classes: RwLock<Vec<ClassDef>>,
@@ -46,19 +53,38 @@ impl DumbClassSystem {
             class_names: RwLock::new(VecMap::new()),
         }
     }
+
+    fn lookup<'a>(classes: &'a [ClassDef], cid: ClassId, proto: bool,
+                  key: &Key) -> Option<&'a Spanned<Slot>> {
+        let mro = &classes[cid.1 as usize].mro;
+        mro.iter().filter_map(|&c| classes[c as usize].fields(proto).get(key)).next()
+    }
 }
 
 impl ClassSystem for DumbClassSystem {
-    fn assume_class(&self, self_csid: ClassSystemId, parent: Option<Spanned<ClassId>>,
-                    _outerspan: Span, _ctx: &mut TypeContext,
+    fn assume_class(&self, self_csid: ClassSystemId, parents: Vec<Spanned<ClassId>>,
+                    outerspan: Span, _ctx: &mut TypeContext,
                     report: &Report) -> kailua_diag::Result<Option<ClassId>> {
-        if let Some(ref parent) = parent {
-            report.error(parent, m::NoInheritanceInDumbClassSystem {}).done()?;
+        let mut classes = self.classes.write();
+
+        let mut parentidxs = Vec::new();
+        for parent in parents {
+            if parent.0 == self_csid {
+                assert!((parent.1 as usize) < classes.len(), "invalid ClassId");
+                parentidxs.push(parent.1.with_loc(parent.span));
+            } else {
+                report.error(parent.span, m::ClassInheritFromDifferentClassSystem {}).done()?;
+            }
         }
 
-        let mut classes = self.classes.write();
-        let cid = ClassId(self_csid, classes.len() as u32);
+        let idx = classes.len() as u32;
+        let mro = super::check_parents(idx, &parentidxs, outerspan,
+                                       |c| classes[c as usize].mro.clone(), report)?;
+
+        let cid = ClassId(self_csid, idx);
         classes.push(ClassDef {
+            parents: parentidxs.into_iter().map(|p| p.base).collect(),
+            mro: mro,
             class_fields: HashMap::new(),
             instance_fields: HashMap::new(),
         });
@@ -78,7 +104,17 @@ impl ClassSystem for DumbClassSystem {
     }
 
     fn is_subclass_of(&self, lhs: ClassId, rhs: ClassId) -> bool {
-        lhs == rhs
+        if lhs.0 != rhs.0 {
+            return false;
+        }
+        if lhs == rhs {
+            return true;
+        }
+
+        let classes = self.classes.read();
+        assert!((lhs.1 as usize) < classes.len(), "invalid ClassId for lhs");
+        assert!((rhs.1 as usize) < classes.len(), "invalid ClassId for rhs");
+        classes[lhs.1 as usize].mro.contains(&rhs.1)
     }
 
     fn index_rval(&self, cls: Class, key: Spanned<&Key>, _expspan: Span,
@@ -89,43 +125,82 @@ impl ClassSystem for DumbClassSystem {
         };
 
         let classes = self.classes.read();
-        let cls = &classes[cid.1 as usize];
         if !proto {
-            if let Some(info) = cls.instance_fields.get(&key) {
-                return Ok(Some(info.clone()));
+            if let Some(info) = Self::lookup(&classes, cid, false, &key) {
+                return Ok(Some(info.base.clone()));
             }
         }
-        if let Some(info) = cls.class_fields.get(&key) {
-            return Ok(Some(info.clone()));
+        if let Some(info) = Self::lookup(&classes, cid, true, &key) {
+            return Ok(Some(info.base.clone()));
         }
         Ok(None)
     }
 
-    fn index_lval(&self, cls: Class, key: Spanned<&Key>, _expspan: Span,
+    fn field_owner(&self, cls: Class, key: &Key) -> ClassId {
+        let (proto, cid) = match cls {
+            Class::Prototype(cid) => (true, cid),
+            Class::Instance(cid) => (false, cid),
+        };
+
+        let classes = self.classes.read();
+        let mro = &classes[cid.1 as usize].mro;
+        let find = |proto| {
+            mro.iter().cloned().find(|&c| classes[c as usize].fields(proto).contains_key(key))
+        };
+        let owner = if proto { find(true) } else { find(false).or_else(|| find(true)) };
+        ClassId(cid.0, owner.unwrap_or(cid.1))
+    }
+
+    fn index_lval(&self, cls: Class, key: Spanned<&Key>, expspan: Span,
                   hint: Option<&Slot>, ctx: &mut TypeContext,
-                  _report: &Report) -> kailua_diag::Result<Option<(bool, Slot)>> {
+                  report: &Report) -> kailua_diag::Result<Option<(bool, Slot)>> {
         let (proto, cid) = match cls {
             Class::Prototype(cid) => (true, cid),
             Class::Instance(cid) => (false, cid),
         };
 
         let mut classes = self.classes.write();
-        let cls = &mut classes[cid.1 as usize];
-        match cls.fields_mut(proto).entry(key.base.clone()) {
-            Entry::Occupied(e) => Ok(Some((false, e.get().clone()))),
-            Entry::Vacant(e) => {
-                let slot = if let Some(hint) = hint {
-                    let slot = hint.clone();
-                    slot.adapt(F::Var, ctx); // always adapt to Var
-                    slot
-                } else {
-                    let tvar = T::TVar(ctx.gen_tvar());
-                    Slot::new(F::Unknown, Ty::new(tvar))
-                };
-                e.insert(slot.clone());
-                Ok(Some((true, slot)))
+
+        // instance fields are shared with all ancestors, so no new field is created
+        if !proto {
+            if let Some(info) = Self::lookup(&classes, cid, false, &key) {
+                return Ok(Some((false, info.base.clone())));
+            }
+        }
+
+        let slot = match classes[cid.1 as usize].fields(proto).get(&key) {
+            Some(info) => return Ok(Some((false, info.base.clone()))),
+            None => if let Some(hint) = hint {
+                let slot = hint.clone();
+                slot.adapt(F::Var, ctx); // always adapt to Var
+                slot
+            } else {
+                let tvar = T::TVar(ctx.gen_tvar());
+                Slot::new(F::Unknown, Ty::new(tvar))
             },
+        };
+
+        // overriding class fields in any ancestor requires the subtyping
+        if proto {
+            for &parent in &classes[cid.1 as usize].mro[1..] {
+                if let Some(parent_slot) = classes[parent as usize].class_fields.get(&key) {
+                    if let Err(r) = slot.assert_sub(&parent_slot.base, ctx) {
+                        report.error(expspan,
+                                     m::NotSubtypeOfParentField {
+                                         key: &key, sub: slot.display(ctx),
+                                         sup: parent_slot.base.display(ctx),
+                                     })
+                              .note_if(parent_slot, m::PreviousParentFieldType {})
+                              .report_types(r, TypeReportHint::None)
+                              .done()?;
+                    }
+                }
+            }
         }
+
+        let fields = classes[cid.1 as usize].fields_mut(proto);
+        fields.insert(key.base.clone(), slot.clone().with_loc(&key));
+        Ok(Some((true, slot)))
     }
 
     fn fmt_class(&self, cid: ClassId, f: &mut fmt::Formatter, st: &DisplayState) -> fmt::Result {
@@ -147,10 +222,11 @@ impl ClassSystem for DumbClassSystem {
         let classes = self.classes.read();
         let mut seen = HashSet::new();
         let mut list = |proto| {
-            let cls = &classes[cid.1 as usize];
-            for (key, slot) in cls.fields(proto) {
-                if seen.insert(key) {
-                    f(key, slot)?;
+            for &c in &classes[cid.1 as usize].mro {
+                for (key, slot) in classes[c as usize].fields(proto) {
+                    if seen.insert(key) {
+                        f(key, slot)?;
+                    }
                 }
             }
             Ok(())
@@ -162,8 +238,12 @@ impl ClassSystem for DumbClassSystem {
         list(true)
     }
 
-    fn list_parents(&self, _cid: ClassId,
-                    _f: &mut FnMut(ClassId) -> Result<(), ()>) -> Result<(), ()> {
+    fn list_parents(&self, cid: ClassId,
+                    f: &mut FnMut(ClassId) -> Result<(), ()>) -> Result<(), ()> {
+        let self_csid = cid.0;
+        for &parent in &self.classes.read()[cid.1 as usize].parents {
+            f(ClassId(self_csid, parent))?;
+        }
         Ok(())
     }
 }
@@ -19,7 +19,9 @@
 // but this may be a serious drawback, so for now we only handle the constructors specificially:
 // they cannot be accessed via instances, and in turn can be overriden as long as
 // non-self arguments match. for other cases overriding requires strict subtyping
-// and methods couldn't be overriden in a usual way.
+// and methods couldn't be overriden in a usual way. the exception is abstract methods
+// (`[abstract]`), which are meant to be overriden and can be replaced with any type;
+// the class cannot be instantiated with `new` until all abstract methods are overriden.
 //
 // Gideros class system also features the topmost genesis class (normally `Object`) which is
 // a parent of all other classes (and `class()` is equivalent to `class(Object)`).
@@ -144,13 +146,30 @@ impl GiderosClassSystem {
         None
     }
 
+    fn check_abstract_methods(classes: &[ClassDef], cid: ClassId, expspan: Span,
+                              ctx: &mut TypeContext, report: &Report) -> kailua_diag::Result<()> {
+        let mut seen = HashSet::new();
+        let mut fields = Vec::new();
+        for (_, cls) in Ancestors::new(classes, cid.1) {
+            for (key, field) in &cls.class_fields {
+                if let Field::Slot(ref slot) = *field {
+                    if seen.insert(key) {
+                        fields.push((key, slot));
+                    }
+                }
+            }
+        }
+        super::check_abstract_methods(fields, expspan, ctx, report)
+    }
+
     fn new_method_from_init(classes: &[ClassDef], cid: ClassId, init: &Spanned<Slot>,
                             ctx: &mut TypeContext, report: &Report) -> kailua_diag::Result<Slot> {
         // ensure that the type can be resolved...
         let ty = if let Some(ty) = ctx.resolve_exact_type(&init.unlift()) {
             ty
         } else {
-            report.error(init, m::InexactInitMethod { init: init.base.display(ctx) }).done()?;
+            report.error(init, m::InexactInitMethod { name: "init", init: init.base.display(ctx) })
+                  .done()?;
             return Ok(Slot::dummy());
         };
 
@@ -159,13 +178,16 @@ impl GiderosClassSystem {
             T::Functions(ref func) => match **func {
                 Functions::Simple(ref f) => f.to_owned(),
                 _ => {
-                    report.error(init, m::OverloadedFuncInitMethod { init: init.base.display(ctx) })
+                    report.error(init, m::OverloadedFuncInitMethod { name: "init",
+                                                                     init: init.base.display(ctx) })
                           .done()?;
                     return Ok(Slot::dummy());
                 }
             },
             _ => {
-                report.error(init, m::NonFuncInitMethod { init: init.base.display(ctx) }).done()?;
+                report.error(init, m::NonFuncInitMethod { name: "init",
+                                                          init: init.base.display(ctx) })
+                      .done()?;
                 return Ok(Slot::dummy());
             },
         };
@@ -190,14 +212,16 @@ impl GiderosClassSystem {
             func.argnames.remove(0);
         }
         if !selfarg_ok {
-            report.error(init, m::BadSelfInInitMethod { init: init.base.display(ctx) }).done()?;
+            report.error(init, m::BadSelfInInitMethod { name: "init",
+                                                        init: init.base.display(ctx) })
+                  .done()?;
             return Ok(Slot::dummy());
         }
 
         // now `init` is: function(/* removed self */, ...) -> any
         // fix the return type to make a signature for the `new` method
         let returns = T::Class(Class::Instance(cid));
-        let ctor = Function { args: func.args, argnames: func.argnames,
+        let ctor = Function { tparams: func.tparams, args: func.args, argnames: func.argnames,
                               returns: Some(TySeq::from(returns)) };
         let ctor = Slot::new(F::Const, Ty::new(T::func(ctor)));
 
@@ -213,16 +237,16 @@ impl ClassSystem for GiderosClassSystem {
             if !self.classes.read().is_empty() {
                 parent = parent.or(Some(ClassId(self_csid, GENESIS_CLASS).without_loc()));
             }
-            self.assume_class(self_csid, parent, outerspan, ctx, report)
+            self.assume_class(self_csid, parent.into_iter().collect(), outerspan, ctx, report)
         } else {
             Ok(None)
         }
     }
 
-    fn assume_class(&self, self_csid: ClassSystemId, parent: Option<Spanned<ClassId>>,
+    fn assume_class(&self, self_csid: ClassSystemId, parents: Vec<Spanned<ClassId>>,
                     outerspan: Span, _ctx: &mut TypeContext,
                     report: &Report) -> kailua_diag::Result<Option<ClassId>> {
-        let parent = match parent {
+        let parent = match super::single_parent(parents, report)? {
             Some(Spanned { base: ClassId(csid, cid), .. }) if csid == self_csid => Some(cid),
             Some(Spanned { base: ClassId(_, _), span }) => {
                 report.error(span, m::ClassInheritFromDifferentClassSystem {}).done()?;
@@ -304,12 +328,14 @@ impl ClassSystem for GiderosClassSystem {
         };
 
         if !proto && is_init_key(&key) {
-            report.error(&key, m::CannotAccessCtorThruInstance {}).done()?;
+            report.error(&key, m::CannotAccessCtorThruInstance { init: "init" }).done()?;
             return Ok(None);
         }
 
         let mut classes = self.classes.write();
         if is_new_key(&key) {
+            Self::check_abstract_methods(&classes, cid, expspan, ctx, report)?;
+
             if let Some(new) = classes[cid.1 as usize].new_ty.as_ref().map(|s| s.clone()) {
                 Ok(Some(new))
             } else {
@@ -321,7 +347,7 @@ impl ClassSystem for GiderosClassSystem {
                            slot, cid, init);
                     slot
                 } else {
-                    report.error(&key, m::NoCtor {}).done()?;
+                    report.error(&key, m::NoCtor { new: "new", init: "init" }).done()?;
                     Slot::dummy()
                 };
                 classes[cid.1 as usize].new_ty = Some(slot.clone());
@@ -362,6 +388,22 @@ impl ClassSystem for GiderosClassSystem {
         }
     }
 
+    fn field_owner(&self, cls: Class, key: &Key) -> ClassId {
+        let (proto, cid) = match cls {
+            Class::Prototype(cid) => (true, cid),
+            Class::Instance(cid) => (false, cid),
+        };
+
+        let classes = self.classes.read();
+        let find = |proto| {
+            Ancestors::new(&classes, cid.1).find(|&(_, cls)| {
+                if let Some(&Field::Slot(_)) = cls.fields(proto).get(key) { true } else { false }
+            }).map(|(c, _)| c)
+        };
+        let owner = if proto { find(true) } else { find(false).or_else(|| find(true)) };
+        ClassId(cid.0, owner.unwrap_or(cid.1))
+    }
+
     fn index_lval(&self, cls: Class, key: Spanned<&Key>, expspan: Span,
                   hint: Option<&Slot>, ctx: &mut TypeContext,
                   report: &Report) -> kailua_diag::Result<Option<(bool, Slot)>> {
@@ -380,7 +422,7 @@ impl ClassSystem for GiderosClassSystem {
         // its constraints are only checked when `new` is accessed for the first time.
         let is_init = is_init_key(&key);
         if !proto && is_init {
-            report.error(&key, m::CannotAccessCtorThruInstance {}).done()?;
+            report.error(&key, m::CannotAccessCtorThruInstance { init: "init" }).done()?;
             return Ok(None);
         }
 
@@ -435,7 +477,8 @@ impl ClassSystem for GiderosClassSystem {
                 // a new field created should be a subtype of that field in order to be compatible.
                 // (note that the subtyping will be actually useful only for const slots.)
                 // we do allow for overwriting class fields with instance fields though.
-                Field::Slot(ref parent_slot) if !is_init => {
+                Field::Slot(ref parent_slot) if !is_init &&
+                                                !super::is_abstract(parent_slot, ctx) => {
                     if let Err(r) = slot.assert_sub(parent_slot, ctx) {
                         report.error(expspan,
                                      m::NotSubtypeOfParentField {
@@ -449,7 +492,7 @@ impl ClassSystem for GiderosClassSystem {
                     Ok(None)
                 },
 
-                // see above for the `init` declaration in the prototype
+                // see above for the `init` declaration in the prototype and abstract methods
                 Field::Slot(_) => Ok(None),
 
                 Field::Children | Field::Instance => Ok(None),
@@ -0,0 +1,654 @@
+// notes on the middleclass class system:
+//
+// middleclass (https://github.com/kikito/middleclass) is a popular class library for Lua,
+// where `class(name, parent)` makes a new class. each class has three kinds of members:
+//
+// - instance methods (including metamethods) are declared through the class itself
+//   (`Foo.x = ...` or `function Foo:x() ... end`), stored to the "instance dictionary"
+//   and shared by all instances. they are also readable through the class.
+// - static members are declared through the `static` table of the class
+//   (`Foo.static.x = ...`) and only readable through the class.
+// - instance fields are declared through instances (`self.x = ...`).
+//
+// all of them are inherited by subclasses. the `static` table is yet another nominal type
+// (it cannot be distinguished from the class prototype otherwise), so each class takes
+// two consecutive class identifiers; one for the class and one for the `static` table.
+//
+// the constructor `new` is derived from the `initialize` method as in the Gideros class system,
+// but `initialize` is optional (defaults to no arguments) and `new` is a static method
+// receiving the class itself, so that `Foo:new(...)` and `Foo(...)` both work.
+// unlike Gideros there is no genesis class and a class can freely have no parent.
+//
+// overriding follows the Gideros class system: methods and static members can be redefined
+// in subclasses only when the new type is a subtype of the parent's, except for `initialize`
+// and abstract methods. `new` reports every abstract method left unimplemented.
+// mixins (`Foo:include(mixin)`) copy fields of the mixin to the class at that point,
+// with an exception of `included` (a callback) and `static` (copied to the `static` table).
+// only the fields of record types can be copied; other mixins are accepted but not tracked.
+
+use std::fmt;
+use std::collections::{HashSet, HashMap};
+use vec_map::{self, VecMap};
+use parking_lot::RwLock;
+
+use kailua_env::{Span, Spanned, WithLoc};
+use kailua_diag::{self, Report, Reporter};
+use kailua_syntax::{Str, Name};
+use kailua_types::ty::{TypeContext, ClassSystemId, ClassId, Class, Display, DisplayState, Lattice};
+use kailua_types::ty::{Slot, SpannedSlotSeq, Key, T, Ty, TySeq, F, Functions, Function, Nil, Tag};
+use kailua_types::ty::Tables;
+use kailua_types::diag::{TypeReportMore, TypeReportHint};
+use message as m;
+use super::ClassSystem;
+
+#[derive(Clone, Debug)]
+struct ClassDef {
+    parent: Option<usize>,
+    new_ty: Option<Slot>, // a dummy slot after the first error on the constructor typing
+    methods: HashMap<Key, Spanned<Slot>>,
+    statics: HashMap<Key, Spanned<Slot>>,
+    fields: HashMap<Key, Spanned<Slot>>,
+}
+
+// which members are being accessed
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+enum Side {
+    // instances see instance fields and methods
+    Instance,
+    // the class sees static members and methods, and declares methods
+    Class,
+    // the `static` table sees static members and methods, and declares static members
+    Static,
+}
+
+impl ClassDef {
+    fn members(&self, side: Side) -> &HashMap<Key, Spanned<Slot>> {
+        match side {
+            Side::Instance => &self.fields,
+            Side::Class => &self.methods,
+            Side::Static => &self.statics,
+        }
+    }
+
+    fn members_mut(&mut self, side: Side) -> &mut HashMap<Key, Spanned<Slot>> {
+        match side {
+            Side::Instance => &mut self.fields,
+            Side::Class => &mut self.methods,
+            Side::Static => &mut self.statics,
+        }
+    }
+}
+
+struct Ancestors<'a> {
+    classes: &'a [ClassDef],
+    current: Option<usize>,
+}
+
+impl<'a> Ancestors<'a> {
+    fn new(classes: &'a [ClassDef], current: usize) -> Ancestors<'a> {
+        Ancestors { classes: classes, current: Some(current) }
+    }
+}
+
+impl<'a> Iterator for Ancestors<'a> {
+    type Item = (usize, &'a ClassDef);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if let Some(current) = self.current {
+            let cls = &self.classes[current];
+            self.current = cls.parent;
+            Some((current, cls))
+        } else {
+            None
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct MiddleclassClassSystem {
+    classes: RwLock<Vec<ClassDef>>,
+
+    // frequently read even when classes are locked, so has to be separated
+    class_names: RwLock<VecMap<Spanned<Name>>>,
+}
+
+const INITIALIZE_KEY: &'static [u8] = b"initialize";
+
+// members defined by middleclass for classes, which cannot be redefined
+const CLASS_MEMBERS: &'static [&'static [u8]] = &[
+    b"new", b"allocate", b"subclass", b"subclasses", b"include", b"isSubclassOf",
+    b"name", b"super", b"static", b"__instanceDict", b"__declaredMethods",
+];
+
+// members defined by middleclass for instances, which cannot be redefined
+const INSTANCE_MEMBERS: &'static [&'static [u8]] = &[b"class", b"isInstanceOf"];
+
+fn is_key_in(key: &Key, list: &[&[u8]]) -> bool {
+    match *key {
+        Key::Str(ref s) => list.iter().any(|k| &s[..] == *k),
+        _ => false,
+    }
+}
+
+fn is_initialize_key(key: &Key) -> bool {
+    is_key_in(key, &[INITIALIZE_KEY])
+}
+
+fn side_of(cls: Class) -> (Side, usize) {
+    match cls {
+        Class::Prototype(cid) | Class::Instance(cid) if cid.1 % 2 == 1 => {
+            (Side::Static, (cid.1 / 2) as usize)
+        }
+        Class::Prototype(cid) => (Side::Class, (cid.1 / 2) as usize),
+        Class::Instance(cid) => (Side::Instance, (cid.1 / 2) as usize),
+    }
+}
+
+fn class_id(csid: ClassSystemId, idx: usize) -> ClassId {
+    ClassId(csid, idx as u32 * 2)
+}
+
+fn static_id(csid: ClassSystemId, idx: usize) -> ClassId {
+    ClassId(csid, idx as u32 * 2 + 1)
+}
+
+fn const_slot(t: T<'static>) -> Slot {
+    Slot::new(F::Const, Ty::new(t))
+}
+
+// classes and `static` tables should be updatable
+fn var_slot(t: T<'static>) -> Slot {
+    Slot::new(F::Var, Ty::new(t))
+}
+
+fn simple_func(args: Vec<Ty>, rest: Option<Ty>, returns: T<'static>) -> T<'static> {
+    T::func(Function { tparams: Vec::new(), args: TySeq { head: args, tail: rest },
+                       argnames: Vec::new(), returns: Some(TySeq::from(returns)) })
+}
+
+impl MiddleclassClassSystem {
+    pub fn new() -> MiddleclassClassSystem {
+        MiddleclassClassSystem {
+            classes: RwLock::new(Vec::new()),
+            class_names: RwLock::new(VecMap::new()),
+        }
+    }
+
+    fn lookup<'a>(classes: &'a [ClassDef], idx: usize, side: Side,
+                  key: &Key) -> Option<&'a Spanned<Slot>> {
+        Ancestors::new(classes, idx).filter_map(|(_, cls)| cls.members(side).get(key)).next()
+    }
+
+    // returns the type of members defined by middleclass for classes if any
+    fn class_member(classes: &mut [ClassDef], csid: ClassSystemId, idx: usize, key: &Key,
+                    expspan: Span, ctx: &mut TypeContext,
+                    report: &Report) -> kailua_diag::Result<Option<Slot>> {
+        let key = if let Key::Str(ref s) = *key { &s[..] } else { return Ok(None); };
+
+        let proto = Ty::new(T::Class(Class::Prototype(class_id(csid, idx))));
+        let inst = T::Class(Class::Instance(class_id(csid, idx)));
+        let slot = match key {
+            b"new" => Self::new_method(classes, csid, idx, expspan, ctx, report)?,
+            b"allocate" => const_slot(simple_func(vec![proto], None, inst)),
+            b"include" => {
+                let ret = T::Class(Class::Prototype(class_id(csid, idx)));
+                let func = simple_func(vec![proto], Some(Ty::new(T::All)), ret);
+                Slot::new(F::Const, Ty::new(func).with_tag(Tag::IncludeMixin))
+            }
+            b"isSubclassOf" => {
+                let func = simple_func(vec![Ty::new(T::All), Ty::new(T::All)], None, T::Boolean);
+                Slot::new(F::Const, Ty::new(func).with_tag(Tag::IsSubclassOf))
+            }
+            b"name" => const_slot(T::String),
+            b"super" => match classes[idx].parent {
+                Some(parent) => var_slot(T::Class(Class::Prototype(class_id(csid, parent)))),
+                None => return Ok(None),
+            },
+            b"static" => var_slot(T::Class(Class::Instance(static_id(csid, idx)))),
+            _ => return Ok(None),
+        };
+        Ok(Some(slot))
+    }
+
+    // returns the type of members defined by middleclass for instances if any
+    fn instance_member(csid: ClassSystemId, idx: usize, key: &Key) -> Option<Slot> {
+        let key = if let Key::Str(ref s) = *key { &s[..] } else { return None; };
+
+        match key {
+            b"class" => Some(var_slot(T::Class(Class::Prototype(class_id(csid, idx))))),
+            b"isInstanceOf" => {
+                let func = simple_func(vec![Ty::new(T::All), Ty::new(T::All)], None, T::Boolean);
+                Some(Slot::new(F::Const, Ty::new(func).with_tag(Tag::IsInstanceOf)))
+            }
+            _ => None,
+        }
+    }
+
+    // returns the fields of the mixin if it has a record type
+    fn mixin_fields(mixin: &Slot, ctx: &mut TypeContext) -> Option<Vec<(Key, Slot)>> {
+        let ty = ctx.resolve_exact_type(&mixin.unlift())?;
+        match *ty {
+            T::Tables(ref tab) => match **tab {
+                Tables::Fields(ref rvar) => Some(ctx.get_rvar_fields(rvar.clone())),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    // copies a field of the mixin to the class as if `cls[key] = slot` has been executed
+    fn include_field(&self, cls: Class, key: Spanned<&Key>, slot: &Slot, expspan: Span,
+                     ctx: &mut TypeContext, report: &Report) -> kailua_diag::Result<()> {
+        if let Some((false, existing)) = self.index_lval(cls, key, expspan, Some(slot),
+                                                         ctx, report)? {
+            if existing.accept(slot, ctx, false).is_err() {
+                report.error(expspan, m::CannotAssign { lhs: existing.display(ctx),
+                                                        rhs: slot.display(ctx) })
+                      .note_if(key, m::OtherTypeOrigin {})
+                      .done()?;
+            }
+        }
+        Ok(())
+    }
+
+    fn new_method(classes: &mut [ClassDef], csid: ClassSystemId, idx: usize, expspan: Span,
+                  ctx: &mut TypeContext, report: &Report) -> kailua_diag::Result<Slot> {
+        Self::check_abstract_methods(classes, idx, expspan, ctx, report)?;
+
+        if let Some(ref new) = classes[idx].new_ty {
+            return Ok(new.clone());
+        }
+
+        let init = Self::lookup(classes, idx, Side::Class, &Key::from(Str::from(INITIALIZE_KEY)));
+        let slot = if let Some(init) = init.cloned() {
+            let slot = Self::new_method_from_init(classes, csid, idx, &init, ctx, report)?;
+            trace!("created a new method {:?} for {:?} from the constructor {:?}",
+                   slot, idx, init);
+            slot
+        } else {
+            // the default `initialize` method receives no arguments
+            let proto = Ty::new(T::Class(Class::Prototype(class_id(csid, idx))));
+            let inst = T::Class(Class::Instance(class_id(csid, idx)));
+            const_slot(simple_func(vec![proto], None, inst))
+        };
+
+        classes[idx].new_ty = Some(slot.clone());
+        Ok(slot)
+    }
+
+    fn check_abstract_methods(classes: &[ClassDef], idx: usize, expspan: Span,
+                              ctx: &mut TypeContext, report: &Report) -> kailua_diag::Result<()> {
+        let mut seen = HashSet::new();
+        let mut methods = Vec::new();
+        for (_, cls) in Ancestors::new(classes, idx) {
+            for (key, slot) in &cls.methods {
+                if seen.insert(key) {
+                    methods.push((key, slot));
+                }
+            }
+        }
+        super::check_abstract_methods(methods, expspan, ctx, report)
+    }
+
+    fn new_method_from_init(classes: &[ClassDef], csid: ClassSystemId, idx: usize,
+                            init: &Spanned<Slot>, ctx: &mut TypeContext,
+                            report: &Report) -> kailua_diag::Result<Slot> {
+        // ensure that the type can be resolved...
+        let ty = if let Some(ty) = ctx.resolve_exact_type(&init.unlift()) {
+            ty
+        } else {
+            report.error(init, m::InexactInitMethod { name: "initialize",
+                                                      init: init.base.display(ctx) })
+                  .done()?;
+            return Ok(Slot::dummy());
+        };
+
+        // ...and is a function.
+        let mut func = match *ty {
+            T::Functions(ref func) => match **func {
+                Functions::Simple(ref f) => f.to_owned(),
+                _ => {
+                    report.error(init, m::OverloadedFuncInitMethod {
+                                           name: "initialize", init: init.base.display(ctx),
+                                       })
+                          .done()?;
+                    return Ok(Slot::dummy());
+                }
+            },
+            _ => {
+                report.error(init, m::NonFuncInitMethod { name: "initialize",
+                                                          init: init.base.display(ctx) })
+                      .done()?;
+                return Ok(Slot::dummy());
+            },
+        };
+
+        // the first argument should be a fresh class instance,
+        // which is replaced with the class itself (`new` is a static method)
+        let mut selfarg_ok = false;
+        if !func.args.head.is_empty() {
+            let selfarg = func.args.head.remove(0);
+            if let Some(selfarg) = ctx.resolve_exact_type(&selfarg) {
+                if selfarg.nil() == Nil::Silent {
+                    if let T::Class(Class::Instance(cid)) = *selfarg {
+                        // the constructor can be inherited from any parent class
+                        selfarg_ok =
+                            cid.0 == csid && cid.1 % 2 == 0 &&
+                            Ancestors::new(classes, idx).any(|(c, _)| c == (cid.1 / 2) as usize);
+                    }
+                }
+            }
+        }
+        if !selfarg_ok {
+            report.error(init, m::BadSelfInInitMethod { name: "initialize",
+                                                        init: init.base.display(ctx) })
+                  .done()?;
+            return Ok(Slot::dummy());
+        }
+        func.args.head.insert(0, Ty::new(T::Class(Class::Prototype(class_id(csid, idx)))));
+        if !func.argnames.is_empty() {
+            func.argnames[0] = None;
+        }
+
+        // now `initialize` is: function(/* the class */, ...) -> any
+        // fix the return type to make a signature for the `new` method
+        let returns = T::Class(Class::Instance(class_id(csid, idx)));
+        let ctor = Function { tparams: func.tparams, args: func.args, argnames: func.argnames,
+                              returns: Some(TySeq::from(returns)) };
+        Ok(const_slot(T::func(ctor)))
+    }
+}
+
+impl ClassSystem for MiddleclassClassSystem {
+    fn make_class(&self, self_csid: ClassSystemId, mut argtys: SpannedSlotSeq, outerspan: Span,
+                  ctx: &mut TypeContext, report: &Report) -> kailua_diag::Result<Option<ClassId>> {
+        // `class(name, parent)`; the name is only used at runtime
+        if !argtys.head.is_empty() {
+            argtys.head.remove(0);
+        }
+        if let Some(parent) = super::extract_parent(argtys, ctx, report)? {
+            self.assume_class(self_csid, parent.into_iter().collect(), outerspan, ctx, report)
+        } else {
+            Ok(None)
+        }
+    }
+
+    fn assume_class(&self, self_csid: ClassSystemId, parents: Vec<Spanned<ClassId>>,
+                    _outerspan: Span, _ctx: &mut TypeContext,
+                    report: &Report) -> kailua_diag::Result<Option<ClassId>> {
+        let parent = match super::single_parent(parents, report)? {
+            Some(Spanned { base: ClassId(csid, cid), .. }) if csid == self_csid => {
+                Some((cid / 2) as usize)
+            },
+            Some(Spanned { base: ClassId(_, _), span }) => {
+                report.error(span, m::ClassInheritFromDifferentClassSystem {}).done()?;
+                None
+            },
+            None => None,
+        };
+
+        let mut classes = self.classes.write();
+        if let Some(parent) = parent {
+            assert!(parent < classes.len(), "invalid ClassId");
+        }
+
+        let cid = class_id(self_csid, classes.len());
+        classes.push(ClassDef {
+            parent: parent,
+            new_ty: None,
+            methods: HashMap::new(),
+            statics: HashMap::new(),
+            fields: HashMap::new(),
+        });
+        Ok(Some(cid))
+    }
+
+    fn name_class(&self, cid: ClassId, name: Spanned<Name>) -> Result<(), Spanned<Name>> {
+        let mut names = self.class_names.write();
+        match names.entry((cid.1 / 2) as usize) {
+            vec_map::Entry::Occupied(e) => Err(e.get().clone()),
+            vec_map::Entry::Vacant(e) => {
+                info!("named {:?} as {:?}", cid, name);
+                e.insert(name);
+                Ok(())
+            },
+        }
+    }
+
+    fn is_subclass_of(&self, lhs: ClassId, rhs: ClassId) -> bool {
+        if lhs.0 != rhs.0 {
+            return false;
+        }
+        if lhs == rhs {
+            return true;
+        }
+
+        // `static` tables are only equal to themselves
+        if lhs.1 % 2 == 1 || rhs.1 % 2 == 1 {
+            return false;
+        }
+
+        let classes = self.classes.read();
+
+        let lhs = (lhs.1 / 2) as usize;
+        let rhs = (rhs.1 / 2) as usize;
+
+        assert!(lhs < classes.len(), "invalid ClassId for lhs");
+        assert!(rhs < classes.len(), "invalid ClassId for rhs");
+
+        Ancestors::new(&classes, lhs).any(|(c, _)| c == rhs)
+    }
+
+    fn index_rval(&self, cls: Class, key: Spanned<&Key>, expspan: Span, ctx: &mut TypeContext,
+                  report: &Report) -> kailua_diag::Result<Option<Slot>> {
+        let csid = cls.system();
+        let (side, idx) = side_of(cls);
+
+        let mut classes = self.classes.write();
+        if side == Side::Instance {
+            if let Some(info) = Self::lookup(&classes, idx, Side::Instance, &key) {
+                return Ok(Some(info.base.clone()));
+            }
+            if let Some(info) = Self::lookup(&classes, idx, Side::Class, &key) {
+                return Ok(Some(info.base.clone()));
+            }
+            Ok(Self::instance_member(csid, idx, &key))
+        } else {
+            if let Some(info) = Self::lookup(&classes, idx, Side::Static, &key) {
+                return Ok(Some(info.base.clone()));
+            }
+            if let Some(slot) = Self::class_member(&mut classes, csid, idx, &key, expspan,
+                                                   ctx, report)? {
+                return Ok(Some(slot));
+            }
+            if let Some(info) = Self::lookup(&classes, idx, Side::Class, &key) {
+                return Ok(Some(info.base.clone()));
+            }
+            // instance methods defined by middleclass are readable through the class as well
+            match Self::instance_member(csid, idx, &key) {
+                Some(ref slot) if !is_key_in(&key, &[b"class"]) => Ok(Some(slot.clone())),
+                _ => Ok(None),
+            }
+        }
+    }
+
+    fn include_mixin(&self, cid: ClassId, mixin: &Spanned<Slot>, expspan: Span,
+                     ctx: &mut TypeContext, report: &Report) -> kailua_diag::Result<()> {
+        let fields = if let Some(fields) = Self::mixin_fields(&mixin.base, ctx) {
+            fields
+        } else {
+            return Ok(());
+        };
+
+        let idx = (cid.1 / 2) as usize;
+        for (key, slot) in fields {
+            if is_key_in(&key, &[b"included"]) {
+                continue;
+            } else if is_key_in(&key, &[b"static"]) {
+                let statics = Self::mixin_fields(&slot, ctx).unwrap_or_default();
+                let cls = Class::Instance(static_id(cid.0, idx));
+                for (key, slot) in statics {
+                    self.include_field(cls, (&key).with_loc(mixin), &slot, expspan, ctx, report)?;
+                }
+            } else {
+                let cls = Class::Prototype(class_id(cid.0, idx));
+                self.include_field(cls, (&key).with_loc(mixin), &slot, expspan, ctx, report)?;
+            }
+        }
+        Ok(())
+    }
+
+    fn field_owner(&self, cls: Class, key: &Key) -> ClassId {
+        let csid = cls.system();
+        let (side, idx) = side_of(cls);
+
+        let classes = self.classes.read();
+        let find = |side| {
+            Ancestors::new(&classes, idx).find(|&(_, cls)| cls.members(side).contains_key(key))
+                                         .map(|(c, _)| c)
+        };
+        let owner = match side {
+            Side::Instance => find(Side::Instance).or_else(|| find(Side::Class)),
+            Side::Class | Side::Static => find(Side::Static).or_else(|| find(Side::Class)),
+        };
+        class_id(csid, owner.unwrap_or(idx))
+    }
+
+    fn index_lval(&self, cls: Class, key: Spanned<&Key>, expspan: Span,
+                  hint: Option<&Slot>, ctx: &mut TypeContext,
+                  report: &Report) -> kailua_diag::Result<Option<(bool, Slot)>> {
+        let (side, idx) = side_of(cls);
+
+        // the class itself declares methods which are also visible to instances
+        let reserved = match side {
+            Side::Instance => is_key_in(&key, INSTANCE_MEMBERS),
+            Side::Class => is_key_in(&key, CLASS_MEMBERS) || is_key_in(&key, INSTANCE_MEMBERS),
+            Side::Static => is_key_in(&key, CLASS_MEMBERS),
+        };
+        if reserved {
+            report.error(&key, m::ReservedClassMember { key: &key }).done()?;
+            return Ok(None);
+        }
+
+        let mut classes = self.classes.write();
+
+        if side == Side::Instance {
+            // instance fields are shared with parent classes, and fields shadowing methods
+            // should be compatible to them. in either case no new field is created.
+            let existing = Self::lookup(&classes, idx, Side::Instance, &key)
+                               .or_else(|| Self::lookup(&classes, idx, Side::Class, &key));
+            if let Some(info) = existing {
+                return Ok(Some((false, info.base.clone())));
+            }
+        } else if let Some(info) = classes[idx].members(side).get(&key) {
+            // the member exists in the current class
+            return Ok(Some((false, info.base.clone())));
+        }
+
+        // this type will be a type of the new member
+        let slot = if let Some(hint) = hint {
+            let slot = hint.clone();
+            slot.adapt(F::Var, ctx); // always adapt to Var
+            slot
+        } else {
+            let tvar = T::TVar(ctx.gen_tvar());
+            Slot::new(F::Unknown, Ty::new(tvar))
+        };
+
+        // overriding methods or static members in parents requires the subtyping,
+        // except for `initialize` which is only called through `new` and abstract methods
+        if side != Side::Instance && !(side == Side::Class && is_initialize_key(&key)) {
+            let parent = classes[idx].parent;
+            let parent_slot = parent.and_then(|p| Self::lookup(&classes, p, side, &key));
+            let parent_slot = parent_slot.filter(|slot| !super::is_abstract(slot, ctx));
+            if let Some(parent_slot) = parent_slot {
+                if let Err(r) = slot.assert_sub(&parent_slot.base, ctx) {
+                    report.error(expspan,
+                                 m::NotSubtypeOfParentField {
+                                     key: &key, sub: slot.display(ctx),
+                                     sup: parent_slot.base.display(ctx),
+                                 })
+                          .note_if(parent_slot, m::PreviousParentFieldType {})
+                          .report_types(r, TypeReportHint::None)
+                          .done()?;
+                }
+            }
+        }
+
+        let members = classes[idx].members_mut(side);
+        members.insert(key.base.clone(), slot.clone().with_loc(&key));
+
+        Ok(Some((true, slot)))
+    }
+
+    fn index_metamethod(&self, cls: Class, name: &[u8], expspan: Span, ctx: &mut TypeContext,
+                        report: &Report) -> kailua_diag::Result<Option<Slot>> {
+        let csid = cls.system();
+        match side_of(cls) {
+            // metamethods are declared as methods and apply to instances
+            (Side::Instance, idx) => {
+                let classes = self.classes.read();
+                let key = Key::from(Str::from(name));
+                Ok(Self::lookup(&classes, idx, Side::Class, &key).map(|info| info.base.clone()))
+            }
+
+            // calling the class is same to calling `new`
+            (Side::Class, idx) if name == b"__call" => {
+                let mut classes = self.classes.write();
+                Self::new_method(&mut classes, csid, idx, expspan, ctx, report).map(Some)
+            }
+
+            _ => Ok(None),
+        }
+    }
+
+    fn fmt_class(&self, cid: ClassId, f: &mut fmt::Formatter, st: &DisplayState) -> fmt::Result {
+        let names = self.class_names.read();
+        let idx = cid.1 & !1;
+        match (&st.locale[..], &names.get((idx / 2) as usize)) {
+            (_,    &Some(ref name)) => write!(f, "{:+}", name)?,
+            ("ko", &None) => write!(f, "<이름 없는 클래스 #{}.{}>", (cid.0).0, idx)?,
+            (_,    &None) => write!(f, "<unnamed class #{}.{}>", (cid.0).0, idx)?,
+        }
+        if cid.1 % 2 == 1 {
+            write!(f, ".static")?;
+        }
+        Ok(())
+    }
+
+    fn list_fields(&self, cls: Class,
+                   f: &mut FnMut(&Key, &Slot) -> Result<(), ()>) -> Result<(), ()> {
+        let (side, idx) = side_of(cls);
+
+        let classes = self.classes.read();
+        let mut seen = HashSet::new();
+        let mut list = |side| {
+            for (_, cls) in Ancestors::new(&classes, idx) {
+                for (key, slot) in cls.members(side) {
+                    if seen.insert(key) {
+                        f(key, slot)?;
+                    }
+                }
+            }
+            Ok(())
+        };
+
+        if side == Side::Instance {
+            list(Side::Instance)?;
+        } else {
+            list(Side::Static)?;
+        }
+        list(Side::Class)
+    }
+
+    fn list_parents(&self, cid: ClassId,
+                    f: &mut FnMut(ClassId) -> Result<(), ()>) -> Result<(), ()> {
+        if cid.1 % 2 == 1 {
+            return Ok(());
+        }
+        if let Some(parent) = self.classes.read()[(cid.1 / 2) as usize].parent {
+            f(class_id(cid.0, parent))?;
+        }
+        Ok(())
+    }
+}
@@ -5,6 +5,9 @@ use kailua_syntax::Name;
 use kailua_types::ty::{TypeContext, ClassSystemId, ClassId, Class, Display, DisplayState};
 use kailua_types::ty::{Slot, SpannedSlotSeq, Key, T, Nil};
 use message as m;
+use options::Options;
+
+pub use kailua_types::ty::Visibility;
 
 /// Defines the various characteristics of class systems.
 ///
@@ -27,14 +30,18 @@ pub trait ClassSystem: Send + Sync + fmt::Debug {
     fn make_class(&self, self_csid: ClassSystemId, argtys: SpannedSlotSeq, outerspan: Span,
                   ctx: &mut TypeContext, report: &Report) -> kailua_diag::Result<Option<ClassId>> {
         if let Some(parent) = extract_parent(argtys, ctx, report)? {
-            self.assume_class(self_csid, parent, outerspan, ctx, report)
+            self.assume_class(self_csid, parent.into_iter().collect(), outerspan, ctx, report)
         } else {
             Ok(None)
         }
     }
 
     /// Invoked when `--# assume class(<this system>)` gets processed.
-    fn assume_class(&self, self_csid: ClassSystemId, parent: Option<Spanned<ClassId>>,
+    ///
+    /// `parents` are direct parent classes (or mixins) in the order of appearance,
+    /// which may be empty. The class system is responsible for reporting too many parents
+    /// or parents not forming a consistent method resolution order (see `linearize`).
+    fn assume_class(&self, self_csid: ClassSystemId, parents: Vec<Spanned<ClassId>>,
                     outerspan: Span, ctx: &mut TypeContext,
                     report: &Report) -> kailua_diag::Result<Option<ClassId>>;
 
@@ -76,6 +83,51 @@ pub trait ClassSystem: Send + Sync + fmt::Debug {
                   hint: Option<&Slot>, ctx: &mut TypeContext,
                   report: &Report) -> kailua_diag::Result<Option<(bool, Slot)>>;
 
+    /// Returns the type of the metamethod `name` (e.g. `__add` or `__call`) for values of
+    /// given nominal type, which is used for operators and calls to such values.
+    ///
+    /// This method has a default implementation which has no metamethods at all.
+    ///
+    /// The caller guarantees that the `cls` refers to a class defined from given class system.
+    fn index_metamethod(&self, _cls: Class, _name: &[u8], _expspan: Span,
+                        _ctx: &mut TypeContext,
+                        _report: &Report) -> kailua_diag::Result<Option<Slot>> {
+        Ok(None)
+    }
+
+    /// Invoked when a function with the `IncludeMixin` tag gets called
+    /// with a class prototype and a mixin table.
+    ///
+    /// This method has a default implementation which ignores mixins.
+    ///
+    /// The caller guarantees that the `cid` refers to a class defined from given class system.
+    fn include_mixin(&self, _cid: ClassId, _mixin: &Spanned<Slot>, _expspan: Span,
+                     _ctx: &mut TypeContext, _report: &Report) -> kailua_diag::Result<()> {
+        Ok(())
+    }
+
+    /// Returns the class which defines the field `key` of given nominal type.
+    ///
+    /// This is used to restrict accesses to private and protected fields (see `Visibility`).
+    /// This method has a default implementation which assumes that
+    /// every field is defined in given class.
+    ///
+    /// The caller guarantees that the `cls` refers to a class defined from given class system.
+    fn field_owner(&self, cls: Class, _key: &Key) -> ClassId {
+        match cls {
+            Class::Prototype(cid) | Class::Instance(cid) => cid,
+        }
+    }
+
+    /// Returns the visibility of the field `key` of given nominal type implied by
+    /// the class system itself. This is combined with `[private]` and `[protected]` attributes
+    /// in the field type, and the more restrictive one is used.
+    ///
+    /// This method has a default implementation which makes every field public.
+    fn field_visibility(&self, _cls: Class, _key: &Key) -> Visibility {
+        Visibility::Public
+    }
+
     /// Prints the nominal type name (or an appropriate placeholder if unnamed) to the formatter.
     fn fmt_class(&self, cid: ClassId, f: &mut fmt::Formatter, st: &DisplayState) -> fmt::Result;
 
@@ -94,15 +146,145 @@ pub trait ClassSystem: Send + Sync + fmt::Debug {
 
 pub mod dumb;
 pub mod gideros;
+pub mod middleclass;
+pub mod declarative;
+
+/// Returns true if the class system of given name is built in the checker.
+/// Built-in class systems cannot be customized with rules.
+pub fn is_builtin_class_system(name: &str) -> bool {
+    match name {
+        "gideros" | "middleclass" => true,
+        _ => false,
+    }
+}
 
 /// Returns the predefined class system object from the name.
 ///
-/// This is currently the only way to define class systems from the Kailua source code.
-pub fn make_predefined_class_system(name: &str) -> Option<Box<ClassSystem>> {
+/// Built-in class systems take precedence over user-defined class systems,
+/// whose rules are given by `Options::class_system_rules`.
+pub fn make_predefined_class_system(name: &str, opts: &Options) -> Option<Box<ClassSystem>> {
     match name {
         "gideros" => Some(Box::new(gideros::GiderosClassSystem::new())),
-        _ => None,
+        "middleclass" => Some(Box::new(middleclass::MiddleclassClassSystem::new())),
+        _ => {
+            let rules = opts.class_system_rules(name)?;
+            Some(Box::new(declarative::DeclarativeClassSystem::new(rules)))
+        }
+    }
+}
+
+/// Computes the method resolution order (MRO) of a class with given direct parents,
+/// which is the class itself followed by all ancestors in the order of field lookups.
+///
+/// `mro_of` should return the MRO of each given parent class.
+/// The C3 linearization is used, which keeps the order of parents and is monotonic---
+/// every MRO of the ancestors is a subsequence of the resulting MRO.
+/// Returns `None` if there is no such order, for example, when a class comes before its parent.
+pub fn linearize<C, F>(current: C, parents: &[C], mut mro_of: F) -> Option<Vec<C>>
+    where C: Copy + PartialEq, F: FnMut(C) -> Vec<C>
+{
+    let mut seqs: Vec<Vec<C>> = parents.iter().map(|&p| mro_of(p)).collect();
+    seqs.push(parents.to_owned());
+
+    let mut mro = vec![current];
+    loop {
+        seqs.retain(|seq| !seq.is_empty());
+        if seqs.is_empty() {
+            return Some(mro);
+        }
+
+        // find the first head which doesn't appear in the tail of any other sequence
+        let next = seqs.iter().map(|seq| seq[0]).find(|&head| {
+            seqs.iter().all(|seq| !seq[1..].contains(&head))
+        });
+        let next = match next {
+            Some(next) if next != current => next,
+            _ => return None,
+        };
+
+        mro.push(next);
+        for seq in &mut seqs {
+            if seq[0] == next {
+                seq.remove(0);
+            }
+        }
+    }
+}
+
+/// Reports common errors from the list of direct parent classes, and returns
+/// the method resolution order of the class from `linearize`.
+///
+/// If parents are erroneous the resulting MRO still contains every (unique) ancestor,
+/// so that the caller can continue.
+fn check_parents<C, F>(current: C, parents: &[Spanned<C>], outerspan: Span, mut mro_of: F,
+                       report: &Report) -> kailua_diag::Result<Vec<C>>
+    where C: Copy + PartialEq, F: FnMut(C) -> Vec<C>
+{
+    let mut unique: Vec<C> = Vec::new();
+    for parent in parents {
+        if unique.contains(&parent.base) {
+            report.error(parent, m::DuplicateParentClass {}).done()?;
+        } else {
+            unique.push(parent.base);
+        }
+    }
+
+    if let Some(mro) = linearize(current, &unique, &mut mro_of) {
+        return Ok(mro);
+    }
+
+    report.error(outerspan, m::InconsistentMethodResolutionOrder {}).done()?;
+
+    // fall back to the depth-first, left-to-right order without duplicates
+    let mut mro = vec![current];
+    for parent in unique {
+        for ancestor in mro_of(parent) {
+            if !mro.contains(&ancestor) {
+                mro.push(ancestor);
+            }
+        }
+    }
+    Ok(mro)
+}
+
+/// Returns true if the slot is an abstract method (`[abstract]`).
+///
+/// Abstract methods are meant to be overriden, so class systems exempt them from
+/// the usual subtyping constraints for overriding fields.
+fn is_abstract(slot: &Slot, ctx: &mut TypeContext) -> bool {
+    // methods defined with a function body are only known after the type variable is resolved
+    slot.unlift().modifiers().abstract_ ||
+        ctx.resolve_exact_type(&slot.unlift()).map_or(false, |ty| ty.modifiers().abstract_)
+}
+
+/// Reports every abstract method left unimplemented when the class is about to be instantiated.
+///
+/// `fields` should contain the nearest definition of each class field
+/// along the method resolution order.
+fn check_abstract_methods<'a, I>(fields: I, expspan: Span, ctx: &mut TypeContext,
+                                 report: &Report) -> kailua_diag::Result<()>
+    where I: IntoIterator<Item = (&'a Key, &'a Spanned<Slot>)>
+{
+    let mut abstracts: Vec<_> = fields.into_iter().filter(|&(_, slot)| {
+        is_abstract(slot, ctx)
+    }).collect();
+    abstracts.sort_by(|&(a, _), &(b, _)| a.cmp(b));
+
+    for (key, slot) in abstracts {
+        report.error(expspan, m::AbstractMethodNotImplemented { key: key })
+              .note_if(slot, m::AbstractMethodDeclaredHere {})
+              .done()?;
     }
+    Ok(())
+}
+
+/// Reports an error if there are more than one parent class and returns the first one.
+fn single_parent(parents: Vec<Spanned<ClassId>>,
+                 report: &Report) -> kailua_diag::Result<Option<Spanned<ClassId>>> {
+    if let Some(extra) = parents.get(1) {
+        report.error(extra, m::NoMultipleInheritance {}).done()?;
+    }
+    Ok(parents.into_iter().next())
 }
 
 fn extract_parent(mut argtys: SpannedSlotSeq, ctx: &mut TypeContext,
@@ -129,3 +311,31 @@ fn extract_parent(mut argtys: SpannedSlotSeq, ctx: &mut TypeContext,
     }
 }
 
+
+fn extract_parents(argtys: SpannedSlotSeq, ctx: &mut TypeContext,
+                   report: &Report) -> kailua_diag::Result<Option<Vec<Spanned<ClassId>>>> {
+    let mut parents = Vec::new();
+
+    for argty in &argtys.head {
+        let parent = if let Some(arg) = ctx.resolve_exact_type(&argty.unlift()) {
+            if let T::None = *arg {
+                continue; // omitted arguments
+            } else if let T::Class(Class::Prototype(cid)) = *arg {
+                if arg.nil() == Nil::Silent { Some(cid) } else { None }
+            } else {
+                None
+            }
+        } else {
+            None
+        };
+
+        if let Some(parent) = parent {
+            parents.push(parent.with_loc(argty));
+        } else {
+            report.error(argty, m::BadClassParent { ty: argty.unlift().display(ctx) }).done()?;
+            return Ok(None);
+        }
+    }
+
+    Ok(Some(parents))
+}
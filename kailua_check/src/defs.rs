@@ -5,6 +5,10 @@ use kailua_syntax::{Chunk, Lexer, Nest, Parser};
 pub struct Def {
     pub name: &'static str,
     pub code: &'static [u8],
+
+    /// If set, the value returned by the definition is also available via `require`
+    /// with this module name (e.g. `require 'ffi'` in LuaJIT).
+    pub module: Option<&'static str>,
 }
 
 impl Def {
@@ -24,10 +28,13 @@ impl Def {
 }
 
 macro_rules! defs {
-    ($($defs:ident, $def:ident = $name:expr, $path:expr;)*) => ($(
-        const $def: Def = Def { name: $name, code: include_bytes!($path) };
+    ($($defs:ident, $def:ident = $name:expr, $path:expr $(, module $module:expr)*;)*) => ($(
+        const $def: Def = Def { name: $name, code: include_bytes!($path),
+                                module: defs!(@module $($module)*) };
         const $defs: &'static [Def] = &[$def];
     )*);
+    (@module) => (None);
+    (@module $module:expr) => (Some($module));
 }
 
 defs! {
@@ -39,6 +46,20 @@ defs! {
     LUA51_IO_DEFS,      LUA51_IO_DEF      = "lua51_io",      "defs/lua51_io.lua";
     LUA51_OS_DEFS,      LUA51_OS_DEF      = "lua51_os",      "defs/lua51_os.lua";
     LUA51_DEBUG_DEFS,   LUA51_DEBUG_DEF   = "lua51_debug",   "defs/lua51_debug.lua";
+    LUA52_BASE_DEFS,    LUA52_BASE_DEF    = "lua52_base",    "defs/lua52_base.lua";
+    LUA52_PACKAGE_DEFS, LUA52_PACKAGE_DEF = "lua52_package", "defs/lua52_package.lua";
+    LUA52_TABLE_DEFS,   LUA52_TABLE_DEF   = "lua52_table",   "defs/lua52_table.lua";
+    LUA52_BIT32_DEFS,   LUA52_BIT32_DEF   = "lua52_bit32",   "defs/lua52_bit32.lua";
+    LUA53_BASE_DEFS,    LUA53_BASE_DEF    = "lua53_base",    "defs/lua53_base.lua";
+    LUA53_PACKAGE_DEFS, LUA53_PACKAGE_DEF = "lua53_package", "defs/lua53_package.lua";
+    LUA53_STRING_DEFS,  LUA53_STRING_DEF  = "lua53_string",  "defs/lua53_string.lua";
+    LUA53_TABLE_DEFS,   LUA53_TABLE_DEF   = "lua53_table",   "defs/lua53_table.lua";
+    LUA53_MATH_DEFS,    LUA53_MATH_DEF    = "lua53_math",    "defs/lua53_math.lua";
+    LUA53_UTF8_DEFS,    LUA53_UTF8_DEF    = "lua53_utf8",    "defs/lua53_utf8.lua";
+    LUA54_BASE_DEFS,    LUA54_BASE_DEF    = "lua54_base",    "defs/lua54_base.lua";
+    LUAJIT_BIT_DEFS,    LUAJIT_BIT_DEF    = "luajit_bit",    "defs/luajit_bit.lua", module "bit";
+    LUAJIT_JIT_DEFS,    LUAJIT_JIT_DEF    = "luajit_jit",    "defs/luajit_jit.lua", module "jit";
+    LUAJIT_FFI_DEFS,    LUAJIT_FFI_DEF    = "luajit_ffi",    "defs/luajit_ffi.lua", module "ffi";
     KAILUA_TEST_DEFS,   KAILUA_TEST_DEF   = "kailua_test",   "defs/kailua_test.lua";
 }
 
@@ -53,6 +74,56 @@ const LUA51_DEFS: &'static [Def] = &[
     LUA51_DEBUG_DEF,
 ];
 
+const LUA52_DEFS: &'static [Def] = &[
+    LUA52_BASE_DEF,
+    LUA52_PACKAGE_DEF,
+    LUA51_STRING_DEF,
+    LUA52_TABLE_DEF,
+    LUA51_MATH_DEF,
+    LUA51_IO_DEF,
+    LUA51_OS_DEF,
+    LUA51_DEBUG_DEF,
+    LUA52_BIT32_DEF,
+];
+
+const LUA53_DEFS: &'static [Def] = &[
+    LUA53_BASE_DEF,
+    LUA53_PACKAGE_DEF,
+    LUA53_STRING_DEF,
+    LUA53_TABLE_DEF,
+    LUA53_MATH_DEF,
+    LUA51_IO_DEF,
+    LUA51_OS_DEF,
+    LUA51_DEBUG_DEF,
+    LUA53_UTF8_DEF,
+];
+
+const LUAJIT_DEFS: &'static [Def] = &[
+    LUA51_BASE_DEF,
+    LUA51_PACKAGE_DEF,
+    LUA51_STRING_DEF,
+    LUA51_TABLE_DEF,
+    LUA51_MATH_DEF,
+    LUA51_IO_DEF,
+    LUA51_OS_DEF,
+    LUA51_DEBUG_DEF,
+    LUAJIT_BIT_DEF,
+    LUAJIT_JIT_DEF,
+    LUAJIT_FFI_DEF,
+];
+
+const LUA54_DEFS: &'static [Def] = &[
+    LUA54_BASE_DEF,
+    LUA53_PACKAGE_DEF,
+    LUA53_STRING_DEF,
+    LUA53_TABLE_DEF,
+    LUA53_MATH_DEF,
+    LUA51_IO_DEF,
+    LUA51_OS_DEF,
+    LUA51_DEBUG_DEF,
+    LUA53_UTF8_DEF,
+];
+
 pub fn get_defs(name: &str) -> Option<&'static [Def]> {
     match name {
         "lua51"         => Some(LUA51_DEFS),
@@ -65,6 +136,28 @@ pub fn get_defs(name: &str) -> Option<&'static [Def]> {
         "lua51_os"      => Some(LUA51_OS_DEFS),
         "lua51_debug"   => Some(LUA51_DEBUG_DEFS),
 
+        "lua52"         => Some(LUA52_DEFS),
+        "lua52_base"    => Some(LUA52_BASE_DEFS),
+        "lua52_package" => Some(LUA52_PACKAGE_DEFS),
+        "lua52_table"   => Some(LUA52_TABLE_DEFS),
+        "lua52_bit32"   => Some(LUA52_BIT32_DEFS),
+
+        "lua53"         => Some(LUA53_DEFS),
+        "lua53_base"    => Some(LUA53_BASE_DEFS),
+        "lua53_package" => Some(LUA53_PACKAGE_DEFS),
+        "lua53_string"  => Some(LUA53_STRING_DEFS),
+        "lua53_table"   => Some(LUA53_TABLE_DEFS),
+        "lua53_math"    => Some(LUA53_MATH_DEFS),
+        "lua53_utf8"    => Some(LUA53_UTF8_DEFS),
+
+        "lua54"         => Some(LUA54_DEFS),
+        "lua54_base"    => Some(LUA54_BASE_DEFS),
+
+        "luajit"        => Some(LUAJIT_DEFS),
+        "luajit_bit"    => Some(LUAJIT_BIT_DEFS),
+        "luajit_jit"    => Some(LUAJIT_JIT_DEFS),
+        "luajit_ffi"    => Some(LUAJIT_FFI_DEFS),
+
         // only internally used
         "internal kailua_test" => Some(KAILUA_TEST_DEFS),
 
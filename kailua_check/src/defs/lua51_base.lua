@@ -23,10 +23,9 @@
 --#     function(object: any) --> table
 --#
 --# assume global `ipairs`:
---#     [generic_pairs]
---#     function(t: vector<const WHATEVER>) -->
---#         (function(vector<const WHATEVER>, integer) --> (integer?, any),
---#          vector<const WHATEVER>, integer)
+--#     function<T>(t: vector<const T>) -->
+--#         (function(vector<const T>, integer) --> (integer?, T),
+--#          vector<const T>, integer)
 --#
 --# -- TODO sequence conditional union: (function) | (nil, string)
 --# assume global `load`:
@@ -72,7 +71,7 @@
 --#     function(f: function|integer?, table: table) --> function
 --#
 --# assume global `setmetatable`:
---#     function(table: table, metatable: any?) --> table
+--#     [setmetatable] function(table: table, metatable: table?) --> table
 --#
 --# assume global `tonumber`:
 --#     function(e: any, base: integer?) --> number
@@ -98,14 +97,12 @@
 --#
 --# assume global `coroutine`:
 --#     {
---#         -- TODO genericity
---#         `create`: function(f: function) --> thread;
---#         `resume`: function(co: thread, any...) --> (boolean, any...);
+--#         `create`: [coroutine_create] function(f: function) --> thread;
+--#         `resume`: [coroutine_resume] function(co: thread, any...) --> (boolean, any...);
 --#         `running`: function() --> thread;
 --#         `status`: function(co: thread) --> string;
---#         -- TODO genericity
---#         `wrap`: function(f: function) --> thread;
---#         `yield`: function(any...) --> (any...);
+--#         `wrap`: [coroutine_wrap] function(f: function) --> function;
+--#         `yield`: [coroutine_yield] function(any...) --> (any...);
 --#         ...
 --#     }
 
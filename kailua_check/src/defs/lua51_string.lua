@@ -5,20 +5,25 @@
 --#         `byte`: function(s: string, i: integer?, j: integer?) --> (integer...);
 --#         `char`: function(integer...) --> string;
 --#         `dump`: function(`function`: function) --> string;
---#         -- TODO repeating return types can contain integer when the pattern contains `()`
---#         `find`: function(s: string, pattern: string, init: integer?, plain: boolean?) -->
---#                          (integer, integer, string...);
---#         `format`: function(formatstring: string, any...) --> string;
---#         `gmatch`: function(s: string, pattern: string) --> function() --> string?;
---#         -- TODO have to constrain the function argument, but not easy
+--#         -- the return types are refined when the pattern is a string literal
+--#         `find`: [string_find]
+--#                 function(s: string, pattern: string, init: integer?, plain: boolean?) -->
+--#                     (integer, integer, string...);
+--#         `format`: [string_format] function(formatstring: string, any...) --> string;
+--#         `gmatch`: [string_gmatch]
+--#                 function(s: string, pattern: string) --> function(any...) --> string?;
+--#         -- TODO the function receives integers for position captures (`()`)
 --#         `gsub`: function(s: string, pattern: string,
---#                          repl: string | map<string, string> |
---#                                (function(WHATEVER...) --> string),
+--#                          repl: string | map<string, string>,
+--#                          n: integer?) --> string &
+--#                 function(s: string, pattern: string,
+--#                          repl: function(string, WHATEVER...) --> (string|number)?,
 --#                          n: integer?) --> string;
 --#         `len`: function(s: string) --> integer;
 --#         `lower`: function(s: string) --> string;
---#         -- TODO repeating return types can contain integer when the pattern contains `()`
---#         `match`: function(s: string, pattern: string, init: integer?) --> (string...);
+--#         -- the return types are refined when the pattern is a string literal
+--#         `match`: [string_match]
+--#                 function(s: string, pattern: string, init: integer?) --> (string...);
 --#         `rep`: function(s: string, n: integer) --> string;
 --#         `reverse`: function(s: string) --> string;
 --#         `sub`: function(s: string, i: integer, j: integer?) --> string;
@@ -0,0 +1,24 @@
+-- definitions for Lua 5.2 package library
+
+--# assume global `module`:
+--#     [become_module] function(name: string, function(table)...)
+--#
+--# assume global `require`:
+--#     [require] function(modname: string) --> any
+--#
+--# assume global `package`:
+--#     {
+--#         `config`: string;
+--#         `cpath`: [package_cpath] string;
+--#         `loaded`: map<string, table>;
+--#         `loadlib`: [geval] function(libname: string, funcname: string);
+--#         `path`: [package_path] string;
+--#         `preload`: vector<function(string) --> (function|string)?>;
+--#         `searchers`: vector<function(string) --> (function|string)?>;
+--#         `searchpath`: function(name: string, path: string, sep: string?,
+--#                                rep: string?) --> (string?, string?);
+--#         -- TODO error type not yet supported (should it be a slot?)
+--#         --`seeall`: error "package.seeall is discouraged, use _G instead";
+--#         ...
+--#     }
+
@@ -0,0 +1,38 @@
+-- definitions for Lua 5.3 string library
+
+--# assume global `string`:
+--#     [string_meta] {
+--#         `byte`: function(s: string, i: integer?, j: integer?) --> (integer...);
+--#         `char`: function(integer...) --> string;
+--#         `dump`: function(`function`: function, strip: boolean?) --> string;
+--#         -- the return types are refined when the pattern is a string literal
+--#         `find`: [string_find]
+--#                 function(s: string, pattern: string, init: integer?, plain: boolean?) -->
+--#                     (integer, integer, string...);
+--#         `format`: [string_format] function(formatstring: string, any...) --> string;
+--#         `gmatch`: [string_gmatch]
+--#                 function(s: string, pattern: string) --> function(any...) --> string?;
+--#         -- TODO the function receives integers for position captures (`()`)
+--#         `gsub`: function(s: string, pattern: string,
+--#                          repl: string | map<string, string>,
+--#                          n: integer?) --> string &
+--#                 function(s: string, pattern: string,
+--#                          repl: function(string, WHATEVER...) --> (string|number)?,
+--#                          n: integer?) --> string;
+--#         `len`: function(s: string) --> integer;
+--#         `lower`: function(s: string) --> string;
+--#         -- the return types are refined when the pattern is a string literal
+--#         `match`: [string_match]
+--#                 function(s: string, pattern: string, init: integer?) --> (string...);
+--#         -- TODO the argument types depend on the format string
+--#         `pack`: function(fmt: string, any...) --> string;
+--#         `packsize`: function(fmt: string) --> integer;
+--#         `rep`: function(s: string, n: integer, sep: string?) --> string;
+--#         `reverse`: function(s: string) --> string;
+--#         `sub`: function(s: string, i: integer, j: integer?) --> string;
+--#         -- TODO the return types depend on the format string
+--#         `unpack`: function(fmt: string, s: string, pos: integer?) --> (any...);
+--#         `upper`: function(s: string) --> string;
+--#         ...
+--#     }
+
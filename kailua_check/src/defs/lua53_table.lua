@@ -0,0 +1,20 @@
+-- definitions for Lua 5.3 table library
+
+--# assume global `table`:
+--#     {
+--#         `concat`: function(table: vector<const string|number>, sep: string?,
+--#                            i: integer?, j: integer?) --> string;
+--#         -- TODO ah fuck, needs overloading with
+--#         --      function<T>(table: vector<T>, pos: integer, value: T)
+--#         `insert`: function<T>(table: vector<T>, value: T);
+--#         `move`: function(a1: table, f: integer, e: integer, t: integer,
+--#                          a2: table?) --> table;
+--#         `pack`: function(any...) --> { n: integer, ... };
+--#         `remove`: function<T>(table: vector<T>, pos: integer?) --> T;
+--#         -- TODO genericity
+--#         `unpack`: function(list: table, i: integer?, j: integer?) --> (any...);
+--#         `sort`: function<T>(table: vector<T>,
+--#                             comp: (function(T, T) --> boolean)?);
+--#         ...
+--#     }
+
@@ -0,0 +1,14 @@
+-- definitions for Lua 5.3 utf8 library
+
+--# assume global `utf8`:
+--#     {
+--#         `char`: function(integer...) --> string;
+--#         `charpattern`: string;
+--#         `codes`: function(s: string) -->
+--#                           (function(string, integer) --> (integer?, integer), string, integer);
+--#         `codepoint`: function(s: string, i: integer?, j: integer?) --> (integer...);
+--#         `len`: function(s: string, i: integer?, j: integer?) --> (integer?, integer?);
+--#         `offset`: function(s: string, n: integer, i: integer?) --> integer?;
+--#         ...
+--#     }
+
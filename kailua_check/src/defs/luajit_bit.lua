@@ -0,0 +1,21 @@
+-- definitions for LuaJIT bit library
+
+--# assume global `bit`:
+--#     {
+--#         `arshift`: function(x: integer, n: integer) --> integer;
+--#         `band`: function(x: integer, integer...) --> integer;
+--#         `bnot`: function(x: integer) --> integer;
+--#         `bor`: function(x: integer, integer...) --> integer;
+--#         `bswap`: function(x: integer) --> integer;
+--#         `bxor`: function(x: integer, integer...) --> integer;
+--#         `lshift`: function(x: integer, n: integer) --> integer;
+--#         `rol`: function(x: integer, n: integer) --> integer;
+--#         `ror`: function(x: integer, n: integer) --> integer;
+--#         `rshift`: function(x: integer, n: integer) --> integer;
+--#         `tobit`: function(x: number) --> integer;
+--#         `tohex`: function(x: integer, n: integer?) --> string;
+--#         ...
+--#     }
+
+-- also available as `require 'bit'`
+return bit
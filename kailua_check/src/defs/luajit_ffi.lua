@@ -0,0 +1,33 @@
+-- definitions for LuaJIT ffi library
+
+--# -- every cdata (including ctypes) is a tagged userdata.
+--# -- C declarations are not parsed, so fields and calls to cdata are not checked.
+--# type global cdata = [cdata] userdata
+--#
+--# -- only available as `require 'ffi'`
+local ffi = {}
+--# assume `ffi`:
+--#     {
+--#         `C`: WHATEVER;
+--#         `abi`: function(param: string) --> boolean;
+--#         `alignof`: function(ct: string | cdata) --> integer;
+--#         `arch`: string;
+--#         `cast`: function(ct: string | cdata, init: any) --> cdata;
+--#         `cdef`: function(def: string);
+--#         `copy`: function(dst: cdata, src: cdata | string, len: integer?);
+--#         `errno`: function(newerr: integer?) --> integer;
+--#         `fill`: function(dst: cdata, len: integer, c: integer?);
+--#         `gc`: function(cdata: cdata, finalizer: function?) --> cdata;
+--#         `istype`: function(ct: string | cdata, obj: any) --> boolean;
+--#         `load`: function(name: string, `global`: boolean?) --> WHATEVER;
+--#         `metatype`: function(ct: string | cdata, metatable: table) --> cdata;
+--#         `new`: function(ct: string | cdata, any...) --> cdata;
+--#         `offsetof`: function(ct: string | cdata, field: string) --> (integer?, integer...);
+--#         `os`: string;
+--#         `sizeof`: function(ct: string | cdata, nelem: integer?) --> integer?;
+--#         `string`: function(ptr: cdata, len: integer?) --> string;
+--#         `typeof`: function(ct: string | cdata, any...) --> cdata;
+--#         ...
+--#     }
+
+return ffi
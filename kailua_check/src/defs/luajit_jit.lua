@@ -0,0 +1,22 @@
+-- definitions for LuaJIT jit library
+
+--# assume global `jit`:
+--#     {
+--#         `arch`: string;
+--#         `attach`: function(f: function, event: string?);
+--#         `flush`: function(f: (function | boolean)?, recursive: boolean?);
+--#         `off`: function(f: (function | boolean)?, recursive: boolean?);
+--#         `on`: function(f: (function | boolean)?, recursive: boolean?);
+--#         `opt`: {
+--#             `start`: function(string...);
+--#             ...
+--#         };
+--#         `os`: string;
+--#         `status`: function() --> (boolean, string...);
+--#         `version`: string;
+--#         `version_num`: integer;
+--#         ...
+--#     }
+
+-- also available as `require 'jit'`
+return jit
@@ -1,13 +1,15 @@
 //! The type checker environment.
 
+use std::mem;
 use std::ops;
 use std::str;
 use std::fmt;
 use std::result;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
 use std::rc::Rc;
 use std::collections::{hash_map, HashMap, HashSet};
 use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
 
 use kailua_env::{self, Span, Spanned, WithLoc, ScopedId, ScopeMap, SpanMap};
@@ -20,9 +22,9 @@ use kailua_types::ty::{Ty, TySeq, Nil, T, Slot, SpannedSlotSeq, F, TVar, Lattice
 use kailua_types::ty::{TypeContext, TypeResolver, ClassId, ClassSystemId, Class};
 use kailua_types::ty::{Tables, Key};
 use kailua_types::ty::flags::*;
-use kailua_types::env::{Types, ClassProvider};
+use kailua_types::env::{Types, TypesSnapshot, ClassProvider};
 use defs::get_defs;
-use class_system::ClassSystem;
+use class_system::{ClassSystem, Visibility};
 use class_system::dumb::DumbClassSystem;
 use options::Options;
 use check::Checker;
@@ -161,6 +163,24 @@ pub struct Frame {
 
     /// Return types.
     pub returns: Returns<TySeq>,
+
+    /// Coroutine-specific information, if the function is known to be a coroutine body.
+    pub coroutine: Option<CoroutineFrame>,
+
+    /// A class whose method is being checked, if any.
+    ///
+    /// Functions nested in a method inherit this, so they can access private members as well.
+    pub class: Option<ClassId>,
+}
+
+/// Additional information for a function frame which is a coroutine body.
+#[derive(Clone, Debug)]
+pub struct CoroutineFrame {
+    /// Argument types given to `coroutine.resume`, which are returned by `coroutine.yield`.
+    pub args: TySeq,
+
+    /// Types given to `coroutine.yield` so far, implicitly unioned.
+    pub yields: Option<TySeq>,
 }
 
 /// A name definition.
@@ -210,11 +230,25 @@ pub struct TypeDef {
 
     /// The type.
     pub ty: Ty,
+
+    /// Type variables for type parameters, empty if the type is not generic.
+    ///
+    /// They appear in `ty` and get substituted whenever the type is used with type arguments.
+    pub tparams: Vec<TVar>,
+}
+
+/// A narrowed variable, to be restored when the scope ends.
+#[derive(Clone, Debug)]
+struct Narrowing {
+    id: Id,
+    prev: NameSlot,
+    narrowed: Slot,
+    outermost: bool, // true if the variable was not narrowed before
 }
 
 /// A scope.
 ///
-/// This is currently used to track the function frame and type names.
+/// This is currently used to track the function frame, type names and narrowed variables.
 /// Local names are resolved at the parser level so it can be uniquely identified.
 /// In the future type names will be also handled in the similar manner,
 /// removing the needs for this type.
@@ -222,15 +256,16 @@ pub struct TypeDef {
 pub struct Scope {
     frame: Option<Frame>,
     types: HashMap<Name, TypeDef>,
+    narrowings: Vec<Narrowing>,
 }
 
 impl Scope {
     pub fn new() -> Scope {
-        Scope { frame: None, types: HashMap::new() }
+        Scope { frame: None, types: HashMap::new(), narrowings: Vec::new() }
     }
 
     pub fn new_function(frame: Frame) -> Scope {
-        Scope { frame: Some(frame), types: HashMap::new() }
+        Scope { frame: Some(frame), types: HashMap::new(), narrowings: Vec::new() }
     }
 
     pub fn get_frame<'a>(&'a self) -> Option<&'a Frame> {
@@ -247,8 +282,9 @@ impl Scope {
     }
 
     // the caller should check for the outermost types first
-    pub fn put_type(&mut self, name: Spanned<Name>, ty: Ty) -> bool {
-        self.types.insert(name.base, TypeDef { span: name.span, ty: ty }).is_none()
+    pub fn put_type(&mut self, name: Spanned<Name>, ty: Ty, tparams: Vec<TVar>) -> bool {
+        let def = TypeDef { span: name.span, ty: ty, tparams: tparams };
+        self.types.insert(name.base, def).is_none()
     }
 }
 
@@ -385,6 +421,52 @@ impl ClassProvider for ClassContext {
             false
         }
     }
+
+    fn list_class_fields(&self, cls: Class, f: &mut FnMut(&Key, &Slot) -> result::Result<(), ()>)
+        -> result::Result<(), ()>
+    {
+        let cid = match cls {
+            Class::Prototype(cid) | Class::Instance(cid) => cid,
+        };
+        let inner = self.inner.read();
+        if let Some(&(_, ref system)) = inner.class_systems.get((cid.0).0 as usize) {
+            system.list_fields(cls, f)
+        } else {
+            Ok(())
+        }
+    }
+}
+
+// a report receiver which suppresses reports during trials (see `Context::begin_trial`),
+// only recording if any error has been reported.
+struct TrialReport<R> {
+    report: R,
+    trials: usize,
+    failed: AtomicBool,
+}
+
+impl<R: Report> Report for TrialReport<R> {
+    fn message_locale(&self) -> Locale {
+        self.report.message_locale()
+    }
+
+    fn add_span(&self, k: Kind, s: Span, m: &Localize) -> Result<()> {
+        if self.trials > 0 {
+            if k >= Kind::Error {
+                self.failed.store(true, Ordering::Relaxed);
+            }
+            return Ok(());
+        }
+        self.report.add_span(k, s, m)
+    }
+}
+
+/// A tentative checking state, made by `Context::begin_trial`.
+pub struct Trial {
+    ids: HashMap<Id, NameDef>,
+    types: TypesSnapshot,
+    spanned_slots: SpanMap<Slot>,
+    outer_failed: bool,
 }
 
 /// The global context, which also contains the type context.
@@ -393,7 +475,7 @@ impl ClassProvider for ClassContext {
 /// Due to the presence of a report receiver this is not easily shared or sent across threads;
 /// `Context::into_output` will give a report-free type that is suitable for analysis.
 pub struct Context<R> {
-    report: R,
+    report: TrialReport<R>,
     output: Output,
 }
 
@@ -426,7 +508,7 @@ impl<R: Report> Context<R> {
         let locale = report.message_locale();
         let classes = ClassContext::new();
         let mut ctx = Context {
-            report: report,
+            report: TrialReport { report: report, trials: 0, failed: AtomicBool::new(false) },
             output: Output {
                 ids: HashMap::new(),
                 scope_maps: Vec::new(),
@@ -441,13 +523,41 @@ impl<R: Report> Context<R> {
         };
 
         // it is fine to return from the top-level, so we treat it as like a function frame
-        let global_frame = Frame { vararg: None, returns: Returns::None };
+        let global_frame = Frame {
+            vararg: None, returns: Returns::None, coroutine: None, class: None,
+        };
         ctx.global_scope.frame = Some(global_frame);
         ctx
     }
 
     pub fn report(&self) -> &R {
-        &self.report
+        &self.report.report
+    }
+
+    /// Begins a tentative checking, which can be later reverted by `Context::end_trial`.
+    ///
+    /// Every report is suppressed until the trial ends, and name definitions,
+    /// constraints to types and spanned slots are restored at the end.
+    /// Slots themselves are not restored, so the trial should not assign to
+    /// existing variables or fields if possible.
+    pub fn begin_trial(&mut self) -> Trial {
+        self.report.trials += 1;
+        Trial {
+            ids: self.output.ids.clone(),
+            types: self.output.types.snapshot(),
+            spanned_slots: self.output.spanned_slots.clone(),
+            outer_failed: self.report.failed.swap(false, Ordering::Relaxed),
+        }
+    }
+
+    /// Ends a tentative checking made by `Context::begin_trial`.
+    /// Returns true if any error has been reported during the trial.
+    pub fn end_trial(&mut self, trial: Trial) -> bool {
+        self.report.trials -= 1;
+        self.output.ids = trial.ids;
+        self.output.types.restore(trial.types);
+        self.output.spanned_slots = trial.spanned_slots;
+        self.report.failed.swap(trial.outer_failed, Ordering::Relaxed)
     }
 
     pub fn open_library(&mut self, name: Spanned<&[u8]>, opts: Rc<RefCell<Options>>) -> Result<()> {
@@ -458,8 +568,12 @@ impl<R: Report> Context<R> {
                     // the built-in code is parsed independently and has no usable span
                     let chunk = def.to_chunk();
                     let mut env = Env::new(self, opts.clone(), chunk.map);
-                    let mut checker = Checker::new(&mut env);
-                    checker.visit(&chunk.block)?
+                    Checker::new(&mut env).visit(&chunk.block)?;
+
+                    // some libraries are also available as preloaded modules
+                    if let Some(module) = def.module {
+                        env.return_from_module(module.as_bytes(), false, name.span)?;
+                    }
                 }
             }
         } else {
@@ -494,11 +608,11 @@ impl<R: Report> Context<R> {
         cls.make_class(csid, argtys, outerspan, &mut self.output.types, &self.report)
     }
 
-    pub fn assume_class(&mut self, csid: ClassSystemId, parent: Option<Spanned<ClassId>>,
+    pub fn assume_class(&mut self, csid: ClassSystemId, parents: Vec<Spanned<ClassId>>,
                         outerspan: Span) -> Result<Option<ClassId>> {
         let classes = self.output.classes.inner.read();
         let cls = classes.get(csid).expect("bad class system id");
-        cls.assume_class(csid, parent, outerspan, &mut self.output.types, &self.report)
+        cls.assume_class(csid, parents, outerspan, &mut self.output.types, &self.report)
     }
 
     pub fn name_class(&mut self, cid: ClassId, name: Spanned<Name>) -> Result<()> {
@@ -513,6 +627,13 @@ impl<R: Report> Context<R> {
         Ok(())
     }
 
+    pub fn include_class_mixin(&mut self, cid: ClassId, mixin: &Spanned<Slot>,
+                               expspan: Span) -> Result<()> {
+        let classes = self.output.classes.inner.read();
+        let c = classes.get(cid.0).expect("bad class system id");
+        c.include_mixin(cid, mixin, expspan, &mut self.output.types, &self.report)
+    }
+
     pub fn index_class_rval(&mut self, cls: Class, key: Spanned<&Key>,
                             expspan: Span) -> Result<Option<Slot>> {
         let classes = self.output.classes.inner.read();
@@ -527,6 +648,25 @@ impl<R: Report> Context<R> {
         c.index_lval(cls, key, expspan, hint, &mut self.output.types, &self.report)
     }
 
+    pub fn class_field_owner(&self, cls: Class, key: &Key) -> ClassId {
+        let classes = self.output.classes.inner.read();
+        let c = classes.get(cls.system()).expect("bad class system id");
+        c.field_owner(cls, key)
+    }
+
+    pub fn class_field_visibility(&self, cls: Class, key: &Key) -> Visibility {
+        let classes = self.output.classes.inner.read();
+        let c = classes.get(cls.system()).expect("bad class system id");
+        c.field_visibility(cls, key)
+    }
+
+    pub fn index_class_metamethod(&mut self, cls: Class, name: &[u8],
+                                  expspan: Span) -> Result<Option<Slot>> {
+        let classes = self.output.classes.inner.read();
+        let c = classes.get(cls.system()).expect("bad class system id");
+        c.index_metamethod(cls, name, expspan, &mut self.output.types, &self.report)
+    }
+
     pub fn into_output(self) -> Output {
         self.output
     }
@@ -668,6 +808,14 @@ pub struct Env<'ctx, R: 'ctx> {
     scopes: Vec<Scope>,
     // separate from scoped types, `--# type` will set both
     exported_types: HashMap<Name, TypeDef>,
+    // the original slots of narrowed variables, which are used for the assignment
+    narrowed: HashMap<Id, Slot>,
+    // type names being resolved by `resolve_named_types`, type variables temporarily standing
+    // for those types, whether each type variable has been used or not,
+    // and the (unsubstituted) definition if the type has been already resolved
+    pending_types: Vec<(Spanned<Name>, TVar, Cell<bool>, Option<Ty>)>,
+    // a generic type name being resolved by `resolve_generic_type` and its type parameters
+    pending_generic_type: Option<(Spanned<Name>, Vec<TVar>)>,
 }
 
 impl<'ctx, R: Report> Env<'ctx, R> {
@@ -675,7 +823,9 @@ impl<'ctx, R: Report> Env<'ctx, R> {
                map: ScopeMap<Name>) -> Env<'ctx, R> {
         let map_index = context.scope_maps.len();
         context.scope_maps.push(map);
-        let global_frame = Frame { vararg: None, returns: Returns::None };
+        let global_frame = Frame {
+            vararg: None, returns: Returns::None, coroutine: None, class: None,
+        };
         Env {
             context: context,
             opts: opts,
@@ -683,6 +833,9 @@ impl<'ctx, R: Report> Env<'ctx, R> {
             // we have local variables even at the global position, so we need at least one Scope
             scopes: vec![Scope::new_function(global_frame)],
             exported_types: HashMap::new(),
+            narrowed: HashMap::new(),
+            pending_types: Vec::new(),
+            pending_generic_type: None,
         }
     }
 
@@ -721,6 +874,25 @@ impl<'ctx, R: Report> Env<'ctx, R> {
         assert!(self.scopes.len() > 1);
         let scope = self.scopes.pop().unwrap();
         debug!("leaving from a scope {:#?}", scope);
+
+        // undo narrowings in the reverse order.
+        // if the variable has been assigned or assumed since then, the slot is already replaced
+        // and should not be restored.
+        for narrowing in scope.narrowings.into_iter().rev() {
+            let def = self.context.ids.get_mut(&narrowing.id).expect("narrowed var disappeared");
+            let unchanged = match def.slot {
+                NameSlot::Set(ref slot) => {
+                    &*slot.unlift() as *const _ == &*narrowing.narrowed.unlift() as *const _
+                }
+                _ => false,
+            };
+            if unchanged {
+                def.slot = narrowing.prev;
+                if narrowing.outermost {
+                    self.narrowed.remove(&narrowing.id);
+                }
+            }
+        }
     }
 
     /// Returns a pair of type flags that is an exact lower and upper bound for that type.
@@ -1046,7 +1218,7 @@ impl<'ctx, R: Report> Env<'ctx, R> {
             Id::Local(..) => self.current_scope_mut(),
             Id::Global(..) => self.global_scope_mut(),
         };
-        let ret = scope.put_type(name, Ty::new(T::Class(Class::Instance(cid))));
+        let ret = scope.put_type(name, Ty::new(T::Class(Class::Instance(cid))), Vec::new());
         assert!(ret, "failed to insert the type");
 
         Ok(())
@@ -1123,6 +1295,31 @@ impl<'ctx, R: Report> Env<'ctx, R> {
         Ok(info)
     }
 
+    /// Replaces the slot of an initialized variable with a narrower slot `narrowed`,
+    /// until the current scope ends or the variable gets assigned.
+    ///
+    /// The assignment is checked against the original slot and undoes the narrowing.
+    pub fn narrow_var(&mut self, nameref: &Spanned<NameRef>, narrowed: Slot) {
+        let id = self.id_from_nameref(nameref).base;
+        debug!("narrowing a variable {} to {:?}", id.display(&self.context), narrowed);
+
+        let prev = {
+            let def = self.context.ids.get_mut(&id).expect("Env::narrow_var with an undefined var");
+            if !def.slot.set() {
+                return;
+            }
+            mem::replace(&mut def.slot, NameSlot::Set(narrowed.clone()))
+        };
+
+        let outermost = !self.narrowed.contains_key(&id);
+        if outermost {
+            self.narrowed.insert(id.clone(), prev.slot().unwrap().clone());
+        }
+        self.current_scope_mut().narrowings.push(Narrowing {
+            id: id, prev: prev, narrowed: narrowed, outermost: outermost,
+        });
+    }
+
     /// Assigns to a global or local variable with a right-hand-side type of `info`.
     ///
     /// This may create a new global variable if there is no variable with that name.
@@ -1133,6 +1330,11 @@ impl<'ctx, R: Report> Env<'ctx, R> {
                          info: Spanned<Slot>) -> Result<Slot> {
         let id = self.id_from_nameref(nameref);
 
+        // the assignment is always checked against the declared type, thus undoes the narrowing
+        if let Some(origslot) = self.narrowed.remove(&id.base) {
+            self.context.ids.get_mut(&id.base).unwrap().slot = NameSlot::Set(origslot);
+        }
+
         let (previnfo, prevset, needslotassign) = if self.context.ids.contains_key(&id.base) {
             let mut def = self.context.ids.get_mut(&id.base).unwrap();
             let (previnfo, prevset, needslotassign) = match def.slot {
@@ -1191,6 +1393,7 @@ impl<'ctx, R: Report> Env<'ctx, R> {
         let varname = id.name(self.context).clone().with_loc(name);
         let info = info.base.set_display(DisplayName::Var(varname));
 
+        self.narrowed.remove(&id);
         let mut def = self.context.ids.entry(id).or_insert_with(|| {
             NameDef { span: name.span, slot: NameSlot::None }
         });
@@ -1222,7 +1425,8 @@ impl<'ctx, R: Report> Env<'ctx, R> {
         self.get_named_local_type(name).or_else(|| self.get_named_global_type(name))
     }
 
-    pub fn define_local_type(&mut self, name: &Spanned<Name>, ty: Ty) -> Result<()> {
+    pub fn define_local_type(&mut self, name: &Spanned<Name>, ty: Ty,
+                             tparams: Vec<TVar>) -> Result<()> {
         if let Some(def) = self.get_named_local_type(name) {
             self.error(name, m::CannotRedefineLocalType { name: &name.base })
                 .note(def.span, m::AlreadyDefinedType {})
@@ -1235,13 +1439,19 @@ impl<'ctx, R: Report> Env<'ctx, R> {
             return Ok(());
         }
 
-        let ty = ty.and_display(DisplayName::Type(name.clone()));
-        let ret = self.current_scope_mut().put_type(name.clone(), ty);
+        // generic types are displayed structurally when instantiated
+        let ty = if tparams.is_empty() {
+            ty.and_display(DisplayName::Type(name.clone()))
+        } else {
+            ty
+        };
+        let ret = self.current_scope_mut().put_type(name.clone(), ty, tparams);
         assert!(ret, "failed to insert the type");
         Ok(())
     }
 
-    pub fn define_global_type(&mut self, name: &Spanned<Name>, ty: Ty) -> Result<()> {
+    pub fn define_global_type(&mut self, name: &Spanned<Name>, ty: Ty,
+                              tparams: Vec<TVar>) -> Result<()> {
         if let Some(def) = self.get_named_local_type(name) {
             self.error(name, m::CannotRedefineLocalTypeAsGlobal { name: &name.base })
                 .note(def.span, m::AlreadyDefinedType {})
@@ -1254,13 +1464,18 @@ impl<'ctx, R: Report> Env<'ctx, R> {
             return Ok(());
         }
 
-        let ty = ty.and_display(DisplayName::Type(name.clone()));
-        let ret = self.global_scope_mut().put_type(name.clone(), ty);
+        let ty = if tparams.is_empty() {
+            ty.and_display(DisplayName::Type(name.clone()))
+        } else {
+            ty
+        };
+        let ret = self.global_scope_mut().put_type(name.clone(), ty, tparams);
         assert!(ret, "failed to insert the type");
         Ok(())
     }
 
-    pub fn define_and_export_type(&mut self, name: &Spanned<Name>, ty: Ty) -> Result<()> {
+    pub fn define_and_export_type(&mut self, name: &Spanned<Name>, ty: Ty,
+                                  tparams: Vec<TVar>) -> Result<()> {
         if let Some(def) = self.get_named_type(name) {
             self.error(name, m::CannotRedefineAndReexportType { name: &name.base })
                 .note(def.span, m::AlreadyDefinedType {})
@@ -1271,7 +1486,7 @@ impl<'ctx, R: Report> Env<'ctx, R> {
         // insert to the exported types (distinct from scoped types)
         let defspan = match self.exported_types.entry(name.base.clone()) {
             hash_map::Entry::Vacant(e) => {
-                e.insert(TypeDef { ty: ty.clone(), span: name.span });
+                e.insert(TypeDef { ty: ty.clone(), span: name.span, tparams: tparams.clone() });
                 None
             },
             hash_map::Entry::Occupied(e) => Some(e.get().span),
@@ -1287,15 +1502,146 @@ impl<'ctx, R: Report> Env<'ctx, R> {
         }
 
         // insert a locally scoped type
-        let ty = ty.and_display(DisplayName::Type(name.clone()));
-        let ret = self.current_scope_mut().put_type(name.clone(), ty);
+        let ty = if tparams.is_empty() {
+            ty.and_display(DisplayName::Type(name.clone()))
+        } else {
+            ty
+        };
+        let ret = self.current_scope_mut().put_type(name.clone(), ty, tparams);
         assert!(ret, "failed to insert the type");
         Ok(())
     }
 
+    /// Resolves a definition of the non-generic type named `name` with given closure,
+    /// where the definition can refer to itself. The span is used for error reporting.
+    ///
+    /// Self references are resolved in two steps: they are first resolved to a fresh type variable,
+    /// which gets substituted to a recursive named type (`T::Named`) once the definition is known.
+    /// The definition is returned as is if it doesn't refer to itself.
+    pub fn resolve_named_type<F>(&mut self, name: &Spanned<Name>, span: Span,
+                                 resolve: F) -> Result<Ty>
+        where F: FnOnce(&mut Self) -> Result<Ty>
+    {
+        let mut resolve = Some(resolve);
+        let mut tys = self.resolve_named_types(&[(name, span)], |env, _| {
+            let resolve = resolve.take().expect("resolve_named_types called the closure twice");
+            resolve(env)
+        })?;
+        Ok(tys.pop().expect("resolve_named_types returned no type"))
+    }
+
+    /// Resolves a definition of the generic type with given type parameters.
+    ///
+    /// Generic types cannot be recursive, so any reference to itself is reported as an error.
+    pub fn resolve_generic_type<F>(&mut self, name: &Spanned<Name>, tparams: Vec<TVar>,
+                                   resolve: F) -> Result<Ty>
+        where F: FnOnce(&mut Self) -> Result<Ty>
+    {
+        let prev = mem::replace(&mut self.pending_generic_type, Some((name.clone(), tparams)));
+        let ret = resolve(self);
+        self.pending_generic_type = prev;
+        ret
+    }
+
+    /// Same to `resolve_named_type`, but resolves definitions of multiple types at once
+    /// so that they can refer to each other. The closure is called with each index to `names`.
+    ///
+    /// A reference to the type resolved earlier is replaced with its definition,
+    /// so only references to itself and later types need a recursive named type.
+    /// Such references should be guarded by table, function or thread types.
+    pub fn resolve_named_types<F>(&mut self, names: &[(&Spanned<Name>, Span)],
+                                  mut resolve: F) -> Result<Vec<Ty>>
+        where F: FnMut(&mut Self, usize) -> Result<Ty>
+    {
+        let pending = names.iter().map(|&(name, _)| {
+            (name.clone(), self.context.types.gen_tvar(), Cell::new(false), None)
+        }).collect();
+        let prev = mem::replace(&mut self.pending_types, pending);
+        let mut tys = Vec::new();
+        for i in 0..names.len() {
+            match resolve(self, i) {
+                Ok(ty) => {
+                    self.pending_types[i].3 = Some(ty.clone());
+                    tys.push(ty);
+                }
+                Err(e) => {
+                    self.pending_types = prev;
+                    return Err(e);
+                }
+            }
+        }
+        let pending = mem::replace(&mut self.pending_types, prev);
+        if pending.iter().all(|&(_, _, ref used, _)| !used.get()) {
+            return Ok(tys);
+        }
+
+        let pending_index = |ty: &Ty| {
+            let tvar = ty.get_tvar();
+            pending.iter().position(|&(_, tv, _, _)| Some(tv) == tvar)
+        };
+
+        // a reference to itself or a later type is only valid when it's guarded
+        let mut bad = vec![false; tys.len()];
+        for (i, &(ref name, tvar, _, _)) in pending.iter().enumerate() {
+            let unguarded = match *tys[i] {
+                T::Intersection(ref tys) => tys.iter().filter_map(|ty| pending_index(ty)).min(),
+                _ => pending_index(&tys[i]),
+            };
+            if let Some(j) = unguarded {
+                bad[i] = true;
+                if j != i {
+                    // the reference to the later type is not guarded, so it is not yet defined
+                    let later = &pending[j].0.base;
+                    self.error(names[i].1, m::NoType { name: later }).done()?;
+                    continue;
+                }
+            }
+
+            // the type variables cannot be constrained at this point,
+            // so any constraint means that the reference has been unioned with other types
+            if bad[i] || self.get_tvar_exact_type(tvar).is_some() ||
+                         self.get_tvar_bounds(tvar) != (T_NONE, !T_NONE) {
+                bad[i] = true;
+                self.error(names[i].1, m::BadRecursiveType { name: &name.base }).done()?;
+            }
+        }
+
+        let types = &mut self.context.types;
+        let mut subst = HashMap::new();
+        let mut ids = Vec::new();
+        for (i, &(ref name, tvar, ref used, _)) in pending.iter().enumerate() {
+            if bad[i] {
+                subst.insert(tvar, Ty::dummy());
+                ids.push(None);
+            } else if used.get() {
+                let ty = &tys[i];
+                let id = types.gen_named_type(name);
+                let named = Ty::new(T::Named(id, (**ty).flags())).union_nil(ty.nil())
+                                                                   .with_tag(ty.tag());
+                subst.insert(tvar, named);
+                ids.push(Some(id));
+            } else {
+                ids.push(None);
+            }
+        }
+
+        let tys = tys.into_iter().zip(ids).enumerate().map(|(i, (ty, id))| {
+            if bad[i] {
+                return Ty::dummy();
+            }
+            let ty = ty.substitute(&subst, types);
+            if let Some(id) = id {
+                let name = pending[i].0.clone();
+                types.set_named_type(id, ty.clone().and_display(DisplayName::Type(name)));
+            }
+            ty
+        }).collect();
+        Ok(tys)
+    }
+
     pub fn redefine_global_type(&mut self, name: &Spanned<Name>, tyspan: Span) -> Result<()> {
-        let ty = if let Some(def) = self.get_named_local_type(name) {
-            def.ty.clone()
+        let (ty, tparams) = if let Some(def) = self.get_named_local_type(name) {
+            (def.ty.clone(), def.tparams.clone())
         } else if let Some(def) = self.get_named_global_type(name) {
             self.error(name, m::CannotRedefineGlobalType { name: &name.base })
                 .note(def.span, m::AlreadyDefinedType {})
@@ -1303,17 +1649,17 @@ impl<'ctx, R: Report> Env<'ctx, R> {
             return Ok(());
         } else {
             self.error(tyspan, m::NoType { name: &name.base }).done()?;
-            Ty::dummy()
+            (Ty::dummy(), Vec::new())
         };
 
-        let ret = self.global_scope_mut().put_type(name.clone(), ty);
+        let ret = self.global_scope_mut().put_type(name.clone(), ty, tparams);
         assert!(ret, "failed to insert the type");
         Ok(())
     }
 
     pub fn reexport_local_type(&mut self, name: &Spanned<Name>, tyspan: Span) -> Result<()> {
-        let ty = if let Some(def) = self.get_named_local_type(name) {
-            def.ty.clone()
+        let (ty, tparams) = if let Some(def) = self.get_named_local_type(name) {
+            (def.ty.clone(), def.tparams.clone())
         } else if let Some(def) = self.get_named_global_type(name) {
             self.error(name, m::CannotRedefineGlobalType { name: &name.base })
                 .note(def.span, m::AlreadyDefinedType {})
@@ -1321,13 +1667,13 @@ impl<'ctx, R: Report> Env<'ctx, R> {
             return Ok(());
         } else {
             self.error(tyspan, m::NoType { name: &name.base }).done()?;
-            Ty::dummy()
+            (Ty::dummy(), Vec::new())
         };
 
         // insert to the exported types
         let defspan = match self.exported_types.entry(name.base.clone()) {
             hash_map::Entry::Vacant(e) => {
-                e.insert(TypeDef { ty: ty.clone(), span: name.span });
+                e.insert(TypeDef { ty: ty, span: name.span, tparams: tparams });
                 None
             },
             hash_map::Entry::Occupied(e) => Some(e.get().span),
@@ -1352,7 +1698,7 @@ impl<'ctx, R: Report> Env<'ctx, R> {
                 return Ok(());
             }
 
-            let ret = self.current_scope_mut().put_type(name.with_loc(def.span), def.ty);
+            let ret = self.current_scope_mut().put_type(name.with_loc(def.span), def.ty, def.tparams);
             assert!(ret, "failed to insert the type");
         }
 
@@ -1408,6 +1754,23 @@ impl<'ctx, R: Report> TypeResolver for Env<'ctx, R> {
     }
 
     fn ty_from_name(&self, name: &Spanned<Name>) -> Result<Ty> {
+        for &(ref pending, tvar, ref used, ref resolved) in &self.pending_types {
+            if pending.base == name.base {
+                if let Some(ref ty) = *resolved {
+                    return Ok(ty.clone().and_display(DisplayName::Type(pending.clone())));
+                }
+                used.set(true);
+                return Ok(Ty::new(T::TVar(tvar)).and_display(DisplayName::Type(pending.clone())));
+            }
+        }
+
+        if let Some((ref pending, _)) = self.pending_generic_type {
+            if pending.base == name.base {
+                self.error(name, m::RecursiveGenericType { name: &name.base }).done()?;
+                return Ok(Ty::dummy());
+            }
+        }
+
         if let Some(def) = self.get_named_type(name) {
             Ok(def.ty.clone())
         } else {
@@ -1424,6 +1787,15 @@ impl<'ctx, R: Report> TypeResolver for Env<'ctx, R> {
             Ok(None)
         }
     }
+
+    fn type_params_from_name(&self, name: &Spanned<Name>) -> Vec<TVar> {
+        if let Some((ref pending, ref tparams)) = self.pending_generic_type {
+            if pending.base == name.base {
+                return tparams.clone();
+            }
+        }
+        self.get_named_type(name).map_or(Vec::new(), |def| def.tparams.clone())
+    }
 }
 
 #[test]
@@ -10,7 +10,8 @@
 //!   but the first `Env` should be given explicitly.
 //!
 //! * `kailua_check::options::Options` is a configurable portion of the type checker.
-//!   Currently it allows you to configure the `require` path and the actual loading process.
+//!   Currently it allows you to configure the `require` path, the actual loading process
+//!   and user-defined class systems.
 //!
 //! * `kailua_check::Checker` is the actual checker.
 //!   Due to the internal architecture, it also holds some side information
@@ -40,6 +41,7 @@ pub mod options;
 pub mod env;
 mod defs;
 mod class_system;
+mod strlib;
 mod check;
 
 /// Options to populate the execution environment before checking.
@@ -82,11 +82,35 @@ define_msg! { pub CannotRedefineTypeAsClass<'a> { name: &'a Name }:
     _    => "A type {name} is already defined",
 }
 
+define_msg! { pub BadRecursiveType<'a> { name: &'a Name }:
+    "ko" => "{name} 타입은 테이블, 함수나 스레드 타입 안에서만 자기 자신을 참조할 수 있으며 \
+             다른 타입과 합칠 수 없습니다",
+    _    => "A type {name} can only refer to itself inside table, function or thread types, \
+             and cannot be unioned with other types",
+}
+
+define_msg! { pub RecursiveGenericType<'a> { name: &'a Name }:
+    "ko" => "제너릭 타입 {name}은(는) 자기 자신을 참조할 수 없습니다",
+    _    => "A generic type {name} cannot refer to itself",
+}
+
 define_msg! { pub AlreadyDefinedType:
     "ko" => "이전 타입 선언은 여기에 있습니다",
     _    => "The type was originally defined here",
 }
 
+define_msg! { pub NonClosableLocal<'a> { ty: Slot<'a> }:
+    "ko" => "<close> 변수는 nil이나 false이거나 `__close` 메타메소드가 있어야 하는데 \
+             `{ty}` 타입이 주어졌습니다",
+    _    => "A <close> variable should be nil, false or have a `__close` metamethod, \
+             but it was given a type `{ty}`",
+}
+
+define_msg! { pub NonClosableLocalValue:
+    "ko" => "값은 여기에서 주어졌습니다",
+    _    => "The value was given here",
+}
+
 define_msg! { pub WrongUnaryOperand<'a> { op: &'static str, ty: Slot<'a> }:
     "ko" => "{op} 연산자를 `{ty}`에 적용할 수 없습니다",
     _    => "Cannot apply {op} operator to `{ty}`",
@@ -132,6 +156,16 @@ define_msg! { pub CallToWrongType<'a> { func: Ty<'a> }:
     _    => "The type `{func}` cannot be called",
 }
 
+define_msg! { pub CallToNoOverload<'a> { func: Ty<'a> }:
+    "ko" => "`{func}` 타입의 오버로드 중 주어진 인자로 호출할 수 있는 것이 없습니다",
+    _    => "No overload of the type `{func}` can be called with given arguments",
+}
+
+define_msg! { pub OverloadNotMatched<'a> { func: Ty<'a> }:
+    "ko" => "오버로드 `{func}`은(는) 맞지 않습니다",
+    _    => "The overload `{func}` does not match",
+}
+
 define_msg! { pub CallToAnyFunc<'a> { func: Ty<'a> }:
     "ko" => "타입이 `{func}`(이)라고만 알려져 있어서 호출할 수 없습니다. \
              타입을 더 구체적으로 명시하거나, 여의치 않으면 `--# assume`을 사용하십시오",
@@ -223,11 +257,27 @@ define_msg! { pub IndexToRecWithUnknownStr<'a> { tab: Slot<'a>, key: Ty<'a> }:
     _    => "Cannot index `{tab}` with `{key}`",
 }
 
+define_msg! { pub IndexToInterfaceWithUnknown<'a> { iface: Slot<'a>, key: Ty<'a> }:
+    "ko" => "`{iface}`에 `{key}`을(를) 키로 써서 인덱싱할 수 없습니다",
+    _    => "Cannot index `{iface}` with `{key}`",
+}
+
 define_msg! { pub IndexToClassWithUnknown<'a> { cls: Slot<'a>, key: Ty<'a> }:
     "ko" => "`{cls}`에 `{key}`을(를) 키로 써서 인덱싱할 수 없습니다",
     _    => "Cannot index `{cls}` with `{key}`",
 }
 
+define_msg! { pub PrivateFieldAccess<'a> { key: &'a Key, cls: Ty<'a> }:
+    "ko" => "비공개 필드 `{key}`는 `{cls}`의 메소드 안에서만 접근할 수 있습니다",
+    _    => "The private field `{key}` can be only accessed from methods of `{cls}`",
+}
+
+define_msg! { pub ProtectedFieldAccess<'a> { key: &'a Key, cls: Ty<'a> }:
+    "ko" => "보호된 필드 `{key}`는 `{cls}` 및 그 하위 클래스의 메소드 안에서만 접근할 수 있습니다",
+    _    => "The protected field `{key}` can be only accessed from methods of `{cls}` \
+             and its subclasses",
+}
+
 define_msg! { pub IndexToArrayWithNonInt<'a> { tab: Slot<'a>, key: Ty<'a> }:
     "ko" => "`{tab}`에 정수가 아닌 `{key}`을(를) 키로 써서 인덱싱할 수 없습니다",
     _    => "Cannot index an array `{tab}` with a non-integral key `{key}`",
@@ -240,6 +290,17 @@ define_msg! { pub IndexToAnyTable<'a> { tab: Slot<'a> }:
              specify more detailed type, or use `--# assume` as a last resort",
 }
 
+define_msg! { pub NonPrimitiveNewtype<'a> { ty: Ty<'a> }:
+    "ko" => "`--# newtype`은 속성이 없는 불리언, 숫자나 문자열 타입에만 쓸 수 있는데 `{ty}` 타입이 주어졌습니다",
+    _    => "`--# newtype` requires a boolean, number or string type without attributes, \
+             but got `{ty}`",
+}
+
+define_msg! { pub CannotUpdateReadonly<'a> { tab: Slot<'a> }:
+    "ko" => "읽기 전용인 `{tab}` 타입을 인덱싱해서 갱신할 수 없습니다",
+    _    => "Cannot update the readonly type `{tab}` by indexing",
+}
+
 define_msg! { pub CannotUpdate<'a> { tab: Slot<'a> }:
     "ko" => "변경할 수 없는 `{tab}` 타입을 인덱싱해서 갱신할 수 없습니다",
     _    => "Cannot update the immutable type `{tab}` by indexing",
@@ -297,11 +358,101 @@ define_msg! { pub BadRecursiveCall:
     _    => "A required type and the actual type of the recursive function is not compatible",
 }
 
+define_msg! { pub CannotExtendYieldType:
+    "ko" => "이 코루틴의 yield 타입을 암묵적으로 확장할 수 없습니다",
+    _    => "Cannot extend the implicit yield type of this coroutine",
+}
+
+define_msg! { pub CannotResumeThread<'a> { thread: Ty<'a> }:
+    "ko" => "`{thread}` 타입의 코루틴을 주어진 인자로 재개할 수 없습니다",
+    _    => "The coroutine of the type `{thread}` cannot be resumed with given arguments",
+}
+
 define_msg! { pub BuiltinGivenLessArgs<'a> { name: &'a str, nargs: usize }:
     "ko" => "`{name}` 내장 함수는 인자가 적어도 {nargs}개 필요합니다",
     _    => "`{name}` needs at least {nargs} argument(s)",
 }
 
+define_msg! { pub PatternEndsWithPercent:
+    "ko" => "패턴이 `%`로 끝납니다",
+    _    => "The pattern ends with `%`",
+}
+
+define_msg! { pub PatternMissingBracket:
+    "ko" => "패턴의 문자 클래스가 `]`로 닫히지 않았습니다",
+    _    => "The character class in the pattern is missing `]`",
+}
+
+define_msg! { pub PatternMissingBalanceArgs:
+    "ko" => "패턴의 `%b` 뒤에 문자가 두 개 있어야 합니다",
+    _    => "`%b` in the pattern should be followed by two characters",
+}
+
+define_msg! { pub PatternMissingFrontierSet:
+    "ko" => "패턴의 `%f` 뒤에 `[`가 있어야 합니다",
+    _    => "`%f` in the pattern should be followed by `[`",
+}
+
+define_msg! { pub PatternInvalidCaptureIndex { index: u8 }:
+    "ko" => "패턴의 `%{index}`가 닫힌 캡처를 가리키지 않습니다",
+    _    => "`%{index}` in the pattern does not refer to a closed capture",
+}
+
+define_msg! { pub PatternInvalidCapture:
+    "ko" => "패턴의 `)`에 대응되는 `(`가 없습니다",
+    _    => "`)` in the pattern has no matching `(`",
+}
+
+define_msg! { pub PatternUnfinishedCapture:
+    "ko" => "패턴의 `(`가 닫히지 않았습니다",
+    _    => "`(` in the pattern is not closed",
+}
+
+define_msg! { pub PatternTooManyCaptures:
+    "ko" => "패턴의 캡처가 너무 많습니다",
+    _    => "The pattern has too many captures",
+}
+
+define_msg! { pub FormatRepeatedFlags<'a> { spec: &'a str }:
+    "ko" => "형식 문자열의 `{spec}`에 플래그가 너무 많습니다",
+    _    => "`{spec}` in the format string has too many flags",
+}
+
+define_msg! { pub FormatWidthTooLong<'a> { spec: &'a str }:
+    "ko" => "형식 문자열의 `{spec}`에 주어진 폭이나 정밀도가 너무 깁니다",
+    _    => "The width or precision of `{spec}` in the format string is too long",
+}
+
+define_msg! { pub FormatInvalidConversion<'a> { spec: &'a str }:
+    "ko" => "형식 문자열의 `{spec}`는(은) 올바른 지시자가 아닙니다",
+    _    => "`{spec}` in the format string is not a valid directive",
+}
+
+define_msg! { pub FormatMissingArg<'a> { spec: &'a str }:
+    "ko" => "형식 문자열의 `{spec}`에 대응되는 인자가 없습니다",
+    _    => "The argument for `{spec}` in the format string is missing",
+}
+
+define_msg! { pub FormatStringOrigin:
+    "ko" => "형식 문자열은 여기에 있습니다",
+    _    => "The format string is given here",
+}
+
+define_msg! { pub FormatExtraArgs { count: usize }:
+    "ko" => "형식 문자열은 인자를 {count}개만 사용하며, 나머지 인자는 무시됩니다",
+    _    => "The format string only uses {count} argument(s), and the rest is ignored",
+}
+
+define_msg! { pub FormatArgNotNumber<'a> { spec: &'a str }:
+    "ko" => "형식 문자열의 `{spec}`에 대응되는 인자는 숫자여야 합니다",
+    _    => "The argument for `{spec}` in the format string should be a number",
+}
+
+define_msg! { pub FormatArgNotString<'a> { spec: &'a str }:
+    "ko" => "형식 문자열의 `{spec}`에 대응되는 인자는 문자열이어야 합니다",
+    _    => "The argument for `{spec}` in the format string should be a string",
+}
+
 define_msg! { pub CannotOpenLibrary:
     "ko" => "`--# open` 명령에 주어진 내장 라이브러리 이름을 찾을 수 없습니다",
     _    => "Cannot find the built-in library name given to `--# open` directive",
@@ -386,6 +537,21 @@ define_msg! { pub PreviousStringMeta:
     _    => "A metatable for `string` type has been previously defined here",
 }
 
+define_msg! { pub StrNotInType<'a> { s: &'a Str, ty: Slot<'a> }:
+    "ko" => "문자열 {s}은(는) `{ty}` 타입에 속하지 않으므로 이 비교는 항상 같은 결과를 냅니다",
+    _    => "The string {s} is not a part of `{ty}`, so the comparison always has the same result",
+}
+
+define_msg! { pub NonExhaustiveIf<'a> { ty: Slot<'a>, missing: T<'a> }:
+    "ko" => "`if` 문이 `{ty}` 타입의 `{missing}` 경우를 처리하지 않습니다",
+    _    => "This `if` statement does not handle `{missing}` of `{ty}`",
+}
+
+define_msg! { pub NonExhaustiveIfVar:
+    "ko" => "이 변수가 `if` 문에서 비교되었습니다",
+    _    => "The variable is compared here",
+}
+
 #[cfg(feature = "warn_on_useless_conds")]
 define_msg! { pub IgnoredIfCase:
     "ko" => "`if` 문의 이 조건(들)은 실행되지 않습니다",
@@ -404,7 +570,6 @@ define_msg! { pub IfCaseWithFalsyCond:
     _    => "This condition always evaluates to a falsy value",
 }
 
-#[cfg(feature = "warn_on_dead_code")]
 define_msg! { pub DeadCode:
     "ko" => "이 코드는 실행되지 않을 것입니다",
     _    => "This code will never execute",
@@ -425,35 +590,40 @@ define_msg! { pub CannotNameUnknownClass<'a> { cls: Slot<'a> }:
     _    => "The type `{cls}` cannot be resolved to a single class so cannot be named",
 }
 
-define_msg! { pub NoCtor:
-    "ko" => "생성자(`init` 메소드)가 없이 `new` 메소드를 호출할 수 없습니다",
-    _    => "The `new` method cannot be called with no constructor (`init` method) defined",
+define_msg! { pub NoCtor<'a> { new: &'a str, init: &'a str }:
+    "ko" => "생성자(`{init}` 메소드)가 없이 `{new}` 메소드를 호출할 수 없습니다",
+    _    => "The `{new}` method cannot be called with no constructor (`{init}` method) defined",
 }
 
-define_msg! { pub CannotAccessCtorThruInstance:
-    "ko" => "생성자(`init` 메소드)는 클래스 인스턴스를 통해 접근할 수 없습니다",
-    _    => "The constructor (`init` method) should not be accessed through instances",
+define_msg! { pub CannotAccessCtorThruInstance<'a> { init: &'a str }:
+    "ko" => "생성자(`{init}` 메소드)는 클래스 인스턴스를 통해 접근할 수 없습니다",
+    _    => "The constructor (`{init}` method) should not be accessed through instances",
 }
 
-define_msg! { pub InexactInitMethod<'a> { init: Slot<'a> }:
-    "ko" => "생성자(`init` 메소드)의 타입 `{init}`이(가) 덜 추론되었습니다",
-    _    => "The type `{init}` of the constructor (`init` method) is not known enough to call",
+define_msg! { pub CannotAccessStaticThruInstance<'a> { key: &'a Key }:
+    "ko" => "정적 필드 `{key}`는 클래스 인스턴스를 통해 접근할 수 없습니다",
+    _    => "The static field `{key}` should not be accessed through instances",
 }
 
-define_msg! { pub NonFuncInitMethod<'a> { init: Slot<'a> }:
-    "ko" => "생성자(`init` 메소드)의 타입 `{init}`이(가) 함수가 아닙니다",
-    _    => "The type `{init}` of the constructor (`init` method) is not a function",
+define_msg! { pub InexactInitMethod<'a> { name: &'a str, init: Slot<'a> }:
+    "ko" => "생성자(`{name}` 메소드)의 타입 `{init}`이(가) 덜 추론되었습니다",
+    _    => "The type `{init}` of the constructor (`{name}` method) is not known enough to call",
 }
 
-define_msg! { pub OverloadedFuncInitMethod<'a> { init: Slot<'a> }:
-    "ko" => "생성자(`init` 메소드)의 타입 `{init}`이(가) 오버로딩되어 있습니다",
-    _    => "The type `{init}` of the constructor (`init` method) is overloaded",
+define_msg! { pub NonFuncInitMethod<'a> { name: &'a str, init: Slot<'a> }:
+    "ko" => "생성자(`{name}` 메소드)의 타입 `{init}`이(가) 함수가 아닙니다",
+    _    => "The type `{init}` of the constructor (`{name}` method) is not a function",
 }
 
-define_msg! { pub BadSelfInInitMethod<'a> { init: Slot<'a> }:
-    "ko" => "생성자(`init` 메소드)의 타입 `{init}`이(가) \
+define_msg! { pub OverloadedFuncInitMethod<'a> { name: &'a str, init: Slot<'a> }:
+    "ko" => "생성자(`{name}` 메소드)의 타입 `{init}`이(가) 오버로딩되어 있습니다",
+    _    => "The type `{init}` of the constructor (`{name}` method) is overloaded",
+}
+
+define_msg! { pub BadSelfInInitMethod<'a> { name: &'a str, init: Slot<'a> }:
+    "ko" => "생성자(`{name}` 메소드)의 타입 `{init}`이(가) \
              첫번째 인자로 올바른 타입을 가지지 않습니다",
-    _    => "The type `{init}` of the constructor (`init` method) \
+    _    => "The type `{init}` of the constructor (`{name}` method) \
              doesn't have a correct type for the first argument",
 }
 
@@ -462,9 +632,24 @@ define_msg! { pub ReservedNewMethod:
     _    => "`new` method is reserved and cannot be defined",
 }
 
-define_msg! { pub NoInheritanceInDumbClassSystem:
-    "ko" => "클래스 시스템에 속하지 않은 클래스는 상속이 지원되지 않습니다",
-    _    => "No inheritance is supported for classes without a class system",
+define_msg! { pub ReservedClassMember<'a> { key: &'a Key }:
+    "ko" => "`{key}` 필드는 클래스 시스템에 의해 예약되어 있으며 선언될 수 없습니다",
+    _    => "`{key}` field is reserved by the class system and cannot be defined",
+}
+
+define_msg! { pub NoMultipleInheritance:
+    "ko" => "이 클래스 시스템은 부모 클래스를 여럿 가지는 것을 지원하지 않습니다",
+    _    => "This class system does not support multiple parent classes",
+}
+
+define_msg! { pub DuplicateParentClass:
+    "ko" => "같은 부모 클래스가 여러 번 주어졌습니다",
+    _    => "The same parent class has been given more than once",
+}
+
+define_msg! { pub InconsistentMethodResolutionOrder:
+    "ko" => "부모 클래스들로부터 일관된 메소드 탐색 순서를 정할 수 없습니다",
+    _    => "Cannot determine a consistent method resolution order from the parent classes",
 }
 
 define_msg! { pub CannotCreateFieldDefinedInInstance<'a> { key: &'a Key }:
@@ -594,6 +779,21 @@ define_msg! { pub NoSuchPredefinedClassSystem<'a> { name: &'a Name }:
     _    => "{name} class system is not yet supported",
 }
 
+define_msg! { pub RulesToBuiltinClassSystem<'a> { name: &'a Name }:
+    "ko" => "{name} 클래스 시스템은 내장되어 있으므로 규칙을 지정할 수 없습니다",
+    _    => "{name} is a built-in class system and cannot be given rules",
+}
+
+define_msg! { pub UnknownClassSystemRule<'a> { name: &'a Name }:
+    "ko" => "{name} 클래스 시스템 규칙을 알 수 없어서 무시합니다",
+    _    => "{name} is an unknown class system rule and ignored",
+}
+
+define_msg! { pub BadClassSystemRuleValue<'a> { name: &'a Name }:
+    "ko" => "{name} 클래스 시스템 규칙에는 올바른 값 하나가 붙어야 합니다",
+    _    => "The class system rule {name} requires a single valid value",
+}
+
 define_msg! { pub TooManyClassSystems:
     "ko" => "클래스 시스템은 최대 256개까지 선언할 수 있습니다",
     _    => "There may be at most 256 class systems defined",
@@ -616,6 +816,16 @@ define_msg! { pub PreviousParentFieldType:
     _    => "Previous definition of the field type here",
 }
 
+define_msg! { pub AbstractMethodNotImplemented<'a> { key: &'a Key }:
+    "ko" => "추상 메소드 `{key}`가 구현되지 않았으므로 클래스의 인스턴스를 만들 수 없습니다",
+    _    => "Cannot instantiate the class because the abstract method `{key}` is not implemented",
+}
+
+define_msg! { pub AbstractMethodDeclaredHere:
+    "ko" => "추상 메소드는 여기에서 선언되었습니다",
+    _    => "The abstract method was declared here",
+}
+
 define_msg! { pub MissingParentClassForGideros:
     "ko" => "`gideros` 클래스 시스템에서 부모가 없는 클래스는 하나만 존재할 수 있습니다",
     _    => "There should be a single class without a parent in the `gideros` class system",
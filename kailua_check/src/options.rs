@@ -38,6 +38,106 @@ pub trait Options {
                      _report: &Report) -> Result<Chunk, Option<Stop>> {
         Err(None)
     }
+
+    /// Called when `--# class system` refers to a class system not built in the checker.
+    /// Should return the rules for the user-defined class system of given name, if any.
+    ///
+    /// Returns nothing by default.
+    fn class_system_rules(&self, _name: &str) -> Option<ClassSystemRules> {
+        None
+    }
+}
+
+/// Which inheritance is allowed for a user-defined class system.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum Inheritance {
+    /// Each class can have at most one parent class.
+    Single,
+
+    /// Each class can have any number of parent classes.
+    /// Fields are looked up from the parents in the method resolution order.
+    Multiple,
+}
+
+/// A declarative description of the user-defined class system.
+///
+/// Each rule has a name and a string value, so that they can be given from any configuration:
+///
+/// * `constructor`: The name of the constructor method, called through the class (`new`).
+/// * `init`: The name of the method called by the constructor (`init`).
+/// * `prototype_fields`: Whether class fields are `visible` or `hidden` through instances
+///   (`visible`).
+/// * `inheritance`: Whether a class can have `single` or `multiple` parents (`single`).
+/// * `static_prefix`: The name prefix for static class fields, which are hidden through instances
+///   regardless of `prototype_fields` (no prefix).
+/// * `private_prefix`: The name prefix for private fields, which are only accessible from
+///   functions defined in the class (no prefix).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ClassSystemRules {
+    pub constructor: String,
+    pub init: String,
+    pub prototype_fields_visible: bool,
+    pub inheritance: Inheritance,
+    pub static_prefix: Option<String>,
+    pub private_prefix: Option<String>,
+}
+
+/// An error returned from `ClassSystemRules::set`.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum ClassSystemRuleError {
+    /// There is no such rule.
+    UnknownRule,
+
+    /// The rule exists but the value is invalid for that rule.
+    BadValue,
+}
+
+impl Default for ClassSystemRules {
+    fn default() -> ClassSystemRules {
+        ClassSystemRules {
+            constructor: "new".to_owned(),
+            init: "init".to_owned(),
+            prototype_fields_visible: true,
+            inheritance: Inheritance::Single,
+            static_prefix: None,
+            private_prefix: None,
+        }
+    }
+}
+
+impl ClassSystemRules {
+    /// Updates a rule with given name to given value.
+    pub fn set(&mut self, rule: &str, value: &str) -> Result<(), ClassSystemRuleError> {
+        fn is_name(s: &str) -> bool {
+            let mut chars = s.chars();
+            match chars.next() {
+                Some(c) if c == '_' || c.is_ascii_alphabetic() => {}
+                _ => return false,
+            }
+            chars.all(|c| c == '_' || c.is_ascii_alphanumeric())
+        }
+
+        match rule {
+            "constructor" | "init" | "static_prefix" | "private_prefix" if !is_name(value) => {
+                Err(ClassSystemRuleError::BadValue)
+            }
+            "constructor" => { self.constructor = value.to_owned(); Ok(()) }
+            "init" => { self.init = value.to_owned(); Ok(()) }
+            "static_prefix" => { self.static_prefix = Some(value.to_owned()); Ok(()) }
+            "private_prefix" => { self.private_prefix = Some(value.to_owned()); Ok(()) }
+            "prototype_fields" => match value {
+                "visible" => { self.prototype_fields_visible = true; Ok(()) }
+                "hidden" => { self.prototype_fields_visible = false; Ok(()) }
+                _ => Err(ClassSystemRuleError::BadValue),
+            },
+            "inheritance" => match value {
+                "single" => { self.inheritance = Inheritance::Single; Ok(()) }
+                "multiple" => { self.inheritance = Inheritance::Multiple; Ok(()) }
+                _ => Err(ClassSystemRuleError::BadValue),
+            },
+            _ => Err(ClassSystemRuleError::UnknownRule),
+        }
+    }
 }
 
 /// Checker options that are tailored to loading from the file system.
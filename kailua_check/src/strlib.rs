@@ -0,0 +1,238 @@
+//! Static analyses of arguments to the string library.
+
+/// The maximum number of captures in a pattern, as defined by `LUA_MAXCAPTURES`.
+const MAX_CAPTURES: usize = 32;
+
+/// A single capture in the Lua pattern.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Capture {
+    /// `(...)`, which captures a substring.
+    String,
+
+    /// `()`, which captures the current position as an integer.
+    Position,
+}
+
+/// A reason that the Lua pattern is malformed.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum PatternError {
+    /// The pattern ends with a lone `%`.
+    EndsWithPercent,
+
+    /// A character class `[...]` is not closed.
+    MissingBracket,
+
+    /// `%b` is not followed by two characters.
+    MissingBalanceArgs,
+
+    /// `%f` is not followed by a character class.
+    MissingFrontierSet,
+
+    /// `%1` through `%9` (and `%0`) refers to a missing or unfinished capture.
+    InvalidCaptureIndex(u8),
+
+    /// `)` has no matching `(`.
+    InvalidCapture,
+
+    /// `(` is not closed.
+    UnfinishedCapture,
+
+    /// There are more than 32 captures.
+    TooManyCaptures,
+}
+
+/// Scans a Lua pattern and returns a list of captures in the order of opening parentheses.
+///
+/// This follows the Lua 5.1 and 5.3 implementation,
+/// which have an identical set of errors for patterns.
+pub fn parse_pattern(pat: &[u8]) -> Result<Vec<Capture>, PatternError> {
+    // the end of the character class started at `i` (right after `[`)
+    fn class_end(pat: &[u8], mut i: usize) -> Result<usize, PatternError> {
+        if pat.get(i) == Some(&b'^') {
+            i += 1;
+        }
+        // the first character is never a closing bracket (e.g. `[]]`)
+        loop {
+            if i >= pat.len() {
+                return Err(PatternError::MissingBracket);
+            }
+            if pat[i] == b'%' {
+                i += 1;
+            }
+            i += 1;
+            if pat.get(i) == Some(&b']') {
+                return Ok(i + 1);
+            }
+        }
+    }
+
+    // (capture, closed?)
+    let mut captures: Vec<(Capture, bool)> = Vec::new();
+    let mut i = 0;
+    while i < pat.len() {
+        match pat[i] {
+            b'(' => {
+                if captures.len() >= MAX_CAPTURES {
+                    return Err(PatternError::TooManyCaptures);
+                }
+                if pat.get(i + 1) == Some(&b')') {
+                    captures.push((Capture::Position, true));
+                    i += 2;
+                } else {
+                    captures.push((Capture::String, false));
+                    i += 1;
+                }
+            }
+
+            b')' => {
+                if let Some(cap) = captures.iter_mut().rev().find(|cap| !cap.1) {
+                    cap.1 = true;
+                } else {
+                    return Err(PatternError::InvalidCapture);
+                }
+                i += 1;
+            }
+
+            b'%' => match pat.get(i + 1) {
+                None => return Err(PatternError::EndsWithPercent),
+                Some(&b'b') => {
+                    if i + 4 > pat.len() {
+                        return Err(PatternError::MissingBalanceArgs);
+                    }
+                    i += 4;
+                }
+                Some(&b'f') => {
+                    if pat.get(i + 2) != Some(&b'[') {
+                        return Err(PatternError::MissingFrontierSet);
+                    }
+                    i = class_end(pat, i + 3)?;
+                }
+                Some(&c @ b'0'...b'9') => {
+                    let index = c - b'0';
+                    let valid = match captures.get((index as usize).wrapping_sub(1)) {
+                        Some(&(_, closed)) => closed,
+                        None => false,
+                    };
+                    if !valid {
+                        return Err(PatternError::InvalidCaptureIndex(index));
+                    }
+                    i += 2;
+                }
+                Some(_) => {
+                    i += 2;
+                }
+            },
+
+            b'[' => {
+                i = class_end(pat, i + 1)?;
+            }
+
+            _ => {
+                i += 1;
+            }
+        }
+    }
+
+    if captures.iter().any(|cap| !cap.1) {
+        return Err(PatternError::UnfinishedCapture);
+    }
+    Ok(captures.into_iter().map(|(cap, _)| cap).collect())
+}
+
+/// A kind of values expected by a single format directive.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum FormatArg {
+    /// `%c`, `%d`, `%i`, `%o`, `%u`, `%x`, `%X`, `%a`, `%A`, `%e`, `%E`, `%f`, `%g` and `%G`.
+    Number,
+
+    /// `%q`.
+    String,
+
+    /// `%s`, which converts any value to a string.
+    Any,
+}
+
+/// A single format directive consuming an argument.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub struct FormatSpec {
+    /// The entire directive, including `%` and flags.
+    pub spec: String,
+
+    /// A kind of values expected for this directive.
+    pub arg: FormatArg,
+}
+
+/// A reason that the format string is malformed, with the offending directive.
+#[derive(Clone, PartialEq, Eq, Debug)]
+pub enum FormatError {
+    /// There are more than 5 flags.
+    RepeatedFlags(String),
+
+    /// The width or precision has more than 2 digits.
+    WidthTooLong(String),
+
+    /// The conversion is unknown or missing.
+    InvalidConversion(String),
+}
+
+/// Scans a format string for `string.format` and returns a list of directives
+/// in the order of arguments they consume.
+///
+/// This follows the Lua 5.3 implementation, which is a superset of Lua 5.1.
+pub fn parse_format(fmt: &[u8]) -> Result<Vec<FormatSpec>, FormatError> {
+    const FLAGS: &'static [u8] = b"-+ #0";
+
+    let is_digit = |i: usize| fmt.get(i).map_or(false, |c| b'0' <= *c && *c <= b'9');
+    let spec_from = |start: usize, end: usize| {
+        String::from_utf8_lossy(&fmt[start..end.min(fmt.len())]).into_owned()
+    };
+
+    let mut specs = Vec::new();
+    let mut i = 0;
+    while i < fmt.len() {
+        if fmt[i] != b'%' {
+            i += 1;
+            continue;
+        }
+        if fmt.get(i + 1) == Some(&b'%') {
+            i += 2;
+            continue;
+        }
+
+        let start = i;
+        i += 1;
+        let flags_start = i;
+        while fmt.get(i).map_or(false, |c| FLAGS.contains(c)) {
+            i += 1;
+        }
+        if i - flags_start > FLAGS.len() {
+            return Err(FormatError::RepeatedFlags(spec_from(start, i + 1)));
+        }
+        for _ in 0..2 {
+            if is_digit(i) { i += 1; }
+        }
+        if fmt.get(i) == Some(&b'.') {
+            i += 1;
+            for _ in 0..2 {
+                if is_digit(i) { i += 1; }
+            }
+        }
+        if is_digit(i) {
+            while is_digit(i) { i += 1; }
+            return Err(FormatError::WidthTooLong(spec_from(start, i + 1)));
+        }
+
+        let arg = match fmt.get(i) {
+            Some(&b'c') | Some(&b'd') | Some(&b'i') | Some(&b'o') | Some(&b'u') |
+            Some(&b'x') | Some(&b'X') | Some(&b'a') | Some(&b'A') | Some(&b'e') |
+            Some(&b'E') | Some(&b'f') | Some(&b'g') | Some(&b'G') => FormatArg::Number,
+            Some(&b'q') => FormatArg::String,
+            Some(&b's') => FormatArg::Any,
+            _ => return Err(FormatError::InvalidConversion(spec_from(start, i + 1))),
+        };
+        i += 1;
+        specs.push(FormatSpec { spec: spec_from(start, i), arg: arg });
+    }
+
+    Ok(specs)
+}
@@ -28,6 +28,101 @@ local x --: Hello
 local y = x + 3 --: integer
 --! error
 
+--8<-- assume-class-inherit
+--# assume global class A
+--# assume global class B: A
+--# assume static A.x: integer
+local x = B.x --: integer
+local b --: B
+local a = b --: A
+--! ok
+
+--8<-- assume-class-inherit-not-parent
+--# assume global class A
+--# assume global class B: A
+local a --: A
+local b = a --: B --@< Error: Cannot assign `A` into `B`
+                  --@^ Note: The other type originates here
+--! error
+
+--8<-- assume-class-inherit-multi
+--# assume global class A
+--# assume global class B
+--# assume global class C: A, B
+--# assume static A.x: integer
+--# assume static B.y: string
+local x = C.x --: integer
+local y = C.y --: string
+local c --: C
+local a = c --: A
+local b = c --: B
+--! ok
+
+--8<-- assume-class-inherit-multi-order
+--# assume global class A
+--# assume global class B
+--# assume global class C: A, B
+--# assume global class D: B, A
+--# assume static A.x: const integer
+--# assume static B.x: const string
+local c = C.x --: integer
+local d = D.x --: string
+--! ok
+
+--8<-- assume-class-inherit-multi-diamond
+--# assume global class A
+--# assume global class B: A
+--# assume global class C: A
+--# assume global class D: B, C
+--# assume static A.x: const number
+--# assume static C.x: const integer
+-- the method resolution order is D, B, C, A
+local x = D.x --: integer
+--! ok
+
+--8<-- assume-class-inherit-multi-inconsistent
+--# assume global class A
+--# assume global class B: A
+--# assume global class C: A, B --@< Error: Cannot determine a consistent method resolution order from the parent classes
+--! error
+
+--8<-- assume-class-inherit-multi-duplicate
+--# assume global class A
+--# assume global class B: A, A --@< Error: The same parent class has been given more than once
+--! error
+
+--8<-- assume-class-inherit-override
+--# assume global class A
+--# assume global class B: A
+--# assume static A.x: integer
+--# assume static B.x: number --@< Error: Tried to override a field `x` in a parent class but `number` is not a subtype of `integer` when being inside the mutable class
+                             --@^^ Note: Previous definition of the field type here
+--! error
+
+--8<-- assume-class-inherit-multi-override
+--# assume global class A
+--# assume global class B
+--# assume global class C: A, B
+--# assume static A.x: const integer
+--# assume static B.x: const string
+--# assume static C.x: const integer --@< Error: Tried to override a field `x` in a parent class but `const integer` is not a subtype of `const string` when being inside the mutable class
+                                    --@^^ Note: Previous definition of the field type here
+--! error
+
+--8<-- assume-class-inherit-instance-field
+--# assume global class A
+--# assume global class B: A
+--v method()
+function A:f()
+    self.a = 42
+end
+--v method()
+function B:g()
+    local a = self.a + 5 --: integer
+    self.a = a
+end
+--! ok
+
 --8<-- class-field-static
 --# assume global class Hello
 Hello.a = 42
@@ -229,6 +324,87 @@ function Hello:f()
 end
 --! error
 
+--8<-- class-private-field
+--# assume global class Account
+--# assume Account.balance: [private] integer
+
+--v [private] method(amount: integer)
+function Account:deposit_unchecked(amount)
+    self.balance = self.balance + amount
+end
+
+--v method(amount: integer)
+function Account:deposit(amount)
+    if amount > 0 then
+        self:deposit_unchecked(amount)
+    end
+end
+
+--v method() --> function() --> integer
+function Account:getter()
+    return function() return self.balance end
+end
+--! ok
+
+--8<-- class-private-field-outside
+--# assume global class Account
+--# assume Account.balance: [private] integer
+--# assume Account.deposit_unchecked: [private] method(integer)
+--# assume global account: Account
+
+local x = account.balance --@< Error: The private field `balance` can be only accessed from methods of `Account`
+account.balance = 0 --@< Error: The private field `balance` can be only accessed from methods of `Account`
+account:deposit_unchecked(5) --@< Error: The private field `deposit_unchecked` can be only accessed from methods of `Account`
+
+--v function(a: Account) --> integer
+local function peek(a)
+    return a.balance --@< Error: The private field `balance` can be only accessed from methods of `Account`
+end
+--! error
+
+--8<-- class-private-field-other-class
+--# assume global class Account
+--# assume global class Bank
+--# assume Account.balance: [private] integer
+
+--v method(a: Account) --> integer
+function Bank:peek(a)
+    return a.balance --@< Error: The private field `balance` can be only accessed from methods of `Account`
+end
+--! error
+
+--8<-- class-protected-field
+--# assume global class Account
+--# assume global class Savings: Account
+--# assume Account.balance: [protected] integer
+--# assume Account.fee: [private] integer
+
+--v method() --> integer
+function Savings:interest()
+    return self.balance
+end
+
+--v method() --> integer
+function Savings:fees()
+    return self.fee --@< Error: The private field `fee` can be only accessed from methods of `Account`
+end
+--! error
+
+--8<-- class-protected-field-outside
+--# assume global class Account
+--# assume global class Savings: Account
+--# assume global class Bank
+--# assume Account.balance: [protected] integer
+--# assume global savings: Savings
+
+local x = savings.balance --@< Error: The protected field `balance` can be only accessed from methods of `Account` and its subclasses
+
+--v method(a: Savings) --> integer
+function Bank:peek(a)
+    return a.balance --@< Error: The protected field `balance` can be only accessed from methods of `Account` and its subclasses
+end
+--! error
+
 --8<-- make-class-no-class-system
 --# assume `class`: [make_class] function() --> table
 --@^ Error: The type attribute `make_class` requires exactly 1 value(s)
@@ -0,0 +1,434 @@
+-- User-defined class system support tests for the Kailua type checker.
+
+--8<-- declarative-default
+--# class system oop [inheritance(single)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+do
+    Hello = class()
+    --v method(x: integer)
+    function Hello:init(x)
+        self.x = x
+    end
+    --v method() --> integer
+    function Hello:get()
+        return self.x
+    end
+end
+local h = Hello.new(42) --: Hello
+local x = h:get() --: integer
+local y = h.x --: integer
+--! ok
+
+--8<-- declarative-config
+--# class system configured
+--# assume global `class`: [make_class(configured)] function(table?) --> table
+Hello = class()
+--v method()
+function Hello:init()
+end
+local h = Hello.create() --: Hello
+--! ok
+
+--8<-- declarative-config-with-attrs
+--# class system configured [init(initialize)]
+--# assume global `class`: [make_class(configured)] function(table?) --> table
+do
+    Hello = class()
+    --v method(x: integer)
+    function Hello:initialize(x)
+    end
+    Hello.s_count = 0 --: integer
+end
+local h = Hello.create(42) --: Hello
+local x = h.s_count --@< Error: The static field `s_count` should not be accessed through instances
+                    --@^ Error: Cannot index `Hello` with `"s_count"`
+--! error
+
+--8<-- declarative-builtin-with-attrs
+--# class system gideros [constructor(create)] --@< Error: `gideros` is a built-in class system and cannot be given rules
+--# assume global `class`: [make_class(gideros)] function(table?) --> table
+Hello = class()
+--v method()
+function Hello:init()
+end
+local h = Hello.new() --: Hello
+--! error
+
+--8<-- declarative-no-attrs-no-config
+--# class system oop --@< Error: `oop` class system is not yet supported
+--! error
+
+--8<-- declarative-assume-class
+--# class system oop [constructor(create)]
+--# assume global class(oop) Hello
+--# assume static Hello.init: function(self: Hello)
+--# assume static Hello.x: integer
+local h = Hello.create() --: Hello
+local x = h.x --: integer
+--! ok
+
+--8<-- declarative-assume-class-parent
+--# class system oop [constructor(create)]
+--# assume global class(oop) Hello
+--# assume global class(oop) World: Hello
+--# assume static Hello.x: integer
+local x = World.x --: integer
+--! ok
+
+--8<-- declarative-custom-ctor
+--# class system oop [constructor(create)] [init(initialize)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+Hello = class()
+--v method(x: integer)
+function Hello:initialize(x)
+    self.x = x
+end
+local h = Hello.create(42) --: Hello
+--! ok
+
+--8<-- declarative-custom-ctor-bad-arity
+--# class system oop [constructor(create)] [init(initialize)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+Hello = class()
+--v method(x: integer)
+function Hello:initialize(x)
+end
+local h = Hello.create() --@< Error: The type `function(x: integer) --> Hello` cannot be called
+                         --@^ Cause: First function argument cannot be omitted because its type is `integer`
+                         --@^^ Note: The other type originates here
+--! error
+
+--8<-- declarative-custom-ctor-no-init
+--# class system oop [constructor(create)] [init(initialize)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+Hello = class()
+local h = Hello.create() --@< Error: The `create` method cannot be called with no constructor (`initialize` method) defined
+--! error
+
+--8<-- declarative-custom-ctor-assign
+--# class system oop [constructor(create)] [init(initialize)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+Hello = class()
+Hello.create = 42 --@< Error: `create` field is reserved by the class system and cannot be defined
+--! error
+
+--8<-- declarative-custom-init-thru-instance
+--# class system oop [constructor(create)] [init(initialize)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+do
+    Hello = class()
+    --v method()
+    function Hello:initialize()
+    end
+end
+local h = Hello.create()
+h:initialize() --@< Error: The constructor (`initialize` method) should not be accessed through instances
+               --@^ Error: Cannot index `Hello` with `"initialize"`
+Hello.initialize(h) -- fine!
+--! error
+
+--8<-- declarative-prototype-fields-visible
+--# class system oop [prototype_fields(visible)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+do
+    Hello = class()
+    --v method()
+    function Hello:init()
+    end
+    Hello.answer = 42 --: integer
+end
+local h = Hello.new()
+local x = h.answer --: integer
+--! ok
+
+--8<-- declarative-prototype-fields-hidden
+--# class system oop [prototype_fields(hidden)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+do
+    Hello = class()
+    --v method()
+    function Hello:init()
+        self.x = 'string'
+    end
+    Hello.answer = 42 --: integer
+end
+local h = Hello.new()
+local x = h.x --: string
+local y = Hello.answer --: integer
+local z = h.answer --@< Error: Cannot index `Hello` with `"answer"`
+--! error
+
+--8<-- declarative-prototype-fields-hidden-assign
+--# class system oop [prototype_fields(hidden)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+do
+    Hello = class()
+    --v method()
+    function Hello:init()
+        self.answer = 'string'
+    end
+    Hello.answer = 42 --: integer
+end
+local h = Hello.new()
+local x = h.answer --: string
+local y = Hello.answer --: integer
+--! ok
+
+--8<-- declarative-static-prefix
+--# class system oop [static_prefix(s_)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+do
+    Hello = class()
+    --v method()
+    function Hello:init()
+    end
+    Hello.s_count = 0 --: integer
+    Hello.count = 0 --: integer
+end
+local h = Hello.new()
+local x = Hello.s_count --: integer
+local y = h.count --: integer
+local z = h.s_count --@< Error: The static field `s_count` should not be accessed through instances
+                    --@^ Error: Cannot index `Hello` with `"s_count"`
+--! error
+
+--8<-- declarative-static-prefix-assign
+--# class system oop [static_prefix(s_)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+do
+    Hello = class()
+    --v method()
+    function Hello:init()
+        self.s_count = 42 --@< Error: The static field `s_count` should not be accessed through instances
+    end
+end
+--! error
+
+--8<-- declarative-single-inheritance
+--# class system oop [inheritance(single)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+do
+    A = class()
+    --v method(x: integer)
+    function A:init(x)
+        self.x = x
+    end
+    --v method() --> integer
+    function A:get()
+        return self.x
+    end
+    B = class(A)
+end
+local b = B.new(42) --: B
+local x = b:get() --: integer
+local a = b --: A
+--! ok
+
+--8<-- declarative-single-inheritance-override
+--# class system oop [inheritance(single)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+A = class()
+B = class(A)
+
+--# assume static A.a: number
+--# assume static B.a: number
+
+--# assume static A.b: integer
+--# assume static B.b: number --@< Error: Tried to override a field `b` in a parent class but `number` is not a subtype of `integer` when being inside the mutable class
+                             --@^^ Note: Previous definition of the field type here
+
+--! error
+
+--8<-- declarative-multiple-inheritance
+--# class system oop [inheritance(multiple)]
+--# assume global `class`: [make_class(oop)] function(table...) --> table
+do
+    A = class()
+    --v method(x: integer)
+    function A:init(x)
+        self.x = x
+    end
+    --v method() --> integer
+    function A:get()
+        return self.x
+    end
+    B = class()
+    --v method() --> string
+    function B:name()
+        return 'b'
+    end
+    C = class(A, B)
+end
+local c = C.new(42) --: C
+local x = c:get() --: integer
+local y = c:name() --: string
+local a = c --: A
+local b = c --: B
+--! ok
+
+--8<-- declarative-multiple-inheritance-order
+--# class system oop [inheritance(multiple)]
+--# assume global `class`: [make_class(oop)] function(table...) --> table
+A = class()
+B = class()
+C = class(A, B)
+D = class(B, A)
+--# assume static A.x: const integer
+--# assume static B.x: const string
+local c = C.x --: integer
+local d = D.x --: string
+--! ok
+
+--8<-- declarative-multiple-inheritance-override
+--# class system oop [inheritance(multiple)]
+--# assume global `class`: [make_class(oop)] function(table...) --> table
+A = class()
+B = class()
+C = class(A, B)
+--# assume static A.x: integer
+--# assume static B.x: string
+--# assume static C.x: integer --@< Error: Tried to override a field `x` in a parent class but `integer` is not a subtype of `string` when being inside the mutable class
+                              --@^^ Note: Previous definition of the field type here
+--! error
+
+--8<-- declarative-multiple-inheritance-bad-parent
+--# class system oop [inheritance(multiple)]
+--# assume global `class`: [make_class(oop)] function(any...) --> table
+A = class()
+B = class(A, 42) --@< Error: The non-class type `42` cannot be a parent class
+--! error
+
+--8<-- declarative-single-inheritance-extra-parent
+--# class system oop [inheritance(single)]
+--# assume global `class`: [make_class(oop)] function(table...) --> table
+A = class()
+B = class()
+C = class(A, B)
+--# assume static B.x: integer
+local x = C.x --@< Error: Cannot index `<initializing> <prototype for C>` with `"x"`
+--! error
+
+--8<-- declarative-unknown-rule
+--# class system oop [constructor(create)] [whatever(foo)] --@< Warning: `whatever` is an unknown class system rule and ignored
+--# assume global class(oop) Hello
+--! ok
+
+--8<-- declarative-bad-rule-value
+--# class system oop [inheritance(many)] --@< Error: The class system rule `inheritance` requires a single valid value
+--! error
+
+--8<-- declarative-bad-rule-no-value
+--# class system oop [constructor] --@< Error: The class system rule `constructor` requires a single valid value
+--! error
+
+--8<-- declarative-bad-rule-multiple-values
+--# class system oop [init(a, b)] --@< Error: The class system rule `init` requires a single valid value
+--! error
+
+--8<-- declarative-other-class-system
+--# class system oop [constructor(create)]
+--# class system gideros
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+--# assume global class(gideros) Object
+Hello = class(Object) --@< Error: The class cannot inherit from a class using a different class system
+--! error
+
+--8<-- declarative-assume-class-multiple-parents
+--# class system oop [inheritance(multiple)]
+--# assume global class(oop) A
+--# assume global class(oop) B
+--# assume global class(oop) C: A, B
+--# assume static A.x: integer
+--# assume static B.y: string
+local x = C.x --: integer
+local y = C.y --: string
+--! ok
+
+--8<-- declarative-assume-class-multiple-parents-single
+--# class system oop [inheritance(single)]
+--# assume global class(oop) A
+--# assume global class(oop) B
+--# assume global class(oop) C: A, B --@< Error: This class system does not support multiple parent classes
+--! error
+
+--8<-- declarative-multiple-inheritance-inconsistent
+--# class system oop [inheritance(multiple)]
+--# assume global `class`: [make_class(oop)] function(table...) --> table
+A = class()
+B = class(A)
+C = class(A, B) --@< Error: Cannot determine a consistent method resolution order from the parent classes
+--! error
+
+--8<-- declarative-multiple-inheritance-diamond
+--# class system oop [inheritance(multiple)]
+--# assume global `class`: [make_class(oop)] function(table...) --> table
+A = class()
+B = class(A)
+C = class(A)
+D = class(B, C)
+--# assume static A.x: const number
+--# assume static C.x: const integer
+-- the method resolution order is D, B, C, A
+local x = D.x --: integer
+--! ok
+
+--8<-- declarative-abstract-method
+--# class system oop [inheritance(multiple)]
+--# assume global `class`: [make_class(oop)] function(table...) --> table
+Drawable = class()
+--# assume Drawable.draw: [abstract] method()
+Named = class()
+--# assume Named.name: [abstract] method() --> string
+--@^ Note: The abstract method was declared here
+--v method()
+function Named:init()
+end
+Sprite = class(Drawable, Named)
+--v method()
+function Sprite:draw()
+end
+local s = Sprite.new() --@< Error: Cannot instantiate the class because the abstract method `name` is not implemented
+--! error
+
+--8<-- declarative-private-prefix
+--# class system oop [private_prefix(_)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+do
+    Hello = class()
+    --v method()
+    function Hello:init()
+        self._x = 42
+    end
+    --v method() --> integer
+    function Hello:_get()
+        return self._x
+    end
+    --v method() --> integer
+    function Hello:get()
+        return self:_get()
+    end
+    Hello._count = 0 --: integer
+end
+local h = Hello.new()
+local x = h:get() --: integer
+local y = h._x --@< Error: The private field `_x` can be only accessed from methods of `Hello`
+local z = h:_get() --@< Error: The private field `_get` can be only accessed from methods of `Hello`
+local w = Hello._count --@< Error: The private field `_count` can be only accessed from methods of `Hello`
+--! error
+
+--8<-- declarative-private-prefix-subclass
+--# class system oop [private_prefix(_)]
+--# assume global `class`: [make_class(oop)] function(table?) --> table
+do
+    A = class()
+    --v method()
+    function A:init()
+        self._x = 42
+    end
+    B = class(A)
+    --v method() --> integer
+    function B:get()
+        return self._x --@< Error: The private field `_x` can be only accessed from methods of `A`
+    end
+end
+--! error
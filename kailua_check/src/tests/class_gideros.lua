@@ -887,3 +887,213 @@ a = c
 --# assume global class(gideros) C: A
 --! error
 
+--8<-- gideros-assume-class-multiple-parents
+--# class system gideros
+--# assume global class(gideros) Object
+--# assume global class(gideros) A: Object
+--# assume global class(gideros) B: Object
+--# assume global class(gideros) C: A, B --@< Error: This class system does not support multiple parent classes
+local c --: C
+local a = c --: A
+--! error
+
+--8<-- gideros-abstract-method
+--# class system gideros
+--# assume `class`: [make_class(gideros)] function() --> table
+Shape = class()
+--v method()
+function Shape:init()
+end
+--# assume Shape.area: [abstract] method() --> number
+
+Square = class(Shape)
+--v method() --> number
+function Square:area()
+    return 4
+end
+
+local s = Square.new() --: Square
+local a = s:area() --: number
+local t = s --: Shape
+local b = t:area() --: number
+--! ok
+
+--8<-- gideros-abstract-method-not-implemented
+--# class system gideros
+--# assume `class`: [make_class(gideros)] function() --> table
+Shape = class()
+--v method()
+function Shape:init()
+end
+--# assume Shape.area: [abstract] method() --> number
+--@^ Note: The abstract method was declared here
+--@^^ Note: The abstract method was declared here
+
+Square = class(Shape)
+
+local s = Shape.new() --@< Error: Cannot instantiate the class because the abstract method `area` is not implemented
+local t = Square.new() --@< Error: Cannot instantiate the class because the abstract method `area` is not implemented
+--! error
+
+--8<-- gideros-abstract-method-chain
+--# class system gideros
+--# assume `class`: [make_class(gideros)] function() --> table
+Shape = class()
+--v method()
+function Shape:init()
+end
+--# assume Shape.area: [abstract] method() --> number
+--@^ Note: The abstract method was declared here
+--@^^ Note: The abstract method was declared here
+
+Polygon = class(Shape)
+--v [abstract] method() --> integer
+function Polygon:sides() --@< Note: The abstract method was declared here
+    return 0
+end
+
+Square = class(Polygon)
+--v method() --> integer
+function Square:sides()
+    return 4
+end
+
+local p = Polygon.new()
+--@^ Error: Cannot instantiate the class because the abstract method `area` is not implemented
+--@^^ Error: Cannot instantiate the class because the abstract method `sides` is not implemented
+local s = Square.new() --@< Error: Cannot instantiate the class because the abstract method `area` is not implemented
+--! error
+
+--8<-- gideros-abstract-method-override-type
+--# class system gideros
+--# assume `class`: [make_class(gideros)] function() --> table
+Shape = class()
+--v method()
+function Shape:init()
+end
+--# assume Shape.area: [abstract] method() --> number
+
+Square = class(Shape)
+--v method() --> integer
+function Square:area()
+    return 4
+end
+
+local s = Square.new()
+local a = s:area() --: integer
+--! ok
+
+--8<-- gideros-private-method
+--# class system gideros
+--# assume `class`: [make_class(gideros)] function() --> table
+Account = class()
+--# assume Account.balance: [private] integer
+
+--v method(balance: integer)
+function Account:init(balance)
+    self.balance = balance
+end
+
+--v [private] method() --> integer
+function Account:secret()
+    return self.balance
+end
+
+--v method() --> integer
+function Account:get()
+    return self:secret()
+end
+
+local a = Account.new(5)
+local x = a:get() --: integer
+local y = a:secret() --@< Error: The private field `secret` can be only accessed from methods of `Account`
+local z = a.balance --@< Error: The private field `balance` can be only accessed from methods of `Account`
+--! error
+
+--8<-- gideros-protected-method
+--# class system gideros
+--# assume `class`: [make_class(gideros)] function() --> table
+Account = class()
+--v method()
+function Account:init()
+end
+--v [protected] method() --> integer
+function Account:rate()
+    return 1
+end
+
+Savings = class(Account)
+--v method() --> integer
+function Savings:interest()
+    return self:rate()
+end
+
+local s = Savings.new()
+local x = s:interest() --: integer
+local y = s:rate() --@< Error: The protected field `rate` can be only accessed from methods of `Account` and its subclasses
+--! error
+
+--8<-- gideros-abstract-private-method
+--# class system gideros
+--# assume `class`: [make_class(gideros)] function() --> table
+Shape = class()
+--v method()
+function Shape:init()
+end
+--v [abstract] [private] method() --> number
+function Shape:area() --@< Note: The abstract method was declared here
+    return 0
+end
+local s = Shape.new() --@< Error: Cannot instantiate the class because the abstract method `area` is not implemented
+local a = s:area() --@< Error: The private field `area` can be only accessed from methods of `Shape`
+--! error
+
+--8<-- gideros-abstract-protected-method
+--# class system gideros
+--# assume `class`: [make_class(gideros)] function() --> table
+Shape = class()
+--v method()
+function Shape:init()
+end
+--# assume Shape.area: [abstract] [protected] method() --> number
+--@^ Note: The abstract method was declared here
+
+--v method() --> number
+function Shape:describe()
+    return self:area()
+end
+
+Polygon = class(Shape)
+--v [abstract] [protected] method() --> integer
+function Polygon:sides() --@< Note: The abstract method was declared here
+    return 0
+end
+
+local p = Polygon.new()
+--@^ Error: Cannot instantiate the class because the abstract method `area` is not implemented
+--@^^ Error: Cannot instantiate the class because the abstract method `sides` is not implemented
+local x = p:describe() --: number
+local y = p:area() --@< Error: The protected field `area` can be only accessed from methods of `Shape` and its subclasses
+local z = p:sides() --@< Error: The protected field `sides` can be only accessed from methods of `Polygon` and its subclasses
+--! error
+
+--8<-- gideros-conflicting-visibility
+--# class system gideros
+--# assume `class`: [make_class(gideros)] function() --> table
+Account = class()
+--# assume Account.balance: [protected] [private] integer
+--@^ Warning: [private] and [protected] attributes cannot be given together, [private] is assumed
+--v [private] [private] method() --> integer
+--@^ Warning: The attribute `private` has been already given and ignored
+function Account:get()
+    return self.balance
+end
+
+--v method()
+function Account:init()
+end
+
+local a = Account.new()
+local x = a:get() --@< Error: The private field `get` can be only accessed from methods of `Account`
+local y = a.balance --@< Error: The private field `balance` can be only accessed from methods of `Account`
+--! error
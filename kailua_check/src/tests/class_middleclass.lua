@@ -0,0 +1,572 @@
+-- middleclass class system support tests for the Kailua type checker.
+
+--8<-- middleclass-assume-class
+--# class system middleclass
+--# assume global class(middleclass) Hello
+local x --: Hello
+--! ok
+
+--8<-- middleclass-assume-class-no-parent
+--# class system middleclass
+--# assume global class(middleclass) Hello
+--# assume global class(middleclass) Goodbye
+local x --: Hello
+local y = x --: Goodbye --@< Error: Cannot assign `Hello` into `Goodbye`
+                        --@^ Note: The other type originates here
+--! error
+
+--8<-- middleclass-assume-class-parent
+--# class system middleclass
+--# assume global class(middleclass) Greeting
+--# assume global class(middleclass) Hello: Greeting
+local x --: Hello
+local y = x --: Greeting
+--! ok
+
+--8<-- middleclass-make-class
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello')
+--! ok
+
+--8<-- middleclass-make-class-named
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello')
+local x = Hello + 3
+--@^ Error: Cannot apply + operator to `<initializing> <prototype for Hello>` and `3`
+--@^^ Cause: `<prototype for Hello>` is not a subtype of `number`
+--! error
+
+--8<-- middleclass-make-class-parent
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Animal = class('Animal')
+Dog = class('Dog', Animal)
+local x --: Dog
+local y = x --: Animal
+--! ok
+
+--8<-- middleclass-make-class-bad-parent
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, any?) --> table
+Hello = class('Hello', 'World') --@< Error: The non-class type `"World"` cannot be a parent class
+--! error
+
+--8<-- middleclass-make-class-other-class-system
+--# class system middleclass
+--# assume global class Foo
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello', Foo) --@< Error: The class cannot inherit from a class using a different class system
+--! error
+
+--8<-- middleclass-new
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+do
+    Point = class('Point')
+    --v method(x: number, y: number)
+    function Point:initialize(x, y)
+        self.x = x
+        self.y = y
+    end
+end
+local p = Point:new(3, 4) --: Point
+local q = Point(3, 4) --: Point
+local r = p.x + q.y --: number
+--! ok
+
+--8<-- middleclass-new-no-initialize
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello')
+local h = Hello:new() --: Hello
+--! ok
+
+--8<-- middleclass-new-bad-arity
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Point = class('Point')
+--v method(x: number, y: number)
+function Point:initialize(x, y)
+end
+local p = Point:new(3)
+--@^ Error: The type `function(<prototype for Point>, x: number, y: number) --> Point` cannot be called
+--@^^ Cause: Third method argument cannot be omitted because its type is `number`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- middleclass-new-inherited
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+do
+    Animal = class('Animal')
+    --v method(name: string)
+    function Animal:initialize(name)
+        self.name = name
+    end
+    Dog = class('Dog', Animal)
+end
+local d = Dog:new('rex') --: Dog
+local n = d.name --: string
+--! ok
+
+--8<-- middleclass-new-overriden
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+do
+    Animal = class('Animal')
+    --v method(name: string)
+    function Animal:initialize(name)
+        self.name = name
+    end
+    Dog = class('Dog', Animal)
+    --v method(name: string, breed: string)
+    function Dog:initialize(name, breed)
+        Animal.initialize(self, name)
+        self.breed = breed
+    end
+end
+local d = Dog:new('rex', 'retriever') --: Dog
+local s = d.name .. d.breed --: string
+--! ok
+
+--8<-- middleclass-initialize-bad-self
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello')
+--v function(x: integer)
+function Hello.initialize(x) --@< Error: The type `function(x: integer) --> ()` of the constructor (`initialize` method) doesn't have a correct type for the first argument
+end
+local h = Hello:new(42)
+--! error
+
+--8<-- middleclass-new-assign
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello')
+--v method()
+function Hello:new() --@< Error: `new` field is reserved by the class system and cannot be defined
+end
+--! error
+
+--8<-- middleclass-reserved-instance
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello')
+--v method()
+function Hello:initialize()
+    self.isInstanceOf = 42 --@< Error: `isInstanceOf` field is reserved by the class system and cannot be defined
+end
+--! error
+
+--8<-- middleclass-method
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello')
+--v method(x: integer) --> integer
+function Hello:double(x)
+    return x * 2
+end
+local h = Hello:new()
+local x = h:double(21) --: integer
+--! ok
+
+--8<-- middleclass-method-through-class
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello')
+--v method(x: integer) --> integer
+function Hello:double(x)
+    return x * 2
+end
+local h = Hello:new()
+local x = Hello.double(h, 21) --: integer
+--! ok
+
+--8<-- middleclass-method-inherited
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Animal = class('Animal')
+--v method() --> string
+function Animal:speak()
+    return '...'
+end
+Dog = class('Dog', Animal)
+local d = Dog:new()
+local s = d:speak() --: string
+--! ok
+
+--8<-- middleclass-method-override
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Animal = class('Animal')
+Dog = class('Dog', Animal)
+
+--# assume static Animal.a: number
+--# assume static Dog.a: number
+
+--# assume static Animal.b: integer
+--# assume static Dog.b: number --@< Error: Tried to override a field `b` in a parent class but `number` is not a subtype of `integer` when being inside the mutable class
+                                --@^^ Note: Previous definition of the field type here
+
+--! error
+
+--8<-- middleclass-static
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello')
+Hello.static.count = 0 --: integer
+--v function() --> integer
+function Hello.static.next()
+    Hello.static.count = Hello.count + 1
+    return Hello.count
+end
+local x = Hello.next() --: integer
+--! ok
+
+--8<-- middleclass-static-inherited
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Animal = class('Animal')
+--v function() --> string
+function Animal.static.kind()
+    return 'animal'
+end
+Dog = class('Dog', Animal)
+local x = Dog.kind() --: string
+--! ok
+
+--8<-- middleclass-static-through-instance
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello')
+--v function() --> string
+function Hello.static.kind()
+    return 'hello'
+end
+local h = Hello:new()
+local x = h.kind() --@< Error: Cannot index `Hello` with `"kind"`
+--! error
+
+--8<-- middleclass-static-reserved
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello')
+Hello.static.name = 'World' --@< Error: `name` field is reserved by the class system and cannot be defined
+--! error
+
+--8<-- middleclass-builtin-members
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Animal = class('Animal')
+--v method() --> string
+function Animal:speak()
+    return '...'
+end
+Dog = class('Dog', Animal)
+local d = Dog:new()
+local a = d.class.name .. Dog.name --: string
+local s = Dog.super.speak(d) --: string
+local e = Dog:allocate() --: Dog
+--! ok
+
+--8<-- middleclass-no-super
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello')
+local x = Hello.super --@< Error: Cannot index `<initializing> <prototype for Hello>` with `"super"`
+--! error
+
+--8<-- middleclass-include
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Hello = class('Hello')
+local HasWings = {}
+Hello:include(HasWings)
+--! ok
+
+--8<-- middleclass-include-fields
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Bird = class('Bird')
+local HasWings = { wings = 2 }
+--v method() --> string
+function HasWings:fly() return 'flap' end
+Bird:include(HasWings)
+local b = Bird:new()
+local s = b:fly() --: string
+local n = b.wings + 1 --: integer
+--! ok
+
+--8<-- middleclass-include-static
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Bird = class('Bird')
+local HasWings = { static = { species = 'bird' } }
+--v function(mixin: table, cls: table)
+function HasWings.included(mixin, cls) end
+Bird.include(Bird, HasWings)
+local s = Bird.static.species .. '' --: string
+local x = Bird.included --@< Error: Cannot index `<initializing> <prototype for Bird>` with `"included"`
+--! error
+
+--8<-- middleclass-include-inherited
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Bird = class('Bird')
+Bird:include({ wings = 2 })
+Penguin = class('Penguin', Bird)
+local n = Penguin:new().wings + 1 --: integer
+--! ok
+
+--8<-- middleclass-include-reserved
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Bird = class('Bird')
+Bird:include({ name = 'x' }) --@< Error: `name` field is reserved by the class system and cannot be defined
+--! error
+
+--8<-- middleclass-include-mismatch
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Bird = class('Bird')
+--v method() --> string
+function Bird:fly() return 'flap' end
+local Other = { fly = 42 }
+Bird:include(Other) --@< Error: Cannot assign `42` into `function(self: Bird) --> string`
+                    --@^ Note: The other type originates here
+--! error
+
+--8<-- middleclass-metamethod
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Point = class('Point')
+--v method(x: number, y: number)
+function Point:initialize(x, y)
+    self.x = x
+    self.y = y
+end
+--v method(other: Point) --> Point
+function Point:__add(other)
+    return Point:new(self.x + other.x, self.y + other.y)
+end
+--v method() --> string
+function Point:__tostring()
+    return 'Point'
+end
+local p = Point:new(1, 2) + Point:new(3, 4) --: Point
+local s = p:__tostring() --: string
+--! ok
+
+--8<-- middleclass-metamethod-inherited
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+--# open lua51
+Animal = class('Animal')
+--v method(other: Animal) --> boolean
+function Animal:__lt(other)
+    return false
+end
+Dog = class('Dog', Animal)
+local a = Dog:new() < Dog:new() --: boolean
+--! ok
+
+--8<-- middleclass-metamethod-missing
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Point = class('Point')
+local p = Point:new() + Point:new()
+--@^ Error: Cannot apply + operator to `Point` and `Point`
+--@^^ Cause: `Point` is not a subtype of `number`
+--@^^^ Cause: `Point` is not a subtype of `number`
+--! error
+
+--8<-- middleclass-is-instance-of
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Animal = class('Animal')
+Dog = class('Dog', Animal)
+--v method() --> string
+function Dog:fetch()
+    return 'ball'
+end
+--v function(a: Animal)
+function f(a)
+    if a:isInstanceOf(Dog) then
+        local x = a:fetch() --: string
+    end
+end
+--! ok
+
+--8<-- middleclass-is-instance-of-else
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Animal = class('Animal')
+Dog = class('Dog', Animal)
+--v method() --> string
+function Dog:fetch()
+    return 'ball'
+end
+--v function(a: Animal)
+function f(a)
+    if not a:isInstanceOf(Dog) then
+        return
+    end
+    local x = a:fetch() --: string
+end
+--! ok
+
+--8<-- middleclass-is-instance-of-negated
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Animal = class('Animal')
+Dog = class('Dog', Animal)
+--v method() --> string
+function Dog:fetch()
+    return 'ball'
+end
+--v function(a: Animal)
+function f(a)
+    if a:isInstanceOf(Dog) then
+        return
+    end
+    local x = a:fetch() --@< Error: Cannot index `Animal` with `"fetch"`
+end
+--! error
+
+--8<-- middleclass-is-instance-of-unrelated
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Animal = class('Animal')
+Rock = class('Rock')
+--v function(a: Animal)
+function f(a)
+    if a:isInstanceOf(Rock) then
+        local b = a --: Animal
+    end
+end
+--! ok
+
+--8<-- middleclass-is-instance-of-function
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Animal = class('Animal')
+Dog = class('Dog', Animal)
+--v method() --> string
+function Dog:fetch()
+    return 'ball'
+end
+--v function(a: Animal?)
+function f(a)
+    if Animal.isInstanceOf(a, Dog) then
+        local x = a:fetch() --: string
+    end
+end
+--! ok
+
+--8<-- middleclass-is-instance-of-assert
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+--# assume assert: const [assert] function(any)
+Animal = class('Animal')
+Dog = class('Dog', Animal)
+--v method() --> string
+function Dog:fetch()
+    return 'ball'
+end
+--v function(a: Animal)
+function f(a)
+    assert(a:isInstanceOf(Dog))
+    local x = a:fetch() --: string
+end
+--! ok
+
+--8<-- middleclass-is-instance-of-in-args
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+--# assume id: function(boolean) --> boolean
+Animal = class('Animal')
+Dog = class('Dog', Animal)
+--v method() --> string
+function Dog:fetch()
+    return 'ball'
+end
+--v function(a: Animal)
+function f(a)
+    if id(a:isInstanceOf(Dog)) then
+        local x = a:fetch() --@< Error: Cannot index `Animal` with `"fetch"`
+    end
+end
+--! error
+
+--8<-- middleclass-is-subclass-of
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Animal = class('Animal')
+Dog = class('Dog', Animal)
+--v function() --> string
+function Animal.static.kind()
+    return 'animal'
+end
+--v function(c: WHATEVER)
+function f(c)
+    if Animal.isSubclassOf(c, Animal) then
+        local x = c.kind() --: string
+    end
+end
+--! ok
+
+--8<-- middleclass-assume-class-multiple-parents
+--# class system middleclass
+--# assume global class(middleclass) A
+--# assume global class(middleclass) B
+--# assume global class(middleclass) C: A, B --@< Error: This class system does not support multiple parent classes
+--! error
+
+--8<-- middleclass-abstract-method
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Shape = class('Shape')
+--# assume Shape.area: [abstract] method() --> number
+Square = class('Square', Shape)
+--v method() --> number
+function Square:area()
+    return 4
+end
+local s = Square:new() --: Square
+local t = Square() --: Square
+local a = s:area() --: number
+--! ok
+
+--8<-- middleclass-abstract-method-not-implemented
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Shape = class('Shape')
+--# assume Shape.area: [abstract] method() --> number
+--@^ Note: The abstract method was declared here
+--@^^ Note: The abstract method was declared here
+Square = class('Square', Shape)
+local s = Shape:new() --@< Error: Cannot instantiate the class because the abstract method `area` is not implemented
+local t = Square() --@< Error: Cannot instantiate the class because the abstract method `area` is not implemented
+--! error
+
+--8<-- middleclass-private-method
+--# class system middleclass
+--# assume `class`: [make_class(middleclass)] function(string, table?) --> table
+Account = class('Account')
+--v method()
+function Account:initialize()
+end
+--v [private] method() --> integer
+function Account:secret()
+    return 42
+end
+--v method() --> integer
+function Account:get()
+    return self:secret()
+end
+local a = Account:new()
+local x = a:get() --: integer
+local y = a:secret() --@< Error: The private field `secret` can be only accessed from methods of `Account`
+--! error
@@ -0,0 +1,189 @@
+-- Typed coroutine tests for the Kailua type checker.
+
+--8<-- coroutine-create
+--# open lua51
+local co = coroutine.create(function(n) --: integer
+                                        --> string
+    local m = coroutine.yield(n + 1)
+    return 'done'
+end)
+local x = co --: thread<integer, integer, string>
+--! ok
+
+--8<-- coroutine-create-display
+--# open lua51
+local co = coroutine.create(function(n) --: integer
+                                        --> string
+    coroutine.yield(n + 1)
+    return 'done'
+end)
+local x = co --: string
+--@^ Error: Cannot assign `thread<integer, integer, string>` into `string`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- coroutine-yield-returns-args
+--# open lua51
+local co = coroutine.create(function(n) --: integer
+    while true do
+        n = coroutine.yield(n) + 1
+    end
+end)
+--! ok
+
+--8<-- coroutine-resume
+--# open lua51
+local co = coroutine.create(function(n) --: integer
+                                        --> string
+    coroutine.yield(n + 1)
+    return 'done'
+end)
+local ok, v = coroutine.resume(co, 42)
+local x = ok --: boolean
+local y = v --: integer|string
+--! ok
+
+--8<-- coroutine-resume-wrong-arg
+--# open lua51
+local co = coroutine.create(function(n) --: integer
+    coroutine.yield(n + 1)
+end)
+coroutine.resume(co, 'string')
+--@^ Error: The coroutine of the type `thread<integer, integer, ()>` cannot be resumed with given arguments
+--@^^ Cause: Second function argument `"string"` is not a subtype of `integer`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- coroutine-resume-result
+--# open lua51
+local co = coroutine.create(function(n) --: integer
+                                        --> string
+    coroutine.yield(n + 1)
+    return 'done'
+end)
+local ok, v = coroutine.resume(co, 42)
+local y = v + 1 --@< Error: Cannot apply + operator to `(integer|string)` and `1`
+                --@^ Cause: `(integer|string)` is not a subtype of `number`
+--! error
+
+--8<-- coroutine-wrap
+--# open lua51
+local f = coroutine.wrap(function(n) --: integer
+                                     --> string
+    coroutine.yield(n + 1)
+    return 'done'
+end)
+local v = f(42) --: integer|string
+--! ok
+
+--8<-- coroutine-yield-multiple
+--# open lua51
+local co = coroutine.create(function()
+    coroutine.yield(1)
+    coroutine.yield(2.5)
+end)
+local x = co --: thread<(), number, ()>
+--! ok
+
+--8<-- coroutine-yield-cannot-extend
+--# open lua51
+--# assume f: function(integer)
+--# assume g: function(string)
+local co = coroutine.create(function()
+    coroutine.yield(f)
+    coroutine.yield(g) --@< Error: Cannot extend the implicit yield type of this coroutine
+                       --@ Cause: Cannot create a union type of `function(string) --> ()` and `function(integer) --> ()` in the first function argument
+end)
+--! error
+
+--8<-- coroutine-yield-nested-function
+--# open lua51
+local co = coroutine.create(function()
+    local function f()
+        coroutine.yield('not tracked')
+    end
+    coroutine.yield(42)
+end)
+local x = co --: thread<(), integer, ()>
+--! ok
+
+--8<-- coroutine-nested
+--# open lua51
+local co = coroutine.create(function(n) --: integer
+    local inner = coroutine.create(function(s) --: string
+        coroutine.yield(s .. 'a')
+    end)
+    local ok, v = coroutine.resume(inner, 'b')
+    coroutine.yield(n)
+end)
+local x = co --: thread<integer, integer, ()>
+--! ok
+
+--8<-- coroutine-not-literal
+--# open lua51
+local function f(n) --: integer
+                    --> string
+    coroutine.yield(n)
+    return 'a'
+end
+local co = coroutine.create(f)
+local x = co --: thread<integer, string, string>
+--! ok
+
+--8<-- coroutine-untyped
+--# open lua51
+--# assume co: thread
+local ok, v = coroutine.resume(co, 1, 2, 3)
+--! ok
+
+--8<-- coroutine-yield-outside
+--# open lua51
+local x = coroutine.yield(1, 2)
+--! ok
+
+--8<-- coroutine-create-non-function
+--# open lua51
+local co = coroutine.create(42)
+--@^ Error: The type `[coroutine_create] function(f: function) --> thread` cannot be called
+--@^^ Cause: First function argument `42` is not a subtype of `function`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- coroutine-subtype
+--# assume co: thread<integer, integer, string>
+local a = co --: thread<integer, number, string>
+local b = co --: thread<integer, integer, string?>
+--! ok
+
+--8<-- coroutine-subtype-args
+--# assume co: thread<number, integer, string>
+local a = co --: thread<integer, integer, string>
+--! ok
+
+--8<-- coroutine-subtype-args-mismatch
+--# assume co: thread<integer, integer, string>
+local a = co --: thread<number, integer, string>
+--@^ Error: Cannot assign `thread<integer, integer, string>` into `thread<number, integer, string>`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- coroutine-untyped-to-typed
+--# assume co: thread
+local a = co --: thread<integer, integer, string>
+--@^ Error: Cannot assign `thread` into `thread<integer, integer, string>`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- coroutine-annotated
+--# open lua51
+--# assume co: thread<string, integer, boolean>
+local ok, v = coroutine.resume(co, 'a')
+local x = v --: integer|boolean
+local y = co --: thread
+--! ok
+
+--8<-- coroutine-running
+--# open lua51
+local co = coroutine.create(function() end)
+local s = coroutine.status(co)
+--! ok
@@ -0,0 +1,117 @@
+-- Diverging function call tests for the Kailua type checker.
+
+--8<-- diverging-helper-narrow
+--# open lua51
+--v function(msg: string) --> !
+local function fail(msg)
+    error(msg)
+end
+--v function(x: integer?) --> integer!
+local function f(x)
+    if not x then fail('x is nil') end
+    return x
+end
+--! ok
+
+--8<-- diverging-helper-narrow-eq
+--# assume global fail: function(string) --> !
+--v function(x: integer?) --> integer!
+local function f(x)
+    if x == nil then fail('x is nil') end
+    return x
+end
+--! ok
+
+--8<-- diverging-helper-field
+--# assume global M: { fail: function(string) --> ! }
+--v function(x: integer?) --> integer!
+local function f(x)
+    if not x then M.fail('x is nil') end
+    return x
+end
+--! ok
+
+--8<-- diverging-helper-method
+--# assume global M: { fail: function(table, string) --> ! }
+--v function(x: integer?) --> integer!
+local function f(x)
+    if not x then M:fail('x is nil') end
+    return x
+end
+--! ok
+
+--8<-- diverging-helper-return-type
+--# assume global fail: function(string) --> !
+--v function(x: integer?) --> integer
+local function f(x)
+    if x then return x end
+    fail('x is nil')
+end
+--! ok
+
+--8<-- diverging-dead-code
+--# assume global fail: function(string) --> !
+--v function()
+local function f()
+    fail('oops')
+    local x = 42 --@<-v Warning: This code will never execute
+    local y = 54
+end
+--! ok
+
+--8<-- diverging-dead-code-error
+--# open lua51
+error('whatever')
+print(42) --@< Warning: This code will never execute
+--! ok
+
+--8<-- diverging-dead-code-branch
+--# assume global fail: function(string) --> !
+--# assume x: boolean
+if x then
+    fail('oops')
+    local y = 42 --@< Warning: This code will never execute
+end
+--! ok
+
+--8<-- diverging-return-call
+--# assume global fail: function(string) --> !
+--v function(x: integer?) --> integer!
+local function f(x)
+    if not x then return fail('x is nil') end
+    return x
+end
+--! ok
+
+--8<-- diverging-or
+--# assume global fail: function(string) --> !
+--v function(x: integer?) --> integer!
+local function f(x)
+    local y = x or fail('x is nil')
+    local z = x --: integer!
+    return y
+end
+--! ok
+
+--8<-- diverging-or-cond
+--# assume global fail: function(string) --> !
+--v function(x: integer?) --> integer!
+local function f(x)
+    local _ = x ~= nil or fail('x is nil')
+    return x
+end
+--! ok
+
+--8<-- diverging-and
+--# assume global fail: function(string) --> !
+--v function(x: string?) --> nil
+local function f(x)
+    local y = x and fail('x is not nil')
+    return y
+end
+--! ok
+
+--8<-- diverging-in-expr
+--# assume global fail: function(string) --> !
+local x = 1 + fail('oops') --@< Warning: A portion of this expression won't be evaluated because it contains a call to a function that never returns
+--! ok
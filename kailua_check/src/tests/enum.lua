@@ -0,0 +1,191 @@
+-- String enum (`--# enum`) tests for the Kailua type checker.
+
+--8<-- enum
+--# enum Dir = "north" | "south" | "east" | "west"
+local d = 'north' --: Dir
+d = 'west'
+--! ok
+
+--8<-- enum-typo
+--# enum Dir = "north" | "south" | "east" | "west"
+local d = 'nroth' --: Dir
+--@^ Error: Cannot assign `"nroth"` into `Dir`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- enum-typo-assign
+--# enum Dir = "north" | "south" | "east" | "west"
+local d = 'north' --: Dir
+d = 'sotuh' --@< Error: Cannot assign `"sotuh"` into `Dir`
+            --@^ Note: The other type originates here
+--! error
+
+--8<-- enum-typo-arg
+--# enum Dir = "north" | "south" | "east" | "west"
+local function go(d) --: Dir
+end
+go('esat') --@< Error: The type `function(d: Dir) --> ()` cannot be called
+           --@^ Cause: First function argument `"esat"` is not a subtype of `Dir`
+           --@^^ Note: The other type originates here
+--! error
+
+--8<-- enum-to-string
+--# enum Dir = "north" | "south"
+--# assume d: Dir
+local s = d --: string
+--! ok
+
+--8<-- enum-local-scope
+do
+    --# enum local Dir = "north" | "south"
+    --# assume d: Dir
+end
+--# assume e: Dir --@< Error: Type `Dir` is not defined
+--! error
+
+--8<-- enum-compare-typo
+--# enum Dir = "north" | "south" | "east" | "west"
+--# assume d: Dir
+if d == 'nroth' then --@< Warning: The string "nroth" is not a part of `Dir`, so the comparison always has the same result
+end --@^ Note: The other type originates here
+--! ok
+
+--8<-- enum-narrow-if
+--# enum Dir = "north" | "south" | "east" | "west"
+--# assume d: Dir
+if d == 'north' then
+    local n = d --: "north"
+elseif 'south' == d then
+    local s = d --: "south"
+else
+    local r = d --: "east" | "west"
+end
+--! ok
+
+--8<-- enum-narrow-if-ne
+--# enum Dir = "north" | "south" | "east"
+--# assume d: Dir
+if d ~= 'north' then
+    local r = d --: "south" | "east"
+else
+    local n = d --: "north"
+end
+--! ok
+
+--8<-- enum-narrow-if-wrong
+--# enum Dir = "north" | "south" | "east" | "west"
+--# assume d: Dir
+if d == 'north' then
+    local s = d --: "south"
+    --@^ Error: Cannot assign `"north"` into `"south"`
+    --@^^ Note: The other type originates here
+end
+--! error
+
+--8<-- enum-narrow-if-or
+--# enum Dir = "north" | "south" | "east" | "west"
+--# assume d: Dir
+if d == 'north' or d == 'south' then
+else
+    local r = d --: "east" | "west"
+end
+--! ok
+
+--8<-- enum-narrow-nil
+--# enum Dir = "north" | "south"
+--# assume d: Dir?
+if d == 'north' then
+    local n = d --: "north"
+else
+    local r = d --: "south"?
+end
+--! ok
+
+--8<-- enum-narrow-after-return
+--# enum Dir = "north" | "south" | "east"
+--v function(d: Dir) --> integer
+local function f(d)
+    if d == 'north' then return 1 end
+    if d == 'south' then return 2 end
+    local e = d --: "east"
+    return 3
+end
+--! ok
+
+--8<-- enum-narrow-restored
+--# enum Dir = "north" | "south"
+--# assume d: Dir
+if d == 'north' then
+end
+local s = d --: "north"
+--@^ Error: Cannot assign `Dir` into `"north"`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- enum-narrow-string
+--# assume s: string
+if s == 'north' then
+    local n = s --: "north"
+    --@^ Error: Cannot assign `string` into `"north"`
+    --@^^ Note: The other type originates here
+end
+--! error
+
+--8<-- enum-exhaustive
+--# enum [exhaustive] Dir = "north" | "south" | "east"
+--# assume d: Dir
+if d == 'north' then
+elseif d == 'south' then
+elseif d == 'east' then
+end
+--! ok
+
+--8<-- enum-exhaustive-missing
+--# enum [exhaustive] Dir = "north" | "south" | "east" | "west"
+--# assume d: Dir
+--@vv-vvvv Warning: This `if` statement does not handle `("east"|"west")` of `Dir`
+--@v Note: The variable is compared here
+if d == 'north' then
+elseif d == 'south' then
+end
+--! ok
+
+--8<-- enum-exhaustive-else
+--# enum [exhaustive] Dir = "north" | "south" | "east"
+--# assume d: Dir
+if d == 'north' then
+else
+end
+--! ok
+
+--8<-- enum-exhaustive-or
+--# enum [exhaustive] Dir = "north" | "south" | "east"
+--# assume d: Dir
+if d == 'north' or d == 'east' then
+elseif d == 'south' then
+end
+--! ok
+
+--8<-- enum-exhaustive-typo
+--# enum [exhaustive] Dir = "north" | "south"
+--# assume d: Dir
+--@vv-vvvv Warning: This `if` statement does not handle `"south"` of `Dir`
+--@v Note: The variable is compared here
+if d == 'north' then
+elseif d == 'sotuh' then --@< Warning: The string "sotuh" is not a part of `[exhaustive] "south"`, so the comparison always has the same result
+end --@^ Note: The other type originates here
+--! ok
+
+--8<-- enum-non-exhaustive
+--# enum Dir = "north" | "south" | "east"
+--# assume d: Dir
+if d == 'north' then
+end
+--! ok
+
+--8<-- enum-exhaustive-assign
+--# enum [exhaustive] Dir = "north" | "south"
+local d = 'north' --: Dir
+d = 'south'
+local e = d --: Dir
+--! ok
@@ -0,0 +1,117 @@
+-- Format string (`string.format` and `[string_format]`) tests for the Kailua type checker.
+
+--8<-- format
+--# open lua51
+local s = string.format('%d: %s (%q) %5.2f%%', 1, {}, 'x', 3.14) --: string
+--! ok
+
+--8<-- format-method
+--# open lua51
+local s = ('%-3d|%x'):format(42, 255) --: string
+--! ok
+
+--8<-- format-no-directives
+--# open lua51
+local s = string.format('100%%') --: string
+--! ok
+
+--8<-- format-not-number
+--# open lua51
+local s = string.format('%d items', 'many') --@< Error: The argument for `%d` in the format string should be a number
+                                            --@^ Cause: `"many"` is not a subtype of `number`
+--! error
+
+--8<-- format-not-string
+--# open lua51
+local s = string.format('%q', 42) --@< Error: The argument for `%q` in the format string should be a string
+                                  --@^ Cause: `42` is not a subtype of `string`
+--! error
+
+--8<-- format-any
+--# open lua51
+local s = string.format('%s %s %s', 1, true, {})
+--! ok
+
+--8<-- format-missing-arg
+--# open lua51
+local s = string.format('%d and %d', 1)
+--@^ Error: The argument for `%d` in the format string is missing
+--@^^ Note: The format string is given here
+--! error
+
+--8<-- format-missing-arg-method
+--# open lua51
+local s = ('%s=%s'):format('a')
+--@^ Error: The argument for `%s` in the format string is missing
+--@^^ Note: The format string is given here
+--! error
+
+--8<-- format-extra-args
+--# open lua51
+local s = string.format('%d', 1, 2) --@< Warning: The format string only uses 1 argument(s), and the rest is ignored
+--! ok
+
+--8<-- format-varargs
+--# open lua51
+--v function(...: any) --> string
+local function f(...)
+    return string.format('%d %d', ...)
+end
+--! ok
+
+--8<-- format-invalid-directive
+--# open lua51
+local s = string.format('%k', 1) --@< Error: `%k` in the format string is not a valid directive
+--! error
+
+--8<-- format-ends-with-percent
+--# open lua51
+local s = string.format('50%') --@< Error: `%` in the format string is not a valid directive
+--! error
+
+--8<-- format-width-too-long
+--# open lua51
+local s = string.format('%100d', 1) --@< Error: The width or precision of `%100d` in the format string is too long
+--! error
+
+--8<-- format-precision-too-long
+--# open lua51
+local s = string.format('%.100f', 1) --@< Error: The width or precision of `%.100f` in the format string is too long
+--! error
+
+--8<-- format-repeated-flags
+--# open lua51
+local s = string.format('%-+ #0-d', 1) --@< Error: `%-+ #0-d` in the format string has too many flags
+--! error
+
+--8<-- format-non-literal
+--# open lua51
+--# assume fmt: string
+local s = string.format(fmt, 1, 'x', {})
+--! ok
+
+--8<-- format-wrapper
+--# open lua51
+--# assume global log: [string_format] function(level: integer, fmt: string, any...)
+log(1, '%d items', 3)
+log(2, '%d items', 'many') --@< Error: The argument for `%d` in the format string should be a number
+                           --@^ Cause: `"many"` is not a subtype of `number`
+--! error
+
+--8<-- format-wrapper-method
+--# open lua51
+--# assume global class Logger
+--# assume logger: Logger
+--v [string_format] method(fmt: string, ...: any)
+function Logger:info(fmt, ...)
+    print(string.format(fmt, ...))
+end
+logger:info('%s: %d', 'count', 3)
+logger:info('%s: %d', 'count') --@< Error: The argument for `%d` in the format string is missing
+                               --@^ Note: The format string is given here
+--! error
+
+--8<-- format-lua53
+--# open lua53
+local s = string.format('%a %d', 1.5, 2) --: string
+--! ok
@@ -0,0 +1,301 @@
+-- Generic function tests for the Kailua type checker.
+
+--8<-- generic-identity
+--# assume id: function<T>(T) --> T
+local x = id(42) --: integer
+local y = id('string') --: string
+--! ok
+
+--8<-- generic-identity-mismatch
+--# assume id: function<T>(T) --> T
+local x = id('string') --: integer
+--@^ Error: Cannot assign `string` into `integer`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- generic-vector-elem
+--# assume first: function<T>(vector<T>) --> T
+local p = {1, 2, 3} --: vector<integer>
+local q = {'a', 'b'} --: vector<string>
+local x = first(p) --: integer
+local y = first(q) --: string
+local z = first(p) + 1
+--! ok
+
+--8<-- generic-vector-elem-mismatch
+--# assume first: function<T>(vector<T>) --> T
+local p = {1, 2, 3} --: vector<integer>
+local x = #first(p) --@< Error: Cannot apply # operator to `integer`
+                    --@^ Cause: `integer` is not a subtype of `(string|table)`
+--! error
+
+--8<-- generic-multiple-params
+--# assume swap: function<A, B>(A, B) --> (B, A)
+local x, y = swap(42, 'string')
+local a = x .. 'a'
+local b = y + 1
+--! ok
+
+--8<-- generic-shared-param
+--# assume push: function<T>(vector<T>, T)
+local p = {1, 2, 3} --: vector<integer>
+push(p, 4)
+push(p, 'string') --@< Error: The type `function<T>(vector<T>, T) --> ()` cannot be called
+                  --@^ Cause: Second function argument `string` is not a subtype of `T`
+                  --@^^ Note: The other type originates here
+                  --@ Cause: `string` is not a subtype of `integer`
+--! error
+
+--8<-- generic-literal-args
+--# assume pair: function<T>(T, T) --> T
+local x = pair(1, 2) --: integer
+local y = pair('a', 'b') --: string
+--! ok
+
+--8<-- generic-literal-record-arg
+--# open lua51
+local u = {1,2,3}
+table.insert(u, 4)
+local m = {'a','b'}
+table.insert(m, 'c')
+--! ok
+
+--8<-- generic-literal-record-arg-remove
+--# open lua51
+local u = {1,2,3}
+local x = table.remove(u) --: integer
+--! ok
+
+--8<-- generic-literal-record-arg-ipairs
+--# open lua51
+local u = {1,2,3}
+for i, v in ipairs(u) do
+    local w = v --: integer
+end
+--! ok
+
+--8<-- generic-literal-record-arg-mismatch
+--# open lua51
+local u = {1,2,3}
+table.insert(u, 'x')
+--@^ Error: The type `function<T>(table: vector<T>, value: T) --> ()` cannot be called
+--@^^ Cause: Second function argument `string` is not a subtype of `T`
+--@^^^ Note: The other type originates here
+--@ Cause: `string` is not a subtype of `integer`
+--! error
+
+--8<-- generic-instantiated-per-call
+--# assume id: function<T>(T) --> T
+local x = id(42) --: integer
+local y = id('string') --: string
+local z = id(x) + id(3)
+--! ok
+
+--8<-- generic-table-literal-per-call
+--# assume first: function<T>(vector<T>) --> T
+local x = first({'a'}) --: string
+local y = first({1, 2}) --: integer
+local z = first({1, 2}) + first({3}) --: integer
+--! ok
+
+--8<-- generic-table-literal-per-call-mismatch
+--# assume first: function<T>(vector<T>) --> T
+local x = first({'a'}) --: string
+local y = first({1, 2}) --: string
+--@^ Error: Cannot assign `integer` into `string`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- generic-callback-per-call
+--# assume fmap: function<T, U>(vector<T>, function(T) --> U) --> vector<U>
+local p = fmap({1, 2}, function(x) return x .. '' end) --: vector<string>
+local q = fmap({'a'}, function(x) return #x end) --: vector<integer>
+--! ok
+
+--8<-- generic-map
+--# assume lookup: function<K, V>(map<K, V>, K) --> V?
+local m = {} --: map<string, integer>
+local x = lookup(m, 'a') --: integer?
+--! ok
+
+--8<-- generic-callback
+--# assume fmap: function<T, U>(vector<T>, function(T) --> U) --> vector<U>
+local p = {1, 2, 3} --: vector<integer>
+local q = fmap(p, function(x) --: integer
+    return 'a'
+end) --: vector<string>
+--! ok
+
+--8<-- generic-display
+--# assume fmap: function<T, U>(vector<T>, function(T) --> U) --> vector<U>
+fmap() --@< Error: The type `function<T, U>(vector<T>, function(T) --> U) --> vector<U>` cannot be called
+       --@^ Cause: First function argument cannot be omitted because its type is `vector<T>`
+       --@^^ Note: The other type originates here
+--! error
+
+--8<-- generic-assign-to-instance
+--# assume id: function<T>(T) --> T
+local f = id --: function(integer) --> integer
+local x = f(42) + 1
+--! ok
+
+--8<-- generic-assign-to-instance-mismatch
+--# assume id: function<T>(T) --> T
+local f = id --: function(integer) --> string
+--@^ Error: Cannot assign `function<T>(T) --> T` into `function(integer) --> string`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- generic-assign-non-generic
+--# assume f: function(integer) --> integer
+local g = f --: function<T>(T) --> T
+--@^ Error: Cannot assign `function(integer) --> integer` into `function<T>(T) --> T`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- generic-assign-generic
+--# assume id: function<T>(T) --> T
+local f = id --: function<U>(U) --> U
+local x = f('string') --: string
+--! ok
+
+--8<-- generic-not-shadowing-outer-type
+--# type T = string
+--# assume f: function<U>(U, T) --> U
+local x = f(42, 'string') --: integer
+--! ok
+
+--8<-- generic-shadowing-outer-type
+--# type T = string
+--# assume f: function<T>(T) --> T
+local x = f(42) --: integer
+--! ok
+
+--8<-- generic-param-outside-scope
+--# assume f: function<T>(T) --> T
+local x --: T --@< Error: Type `T` is not defined
+--! error
+
+--8<-- generic-table-remove
+--# open lua51
+local p = {1, 2, 3} --: vector<integer>
+local x = table.remove(p) + 1
+local q = {'a', 'b'} --: vector<string>
+local y = table.remove(q, 1) .. 'c'
+--! ok
+
+--8<-- generic-unconstrained
+--# assume make: function<T>() --> T
+local x = make()
+local y = x + #x
+--! ok
+
+--8<-- generic-table-sort
+--# open lua51
+local p = {3, 1, 2} --: vector<integer>
+table.sort(p, function(a, b) return a < b end)
+--! ok
+
+--8<-- generic-ipairs
+--# open lua51
+local p = {'a', 'b'} --: vector<string>
+for i, v in ipairs(p) do
+    local x = i + 1
+    local y = v .. 'c'
+end
+--! ok
+
+-- generic type aliases
+
+--8<-- generic-alias
+--# type Result<T> = {ok: boolean, value: T?}
+local p = {ok = true, value = 42} --: Result<integer>
+local q = {ok = false} --: Result<string>
+local x = p.value --: integer?
+local y = q.value --: string?
+--! ok
+
+--8<-- generic-alias-mismatch
+--# type Result<T> = {ok: boolean, value: T?}
+local p = {ok = true, value = 'string'} --: Result<integer>
+--@^ Error: Cannot assign `{ok: true, value: "string", ...}` into `{ok: boolean, value: integer?}`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- generic-alias-multiple-params
+--# type local MultiMap<K, V> = map<K, vector<V>>
+local m = {} --: MultiMap<string, integer>
+local n = {} --: MultiMap<integer, MultiMap<string, boolean>>
+m.a = {1, 2, 3}
+n[1] = {{b = {true, false}}}
+--! ok
+
+--8<-- generic-alias-global
+--# type global Pair<A, B> = {A, B}
+local function f()
+    local p = {1, 'a'} --: Pair<integer, string>
+    return p[1] + 1, p[2] .. 'b'
+end
+--! ok
+
+--8<-- generic-alias-no-args
+--# type Result<T> = {ok: boolean, value: T?}
+local p --: Result --@< Error: The type `Result` requires 1 type argument(s) but 0 were given
+--! error
+
+--8<-- generic-alias-wrong-arity
+--# type Result<T> = {ok: boolean, value: T?}
+local p --: Result<integer, string>
+--@^ Error: The type `Result` requires 1 type argument(s) but 2 were given
+--! error
+
+--8<-- generic-alias-args-to-non-generic
+--# type Point = {x: number, y: number}
+local p --: Point<integer> --@< Error: The type `Point` requires 0 type argument(s) but 1 were given
+--! error
+
+--8<-- generic-alias-param-outside-scope
+--# type Result<T> = {ok: boolean, value: T?}
+local x --: T --@< Error: Type `T` is not defined
+--! error
+
+--8<-- generic-alias-with-generic-function
+--# type Mapper<T> = function<U>(vector<T>, function(T) --> U) --> vector<U>
+--# assume fmap: Mapper<integer>
+local p = {1, 2, 3} --: vector<integer>
+local q = fmap(p, function(x) --: integer
+    return 'a'
+end) --: vector<string>
+--! ok
+
+--8<-- generic-alias-require
+--# assume global `require`: [require] function(string) --> any
+local x = require('x')
+local p = {ok = true, value = 42} --: Result<integer>
+local y = x(p) .. 'a'
+
+--& x
+--# type Result<T> = {ok: boolean, value: T?}
+local function f(r) --: Result<integer>
+                    --> string
+    return 'a'
+end
+return f
+
+--! ok
+
+--8<-- generic-alias-require-reexport
+--# assume global `require`: [require] function(string) --> any
+local x = require('x')
+local p = {ok = true, value = 'a'} --: Result<string>
+
+--& x
+local y = require('y')
+--# type Result = Result
+return y
+
+--& y
+--# type Result<T> = {ok: boolean, value: T?}
+return 42
+
+--! ok
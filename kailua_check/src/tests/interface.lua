@@ -0,0 +1,228 @@
+-- Interface and intersection type tests for the Kailua type checker.
+
+--8<-- interface-record
+--# interface Drawable { pos: {x: number, y: number}, draw: method() --> () }
+local p = {pos = {x = 1, y = 2}, draw = function(self) --: any
+                                        end}
+local d = p --: Drawable
+--! ok
+
+--8<-- interface-record-missing-member
+--# interface Drawable { pos: {x: number, y: number}, draw: method() --> () }
+local p = {pos = {x = 1, y = 2}}
+local d = p --: Drawable
+--@^ Error: Cannot assign `{pos: {x: 1, y: 2, ...}, ...}` into `Drawable`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- interface-record-field-mismatch
+--# interface Named { name: string }
+local p = {name = 42}
+local n = p --: Named
+--@^ Error: Cannot assign `{name: 42, ...}` into `Named`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- interface-record-method-mismatch
+--# interface Drawable { draw: method(integer) --> string }
+--v function(self: any, x: integer) --> integer
+local function draw(self, x)
+    return x
+end
+local p = {draw = draw}
+local d = p --: Drawable
+--@^ Error: Cannot assign `{draw: function(self: any, x: integer) --> integer, ...}` into `Drawable`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- interface-extra-fields
+--# interface Named { name: string }
+local p = {name = 'foo', age = 42}
+local n = p --: Named
+--! ok
+
+--8<-- interface-index
+--# interface Drawable { pos: {x: number, y: number}, draw: method(number) --> string }
+--# assume d: Drawable
+local x = d.pos.x + 1 --: number
+local s = d:draw(42) .. 'a' --: string
+--! ok
+
+--8<-- interface-index-missing
+--# interface Drawable { draw: method() --> () }
+--# assume d: Drawable
+local x = d.pos --@< Error: Cannot index `Drawable` with `"pos"`
+--! error
+
+--8<-- interface-index-unknown-key
+--# interface Named { name: string }
+--# assume n: Named
+--# assume k: string
+local x = n[k] --@< Error: Cannot index `Named` with `string`
+--! error
+
+--8<-- interface-method-wrong-args
+--# interface Drawable { draw: method(number) --> string }
+--# assume d: Drawable
+local s = d:draw('a') --@< Error: The type `function(self: Drawable, number) --> string` cannot be called
+                      --@^ Cause: First method argument `"a"` is not a subtype of `number`
+                      --@^^ Note: The other type originates here
+--! error
+
+--8<-- interface-function-arg
+--# interface Drawable { pos: {x: number, y: number}, draw: method() --> () }
+local function render(d) --: Drawable
+    d:draw()
+    return d.pos.x
+end
+render({pos = {x = 1, y = 2}, draw = function(self) --: any
+                                     end})
+--! ok
+
+--8<-- interface-function-arg-missing
+--# interface Drawable { pos: {x: number, y: number}, draw: method() --> () }
+local function render(d) --: Drawable
+    d:draw()
+end
+render({pos = {x = 1, y = 2}})
+--@^ Error: The type `function(d: Drawable) --> ()` cannot be called
+--@^^ Cause: First function argument `{pos: {x: 1, y: 2, ...}, ...}` is not a subtype of `Drawable`
+--@^^^ Note: The other type originates here
+--@ Cause: The member `draw` required by the interface is missing
+--! error
+
+--8<-- interface-class
+--# assume global class Sprite
+--# assume Sprite.pos: {x: number, y: number}
+--# assume Sprite.draw: function(Sprite)
+--# interface Drawable { pos: {x: number, y: number}, draw: method() --> () }
+--# assume s: Sprite
+local d = s --: Drawable
+--! ok
+
+--8<-- interface-class-missing-member
+--# assume global class Sprite
+--# assume Sprite.draw: function(Sprite)
+--# interface Drawable { pos: {x: number, y: number}, draw: method() --> () }
+--# assume s: Sprite
+local d = s --: Drawable
+--@^ Error: Cannot assign `Sprite` into `Drawable`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- interface-class-method
+--# assume global class Sprite
+--v method()
+function Sprite:draw()
+end
+--# interface Drawable { draw: method() --> () }
+--# assume s: Sprite
+local d = s --: Drawable
+d:draw()
+--! ok
+
+--8<-- interface-to-interface
+--# interface Drawable { draw: method() --> () }
+--# interface Sprite { pos: {x: number, y: number}, draw: method() --> () }
+--# assume s: Sprite
+local d = s --: Drawable
+--! ok
+
+--8<-- interface-to-interface-missing
+--# interface Drawable { draw: method() --> () }
+--# interface Sprite { pos: {x: number, y: number}, draw: method() --> () }
+--# assume d: Drawable
+local s = d --: Sprite
+--@^ Error: Cannot assign `Drawable` into `Sprite`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- interface-not-assignable-to-record
+--# interface Named { name: string }
+--# assume n: Named
+local r = n --: {name: string}
+--@^ Error: Cannot assign `Named` into `{name: string}`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- interface-display
+--# interface Drawable { pos: {x: number, y: number}, draw: method(number) --> string }
+--# assume d: Drawable
+local x = d + 1 --@< Error: Cannot apply + operator to `Drawable` and `1`
+                --@^ Cause: `Drawable` is not a subtype of `number`
+--! error
+
+--8<-- interface-local-scope
+do
+    --# interface local Named { name: string }
+    --# assume n: Named
+end
+--# assume m: Named --@< Error: Type `Named` is not defined
+--! error
+
+--8<-- intersection
+--# interface Drawable { draw: method() --> () }
+--# interface Positioned { pos: {x: number, y: number} }
+local function render(d) --: Drawable & Positioned
+    d:draw()
+    return d.pos.x + d.pos.y
+end
+render({pos = {x = 1, y = 2}, draw = function(self) --: any
+                                     end})
+--! ok
+
+--8<-- intersection-missing
+--# interface Drawable { draw: method() --> () }
+--# interface Positioned { pos: {x: number, y: number} }
+local p = {draw = function(self) --: any
+                  end}
+local d = p --: Drawable & Positioned
+--@^ Error: Cannot assign `{draw: function(self: any) --> (), ...}` into `Drawable & Positioned`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- intersection-to-part
+--# interface Drawable { draw: method() --> () }
+--# interface Positioned { pos: {x: number, y: number} }
+--# assume d: Drawable & Positioned
+local a = d --: Drawable
+local b = d --: Positioned
+--! ok
+
+--8<-- intersection-to-merged-interface
+--# interface Drawable { draw: method() --> () }
+--# interface Positioned { pos: {x: number, y: number} }
+--# interface Sprite { pos: {x: number, y: number}, draw: method() --> () }
+--# assume d: Drawable & Positioned
+local s = d --: Sprite
+--! ok
+
+--8<-- intersection-named
+--# interface Drawable { draw: method() --> () }
+--# interface Positioned { pos: {x: number, y: number} }
+--# type Sprite = Drawable & Positioned
+--# assume s: Sprite
+s:draw()
+local x = s.pos.x --: number
+--! ok
+
+--8<-- intersection-display
+--# interface Drawable { draw: method() --> () }
+--# interface Positioned { pos: {x: number, y: number} }
+--# assume d: Drawable & Positioned
+local x = d + 1 --@< Error: Cannot apply + operator to `Drawable & Positioned` and `1`
+                --@^ Cause: `Drawable & Positioned` is not a subtype of `number`
+--! error
+
+--8<-- intersection-index-missing
+--# interface Drawable { draw: method() --> () }
+--# interface Positioned { pos: {x: number, y: number} }
+--# assume d: Drawable & Positioned
+local x = d.size --@< Error: Cannot index `Drawable & Positioned` with `"size"`
+--! error
+
+--8<-- intersection-records
+--# assume p: {a: integer, ...} & {b: string, ...}
+local x = p.a + 1 --: integer
+local y = p.b .. 'c' --: string
+--! ok
@@ -149,10 +149,9 @@ end
 --# open lua51
 --# assume p: map<integer, string>
 for x, y in ipairs(p) do
-    --@^ Error: The type `[generic_pairs] function(t: vector<const WHATEVER>) --> (function(vector<const WHATEVER>, integer) --> (integer?, any), vector<const WHATEVER>, integer)` cannot be called
-    --@^^ Cause: First function argument `map<integer, string>` is not a subtype of `vector<const WHATEVER>`
+    --@^ Error: The type `function<T>(t: vector<const T>) --> (function(vector<const T>, integer) --> (integer?, T), vector<const T>, integer)` cannot be called
+    --@^^ Cause: First function argument `map<integer, string>` is not a subtype of `vector<const T>`
     --@^^^ Note: The other type originates here
-    -- XXX WHATEVER is temporary
 end
 --! error
 
@@ -160,10 +159,9 @@ end
 --# open lua51
 --# assume p: table
 for x, y in ipairs(p) do
-    --@^ Error: The type `[generic_pairs] function(t: vector<const WHATEVER>) --> (function(vector<const WHATEVER>, integer) --> (integer?, any), vector<const WHATEVER>, integer)` cannot be called
-    --@^^ Cause: First function argument `table` is not a subtype of `vector<const WHATEVER>`
+    --@^ Error: The type `function<T>(t: vector<const T>) --> (function(vector<const T>, integer) --> (integer?, T), vector<const T>, integer)` cannot be called
+    --@^^ Cause: First function argument `table` is not a subtype of `vector<const T>`
     --@^^^ Note: The other type originates here
-    -- XXX WHATEVER is temporary
 end
 --! error
 
@@ -171,10 +169,9 @@ end
 --# open lua51
 --# assume p: string
 for x, y in ipairs(p) do
-    --@^ Error: The type `[generic_pairs] function(t: vector<const WHATEVER>) --> (function(vector<const WHATEVER>, integer) --> (integer?, any), vector<const WHATEVER>, integer)` cannot be called
-    --@^^ Cause: First function argument `string` is not a subtype of `vector<const WHATEVER>`
+    --@^ Error: The type `function<T>(t: vector<const T>) --> (function(vector<const T>, integer) --> (integer?, T), vector<const T>, integer)` cannot be called
+    --@^^ Cause: First function argument `string` is not a subtype of `vector<const T>`
     --@^^^ Note: The other type originates here
-    -- XXX WHATEVER is temporary
 end
 --! error
 
@@ -397,11 +394,15 @@ table.insert(x, 42)
 table.insert(x, 54)
 --! ok
 
--->8-- lua51-table-insert-2
+--8<-- lua51-table-insert-2
 --# open lua51
 local x = {} --: vector<integer>
 table.insert(x, 42)
-table.insert(x, 'not an integer') -- should error, but current definition is rather weak
+table.insert(x, 'not an integer')
+--@^ Error: The type `function<T>(table: vector<T>, value: T) --> ()` cannot be called
+--@^^ Cause: Second function argument `string` is not a subtype of `T`
+--@^^^ Note: The other type originates here
+--@ Cause: `string` is not a subtype of `integer`
 --! error
 
 --8<-- lua51-table-maxn
@@ -0,0 +1,105 @@
+-- Tests specific to Lua 5.2 library support in the Kailua type checker.
+
+--8<-- lua52-print
+--# open lua52
+print('hello')
+--! ok
+
+--8<-- lua52-no-setfenv
+--# open lua52
+setfenv(1, {}) --@< Error: Global or local variable `setfenv` is not defined
+--! error
+
+--8<-- lua52-no-unpack
+--# open lua52
+local a, b = unpack({1, 2}) --@< Error: Global or local variable `unpack` is not defined
+--! error
+
+--8<-- lua52-table-unpack
+--# open lua52
+local a, b = table.unpack({1, 2})
+--! ok
+
+--8<-- lua52-table-pack
+--# open lua52
+local t = table.pack(1, 2, 3)
+local n = t.n --: integer
+--! ok
+
+--8<-- lua52-bit32
+--# open lua52
+local x = bit32.band(0xff, 0x0f, 0x3) --: integer
+local y = bit32.lshift(x, 4) --: integer
+local z = bit32.btest(x, y) --: boolean
+--! ok
+
+--8<-- lua52-load-with-env
+--# open lua52
+local f = load('return x', 'chunk', 't', { x = 42 })
+--! ok
+
+--8<-- lua52-rawlen
+--# open lua52
+local n = rawlen({1, 2, 3}) --: integer
+--! ok
+
+--8<-- lua52-goto-continue
+--# open lua52
+for i = 1, 10 do
+    if i % 2 == 0 then goto continue end
+    print(i)
+    ::continue::
+end
+--! ok
+
+--8<-- lua52-goto-into-local-scope
+--# open lua52
+goto skip --@< Error: `goto` to the label `skip` jumps into the scope of local `x`
+local x = 1
+::skip::
+print(x)
+--! ok
+
+--8<-- lua52-goto-continue-after-local
+--# open lua52
+for i = 1, 10 do
+    if i % 2 == 0 then goto continue end
+    local j = i * 2
+    print(j)
+    ::continue::
+end
+--! ok
+
+--8<-- lua52-stmt-after-goto -- feature:warn_on_dead_code
+--# open lua52
+::retry::
+goto retry
+print('not dead, as far as the checker knows')
+--! ok
+
+--8<-- lua52-local-env
+--# open lua52
+local print = print
+local _ENV = { x = 42 }
+local y = x --: integer
+print(y)
+--! ok
+
+--8<-- lua52-local-env-hides-globals
+--# open lua52
+local _ENV = { x = 42 }
+print(x) --@< Error: Missing key "print" in `{x: 42, ...}`
+--! error
+
+--8<-- lua52-local-env-assign
+--# open lua52
+local _ENV = { x = 42 }
+x = 'string' --@< Error: Cannot assign `"string"` into `42`
+             --@^ Note: The other type originates here
+--! error
+
+--8<-- lua52-global-env
+--# open lua52
+local x = _ENV.x --@< Error: Cannot index `[genv] table` without further type information; specify more detailed type, or use `--# assume` as a last resort
+--! error
+
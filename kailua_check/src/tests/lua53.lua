@@ -0,0 +1,115 @@
+-- Tests specific to Lua 5.3 library support in the Kailua type checker.
+
+--8<-- lua53-print
+--# open lua53
+print('hello')
+--! ok
+
+--8<-- lua53-no-bit32
+--# open lua53
+local x = bit32.band(1, 2) --@< Error: Global or local variable `bit32` is not defined
+--! error
+
+--8<-- lua53-no-loadstring
+--# open lua53
+local f = loadstring('return 42') --@< Error: Global or local variable `loadstring` is not defined
+--! error
+
+--8<-- lua53-no-module
+--# open lua53
+module('foo') --@< Error: Global or local variable `module` is not defined
+--! error
+
+--8<-- lua53-load
+--# open lua53
+local f = load('return 42')
+local co = coroutine.isyieldable() --: boolean
+--! ok
+
+--8<-- lua53-idiv
+--# open lua53
+local a = 7 // 2 --: integer
+local b = 7.5 // 2 --: number
+--! ok
+
+--8<-- lua53-idiv-integer-only
+--# open lua53
+local a = 7.5 // 2 --: integer --@< Error: Cannot assign `number` into `integer`
+                              --@^ Note: The other type originates here
+--! error
+
+--8<-- lua53-bitwise
+--# open lua53
+local a = 3 & 5 --: integer
+local b = 3 | 5.0 --: integer
+local c = 3 ~ 5 --: integer
+local d = 1 << 4 --: integer
+local e = 256 >> 4 --: integer
+local f = ~0 --: integer
+--! ok
+
+--8<-- lua53-bitwise-string
+--# open lua53
+local a = 3 & 'foo' --@< Error: Cannot apply & operator to `3` and `"foo"`
+                    --@^ Cause: `"foo"` is not a subtype of `number`
+--! error
+
+--8<-- lua53-bnot-string
+--# open lua53
+local a = ~'foo' --@< Error: Cannot apply ~ operator to `"foo"`
+                 --@^ Cause: `"foo"` is not a subtype of `number`
+--! error
+
+--8<-- lua53-math
+--# open lua53
+local a = math.tointeger(3.0) --: integer?
+local b = math.maxinteger --: integer
+local c = math.ult(1, -1) --: boolean
+local t = math.type(42)
+if t == 'float' then print('float') end
+--! ok
+
+--8<-- lua53-string-pack
+--# open lua53
+local s = string.pack('i4', 42) --: string
+local n = string.packsize('i4') --: integer
+local x = string.unpack('i4', s)
+--! ok
+
+--8<-- lua53-utf8
+--# open lua53
+local s = utf8.char(72, 105) --: string
+local n = utf8.len(s)
+for p, c in utf8.codes(s) do
+    local q = p --: integer
+    local d = c --: integer
+end
+--! ok
+
+--8<-- lua53-table-move
+--# open lua53
+local t = table.move({1, 2, 3}, 1, 3, 2)
+--! ok
+
+--8<-- lua53-lang-in-required-file
+--# open lua51
+local m = require 'a'
+local x = m.half(7) --: integer
+--! ok
+
+--& a
+--# lang lua53
+local M = {}
+function M.half(n) --: integer --> integer
+    return n // 2
+end
+return M
+
+--8<-- lua53-lang-not-inherited-by-required-file
+--# open lua53
+local a = require 'a'
+--! error
+
+--& a
+return 7 // 2 --@< Error: `//` cannot be used in Lua 5.1, it is only available since Lua 5.3
+
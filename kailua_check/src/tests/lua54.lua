@@ -0,0 +1,108 @@
+-- Tests specific to Lua 5.4 library support in the Kailua type checker.
+
+--8<-- lua54-print
+--# open lua54
+print('hello')
+warn('@on')
+--! ok
+
+--8<-- lua54-no-loadstring
+--# open lua54
+local f = loadstring('return 42') --@< Error: Global or local variable `loadstring` is not defined
+--! error
+
+--8<-- lua54-no-module
+--# open lua54
+module('foo') --@< Error: Global or local variable `module` is not defined
+--! error
+
+--8<-- lua54-const
+--# open lua54
+local x <const> = 42
+local y = x + 1 --: integer
+--! ok
+
+--8<-- lua54-const-reassign
+--# open lua54
+local x <const> = 42
+x = 54 --@< Error: Cannot assign `54` into `const integer`
+       --@^ Note: The other type originates here
+--! error
+
+--8<-- lua54-const-with-type
+--# open lua54
+local x <const> --: number
+= 42
+x = 54 --@< Error: Cannot assign `54` into `const number`
+       --@^ Note: The other type originates here
+--! error
+
+--8<-- lua54-close-table
+--# open lua54
+local x <close> = setmetatable({}, { __close = function() end })
+--! ok
+
+--8<-- lua54-close-nil
+--# open lua54
+local x <close> = nil
+local y <close> = false
+local z <close>
+--! ok
+
+--8<-- lua54-close-reassign
+--# open lua54
+local x <close> = setmetatable({}, { __close = function() end })
+x = nil --@< Error: Cannot assign `nil` into `const [metatable] {...}`
+        --@^ Note: The other type originates here
+--! error
+
+--8<-- lua54-close-no-metatable
+--# open lua54
+local x <close> = {} --@< Error: A <close> variable should be nil, false or have a `__close` metamethod, but it was given a type `{...}`
+                     --@^ Note: The value was given here
+--! error
+
+--8<-- lua54-close-no-close-metamethod
+--# open lua54
+local x <close> = setmetatable({}, { __index = {} }) --@< Error: A <close> variable should be nil, false or have a `__close` metamethod, but it was given a type `[metatable] {...}`
+                                                     --@^ Note: The value was given here
+--! error
+
+--8<-- lua54-close-userdata
+--# open lua54
+--# assume global u: userdata
+local x <close> = u --@< Error: A <close> variable should be nil, false or have a `__close` metamethod, but it was given a type `userdata`
+                    --@^ Note: The value was given here
+--! error
+
+--8<-- lua54-close-optional
+--# open lua54
+--v function() --> table?
+local function f()
+    return nil
+end
+local x <close> = f() --@< Error: A <close> variable should be nil, false or have a `__close` metamethod, but it was given a type `table?`
+                      --@^ Note: The value was given here
+--! error
+
+--8<-- lua54-close-string
+--# open lua54
+local x <close> = 'foo' --@< Error: A <close> variable should be nil, false or have a `__close` metamethod, but it was given a type `"foo"`
+                        --@^ Note: The value was given here
+--! error
+
+--8<-- lua54-close-number
+--# open lua54
+local function f() --> integer
+    return 42
+end
+local x <close> = f() --@< Error: A <close> variable should be nil, false or have a `__close` metamethod, but it was given a type `integer`
+                      --@^ Note: The value was given here
+--! error
+
+--8<-- lua54-close-dynamic
+--# open lua54
+local x = {} --: WHATEVER
+local y <close> = x
+--! ok
+
@@ -0,0 +1,117 @@
+-- Tests specific to LuaJIT library support in the Kailua type checker.
+
+--8<-- luajit-print
+--# open luajit
+print('hello')
+--! ok
+
+--8<-- luajit-bit
+--# open luajit
+local x = bit.band(0xff, 0x0f, 0x3) --: integer
+local y = bit.tohex(bit.lshift(x, 4)) --: string
+--! ok
+
+--8<-- luajit-require-bit
+--# open luajit
+local b = require 'bit'
+local x = b.bor(1, 2) --: integer
+--! ok
+
+--8<-- luajit-jit
+--# open luajit
+if jit.status() then
+    jit.off()
+end
+local v = jit.version --: string
+--! ok
+
+--8<-- luajit-no-global-ffi
+--# open luajit
+ffi.cdef 'int printf(const char *fmt, ...);' --@< Error: Global or local variable `ffi` is not defined
+--! error
+
+--8<-- luajit-ffi-new
+--# open luajit
+local ffi = require 'ffi'
+ffi.cdef [[
+    typedef struct { double x, y; } point_t;
+]]
+local p = ffi.new('point_t', 3, 4) --: cdata
+p.x = p.x + p.y
+local n = ffi.sizeof(p)
+--! ok
+
+--8<-- luajit-ffi-cast
+--# open luajit
+local ffi = require 'ffi'
+local p = ffi.cast('uint8_t*', ffi.new('int[4]'))
+local q = p --: cdata
+--! ok
+
+--8<-- luajit-ffi-cdata-not-number
+--# open luajit
+local ffi = require 'ffi'
+local p = ffi.new('int[4]')
+local q = p --: number --@< Error: Cannot assign `cdata` into `number`
+                       --@^ Note: The other type originates here
+--! error
+
+--8<-- luajit-ffi-c
+--# open luajit
+local ffi = require 'ffi'
+ffi.cdef 'int printf(const char *fmt, ...);'
+ffi.C.printf('hello %s!', 'world')
+--! ok
+
+--8<-- luajit-ffi-call-cdata
+--# open luajit
+local ffi = require 'ffi'
+local f = ffi.cast('int (*)(int)', 0)
+local x = f(42)
+--! ok
+
+--8<-- luajit-suffixed-num
+--# open luajit
+local a = 42LL --: cdata
+local b = 42ULL --: cdata
+local c = 1.5i --: cdata
+--! ok
+
+--8<-- luajit-suffixed-num-arith
+--# open luajit
+local a = 42LL + 1 --: cdata
+local b = -a * a --: cdata
+local c = a < 54 --: boolean
+--! ok
+
+--8<-- luajit-suffixed-num-arith-string
+--# open luajit
+local a = 42LL + 'x' --@< Error: Cannot apply + operator to `[cdata] userdata` and `"x"`
+                     --@^ Cause: `"x"` is not a subtype of `number`
+--! error
+
+--8<-- luajit-suffixed-num-not-number
+--# open luajit
+local a = 42LL --: integer --@< Error: Cannot assign `[cdata] userdata` into `integer`
+                           --@^ Note: The other type originates here
+--! error
+
+--8<-- luajit-goto
+--# open luajit
+for i = 1, 3 do
+    if i == 2 then goto continue end
+    print(i)
+    ::continue::
+end
+--! ok
+
+--8<-- lua51-no-bit
+--# open lua51
+local x = bit.band(1, 2) --@< Error: Global or local variable `bit` is not defined
+--! error
+
+--8<-- lua51-no-ffi
+--# open lua51
+local ffi = require 'ffi' --@< Warning: Cannot resolve the module name given to `require`
+--! ok
+
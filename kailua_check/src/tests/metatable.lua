@@ -0,0 +1,202 @@
+-- Metatable and metamethod tests for the Kailua type checker.
+
+--8<-- setmetatable-returns-table
+--# open lua51
+local t = setmetatable({x = 1}, {})
+local y = t.x + 1
+--! ok
+
+--8<-- setmetatable-non-table
+--# open lua51
+local t = setmetatable({}, 42)
+--@^ Error: The type `[setmetatable] function(table: table, metatable: table?) --> table` cannot be called
+--@^^ Cause: Second function argument `42` is not a subtype of `table?`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- metatable-index-table
+--# open lua51
+local base = {
+    greet = function(name) --: string
+        return 'hello, ' .. name
+    end,
+}
+local t = setmetatable({x = 1}, {__index = base})
+local a = t.x + 1
+local b = t.greet('world') .. '!'
+--! ok
+
+--8<-- metatable-index-table-missing
+--# open lua51
+local t = setmetatable({x = 1}, {__index = {y = 2}})
+local a = t.z --@< Error: Missing key "z" in `{y: 2, ...}`
+--! error
+
+--8<-- metatable-index-table-chain
+--# open lua51
+local a = setmetatable({}, {__index = {x = 1}})
+local b = setmetatable({}, {__index = a})
+local x = b.x + 1
+--! ok
+
+--8<-- metatable-index-function
+--# open lua51
+local t = setmetatable({}, {
+    __index = --v function(self: table, key: string) --> integer
+              function(self, key)
+        return #key
+    end,
+})
+local a = t.whatever + 1 --: integer
+--! ok
+
+--8<-- metatable-index-function-wrong-key
+--# open lua51
+local t = setmetatable({}, {
+    __index = --v function(self: table, key: string) --> integer
+              function(self, key)
+        return #key
+    end,
+})
+local a = t[true]
+--@^ Error: The type `function(self: table, key: string) --> integer` cannot be called
+--@^^ Cause: Second function argument `true` is not a subtype of `string`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- metatable-index-method
+--# open lua51
+local Point = {}
+Point.__index = Point
+--v function(self: table) --> number
+function Point.len(self)
+    return 0
+end
+local p = setmetatable({x = 1, y = 2}, Point)
+local l = p:len() + 1
+--! ok
+
+--8<-- metatable-without-index
+--# open lua51
+local t = setmetatable({x = 1}, {})
+local a = t.y --@< Error: Missing key "y" in `[metatable] {x: 1, ...}`
+--! error
+
+--8<-- metatable-removed
+--# open lua51
+local t = setmetatable({x = 1}, {__index = {y = 2}})
+local u = setmetatable(t, nil)
+local a = u.y --@< Error: Missing key "y" in `{x: 1, ...}`
+--! error
+
+--8<-- metatable-call
+--# open lua51
+local t = setmetatable({}, {
+    __call = --v function(self: table, a: integer, b: integer) --> integer
+             function(self, a, b)
+        return a + b
+    end,
+})
+local x = t(1, 2) + 3 --: integer
+--! ok
+
+--8<-- metatable-call-wrong-args
+--# open lua51
+local t = setmetatable({}, {
+    __call = --v function(self: table, a: integer) --> integer
+             function(self, a)
+        return a
+    end,
+})
+local x = t('string')
+--@^ Error: The type `function(self: table, a: integer) --> integer` cannot be called
+--@^^ Cause: First method argument `"string"` is not a subtype of `integer`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- metatable-call-without-call
+--# open lua51
+local t = setmetatable({}, {})
+t() --@< Error: Tried to call a non-function `[metatable] {...}`
+--! error
+
+--8<-- metatable-add
+--# open lua51
+--# type local Vec = {x: number, y: number}
+local mt = {}
+--v function(a: Vec, b: Vec) --> Vec
+function mt.__add(a, b)
+    return {x = a.x + b.x, y = a.y + b.y}
+end
+local p = setmetatable({x = 1, y = 2}, mt)
+local q = setmetatable({x = 3, y = 4}, mt)
+local r = p + q --: Vec
+--! ok
+
+--8<-- metatable-add-wrong-operand
+--# open lua51
+local mt = {}
+--v function(a: table, b: table) --> table
+function mt.__add(a, b)
+    return {}
+end
+local p = setmetatable({}, mt)
+local r = p + 1
+--@^ Error: The type `function(a: table, b: table) --> table` cannot be called
+--@^^ Cause: Second function argument `1` is not a subtype of `table`
+--@^^^ Note: The other type originates here
+--! error
+
+--8<-- metatable-add-right-operand
+--# open lua51
+local mt = {}
+--v function(a: integer, b: table) --> integer
+function mt.__add(a, b)
+    return a
+end
+local p = setmetatable({}, mt)
+local r = 1 + p --: integer
+--! ok
+
+--8<-- metatable-add-missing
+--# open lua51
+local p = setmetatable({}, {__concat = 'x'})
+local r = p + 1 --@< Error: Cannot apply + operator to `[metatable] {...}` and `1`
+                --@^ Cause: `[metatable] {...}` is not a subtype of `number`
+--! error
+
+--8<-- metatable-concat
+--# open lua51
+local mt = {}
+--v function(a: table, b: string) --> string
+function mt.__concat(a, b)
+    return b
+end
+local p = setmetatable({}, mt)
+local s = (p .. 'a') .. 'b' --: string
+--! ok
+
+--8<-- metatable-eq-lt
+--# open lua51
+local mt = {}
+--v function(a: table, b: table) --> boolean
+function mt.__eq(a, b)
+    return true
+end
+--v function(a: table, b: table) --> boolean
+function mt.__lt(a, b)
+    return true
+end
+local p = setmetatable({}, mt)
+local q = setmetatable({}, mt)
+local a = p == q --: boolean
+local b = p < q --: boolean
+local c = p > q --: boolean
+--! ok
+
+--8<-- metatable-lt-missing
+--# open lua51
+local p = setmetatable({}, {})
+local q = setmetatable({}, {})
+local b = p < q --@< Error: Cannot apply < operator to `[metatable] {...}` and `[metatable] {...}`
+--! error
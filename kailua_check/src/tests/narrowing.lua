@@ -0,0 +1,351 @@
+-- Flow-sensitive narrowing tests for the Kailua type checker.
+
+--8<-- narrow-if-truthy
+local x --: string?
+if x then
+    local y = x .. 'a'
+end
+--! ok
+
+--8<-- narrow-if-truthy-restored
+local x --: string?
+if x then
+    local y = x .. 'a'
+end
+local z = x .. 'a' --@< Error: Cannot apply .. operator to `string?` and `"a"`
+                   --@^ Cause: `string?` is not a subtype of `(number|string)`
+--! error
+
+--8<-- narrow-if-not-nil
+local x --: integer?
+if x ~= nil then
+    local y = x + 1
+end
+--! ok
+
+--8<-- narrow-if-nil-else
+local x --: integer?
+if x == nil then
+    x = 42
+else
+    local y = x + 1
+end
+--! ok
+
+--8<-- narrow-if-nil-reversed
+local x --: integer?
+if nil ~= x then
+    local y = x + 1
+end
+--! ok
+
+--8<-- narrow-if-type
+--# open lua51
+local x --: string|{string}
+if type(x) == 'table' then
+    local y = x[1] .. 'a'
+else
+    local y = x .. 'a'
+end
+--! ok
+
+--8<-- narrow-if-type-ne
+--# open lua51
+local x --: integer|string
+if type(x) ~= 'string' then
+    local y = x + 1
+end
+--! ok
+
+--8<-- narrow-if-not
+local x --: integer?
+if not x then
+    return
+else
+    local y = x + 1
+end
+--! ok
+
+--8<-- narrow-if-and
+local x --: integer?
+local y --: string?
+if x and y then
+    local z = x + #y
+end
+--! ok
+
+--8<-- narrow-if-or-negated
+local x --: integer?
+local y --: string?
+if not x or not y then
+    return
+end
+local z = x + #y
+--! ok
+
+--8<-- narrow-if-or
+local x --: integer?
+local y --: string?
+if x or y then
+    local z = x + 1 --@< Error: Cannot apply + operator to `integer?` and `1`
+                    --@^ Cause: `integer?` is not a subtype of `number`
+end
+--! error
+
+--8<-- narrow-elseif
+--# open lua51
+local x --: integer|string|boolean
+if type(x) == 'string' then
+    local y = x .. 'a'
+elseif type(x) == 'boolean' then
+    local y = x --: boolean
+else
+    local y = x + 1
+end
+--! ok
+
+--8<-- narrow-elseif-cond
+local x --: {a: integer}?
+if x == nil then
+    return
+elseif x.a > 0 then
+    local y = x.a + 1
+end
+--! ok
+
+--8<-- narrow-early-return
+local function f(x) --: integer?
+    if x == nil then
+        return 0
+    end
+    return x + 1
+end
+--! ok
+
+--8<-- narrow-early-return-nested
+local function f(x) --: integer?
+    do
+        if not x then return 0 end
+    end
+    return x + 1 --@< Error: Cannot apply + operator to `integer?` and `1`
+                 --@^ Cause: `integer?` is not a subtype of `number`
+end
+--! error
+
+--8<-- narrow-early-error
+--# open lua51
+local function f(x) --: string?
+                    --> string
+    if not x then
+        error('x is missing')
+    end
+    return x .. 'a'
+end
+--! ok
+
+--8<-- narrow-early-break
+--# open lua51
+local x --: integer?
+while true do
+    if x == nil then break end
+    x = x + 1
+end
+--! ok
+
+--8<-- narrow-early-return-else
+local function f(x) --: integer?
+    if x then
+        x = nil
+    else
+        return 0
+    end
+    return x + 1 --@< Error: Cannot apply + operator to `integer?` and `1`
+                 --@^ Cause: `integer?` is not a subtype of `number`
+end
+--! error
+
+--8<-- narrow-both-branches-reach
+--# open lua51
+local function f(x) --: integer?
+    if x == nil then
+        print('nil')
+    else
+        print('not nil')
+    end
+    return x + 1 --@< Error: Cannot apply + operator to `integer?` and `1`
+                 --@^ Cause: `integer?` is not a subtype of `number`
+end
+--! error
+
+--8<-- narrow-assign-widens
+local x --: integer?
+if x then
+    x = nil
+    local y = x + 1 --@< Error: Cannot apply + operator to `integer?` and `1`
+                    --@^ Cause: `integer?` is not a subtype of `number`
+end
+--! error
+
+--8<-- narrow-assign-declared-type
+local x --: integer?
+if x == nil then
+    x = 'string' --@< Error: Cannot assign `"string"` into `integer?`
+                 --@^ Note: The other type originates here
+end
+--! error
+
+--8<-- narrow-assign-then-narrow
+local x --: integer?
+if x then
+    x = nil
+    if not x then return end
+    local y = x + 1
+end
+--! ok
+
+--8<-- narrow-global-not-narrowed
+--# assume global x: integer?
+if x then
+    local y = x + 1 --@< Error: Cannot apply + operator to `integer?` and `1`
+                    --@^ Cause: `integer?` is not a subtype of `number`
+end
+--! error
+
+--8<-- narrow-field-not-narrowed
+local t = {} --: {x: integer?}
+if t.x then
+    local y = t.x + 1 --@< Error: Cannot apply + operator to `integer?` and `1`
+                      --@^ Cause: `integer?` is not a subtype of `number`
+end
+--! error
+
+--8<-- narrow-impossible-branch
+--# open lua51
+local x --: integer
+if type(x) == 'string' then
+    local y = x + 1
+end
+--! ok
+
+--8<-- narrow-while
+local x --: integer?
+while x do
+    local y = x + 1
+    x = nil
+end
+--! ok
+
+--8<-- narrow-while-after
+--# assume f: function() --> integer?
+local x = f()
+while x == nil do
+    x = f()
+end
+local y = x + 1
+--! ok
+
+--8<-- narrow-while-after-break
+--# assume f: function() --> integer?
+local x = f()
+while x == nil do
+    if f() then break end
+    x = f()
+end
+local y = x + 1 --@< Error: Cannot apply + operator to `integer?` and `1`
+                --@^ Cause: `integer?` is not a subtype of `number`
+--! error
+
+--8<-- narrow-repeat-after
+--# assume f: function() --> integer?
+local x
+repeat
+    x = f()
+until x ~= nil
+local y = x + 1
+--! ok
+
+
+--8<-- narrow-default-assign
+--v function(x: integer?)
+local function f(x)
+    x = x or 42
+    local y = x + 1
+end
+--! ok
+
+--8<-- narrow-default-assign-table
+--# type Opts = { verbose: boolean? }
+--v function(opts: Opts?)
+local function f(opts)
+    opts = opts or {}
+    local v = opts.verbose
+end
+--! ok
+
+--8<-- narrow-default-assign-false
+--v function(b: boolean?)
+local function f(b)
+    b = b or false
+    local c = b --: boolean!
+end
+--! ok
+
+--8<-- narrow-default-local
+--v function(x: integer?)
+local function f(x)
+    local x = x or 42
+    local y = x + 1
+end
+--! ok
+
+--8<-- narrow-default-assign-nilable
+--# assume g: integer?
+--v function(x: integer?)
+local function f(x)
+    x = x or g
+    local y = x + 1 --@< Error: Cannot apply + operator to `integer?` and `1`
+                    --@^ Cause: `integer?` is not a subtype of `number`
+end
+--! error
+
+--8<-- narrow-default-assign-other-var
+--# assume g: integer?
+--v function(x: integer?)
+local function f(x)
+    x = g or 42
+    local y = x + 1 --@< Error: Cannot apply + operator to `integer?` and `1`
+                    --@^ Cause: `integer?` is not a subtype of `number`
+end
+--! error
+
+--8<-- narrow-default-assign-scope
+--# assume c: boolean
+--v function(x: integer?)
+local function f(x)
+    if c then
+        x = x or 42
+        local y = x + 1
+    end
+    local z = x + 1 --@< Error: Cannot apply + operator to `integer?` and `1`
+                    --@^ Cause: `integer?` is not a subtype of `number`
+end
+--! error
+
+--8<-- narrow-default-assign-reassign
+--v function(x: integer?)
+local function f(x)
+    x = x or 42
+    x = nil
+    local y = x + 1 --@< Error: Cannot apply + operator to `integer?` and `1`
+                    --@^ Cause: `integer?` is not a subtype of `number`
+end
+--! error
+
+--8<-- narrow-default-assign-multi
+--v function(x: integer?, s: string?)
+local function f(x, s)
+    x, s = x or 42, s or 'default'
+    local y = x + 1
+    local t = s .. '!'
+end
+--! ok
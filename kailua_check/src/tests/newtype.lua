@@ -0,0 +1,132 @@
+-- Nominal type (`--# newtype`) tests for the Kailua type checker.
+
+--8<-- newtype
+--# newtype UserId = integer
+--# assume u: UserId
+local v = u --: UserId
+--! ok
+
+--8<-- newtype-to-base
+--# newtype UserId = integer
+--# assume u: UserId
+local n = u --: integer
+--@^ Error: Cannot assign `UserId` into `integer`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- newtype-from-base
+--# newtype UserId = integer
+local u = 42 --: UserId
+--@^ Error: Cannot assign `42` into `UserId`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- newtype-distinct
+--# newtype UserId = integer
+--# newtype ItemId = integer
+--# assume i: ItemId
+local u = i --: UserId
+--@^ Error: Cannot assign `ItemId` into `UserId`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- newtype-func-arg
+--# newtype UserId = integer
+--# assume u: UserId
+--v function(id: UserId) --> string
+local function name_of(id) return 'x' end
+local n = name_of(u) --: string
+name_of(42) --@< Error: The type `function(id: UserId) --> string` cannot be called
+            --@^ Cause: First function argument `42` is not a subtype of `UserId`
+            --@^^ Note: The other type originates here
+--! error
+
+--8<-- newtype-map-key
+--# newtype UserId = integer
+--# assume u: UserId
+local names = {} --: map<UserId, string>
+names[u] = 'alice'
+local n = names[u] --: string?
+names[42] = 'bob' --@< Error: Cannot index `map<UserId, string>` with `42`
+--! error
+
+--8<-- newtype-arith
+--# newtype UserId = integer
+--# assume u: UserId
+local v = u + 1 --@< Error: Cannot apply + operator to `UserId` and `1`
+                --@^ Cause: `UserId` is not a subtype of `integer`
+--! error
+
+--8<-- newtype-eq
+--# newtype UserId = integer
+--# assume u: UserId
+--# assume v: UserId
+local b = u == v --: boolean
+--! ok
+
+--8<-- newtype-any
+--# open lua51
+--# newtype UserId = integer
+--# assume u: UserId
+print(u)
+local a = u --: any
+local s = tostring(u) --: string
+--! ok
+
+--8<-- newtype-dynamic
+--# newtype UserId = integer
+--# assume x: WHATEVER
+local u = x --: UserId
+--! ok
+
+--8<-- newtype-optional
+--# newtype UserId = integer
+--# assume u: UserId
+local v = u --: UserId?
+local w = v --: UserId
+--! ok
+
+--8<-- newtype-explicit-conversion
+--# newtype UserId = integer
+--v function(n: integer) --> UserId
+local function to_user_id(n)
+    --# assume n: UserId
+    return n
+end
+--v function(id: UserId) --> integer
+local function from_user_id(id)
+    --# assume id: integer
+    return id
+end
+local u = to_user_id(42) --: UserId
+local n = from_user_id(u) --: integer
+--! ok
+
+--8<-- newtype-string
+--# newtype Token = string
+--# assume t: Token
+local s = t --: string
+--@^ Error: Cannot assign `Token` into `string`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- newtype-display
+--# newtype UserId = integer
+--# assume u: UserId?
+local s = u --: string
+--@^ Error: Cannot assign `UserId?` into `string`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- newtype-non-primitive
+--# newtype Point = { x: number, y: number }
+--@^ Error: `--# newtype` requires a boolean, number or string type without attributes, but got `{x: number, y: number}`
+--! error
+
+--8<-- newtype-local-scope
+do
+    --# newtype local UserId = integer
+    --# assume u: UserId
+end
+--# assume v: UserId --@< Error: Type `UserId` is not defined
+--! error
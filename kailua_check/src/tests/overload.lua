@@ -0,0 +1,74 @@
+-- Overloaded function tests for the Kailua type checker.
+
+--8<-- overload
+--# assume f: function(string) --> integer & function(integer) --> string
+local a = f('x') --: integer
+local b = f(42) --: string
+--! ok
+
+--8<-- overload-first-match
+--# assume f: function(integer) --> integer & function(number) --> string
+local a = f(42) --: integer
+local b = f(4.2) --: string
+--! ok
+
+--8<-- overload-no-match
+--# assume f: function(string) --> integer & function(integer) --> string
+f(true) --@< Error: No overload of the type `function(string) --> integer & function(integer) --> string` can be called with given arguments
+        --@^ Note: The overload `function(string) --> integer` does not match
+        --@^^ Cause: First function argument `true` is not a subtype of `string`
+        --@^^^ Note: The other type originates here
+        --@^^^^ Note: The overload `function(integer) --> string` does not match
+        --@^^^^^ Cause: First function argument `true` is not a subtype of `integer`
+        --@^^^^^^ Note: The other type originates here
+--! error
+
+--8<-- overload-arity
+--# assume f: function() --> string & function(integer, integer) --> integer
+local a = f() --: string
+local b = f(1, 2) --: integer
+--! ok
+
+--8<-- overload-method
+--# assume global class Sprite
+--# assume Sprite.move: method(number, number) --> () & method({x: number, y: number}) --> ()
+--# assume s: Sprite
+s:move(1, 2)
+s:move({x = 1, y = 2})
+--! ok
+
+--8<-- overload-method-no-match
+--# assume global class Sprite
+--# assume Sprite.move: method(number, number) --> () & method({x: number, y: number}) --> ()
+--# assume s: Sprite
+s:move('x') --@< Error: No overload of the type `function(self: Sprite, number, number) --> () & function(self: Sprite, {x: number, y: number}) --> ()` can be called with given arguments
+--! error
+
+--8<-- overload-func-arg
+--# assume f: function(string) --> integer & function(function(string) --> string) --> string
+local a = f(function(s) return s .. '!' end) --: string
+--! ok
+
+--8<-- overload-func-arg-later
+--# assume g: function(function(string)) --> integer & function(function(integer)) --> string
+local r = g(function(x) local y = x + 1 end) --: string
+local s = g(function(x) local y = x .. '!' end) --: integer
+--! ok
+
+--8<-- overload-func-arg-no-match
+--# assume g: function(function(string)) --> integer & function(function(integer)) --> string
+local r = g(function(x) local y = x + {} end) --@< Error: Cannot apply + operator to `string` and `{...}`
+                                              --@^ Cause: `string` is not a subtype of `number`
+--! error
+
+--8<-- gsub-overload
+--# open lua51
+local s = ('hello'):gsub('l', 'L') --: string
+local t = ('hello'):gsub('%w', {h = 'H'}) --: string
+local u = ('hello'):gsub('%w', function(c) return c:upper() end) --: string
+--! ok
+
+--8<-- gsub-overload-no-match
+--# open lua51
+('hello'):gsub('%w', true) --@< Error: No overload of the type `function(s: string, pattern: string, repl: (string|map<string, string>), n: integer?) --> string & function(s: string, pattern: string, repl: function(string, WHATEVER...) --> (number|string)?, n: integer?) --> string` can be called with given arguments
+--! error
@@ -0,0 +1,179 @@
+-- String pattern tests for the Kailua type checker.
+
+--8<-- pattern-match-captures
+--# open lua51
+local k, v = ('a=b'):match('(%w+)=(%w+)')
+if k then
+    local s = k .. v --: string
+end
+--! ok
+
+--8<-- pattern-match-exact
+--# open lua51
+local k, v, w = ('a=b'):match('(%w+)=(%w+)')
+local x = w --: string!
+--@^ Error: Cannot assign `nil` into `string!`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- pattern-match-no-captures
+--# open lua51
+local m = string.match('a=b', '%w+') --: string
+--! ok
+
+--8<-- pattern-match-position
+--# open lua51
+local p, m = string.match('a=b', '()(%w+)')
+if p then
+    local q = p + 1 --: integer
+    local n = m .. '' --: string
+end
+--! ok
+
+--8<-- pattern-match-position-not-string
+--# open lua51
+local p = ('a=b'):match('()=')
+local s = p --: string
+--@^ Error: Cannot assign `integer?` into `string`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- pattern-match-nested
+--# open lua51
+local a, b, c = ('abc'):match('((a)(b))')
+if a then
+    local s = a .. b .. c --: string
+end
+--! ok
+
+--8<-- pattern-match-no-match
+--# open lua51
+local m = ('a=b'):match('(%w+)=') --: string!
+--@^ Error: Cannot assign `string?` into `string!`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- pattern-match-escaped-paren
+--# open lua51
+local m = ('(x)'):match('%((%w)%)') --: string
+local n = ('(x)'):match('[(]%w[)]') --: string
+--! ok
+
+--8<-- pattern-match-non-literal
+--# open lua51
+--# assume pat: string
+local a, b, c = ('abc'):match(pat)
+local s = c --: string
+--! ok
+
+--8<-- pattern-find
+--# open lua51
+local i, j, k = ('a=b'):find('(%w+)=')
+if i then
+    local n = i + j --: integer
+    local s = k .. '' --: string
+end
+--! ok
+
+--8<-- pattern-find-no-match
+--# open lua51
+local i, j = ('a=b'):find('=')
+local n = i + 1
+--@^ Error: Cannot apply + operator to `integer?` and `1`
+--@^^ Cause: `integer?` is not a subtype of `number`
+--! error
+
+--8<-- pattern-find-plain
+--# open lua51
+local i, j, k = ('a(b'):find('(', 1, true)
+local n = j + 1 --: integer
+local s = k --: string!
+--@^ Error: Cannot assign `nil` into `string!`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- pattern-find-plain-unknown
+--# open lua51
+--# assume plain: boolean
+local i, j, k = ('a(b'):find('(', 1, plain)
+--! ok
+
+--8<-- pattern-gmatch
+--# open lua51
+for k, v in ('a=b, c=d'):gmatch('(%w+)=(%w+)') do
+    local s = k .. v --: string
+end
+--! ok
+
+--8<-- pattern-gmatch-no-captures
+--# open lua51
+for w in ('a b c'):gmatch('%a+') do
+    local s = w --: string
+end
+--! ok
+
+--8<-- pattern-gmatch-non-literal
+--# open lua51
+--# assume pat: string
+for w in ('a b c'):gmatch(pat) do
+    local s = w --: string
+end
+--! ok
+
+--8<-- pattern-malformed-unfinished
+--# open lua51
+local m = ('abc'):match('(%w+') --@< Warning: `(` in the pattern is not closed
+--! ok
+
+--8<-- pattern-malformed-unmatched
+--# open lua51
+local m = ('abc'):match('%w+)') --@< Warning: `)` in the pattern has no matching `(`
+--! ok
+
+--8<-- pattern-malformed-percent
+--# open lua51
+local m = string.find('abc', 'c%') --@< Warning: The pattern ends with `%`
+--! ok
+
+--8<-- pattern-malformed-bracket
+--# open lua51
+local m = ('abc'):match('[%]') --@< Warning: The character class in the pattern is missing `]`
+--! ok
+
+--8<-- pattern-closing-bracket-first
+--# open lua51
+local m = ('abc'):match('[]]') --: string
+local n = ('abc'):match('[^]]') --: string
+--! ok
+
+--8<-- pattern-malformed-balance
+--# open lua51
+local m = ('abc'):match('%b(') --@< Warning: `%b` in the pattern should be followed by two characters
+--! ok
+
+--8<-- pattern-malformed-frontier
+--# open lua51
+local m = ('abc'):match('%fa') --@< Warning: `%f` in the pattern should be followed by `[`
+--! ok
+
+--8<-- pattern-backreference
+--# open lua51
+local q, s = ('"x"'):match('(["\'])(.-)%1') --: string, string
+--! ok
+
+--8<-- pattern-malformed-backreference
+--# open lua51
+local m = ('abc'):match('(a%1)') --@< Warning: `%1` in the pattern does not refer to a closed capture
+--! ok
+
+--8<-- pattern-malformed-gmatch
+--# open lua51
+for w in ('abc'):gmatch('(') do --@< Warning: `(` in the pattern is not closed
+end
+--! ok
+
+--8<-- pattern-lua53
+--# open lua53
+local k, v = ('a=b'):match('(%w+)=()')
+local n = v + 1 --: integer
+--! ok
@@ -0,0 +1,98 @@
+-- Readonly table tests for the Kailua type checker.
+
+--8<-- readonly-read
+--# assume c: readonly { name: string, size: integer }
+local n = c.name --: string
+local s = c.size --: integer
+--! ok
+
+--8<-- readonly-write
+--# assume c: readonly { name: string, size: integer }
+c.name = 'x' --@< Error: Cannot update the readonly type `readonly {name: const string, size: const integer}` by indexing
+--! error
+
+--8<-- readonly-new-field
+--# assume c: readonly { name: string }
+c.other = 3 --@< Error: Cannot update the readonly type `readonly {name: const string}` by indexing
+--! error
+
+--8<-- readonly-nested-write
+--# assume c: readonly { inner: { size: integer } }
+c.inner.size = 3 --@< Error: Cannot update the immutable type `const {size: integer}` by indexing
+--! error
+
+--8<-- readonly-vector-write
+--# assume v: readonly vector<integer>
+local x = v[1] --: integer
+v[1] = 2 --@< Error: Cannot update the readonly type `readonly vector<const integer>` by indexing
+--! error
+
+--8<-- readonly-map-write
+--# assume m: readonly map<string, integer>
+m.a = 1 --@< Error: Cannot update the readonly type `readonly map<string, const integer>` by indexing
+--! error
+
+--8<-- readonly-named
+--# type Config = { name: string, size: integer }
+--# assume c: readonly Config
+local n = c.name --: string
+c.size = 3 --@< Error: Cannot update the readonly type `readonly Config` by indexing
+--! error
+
+--8<-- readonly-alias
+--# type RC = readonly { x: integer }
+--# assume r: RC
+local x = r.x --: integer
+r.x = 1 --@< Error: Cannot update the readonly type `RC` by indexing
+--! error
+
+--8<-- readonly-from-mutable
+--# type Config = { name: string, size: integer }
+local c = { name = 'a', size = 3 } --: Config
+local r = c --: readonly Config
+c.size = 4
+--! ok
+
+--8<-- readonly-from-mutable-arg
+--# type Config = { name: string, size: integer }
+--v function(c: readonly Config)
+local function plugin(c)
+end
+local c = { name = 'a', size = 3 } --: Config
+plugin(c)
+plugin({ name = 'b', size = 4 })
+--! ok
+
+--8<-- readonly-covariant
+local v = {1, 2, 3} --: vector<integer>
+local r = v --: readonly vector<number>
+--! ok
+
+--8<-- readonly-covariant-record
+local t = { a = 1 } --: { a: integer }
+local r = t --: readonly { a: number }
+--! ok
+
+--8<-- readonly-to-mutable
+--# type Config = { name: string, size: integer }
+--# assume r: readonly Config
+local c = r --: Config
+--@^ Error: Cannot assign `readonly Config` into `Config`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- readonly-vector-to-mutable
+--# assume r: readonly vector<integer>
+local v = r --: vector<integer>
+--@^ Error: Cannot assign `readonly vector<const integer>` into `vector<integer>`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- readonly-optional
+--# assume c: readonly { a: integer }?
+local r = c --: readonly { a: integer }?
+--! ok
+
+--8<-- readonly-non-table
+--# assume c: readonly integer --@< Error: A non-table type `integer` cannot be `readonly`
+--! error
@@ -0,0 +1,191 @@
+-- Recursive type tests for the Kailua type checker.
+
+--8<-- recursive-list
+--# type Node = {value: integer, next: Node?}
+--# assume head: Node
+local x = head.value + 1 --: integer
+local n = head.next
+if n then
+    local y = n.value + 1 --: integer
+    local z = n.next --: Node?
+end
+--! ok
+
+--8<-- recursive-list-literal
+--# type Node = {value: integer, next: Node?}
+local a = { value = 1 } --: Node
+local b = { value = 2, next = a } --: Node
+--! ok
+
+--8<-- recursive-list-wrong-field
+--# type Node = {value: integer, next: Node?}
+--# assume head: Node
+local n = head.next
+if n then
+    local s = n.value .. 'a' --: string
+    local t = n.name --@< Error: Cannot index `Node` with `"name"`
+end
+--! error
+
+--8<-- recursive-tree
+--# open lua51
+--# type Tree = {value: string, children: vector<Tree>}
+--# assume t: Tree
+for _, child in ipairs(t.children) do
+    for _, grandchild in ipairs(child.children) do
+        local s = grandchild.value .. 'a' --: string
+    end
+end
+--! ok
+
+--8<-- recursive-function
+--# type Handler = function(integer) --> Handler?
+--# assume h: Handler
+local h2 = h(1)
+if h2 then
+    local h3 = h2(2) --: Handler?
+end
+--! ok
+
+--8<-- recursive-sub
+--# type A = {value: integer, next: A?}
+--# type B = {value: integer, next: B?}
+--# assume a: A
+local b = a --: B
+--! ok
+
+--8<-- recursive-sub-mismatch
+--# type A = {value: integer, next: A?}
+--# type B = {value: string, next: B?}
+--# assume a: A
+local b = a --: B
+--@^ Error: Cannot assign `A` into `B`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- recursive-sub-nested-mismatch
+--# type A = {next: {next: A?}?}
+--# type B = {next: B?, value: integer}
+--# assume a: A
+local b = a --: B
+--@^ Error: Cannot assign `A` into `B`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- recursive-interface
+--# interface Shape { area: method() --> number, parent: Shape? }
+--# assume s: Shape
+local p = s.parent
+if p then
+    local a = p:area() + 1 --: number
+end
+--! ok
+
+--8<-- recursive-display
+--# type Node = {value: integer, next: Node?}
+--# assume head: Node
+local x = head.next + 1 --@< Error: Cannot apply + operator to `Node?` and `1`
+                        --@^ Cause: `Node?` is not a subtype of `number`
+--! error
+
+--8<-- recursive-unguarded
+--# type Node = Node? --@< Error: A type `Node` can only refer to itself inside table, function or thread types, and cannot be unioned with other types
+--! error
+
+--8<-- recursive-union
+--# type Node = {next: Node | integer} --@< Error: A type `Node` can only refer to itself inside table, function or thread types, and cannot be unioned with other types
+--! error
+
+--8<-- recursive-unguarded-alias
+--# type Node = Node --@< Error: A type `Node` can only refer to itself inside table, function or thread types, and cannot be unioned with other types
+--! error
+
+--8<-- recursive-mutual
+--# type Foo = {bar: Bar?, x: integer}
+--# type Bar = {foo: Foo?, y: string}
+--# assume foo: Foo
+local bar = foo.bar
+if bar then
+    local y = bar.y --: string
+    local foo2 = bar.foo
+    if foo2 then
+        local x = foo2.x --: integer
+    end
+end
+--! ok
+
+--8<-- recursive-mutual-interface
+--# interface Parent { children: vector<Child> }
+--# interface Child { parent: Parent }
+--# assume c: Child
+local d = c.parent.children[1] --: Child?
+--! ok
+
+--8<-- recursive-mutual-union
+--# type Foo = {bar: Bar?}
+--# type Bar = Foo | string
+--# assume bar: Bar
+local s = bar --: string
+--@^ Error: Cannot assign `Bar` into `string`
+--@^^ Note: The other type originates here
+--! error
+
+--8<-- recursive-mutual-unguarded
+--# type Foo = Bar --@< Error: Type `Bar` is not defined
+--# type Bar = Foo --@< Error: A type `Bar` can only refer to itself inside table, function or thread types, and cannot be unioned with other types
+--! error
+
+--8<-- recursive-mutual-not-adjacent
+--# type Foo = {bar: Bar?} --@< Error: Type `Bar` is not defined
+local x = 42
+--# type Bar = {foo: Foo?}
+--! error
+
+--8<-- recursive-local
+do
+    --# type local Node = {value: integer, next: Node?}
+    --# assume head: Node
+    local x = head.value + 1 --: integer
+end
+--! ok
+
+--8<-- recursive-generic
+--# type Tree<T> = {value: T, children: vector<Tree<T>>}
+--@^ Error: A generic type `Tree` cannot refer to itself
+--! error
+
+--8<-- recursive-generic-shadow
+--# type Tree = {value: string}
+--# type Tree<T> = {value: T, parent: Tree?}
+--@^ Error: A generic type `Tree` cannot refer to itself
+--! error
+
+
+--8<-- recursive-traverse
+--# type Node = {value: integer, next: Node?}
+--v function(head: Node) --> integer
+local function sum(head)
+    local total = 0
+    local n = head --: Node?
+    while n do
+        total = total + n.value
+        n = n.next
+    end
+    return total
+end
+local s = sum({value = 1, next = {value = 2}}) --: integer
+--! ok
+
+--8<-- recursive-traverse-mismatch
+--# type Node = {value: integer, next: Node?}
+--v function(head: Node)
+local function f(head)
+end
+f({value = 1, next = {value = 'x'}})
+--@^ Error: The type `function(head: Node) --> ()` cannot be called
+--@^^ Cause: First function argument `{next: {value: "x", ...}, value: 1, ...}` is not a subtype of `Node`
+--@^^^ Note: The other type originates here
+--@ Cause: `{value: "x", ...}` is not a subtype of `Node?`
+--@ Cause: `{value: "x", ...}` is not a subtype of `{next: Node?, value: integer}`
+--@ Cause: `"x"` is not a subtype of `integer`
+--! error
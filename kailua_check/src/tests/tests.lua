@@ -1772,9 +1772,9 @@ end
 --! error
 
 --8<-- type-no-recursive
---# type known_type = known_type --@< Error: Type `known_type` is not defined
---# type local well_known_type = well_known_type --@< Error: Type `well_known_type` is not defined
---# type global widespread_type = widespread_type --@< Error: Type `widespread_type` is not defined
+--# type known_type = known_type --@< Error: A type `known_type` can only refer to itself inside table, function or thread types, and cannot be unioned with other types
+--# type local well_known_type = well_known_type --@< Error: A type `well_known_type` can only refer to itself inside table, function or thread types, and cannot be unioned with other types
+--# type global widespread_type = widespread_type --@< Error: A type `widespread_type` can only refer to itself inside table, function or thread types, and cannot be unioned with other types
 --! error
 
 --8<-- type-reexport-1
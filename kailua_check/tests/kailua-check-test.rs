@@ -19,7 +19,7 @@ use kailua_diag::{Stop, Locale, Report, Reporter, TrackMaxKind};
 use kailua_syntax::{Chunk, parse_chunk};
 use kailua_types::ty::{TypeContext, Display};
 use kailua_check::check_from_chunk;
-use kailua_check::options::Options;
+use kailua_check::options::{Options, ClassSystemRules};
 use kailua_check::env::Context;
 
 struct Testing {
@@ -65,6 +65,17 @@ impl kailua_test::Testing for Testing {
                 let span = *self.filespans.get(path).ok_or(None)?;
                 parse_chunk(&self.source.borrow(), span, report).map_err(|_| None)
             }
+
+            // a user-defined class system `configured` stands for one given in `kailua.json`
+            fn class_system_rules(&self, name: &str) -> Option<ClassSystemRules> {
+                if name != "configured" {
+                    return None;
+                }
+                let mut rules = ClassSystemRules::default();
+                rules.set("constructor", "create").unwrap();
+                rules.set("static_prefix", "s_").unwrap();
+                Some(rules)
+            }
         }
 
         let report = Rc::new(TrackMaxKind::new(report));
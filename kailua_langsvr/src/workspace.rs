@@ -16,7 +16,7 @@ use walkdir::WalkDir;
 
 use kailua_env::{Unit, Pos, Span, Spanned, Source, SourceFile, SourceSlice};
 use kailua_diag::{self, Stop, Report, Locale, Localize, Localized};
-use kailua_syntax::{Lexer, Nest, NestedToken, Parser, Chunk};
+use kailua_syntax::{Lexer, Nest, NestedToken, Parser, Chunk, Language};
 use kailua_check;
 use kailua_check::options::FsSource;
 use kailua_check::env::{Context, Output};
@@ -130,9 +130,10 @@ fn collect_tokens(source: &Source, span: Span, report: &Report) -> Vec<NestedTok
     tokens
 }
 
-fn parse_to_chunk(tokens: Vec<NestedToken>, report: &Report) -> kailua_diag::Result<Chunk> {
+fn parse_to_chunk(tokens: Vec<NestedToken>, language: Language,
+                  report: &Report) -> kailua_diag::Result<Chunk> {
     let mut tokens = tokens.into_iter();
-    let chunk = Parser::new(&mut tokens, report).into_chunk();
+    let chunk = Parser::with_language(&mut tokens, language, report).into_chunk();
     chunk
 }
 
@@ -168,6 +169,7 @@ struct WorkspaceFileInner {
 
     source: Arc<RwLock<Source>>,
     message_locale: Locale,
+    language: Language,
 
     path: PathBuf,
     unit: Unit,
@@ -200,6 +202,7 @@ impl fmt::Debug for WorkspaceFile {
          .field("cancel_token", &inner.cancel_token)
          .field("source", &Ellipsis)
          .field("message_locale", &inner.message_locale)
+         .field("language", &inner.language)
          .field("path", &inner.path)
          .field("unit", &inner.unit)
          .field("document", &inner.document)
@@ -213,7 +216,8 @@ impl fmt::Debug for WorkspaceFile {
 
 impl WorkspaceFile {
     fn new(shared: &Arc<RwLock<WorkspaceShared>>, pool: &Arc<CpuPool>,
-           source: &Arc<RwLock<Source>>, message_locale: Locale, path: PathBuf) -> WorkspaceFile {
+           source: &Arc<RwLock<Source>>, message_locale: Locale, language: Language,
+           path: PathBuf) -> WorkspaceFile {
         WorkspaceFile {
             inner: Arc::new(RwLock::new(WorkspaceFileInner {
                 workspace: shared.clone(),
@@ -221,6 +225,7 @@ impl WorkspaceFile {
                 cancel_token: CancelToken::new(),
                 source: source.clone(),
                 message_locale: message_locale,
+                language: language,
                 path: path,
                 unit: Unit::dummy(),
                 document: None,
@@ -385,7 +390,7 @@ impl WorkspaceFile {
                     let report = diags.report(|span| {
                         diags::translate_span(span, &inner.source.read())
                     });
-                    parse_to_chunk(tokens, &report)
+                    parse_to_chunk(tokens, inner.language, &report)
                 };
                 match chunk {
                     Ok(chunk) => {
@@ -538,6 +543,7 @@ struct WorkspaceFsSourceInner {
     temp_files: HashMap<PathBuf, Chunk>,
 
     message_locale: Locale,
+    language: Language,
     root_report: ReportTree,
 }
 
@@ -596,7 +602,7 @@ impl FsSource for WorkspaceFsSource {
             let source = fssource.source.read();
             let report = diags.report(|span| diags::translate_span(span, &source));
             let tokens = collect_tokens(&source, span, &report);
-            parse_to_chunk(tokens, &report)
+            parse_to_chunk(tokens, fssource.language, &report)
         };
         match chunk {
             Ok(chunk) => {
@@ -611,6 +617,9 @@ impl FsSource for WorkspaceFsSource {
 pub struct Workspace {
     message_locale: Locale,
 
+    // the default language for every file, set by the configuration
+    language: Language,
+
     pool: Arc<CpuPool>,
     files: Arc<RwLock<HashMap<PathBuf, WorkspaceFile>>>,
 
@@ -625,6 +634,7 @@ impl fmt::Debug for Workspace {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         f.debug_struct("Workspace")
          .field("message_locale", &self.message_locale)
+         .field("language", &self.language)
          .field("pool", &Ellipsis)
          .field("files", &self.files)
          .field("source", &Ellipsis)
@@ -637,6 +647,7 @@ impl Workspace {
     pub fn new(base_dir: PathBuf, pool: Arc<CpuPool>, default_locale: Locale) -> Workspace {
         Workspace {
             message_locale: default_locale,
+            language: Language::default(),
             pool: pool,
             files: Arc::new(RwLock::new(HashMap::new())),
             source: Arc::new(RwLock::new(Source::new())),
@@ -683,6 +694,7 @@ impl Workspace {
             None
         };
         if let Some(ws) = ws {
+            self.language = ws.language();
             let noutputs = ws.start_paths().len();
             shared.base = WorkspaceBase::Workspace(ws);
             shared.check_outputs.resize(noutputs, None);
@@ -721,7 +733,8 @@ impl Workspace {
     }
 
     fn make_file(&self, path: PathBuf) -> WorkspaceFile {
-        WorkspaceFile::new(&self.shared, &self.pool, &self.source, self.message_locale, path)
+        WorkspaceFile::new(&self.shared, &self.pool, &self.source,
+                           self.message_locale, self.language, path)
     }
 
     fn destroy_file(&self, file: WorkspaceFile) -> bool {
@@ -820,6 +833,7 @@ impl Workspace {
         let source = self.source.clone();
         let cancel_token = shared.cancel_token.clone();
         let message_locale = self.message_locale;
+        let language = self.language;
 
         let fut = start_chunk_fut.map_err(|e| (*e).clone()).and_then(move |chunk_ret| {
             cancel_token.keep_going()?;
@@ -840,6 +854,7 @@ impl Workspace {
                     temp_units: Vec::new(),
                     temp_files: HashMap::new(),
                     message_locale: message_locale,
+                    language: language,
                     root_report: diags.clone(),
                 })),
             };
@@ -2,6 +2,7 @@
 //!
 //! The basic AST roughly follows Lua's own [syntax description][lua51-syntax].
 //! All Kailua-specific variants have names starting with `Kailua`.
+//! Variants only available in later Lua versions or LuaJIT are marked as [5.x+] or [JIT].
 //!
 //! [lua51-syntax]: https://www.lua.org/manual/5.1/manual.html#8
 
@@ -11,6 +12,7 @@ use std::collections::HashMap;
 use kailua_env::{Span, Spanned, Scope, ScopedId, ScopeMap};
 
 use string::{Str, Name};
+use lex::NumSuffix;
 
 // a helper type for printing commas
 struct Comma(Cell<bool>);
@@ -369,6 +371,11 @@ pub enum Ex {
     /// A number literal.
     Num(f64),
 
+    /// A number literal with a suffix (e.g. `42LL`). [JIT]
+    ///
+    /// This evaluates to a cdata instead of a number.
+    SuffixedNum(f64, NumSuffix),
+
     /// A string literal.
     ///
     /// A difference between `"string"` and `[[string]]` is not recorded.
@@ -423,6 +430,7 @@ impl fmt::Debug for Ex {
             Ex::False => write!(f, "false"),
             Ex::True => write!(f, "true"),
             Ex::Num(v) => write!(f, "{:?}", v),
+            Ex::SuffixedNum(v, s) => write!(f, "{:?}{}", v, s.name()),
             Ex::Str(ref s) => write!(f, "{:?}", *s),
             Ex::Varargs => write!(f, "..."),
             Ex::Func(ref p, bs, ref b) => write!(f, "Func({:?}, {:?}{:?})", *p, bs, *b),
@@ -453,6 +461,8 @@ pub enum UnOp {
     Not,
     /// `#`.
     Len,
+    /// `~`. [5.3+]
+    BNot,
 }
 
 impl UnOp {
@@ -461,6 +471,7 @@ impl UnOp {
             UnOp::Neg => "-",
             UnOp::Not => "not",
             UnOp::Len => "#",
+            UnOp::BNot => "~",
         }
     }
 }
@@ -476,12 +487,24 @@ pub enum BinOp {
     Mul,
     /// `/`.
     Div,
+    /// `//`. [5.3+]
+    IDiv,
     /// `^`.
     Pow,
     /// `%`.
     Mod,
     /// `..`.
     Cat,
+    /// `&`. [5.3+]
+    BAnd,
+    /// `|`. [5.3+]
+    BOr,
+    /// `~`. [5.3+]
+    BXor,
+    /// `<<`. [5.3+]
+    Shl,
+    /// `>>`. [5.3+]
+    Shr,
     /// `<`.
     Lt,
     /// `<=`.
@@ -507,9 +530,15 @@ impl BinOp {
             BinOp::Sub => "-",
             BinOp::Mul => "*",
             BinOp::Div => "/",
+            BinOp::IDiv => "//",
             BinOp::Pow => "^",
             BinOp::Mod => "%",
             BinOp::Cat => "..",
+            BinOp::BAnd => "&",
+            BinOp::BOr => "|",
+            BinOp::BXor => "~",
+            BinOp::Shl => "<<",
+            BinOp::Shr => ">>",
             BinOp::Lt  => "<",
             BinOp::Le  => "<=",
             BinOp::Gt  => ">",
@@ -609,11 +638,30 @@ pub enum St {
     /// `break`.
     Break,
 
+    /// `goto name`. [5.2+ or JIT]
+    Goto(Spanned<Name>),
+
+    /// `::name::`. [5.2+ or JIT]
+    Label(Spanned<Name>),
+
     /// `--# open name`.
     KailuaOpen(Spanned<Name>),
 
-    /// `--# type [scope] name = type`.
-    KailuaType(TypeScope, Spanned<Name>, Spanned<Kind>),
+    /// `--# type [scope] name[<param, ...>] = type`.
+    ///
+    /// The list of type parameters is empty if the type is not generic.
+    /// The parser ensures that the names are distinct.
+    KailuaType(TypeScope, Spanned<Name>, Vec<Spanned<Name>>, Spanned<Kind>),
+
+    /// `--# newtype [scope] name = type`.
+    ///
+    /// Unlike `St::KailuaType` this makes a distinct nominal type from the original type.
+    KailuaNewtype(TypeScope, Spanned<Name>, Spanned<Kind>),
+
+    /// `--# interface [scope] name { name: type, name: method(...) --> ..., ... }`.
+    ///
+    /// The parser ensures that the member names are distinct.
+    KailuaInterface(TypeScope, Spanned<Name>, Vec<(Spanned<Str>, Spanned<MemberKind>)>),
 
     /// `--# assume [global] name: type`.
     ///
@@ -626,18 +674,23 @@ pub enum St {
     KailuaAssumeField(bool /*static*/, Spanned<(Spanned<NameRef>, Vec<Spanned<Name>>)>,
                       M, Spanned<Kind>),
 
-    /// `--# assume name.field.field: method(...) --> ...`.
+    /// `--# assume name.field.field: [ATTR ...] method(...) --> ...`.
     ///
     /// This is distinct from `St::KailuaAssumeField` because it is not possible to
     /// desugar it without knowing the type of `self`.
-    KailuaAssumeMethod(Spanned<(Spanned<NameRef>, Vec<Spanned<Name>>)>, M, Spanned<FuncKind>),
+    ///
+    /// Multiple function types (`method(...) & method(...)`) denote overloads.
+    KailuaAssumeMethod(Spanned<(Spanned<NameRef>, Vec<Spanned<Name>>)>, M,
+                       Vec<Spanned<Attr>>, Vec<Spanned<FuncKind>>),
 
-    /// `--# class system ...`.
-    KailuaClassSystem(Spanned<Name>),
+    /// `--# class system ... [ATTR ...]`.
+    ///
+    /// Attributes, if any, describe the rules for the user-defined class system.
+    KailuaClassSystem(Spanned<Name>, Vec<Spanned<Attr>>),
 
-    /// `--# assume [global] class[(...)] ClassName[: ParentClassName]`.
+    /// `--# assume [global] class[(...)] ClassName[: ParentClassName, ...]`.
     KailuaAssumeClass(Option<Spanned<Name>> /*system*/, Spanned<RenameRef> /*variable & type name*/,
-                      Option<Spanned<Name>> /*parent type name*/, Option<Scope>),
+                      Vec<Spanned<Name>> /*parent type names*/, Option<Scope>),
 }
 
 /// In the debugging output scopes are printed in two ways:
@@ -687,10 +740,33 @@ impl fmt::Debug for St {
             St::Local(ref ii, ref ee, is) => write!(f, "Local({:?}, {:?}){:?}", ii, ee, is),
             St::Return(ref ee) => write!(f, "Return({:?})", ee),
             St::Break => write!(f, "Break"),
+            St::Goto(ref i) => write!(f, "Goto({:?})", i),
+            St::Label(ref i) => write!(f, "Label({:?})", i),
 
             St::KailuaOpen(ref lib) => write!(f, "KailuaOpen({:?})", lib),
-            St::KailuaType(scope, ref t, ref k) =>
-                write!(f, "KailuaType({:?}, {:?}, {:?})", scope, t, k),
+            St::KailuaType(scope, ref t, ref params, ref k) => {
+                write!(f, "KailuaType({:?}, {:?}", scope, t)?;
+                if !params.is_empty() {
+                    write!(f, "<")?;
+                    let comma = Comma::new();
+                    for param in params {
+                        write!(f, "{}{:?}", comma, param)?;
+                    }
+                    write!(f, ">")?;
+                }
+                write!(f, ", {:?})", k)
+            },
+            St::KailuaNewtype(scope, ref t, ref k) => {
+                write!(f, "KailuaNewtype({:?}, {:?}, {:?})", scope, t, k)
+            },
+            St::KailuaInterface(scope, ref t, ref members) => {
+                write!(f, "KailuaInterface({:?}, {:?}, [", scope, t)?;
+                let comma = Comma::new();
+                for &(ref name, ref member) in members {
+                    write!(f, "{}{:?}: {:?}", comma, name, member)?;
+                }
+                write!(f, "])")
+            },
             St::KailuaAssume(ref i, m, ref k, is) => {
                 write!(f, "KailuaAssume({:?}, {:?}, {:?})", i, m, k)?;
                 if let Some(is) = is { write!(f, "{:?}", is)?; }
@@ -701,14 +777,27 @@ impl fmt::Debug for St {
                 for i in ii { write!(f, ".{:?}", i)?; }
                 write!(f, "){:?}, {:?}, {:?})", span, m, k)
             },
-            St::KailuaAssumeMethod(Spanned { base: (ref i, ref ii), span }, m, ref fk) => {
+            St::KailuaAssumeMethod(Spanned { base: (ref i, ref ii), span }, m, ref aa, ref fks) => {
                 write!(f, "KailuaAssumeMethod(({:?}", i)?;
                 for i in ii { write!(f, ".{:?}", i)?; }
-                write!(f, "){:?}, {:?}, {:?})", span, m, fk)
+                write!(f, "){:?}, {:?}, ", span, m)?;
+                for a in aa { write!(f, "{:?} ", a)?; }
+                let mut first = true;
+                for fk in fks {
+                    if first { first = false; } else { write!(f, " & ")?; }
+                    write!(f, "{:?}", fk)?;
+                }
+                write!(f, ")")
+            },
+            St::KailuaClassSystem(ref sys, ref attrs) => {
+                write!(f, "KailuaClassSystem({:?}", sys)?;
+                for attr in attrs {
+                    write!(f, ", {:?}", attr)?;
+                }
+                write!(f, ")")
             },
-            St::KailuaClassSystem(ref sys) => write!(f, "KailuaClassSystem({:?})", sys),
-            St::KailuaAssumeClass(ref sys, ref i, ref pi, is) => {
-                write!(f, "KailuaAssumeClass({:?}, {:?}, {:?})", sys, i, pi)?;
+            St::KailuaAssumeClass(ref sys, ref i, ref pis, is) => {
+                write!(f, "KailuaAssumeClass({:?}, {:?}, {:?})", sys, i, pis)?;
                 if let Some(is) = is { write!(f, "{:?}", is)?; }
                 Ok(())
             },
@@ -754,6 +843,11 @@ pub enum MM {
     /// Immutable (`const`).
     Const,
 
+    /// Immutable, and closed when the variable goes out of scope (`<close>`). [5.4+]
+    ///
+    /// There is no corresponding Kailua keyword; only the Lua 5.4 attribute can set this.
+    Close,
+
     /// Mutable, but registers for the delayed type checking (`module`).
     ///
     /// Indexing such variables will require an explicit type,
@@ -767,6 +861,7 @@ impl fmt::Debug for MM {
         match *self {
             MM::None => write!(f, "_"),
             MM::Const => write!(f, "Const"),
+            MM::Close => write!(f, "Close"),
             MM::Module => write!(f, "Module"),
         }
     }
@@ -788,9 +883,72 @@ impl fmt::Debug for SlotKind {
     }
 }
 
+/// A member of the interface type for Kailua (from `--# interface`).
+#[derive(Clone, PartialEq)]
+pub enum MemberKind {
+    /// `name: [MODF] T`, a field accessed as `value.name`.
+    Field(SlotKind),
+
+    /// `name: method(...) --> ...`, a method called as `value:name(...)`.
+    ///
+    /// The function type does not include `self`, which is the type of the value itself.
+    Method(FuncKind),
+}
+
+impl fmt::Debug for MemberKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MemberKind::Field(ref slotkind) => write!(f, "{:?}", slotkind),
+            MemberKind::Method(ref funckind) => write!(f, "Method({:?})", funckind),
+        }
+    }
+}
+
+/// A typed thread (coroutine) type for Kailua.
+#[derive(Clone, PartialEq)]
+pub struct ThreadKind {
+    /// Types of arguments passed to `coroutine.resume`.
+    ///
+    /// The first `resume` call passes them to the coroutine body,
+    /// and subsequent calls make them returned from `coroutine.yield`.
+    pub args: Seq<Spanned<Kind>>,
+
+    /// Types of values passed to `coroutine.yield`.
+    pub yields: Seq<Spanned<Kind>>,
+
+    /// Types of values returned from the coroutine body.
+    pub returns: Seq<Spanned<Kind>>,
+}
+
+impl fmt::Debug for ThreadKind {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fn fmt_seq(seq: &Seq<Spanned<Kind>>, f: &mut fmt::Formatter) -> fmt::Result {
+            if seq.head.len() == 1 && seq.tail.is_none() {
+                write!(f, "{:?}", seq.head[0])
+            } else {
+                write!(f, "({:-?})", seq)
+            }
+        }
+
+        write!(f, "<")?;
+        fmt_seq(&self.args, f)?;
+        write!(f, ", ")?;
+        fmt_seq(&self.yields, f)?;
+        write!(f, ", ")?;
+        fmt_seq(&self.returns, f)?;
+        write!(f, ">")
+    }
+}
+
 /// A function type for Kailua.
 #[derive(Clone, PartialEq)]
 pub struct FuncKind {
+    /// A list of type parameters, empty if the function is not generic.
+    ///
+    /// Each type parameter is instantiated to a fresh type at every call site.
+    /// The parser ensures that the names are distinct.
+    pub tparams: Vec<Spanned<Name>>,
+
     /// A list of argument types with optional names.
     ///
     /// The name is purely for description and has no effect in the type.
@@ -804,6 +962,14 @@ pub struct FuncKind {
 
 impl fmt::Debug for FuncKind {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if !self.tparams.is_empty() {
+            write!(f, "<")?;
+            let comma = Comma::new();
+            for name in &self.tparams {
+                write!(f, "{}{:?}", comma, name)?;
+            }
+            write!(f, ">")?;
+        }
         write!(f, "(")?;
         let comma = Comma::new();
         for &(ref name, ref arg) in &self.args.head {
@@ -892,11 +1058,14 @@ pub enum K {
     /// `thread`.
     Thread,
 
+    /// `thread<A, Y, R>`.
+    ThreadOf(ThreadKind),
+
     /// `userdata`.
     UserData,
 
-    /// A named type.
-    Named(Spanned<Name>),
+    /// A named type, optionally with type arguments (`Name<T, ...>`) for generic types.
+    Named(Spanned<Name>, Vec<Spanned<Kind>>),
 
     /// `T?`.
     ///
@@ -913,6 +1082,18 @@ pub enum K {
     /// `T | U | ...`.
     Union(Vec<Spanned<Kind>>),
 
+    /// `T & U & ...`.
+    ///
+    /// This binds tighter than `|`, so `A & B | C` is `(A & B) | C`.
+    Intersection(Vec<Spanned<Kind>>),
+
+    /// `readonly T`.
+    ///
+    /// Only meaningful for table types, whose values cannot be updated through this type.
+    /// This applies to the following atomic type including postfix operators,
+    /// so `readonly T?` is `readonly (T?)` (which is same to `(readonly T)?` in the checker).
+    Readonly(Spanned<Kind>),
+
     /// `[attribute] T`.
     Attr(Spanned<Kind>, Spanned<Attr>),
 
@@ -940,10 +1121,22 @@ impl fmt::Debug for K {
             K::Array(ref v)       => write!(f, "Array({:?})", *v),
             K::Map(ref k, ref v)  => write!(f, "Map({:?}, {:?})", *k, *v),
             K::Function           => write!(f, "Function"),
-            K::Named(ref name)    => write!(f, "{:?}", *name),
+            K::Named(ref name, ref args) => {
+                write!(f, "{:?}", *name)?;
+                if !args.is_empty() {
+                    write!(f, "<")?;
+                    let comma = Comma::new();
+                    for arg in args {
+                        write!(f, "{}{:?}", comma, arg)?;
+                    }
+                    write!(f, ">")?;
+                }
+                Ok(())
+            },
             K::WithNil(ref k)     => write!(f, "{:?}?", *k),
             K::WithoutNil(ref k)  => write!(f, "{:?}!", *k),
             K::Thread             => write!(f, "Thread"),
+            K::ThreadOf(ref th)   => write!(f, "Thread{:?}", *th),
             K::UserData           => write!(f, "UserData"),
             K::Error(None)        => write!(f, "Error"),
             K::Error(Some(ref s)) => write!(f, "Error({:?})", *s),
@@ -963,6 +1156,8 @@ impl fmt::Debug for K {
             K::Tuple(ref fields) => write!(f, "Tuple({:?})", *fields),
             K::Func(ref func) => write!(f, "Func({:?})", *func),
             K::Union(ref kinds) => write!(f, "Union({:?})", *kinds),
+            K::Intersection(ref kinds) => write!(f, "Intersection({:?})", *kinds),
+            K::Readonly(ref k)    => write!(f, "Readonly({:?})", *k),
             K::Attr(ref k, ref a) => write!(f, "{:?} {:?}", a, k),
         }
     }
@@ -1,7 +1,7 @@
 //! Source language description.
 //!
-//! Right now Kailua only supports the entirety of Lua 5.1 and its own extension syntax,
-//! but these types may allow other versions of Lua in the future.
+//! Right now Kailua supports the entirety of Lua 5.1 through 5.4, LuaJIT 2 and
+//! its own extension syntax, but these types may allow other versions of Lua in the future.
 
 use std::fmt;
 use kailua_diag::{Locale, Localize, Localized};
@@ -10,22 +10,31 @@ use kailua_diag::{Locale, Localize, Localized};
 ///
 /// Internally this is a combination of two bit fields `0xLLKK0000`,
 /// where `0xLL` is the Lua version and `0xKK` is the Kailua version (or `0x00` if disabled).
-/// Lower 16 bits are reserved for the future usage and significant extensions.
+/// Lower 16 bits are reserved for the future usage and significant extensions;
+/// currently the lowest bit is set for LuaJIT, which is only valid with Lua 5.1.
 /// `0x00000000` is reserved for the absence of language information.
 #[derive(Copy, Clone, PartialEq, Eq, Hash)]
 pub struct Language(u32);
 
+const EXT_LUAJIT: u32 = 0x0001;
+
 impl Language {
     pub fn new(lua: Lua, kailua: Kailua) -> Language {
         Language((lua as u32) << 24 | (kailua as u32) << 16)
     }
 
+    /// Creates a LuaJIT 2.x language, which is a Lua 5.1 with some extensions.
+    pub fn new_luajit(kailua: Kailua) -> Language {
+        Language::new(Lua::Lua51, kailua).with_luajit(true)
+    }
+
     pub fn from_u32(v: u32) -> Option<Language> {
         let lua = v >> 24;
         let kailua = (v >> 16) & 0xff;
         let exts = v & 0xffff;
         match (Lua::from_u32(lua), Kailua::from_u32(kailua), exts) {
             (Some(_), Some(_), 0) => Some(Language(v)),
+            (Some(Lua::Lua51), Some(_), EXT_LUAJIT) => Some(Language(v)),
             (_, _, _) => None,
         }
     }
@@ -34,23 +43,83 @@ impl Language {
         self.0
     }
 
+    /// Parses a language name used in the configuration, e.g. `lua51` or `lua53+kailua`.
+    ///
+    /// The Lua part is one of `lua51`, `lua52`, `lua53`, `lua54` or `luajit`,
+    /// optionally followed by `+kailua` to enable Kailua extensions.
+    /// (The parser currently recognizes Kailua extensions regardless of this.)
+    pub fn from_name(name: &str) -> Option<Language> {
+        let (lua, kailua) = if name.ends_with("+kailua") {
+            (&name[..name.len() - 7], Some(Kailua::Kailua10))
+        } else {
+            (name, None)
+        };
+        let kailua = kailua.map_or(0, |kailua| (kailua as u32) << 16);
+        Language(kailua).with_lua_name(lua.as_bytes())
+    }
+
+    /// Returns the same language with the Lua version replaced by given name,
+    /// or `None` if the name is not known.
+    ///
+    /// Accepted names are same to those of standard libraries (`lua51`, `luajit` etc.).
+    pub fn with_lua_name(&self, name: &[u8]) -> Option<Language> {
+        match name {
+            b"lua51" => Some(self.with_lua(Lua::Lua51)),
+            b"lua52" => Some(self.with_lua(Lua::Lua52)),
+            b"lua53" => Some(self.with_lua(Lua::Lua53)),
+            b"lua54" => Some(self.with_lua(Lua::Lua54)),
+            b"luajit" => Some(self.with_luajit(true)),
+            _ => None,
+        }
+    }
+
     pub fn lua(&self) -> Lua {
         Lua::from_u32(self.0 >> 24).unwrap()
     }
 
+    /// Returns the same language with the Lua version replaced.
+    ///
+    /// LuaJIT extensions, if any, are dropped.
+    pub fn with_lua(&self, lua: Lua) -> Language {
+        Language((lua as u32) << 24 | (self.0 & 0xffffff & !EXT_LUAJIT))
+    }
+
+    /// Returns true if LuaJIT extensions are enabled.
+    pub fn luajit(&self) -> bool {
+        self.0 & EXT_LUAJIT != 0
+    }
+
+    /// Returns the same language with LuaJIT extensions enabled or disabled.
+    ///
+    /// Enabling LuaJIT extensions also switches the Lua version to 5.1.
+    pub fn with_luajit(&self, luajit: bool) -> Language {
+        if luajit {
+            Language((Lua::Lua51 as u32) << 24 | (self.0 & 0xffffff) | EXT_LUAJIT)
+        } else {
+            Language(self.0 & !EXT_LUAJIT)
+        }
+    }
+
     pub fn kailua(&self) -> Option<Kailua> {
         let v = (self.0 >> 16) & 0xff;
         if v == 0 { None } else { Some(Kailua::from_u32(v).unwrap()) }
     }
 }
 
+impl Default for Language {
+    /// Lua 5.1 with Kailua extensions, which had been the only supported language.
+    fn default() -> Language {
+        Language::new(Lua::Lua51, Kailua::Kailua10)
+    }
+}
+
 impl fmt::Debug for Language {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
-        let lua = self.lua();
+        let lua = if self.luajit() { LUAJIT_NAME } else { self.lua().name() };
         if let Some(kailua) = self.kailua() {
-            write!(f, "<{} + {}>", lua.name(), kailua.name())
+            write!(f, "<{} + {}>", lua, kailua.name())
         } else {
-            write!(f, "<{}>", lua.name())
+            write!(f, "<{}>", lua)
         }
     }
 }
@@ -59,6 +128,7 @@ impl Localize for Language {
     fn fmt_localized(&self, f: &mut fmt::Formatter, locale: Locale) -> fmt::Result {
         let lua = self.lua();
         let lua_name = Localized::new(&lua, locale);
+        let lua_name: &fmt::Display = if self.luajit() { &LUAJIT_NAME } else { &lua_name };
         if let Some(kailua) = self.kailua() {
             let kailua_name = Localized::new(&kailua, locale);
             match &locale[..] {
@@ -71,6 +141,8 @@ impl Localize for Language {
     }
 }
 
+const LUAJIT_NAME: &'static str = "LuaJIT 2";
+
 /// Lua version.
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub enum Lua {
@@ -82,6 +154,9 @@ pub enum Lua {
 
     /// Lua 5.3.
     Lua53 = 0x53,
+
+    /// Lua 5.4.
+    Lua54 = 0x54,
 }
 
 impl Lua {
@@ -90,6 +165,7 @@ impl Lua {
             0x51 => Some(Lua::Lua51),
             0x52 => Some(Lua::Lua52),
             0x53 => Some(Lua::Lua53),
+            0x54 => Some(Lua::Lua54),
             _ => None,
         }
     }
@@ -99,6 +175,7 @@ impl Lua {
             Lua::Lua51 => "Lua 5.1",
             Lua::Lua52 => "Lua 5.2",
             Lua::Lua53 => "Lua 5.3",
+            Lua::Lua54 => "Lua 5.4",
         }
     }
 }
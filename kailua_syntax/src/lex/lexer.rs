@@ -7,7 +7,7 @@ use kailua_env::{SourceData, Pos, Span, Spanned, WithLoc};
 use kailua_env::SourceData::{U8, U16, EOF};
 use kailua_diag::{Report, Reporter, Localize};
 
-use super::{Tok, Punct, Keyword};
+use super::{Tok, Punct, Keyword, NumSuffix};
 
 fn is_digit(c: SourceData) -> bool {
     match c { U8(b'0'...b'9') => true, _ => false }
@@ -129,6 +129,27 @@ impl<'a> Lexer<'a> {
         }
     }
 
+    // scans a LuaJIT number suffix (`LL`, `ULL` or `i`, all case-insensitive) if any.
+    // returns `Err(())` when the suffix is malformed.
+    fn scan_num_suffix(&mut self) -> Result<Option<NumSuffix>, ()> {
+        let is = |c: SourceData, ch: u8| c == U8(ch) || c == U8(ch.to_ascii_uppercase());
+        if self.try(|c| is(c, b'i')).is_some() {
+            return Ok(Some(NumSuffix::I));
+        }
+        let unsigned = self.try(|c| is(c, b'u')).is_some();
+        if self.try(|c| is(c, b'l')).is_some() {
+            if self.try(|c| is(c, b'l')).is_some() {
+                Ok(Some(if unsigned { NumSuffix::ULL } else { NumSuffix::LL }))
+            } else {
+                Err(())
+            }
+        } else if unsigned {
+            Err(())
+        } else {
+            Ok(None)
+        }
+    }
+
     fn scan_while<Cond, F>(&mut self, mut cond: Cond, mut f: F)
             where Cond: FnMut(SourceData) -> bool, F: FnMut(SourceData) {
         loop {
@@ -363,6 +384,7 @@ impl<'a> Lexer<'a> {
                 (@token Keyword($e:expr)) => (Tok::Keyword($e));
                 (@token Name($e:expr))    => (Tok::Name($e));
                 (@token Num($e:expr))     => (Tok::Num($e));
+                (@token SuffixedNum($e:expr, $s:expr)) => (Tok::SuffixedNum($e, $s));
                 (@token Str($e:expr))     => (Tok::Str($e));
                 (@token $i:ident)         => (Tok::Punct(Punct::$i));
 
@@ -397,7 +419,8 @@ impl<'a> Lexer<'a> {
 
                 // numbers
                 U8(c @ b'0'...b'9') => {
-                    if c == b'0' && self.try(|c| c == U8(b'x')).is_some() {
+                    let mut integral = true;
+                    let v = if c == b'0' && self.try(|c| c == U8(b'x')).is_some() {
                         // hexadecimal
                         let mut num = Vec::new();
                         self.scan_while(
@@ -409,11 +432,10 @@ impl<'a> Lexer<'a> {
 
                         let s = str::from_utf8(&num).unwrap();
                         if s.is_empty() {
-                            self.report.error(begin..self.pos(), m::InvalidNumber {}).done()?;
-                            // continue reading other tokens
+                            None
                         } else if s.len() <= 16 {
                             let v = u64::from_str_radix(s, 16).unwrap();
-                            return tok!(Num(v as f64));
+                            Some(v as f64)
                         } else {
                             // uh, this is possible when `0x` is followed by 17+ hex digits.
                             // it is still a valid number however,
@@ -424,16 +446,18 @@ impl<'a> Lexer<'a> {
                             // so we won't care.
                             let v = u64::from_str_radix(&s[..16], 16).unwrap();
                             let shift = 4 * (s.len() - 16);
-                            return tok!(Num(v as f64 * (shift as f64).exp2()));
+                            Some(v as f64 * (shift as f64).exp2())
                         }
                     } else {
                         let mut num = vec![c];
                         self.scan_while(is_digit, |c| num.push(c.u8()));
                         if let Some(c) = self.try(|c| c == U8(b'.')) {
+                            integral = false;
                             num.push(c.u8());
                             self.scan_while(is_digit, |c| num.push(c.u8()));
                         }
                         if let Some(c) = self.try(|c| c == U8(b'e') || c == U8(b'E')) {
+                            integral = false;
                             num.push(c.u8());
                             if let Some(c) = self.try(|c| c == U8(b'-')) {
                                 num.push(c.u8());
@@ -441,14 +465,22 @@ impl<'a> Lexer<'a> {
                             self.scan_while(is_digit, |c| num.push(c.u8()));
                         }
 
-                        if let Ok(s) = str::from_utf8(&num) {
-                            if let Ok(v) = s.parse::<f64>() {
-                                return tok!(Num(v));
-                            }
-                        }
+                        str::from_utf8(&num).ok().and_then(|s| s.parse::<f64>().ok())
+                    };
 
-                        self.report.error(begin..self.pos(), m::InvalidNumber {}).done()?;
-                        // continue reading other tokens
+                    // LuaJIT suffixes are always recognized, the parser will filter them
+                    match (v, self.scan_num_suffix()) {
+                        (Some(v), Ok(None)) => return tok!(Num(v)),
+                        (Some(v), Ok(Some(NumSuffix::I))) => {
+                            return tok!(SuffixedNum(v, NumSuffix::I));
+                        }
+                        (Some(v), Ok(Some(suffix))) if integral => {
+                            return tok!(SuffixedNum(v, suffix));
+                        }
+                        (_, _) => {
+                            self.report.error(begin..self.pos(), m::InvalidNumber {}).done()?;
+                            // continue reading other tokens
+                        }
                     }
                 }
 
@@ -523,25 +555,31 @@ impl<'a> Lexer<'a> {
 
                 U8(b'+') => return tok!(Plus),
                 U8(b'*') => return tok!(Star),
-                U8(b'/') => return tok!(Slash),
+                U8(b'/') => {
+                    if let Some(_) = self.try(|c| c == U8(b'/')) { return tok!(SlashSlash); }
+                    return tok!(Slash);
+                },
+                U8(b'&') => return tok!(Amp),
                 U8(b'%') => return tok!(Percent),
                 U8(b'^') => return tok!(Caret),
                 U8(b'#') => return tok!(Hash),
+                U8(b'|') => return tok!(Pipe),
                 U8(b'=') => {
                     if let Some(_) = self.try(|c| c == U8(b'=')) { return tok!(EqEq); }
                     return tok!(Eq);
                 },
                 U8(b'~') => {
                     if let Some(_) = self.try(|c| c == U8(b'=')) { return tok!(TildeEq); }
-                    self.report.error(begin..self.pos(), m::UnexpectedChar {}).done()?;
-                    // continue reading other tokens
+                    return tok!(Tilde);
                 },
                 U8(b'<') => {
                     if let Some(_) = self.try(|c| c == U8(b'=')) { return tok!(LtEq); }
+                    if let Some(_) = self.try(|c| c == U8(b'<')) { return tok!(LtLt); }
                     return tok!(Lt);
                 },
                 U8(b'>') => {
                     if let Some(_) = self.try(|c| c == U8(b'=')) { return tok!(GtEq); }
+                    if let Some(_) = self.try(|c| c == U8(b'>')) { return tok!(GtGt); }
                     return tok!(Gt);
                 },
                 U8(b'(') => return tok!(LParen),
@@ -550,7 +588,10 @@ impl<'a> Lexer<'a> {
                 U8(b'}') => return tok!(RBrace),
                 U8(b']') => return tok!(RBracket),
                 U8(b';') => return tok!(Semicolon),
-                U8(b':') => return tok!(Colon),
+                U8(b':') => {
+                    if let Some(_) = self.try(|c| c == U8(b':')) { return tok!(ColonColon); }
+                    return tok!(Colon);
+                },
                 U8(b',') => return tok!(Comma),
                 U8(b'.') => {
                     if let Some(_) = self.try(|c| c == U8(b'.')) {
@@ -572,7 +613,6 @@ impl<'a> Lexer<'a> {
                 },
                 U8(b'?') if self.meta => return tok!(Ques),
                 U8(b'!') if self.meta => return tok!(Bang),
-                U8(b'|') if self.meta => return tok!(Pipe),
 
                 U8(_) | U16(_) => {
                     // try to consume more invalid multi-byte characters in a row
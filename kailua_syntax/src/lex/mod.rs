@@ -28,6 +28,9 @@ pub enum Tok {
     /// A number.
     Num(f64),
 
+    /// A number with a LuaJIT-specific suffix (e.g. `42LL`).
+    SuffixedNum(f64, NumSuffix),
+
     /// A name (either an identifier or a quoted name in the meta block).
     Name(Name),
 
@@ -51,6 +54,8 @@ impl Localize for Tok {
             (_,    &Tok::Keyword(w)) => write!(f, "{}", Localized::new(&w, locale)),
             ("ko", &Tok::Num(_))     => write!(f, "숫자"),
             (_,    &Tok::Num(_))     => write!(f, "a number"),
+            ("ko", &Tok::SuffixedNum(_, s)) => write!(f, "접미사 `{}`가 붙은 숫자", s.name()),
+            (_,    &Tok::SuffixedNum(_, s)) => write!(f, "a number with a suffix `{}`", s.name()),
             ("ko", &Tok::Name(_))    => write!(f, "이름"),
             (_,    &Tok::Name(_))    => write!(f, "a name"),
             ("ko", &Tok::Str(_))     => write!(f, "문자열 리터럴"),
@@ -102,7 +107,7 @@ define_puncts! { Punct |locale|:
     Lt          "`<`",      /// `<`.
     Gt          "`>`",      /// `>`.
     Eq          "`=`",      /// `=`.
-    Amp         "`&`",      /// `&`. [5.3+]
+    Amp         "`&`",      /// `&`. [5.3+ or M]
     Tilde       "`~`",      /// `~`. [5.3+]
     Pipe        "`|`",      /// `|`. [5.3+ or M]
     LtLt        "`<<`",     /// `<<`. [5.3+]
@@ -191,12 +196,17 @@ define_keywords! { Keyword:
         Assume      b"assume",      /// `assume`. [M]
         Class       b"class",       /// `class`. [M]
         Const       b"const",       /// `const`. [M]
+        Enum        b"enum",        /// `enum`. [M]
         Global      b"global",      /// `global`. [M]
+        Interface   b"interface",   /// `interface`. [M]
+        Lang        b"lang",        /// `lang`. [M]
         Map         b"map",         /// `map`. [M]
         Method      b"method",      /// `method`. [M]
         Module      b"module",      /// `module`. [M]
+        Newtype     b"newtype",     /// `newtype`. [M]
         Once        b"once",        /// `once`. [M]
         Open        b"open",        /// `open`. [M]
+        Readonly    b"readonly",    /// `readonly`. [M]
         Static      b"static",      /// `static`. [M]
         Type        b"type",        /// `type`. [M]
         Var         b"var",         /// `var`. [M]
@@ -226,6 +236,29 @@ impl Localize for Keyword {
     }
 }
 
+/// A suffix to the number literal, only available in LuaJIT.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum NumSuffix {
+    /// `LL`, a signed 64-bit integer (`int64_t`).
+    LL,
+
+    /// `ULL`, an unsigned 64-bit integer (`uint64_t`).
+    ULL,
+
+    /// `i`, an imaginary part of the complex number (`complex`).
+    I,
+}
+
+impl NumSuffix {
+    pub fn name(&self) -> &'static str {
+        match *self {
+            NumSuffix::LL => "LL",
+            NumSuffix::ULL => "ULL",
+            NumSuffix::I => "i",
+        }
+    }
+}
+
 mod lexer;
 mod nesting;
 
@@ -46,6 +46,7 @@ pub use string::{Str, Name};
 pub use lex::{Lexer, Nest, Tok, NestedToken};
 pub use ast::Chunk;
 pub use parser::Parser;
+pub use lang::Language;
 
 pub mod lang;
 mod message;
@@ -60,10 +61,16 @@ mod parser;
 /// it can continue in spite of reported errors.
 /// `kailua_diag::report::TrackMaxKind` is useful for this.
 pub fn parse_chunk(source: &Source, span: Span, report: &Report) -> kailua_diag::Result<Chunk> {
+    parse_chunk_with_language(source, span, Language::default(), report)
+}
+
+/// Same to `parse_chunk` but with a given initial language.
+pub fn parse_chunk_with_language(source: &Source, span: Span, language: Language,
+                                 report: &Report) -> kailua_diag::Result<Chunk> {
     if let Some(mut iter) = source.iter_from_span(span) {
         let mut lexer = Lexer::new(&mut iter, &report);
         let mut nest = Nest::new(&mut lexer);
-        let parser = Parser::new(&mut nest, &report);
+        let parser = Parser::with_language(&mut nest, language, &report);
         parser.into_chunk()
     } else {
         use kailua_diag::Reporter;
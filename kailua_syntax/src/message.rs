@@ -1,6 +1,6 @@
 use lang::Lua;
 use lex::Tok;
-use string::Name;
+use string::{Str, Name};
 use parser::Expectable;
 
 define_msg! { pub NoFileForSpan:
@@ -362,6 +362,16 @@ define_msg! { pub FirstArgNameInFuncKind:
     _    => "The first duplicate appeared here",
 }
 
+define_msg! { pub DuplicateTypeParam<'a> { name: &'a Name }:
+    "ko" => "타입에서 타입 인자 {name}이 중복됩니다",
+    _    => "Duplicate type parameter {name} in the type specification",
+}
+
+define_msg! { pub FirstTypeParam:
+    "ko" => "여기서 처음 나왔습니다",
+    _    => "The first duplicate appeared here",
+}
+
 define_msg! { pub PartiallyNamedFieldsInFuncKind:
     "ko" => "타입에서 일부 인자에만 이름이 붙어 있습니다",
     _    => "Not all but only some arguments in the type are named",
@@ -382,11 +392,46 @@ define_msg! { pub NoTypeSeqInUnion:
     _    => "A sequence of types cannot be inside a union",
 }
 
+define_msg! { pub NoTypeSeqInIntersection:
+    "ko" => "교집합 타입에는 타입열이 들어갈 수 없습니다",
+    _    => "A sequence of types cannot be inside an intersection",
+}
+
+define_msg! { pub NoTypeSeqInReadonly:
+    "ko" => "타입열은 `readonly`가 될 수 없습니다",
+    _    => "A sequence of types cannot be `readonly`",
+}
+
 define_msg! { pub NoSingleTypeButTypeSeq:
     "ko" => "하나의 타입이 나와야 하는데 타입열이 나왔습니다",
     _    => "Expected a single type, not type sequence",
 }
 
+define_msg! { pub DuplicateMemberNameInInterface<'a> { name: &'a Name }:
+    "ko" => "인터페이스에서 멤버 이름 {name}이 중복됩니다",
+    _    => "Duplicate interface member {name}",
+}
+
+define_msg! { pub FirstMemberNameInInterface:
+    "ko" => "여기서 처음 나왔습니다",
+    _    => "The first duplicate appeared here",
+}
+
+define_msg! { pub NoStringInEnum<'a> { read: &'a Tok }:
+    "ko" => "열거형에는 문자열 리터럴이 나와야 하는데 {read}이(가) 나왔습니다",
+    _    => "Expected a string literal in the enum, got {read}",
+}
+
+define_msg! { pub DuplicateStringInEnum<'a> { s: &'a Str }:
+    "ko" => "열거형에서 문자열 {s}이(가) 중복됩니다",
+    _    => "Duplicate enum string {s}",
+}
+
+define_msg! { pub FirstStringInEnum:
+    "ko" => "여기서 처음 나왔습니다",
+    _    => "The first duplicate appeared here",
+}
+
 define_msg! { pub CannotRedefineBuiltin:
     "ko" => "내장 타입은 재선언할 수 없습니다",
     _    => "Cannot redefine a builtin type",
@@ -412,11 +457,22 @@ define_msg! { pub WrongMapParamsArity:
     _    => "`map` type needs two type parameters",
 }
 
+define_msg! { pub WrongThreadParamsArity:
+    "ko" => "`thread` 타입에 타입 인자를 주려면 인자, yield, 반환 타입 세 개가 있어야 합니다",
+    _    => "`thread` type with type parameters needs three of them \
+             (argument, yield and return types)",
+}
+
 define_msg! { pub WrongMapParamsModf:
     "ko" => "`map` 타입의 첫 타입 인자에는 변수 종류를 사용할 수 없습니다",
     _    => "The first type parameter of `map` type cannot have modifiers",
 }
 
+define_msg! { pub TypeArgsModf:
+    "ko" => "제너릭 타입의 타입 인자에는 변수 종류를 사용할 수 없습니다",
+    _    => "Type arguments to the generic type cannot have modifiers",
+}
+
 define_msg! { pub NonIntegerType:
     "ko" => "정수가 아닌 숫자나, 너무 작거나 큰 정수는 타입으로 쓸 수 없습니다",
     _    => "Cannot use a non-integer, or too small or too large integer as a type",
@@ -444,6 +500,63 @@ define_msg! { pub FutureKeyword<'a> { read: &'a Tok, current: Lua, future: Lua }
              but it became a keyword since {future}",
 }
 
+define_msg! { pub FutureToken<'a> { read: &'a Tok, current: Lua, future: Lua }:
+    "ko" => "{read}은(는) {current}에서는 쓸 수 없으며 {future}부터 사용할 수 있습니다",
+    _    => "{read} cannot be used in {current}, it is only available since {future}",
+}
+
+define_msg! { pub UnknownLanguage<'a> { name: &'a Name }:
+    "ko" => "언어 {name}을(를) 알 수 없습니다. \
+             `lua51`, `lua52`, `lua53`, `lua54`, `luajit` 중 하나여야 합니다",
+    _    => "Unknown language {name}, should be one of \
+             `lua51`, `lua52`, `lua53`, `lua54` or `luajit`",
+}
+
+define_msg! { pub LuaJITOnlyToken<'a> { read: &'a Tok, current: Lua }:
+    "ko" => "{current}에서는 {read}을(를) 쓸 수 없으며 LuaJIT에서만 사용할 수 있습니다",
+    _    => "Cannot use {read} in {current}, it is only available in LuaJIT",
+}
+
+define_msg! { pub DuplicateLabel<'a> { name: &'a Name }:
+    "ko" => "레이블 {name}이(가) 같은 블록 안에서 이미 정의되었습니다",
+    _    => "The label {name} has been already defined in the same block",
+}
+
+define_msg! { pub PreviousLabel:
+    "ko" => "이전 레이블은 여기에서 정의되었습니다",
+    _    => "The previous label was defined here",
+}
+
+define_msg! { pub NoVisibleLabel<'a> { name: &'a Name }:
+    "ko" => "`goto`가 가리키는 레이블 {name}이(가) 보이지 않습니다",
+    _    => "No visible label {name} for `goto`",
+}
+
+define_msg! { pub GotoIntoLocalScope<'a> { name: &'a Name, local: &'a Name }:
+    "ko" => "레이블 {name}(으)로 가는 `goto`가 지역 변수 {local}의 범위 안으로 들어갑니다",
+    _    => "`goto` to the label {name} jumps into the scope of local {local}",
+}
+
+define_msg! { pub UnknownLocalAttrib<'a> { name: &'a Name }:
+    "ko" => "지역 변수에 알 수 없는 속성 {name}이(가) 붙었습니다",
+    _    => "Unknown attribute {name} for a local variable",
+}
+
+define_msg! { pub MultipleCloseAttribs:
+    "ko" => "`local` 선언 하나에는 <close> 속성을 가진 변수가 최대 하나만 있을 수 있습니다",
+    _    => "Only one variable can have the <close> attribute in a single `local` declaration",
+}
+
+define_msg! { pub PreviousCloseAttrib:
+    "ko" => "이전 <close> 속성은 여기에 붙었습니다",
+    _    => "The previous <close> attribute was given here",
+}
+
+define_msg! { pub LocalAttribWithModule<'a> { name: &'a Name }:
+    "ko" => "속성 {name}은(는) `module` 타입 명세와 함께 쓸 수 없습니다",
+    _    => "The attribute {name} cannot be used with the `module` type specification",
+}
+
 define_msg! { pub AssumeNameStatic:
     "ko" => "`--# assume static`은 클래스 프로토타입의 필드를 설정하는 데만 쓸 수 있습니다",
     _    => "`--# assume static` can only be used to set fields in class prototypes",
@@ -492,6 +605,36 @@ define_msg! { pub TypeExportInLocalScope:
     _    => "`--# type` with an exported type should be in the top-level scope",
 }
 
+define_msg! { pub InterfaceGlobalInLocalScope:
+    "ko" => "`--# interface global`은 최상위 블록에서만 쓸 수 있습니다",
+    _    => "`--# interface global` should be in the top-level scope",
+}
+
+define_msg! { pub InterfaceExportInLocalScope:
+    "ko" => "타입을 바깥으로 내보내는 `--# interface`는 최상위 블록에서만 쓸 수 있습니다",
+    _    => "`--# interface` with an exported type should be in the top-level scope",
+}
+
+define_msg! { pub EnumGlobalInLocalScope:
+    "ko" => "`--# enum global`은 최상위 블록에서만 쓸 수 있습니다",
+    _    => "`--# enum global` should be in the top-level scope",
+}
+
+define_msg! { pub EnumExportInLocalScope:
+    "ko" => "타입을 바깥으로 내보내는 `--# enum`은 최상위 블록에서만 쓸 수 있습니다",
+    _    => "`--# enum` with an exported type should be in the top-level scope",
+}
+
+define_msg! { pub NewtypeGlobalInLocalScope:
+    "ko" => "`--# newtype global`은 최상위 블록에서만 쓸 수 있습니다",
+    _    => "`--# newtype global` should be in the top-level scope",
+}
+
+define_msg! { pub NewtypeExportInLocalScope:
+    "ko" => "타입을 바깥으로 내보내는 `--# newtype`은 최상위 블록에서만 쓸 수 있습니다",
+    _    => "`--# newtype` with an exported type should be in the top-level scope",
+}
+
 define_msg! { pub AssumeMethodToNonInstanceField:
     "ko" => "`method(...) --> ...` 타입은 정적이 아닌 필드를 `--# assume` 할 때만 쓸 수 있습니다",
     _    => "`method(...) --> ...` type is only available when using `--# assume` \
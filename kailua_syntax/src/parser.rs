@@ -1,4 +1,5 @@
 use std::ops;
+use std::mem;
 use std::iter;
 use std::u8;
 use std::i32;
@@ -10,12 +11,12 @@ use kailua_env::{Pos, Span, Spanned, WithLoc, Scope, ScopedId, ScopeMap};
 use kailua_diag::{report, Locale, Report, Reporter, Localize};
 
 use message as m;
-use lang::{Language, Lua, Kailua};
+use lang::{Language, Lua};
 use lex::{Tok, Punct, Keyword, NestedToken, NestingCategory, NestingSerial};
 use string::{Str, Name};
 use ast::{NameRef, RenameRef, Var, Seq, Sig, Attr, AttrValue, Args, Table};
 use ast::{Ex, Exp, UnOp, BinOp, SelfParam, TypeScope, St, Stmt, Block};
-use ast::{M, MM, K, Kind, SlotKind, FuncKind, TypeSpec, Varargs, Returns};
+use ast::{M, MM, K, Kind, SlotKind, FuncKind, ThreadKind, MemberKind, TypeSpec, Varargs, Returns};
 use ast::{LocalName, LocalNameKind, TokenAux, Chunk};
 
 /// The parser.
@@ -53,6 +54,15 @@ pub struct Parser<'a> {
     scope_stack: Vec<(Scope, Pos)>,
     block_depth: usize,
 
+    // labels and pending `goto`s for each block in the current function (5.2+).
+    // replaced with an empty stack while parsing a nested function,
+    // since labels are not visible across function boundaries.
+    label_blocks: Vec<LabelBlock>,
+
+    // true when parsing the top level of return types in function types,
+    // where `& function` (or `& method`) ends the return types and starts another overload.
+    in_func_returns: bool,
+
     // auxiliary info for each *input* token (i.e. including elided tokens)
     token_aux: Vec<TokenAux>,
 }
@@ -227,6 +237,25 @@ struct ElidedTokens {
 #[derive(Copy, Clone, Debug, PartialEq)]
 struct TokenIdx(usize);
 
+#[derive(Clone, Debug)]
+struct LabelBlock {
+    // labels with the number of preceding local declarations and non-void statements
+    labels: Vec<(Spanned<Name>, usize, usize)>,
+    // `goto`s which target has not been found yet, with the number of preceding local
+    // declarations in this block (for `goto`s from inner blocks, of the enclosing statement)
+    gotos: Vec<(Spanned<Name>, usize)>,
+    // the first name of each local declaration in this block
+    locals: Vec<Spanned<Name>>,
+    // the number of non-void statements (i.e. other than labels) so far
+    nstmts: usize,
+}
+
+impl LabelBlock {
+    fn new() -> LabelBlock {
+        LabelBlock { labels: Vec::new(), gotos: Vec::new(), locals: Vec::new(), nstmts: 0 }
+    }
+}
+
 #[derive(Clone, Debug)]
 struct IndexedName {
     idx: TokenIdx,
@@ -352,10 +381,20 @@ macro_rules! match_next {
 impl<'a> Parser<'a> {
     /// Creates a new nesting analyzer with given stream of spanned tokens
     /// with nesting informations and the report receiver.
+    ///
+    /// The default language (Lua 5.1 with Kailua extensions) is used.
     pub fn new(iter: &'a mut Iterator<Item=NestedToken>, report: &'a Report) -> Parser<'a> {
+        Parser::with_language(iter, Language::default(), report)
+    }
+
+    /// Same to `Parser::new` but uses given language at the beginning.
+    ///
+    /// The language can be still changed by `--# open` or `--# lang` in the source code.
+    pub fn with_language(iter: &'a mut Iterator<Item=NestedToken>, language: Language,
+                         report: &'a Report) -> Parser<'a> {
         let mut parser = Parser {
             iter: iter.fuse(),
-            language: Language::new(Lua::Lua51, Kailua::Kailua10), // XXX should be configurable
+            language: language,
             elided_newline: None,
             lookahead: None,
             lookahead2: None,
@@ -372,6 +411,8 @@ impl<'a> Parser<'a> {
             global_scope: HashMap::new(),
             scope_stack: Vec::new(),
             block_depth: 0,
+            label_blocks: Vec::new(),
+            in_func_returns: false,
             token_aux: Vec::new(),
         };
 
@@ -400,9 +441,9 @@ impl<'a> Parser<'a> {
                 // comments should be ignored in the parser
                 if let Tok::Comment = t.tok.base { continue; }
 
-                // `goto` is converted to a name on Lua 5.1
+                // `goto` is converted to a name on Lua 5.1 (but not in LuaJIT)
                 let lua = self.language.lua();
-                if lua < Lua::Lua52 {
+                if !self.supports_goto() {
                     if let Tok::Keyword(kw @ Keyword::Goto) = t.tok.base {
                         // XXX don't want to make this failable
                         let _ = self.warn(t.tok.span,
@@ -872,6 +913,34 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // LuaJIT also supports `goto` and labels (and `break` in the middle of the block)
+    fn supports_goto(&self) -> bool {
+        self.language.lua() >= Lua::Lua52 || self.language.luajit()
+    }
+
+    // returns a local `_ENV` visible in the current scope, if any.
+    // since Lua 5.2 every global name `x` is a sugar for `_ENV.x`,
+    // so we have to desugar global names when `_ENV` is not the global environment.
+    fn resolve_local_env(&mut self) -> Option<ScopedId> {
+        if self.language.lua() < Lua::Lua52 {
+            return None;
+        }
+        self.resolve_local_name_without_idx(&Name::from(&b"_ENV"[..]))
+    }
+
+    fn exp_from_nameref(&mut self, nameref: Spanned<NameRef>) -> Spanned<Exp> {
+        let span = nameref.span;
+        if let NameRef::Global(name) = nameref.base {
+            if let Some(env) = self.resolve_local_env() {
+                let env = Box::new(Ex::Var(NameRef::Local(env).with_loc(span))).with_loc(span);
+                return Box::new(Ex::IndexName(env, name.with_loc(span))).with_loc(span);
+            }
+            Box::new(Ex::Var(NameRef::Global(name).with_loc(span))).with_loc(span)
+        } else {
+            Box::new(Ex::Var(nameref)).with_loc(span)
+        }
+    }
+
     fn add_spanned_local_name_with_prev_span(&mut self, scope: Scope, name: Spanned<Name>,
                                              kind: LocalNameKind)
         -> Result<(Spanned<ScopedId>, Option<Span>)>
@@ -920,6 +989,13 @@ impl<'a> Parser<'a> {
         self.scope_stack.push((scope, scopebegin));
     }
 
+    fn set_scope_span(&mut self, scope: Scope, span: Span) {
+        // built-in code has no usable span, and no one is going to look up its scopes anyway
+        if span.is_source_dependent() {
+            self.scope_map.set_span(scope.with_loc(span));
+        }
+    }
+
     fn pop_scope_upto(&mut self, nscopes: usize) {
         if self.scope_stack.len() > nscopes {
             // XXX end might be too short on the recovery case.
@@ -927,7 +1003,7 @@ impl<'a> Parser<'a> {
             let end = self.pos();
             while self.scope_stack.len() > nscopes {
                 let (scope, scopebegin) = self.scope_stack.pop().unwrap();
-                self.scope_map.set_span(scope.with_loc(scopebegin..end));
+                self.set_scope_span(scope, Span::from(scopebegin..end));
             }
         }
     }
@@ -950,6 +1026,60 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // NAME [`<` NAME `>`] (the attribute is only available in Lua 5.4 or later)
+    fn parse_name_with_attrib(&mut self)
+        -> Result<(Spanned<IndexedName>, Option<Spanned<Name>>)>
+    {
+        let name = self.parse_name()?;
+        let attrib = match_next! { self;
+            Tok::Punct(Punct::Lt) in span => {
+                let lua = self.language.lua();
+                if lua < Lua::Lua54 {
+                    self.error(span, m::FutureToken { read: &Tok::Punct(Punct::Lt),
+                                                      current: lua, future: Lua::Lua54 })
+                        .done()?;
+                }
+                let attrib = self.parse_name()?.map(|n| n.name);
+                self.expect(Punct::Gt)?;
+                Some(attrib)
+            };
+            'unread: _ => None;
+        };
+        Ok((name, attrib))
+    }
+
+    // combines the Lua 5.4 attribute with the modifier from the type specification
+    fn apply_local_attrib(&self, modf: MM, attrib: Option<Spanned<Name>>,
+                          closespan: &mut Option<Span>) -> Result<MM> {
+        let attrib = if let Some(attrib) = attrib { attrib } else { return Ok(modf); };
+
+        let attribmodf = match &attrib.base[..] {
+            b"const" => MM::Const,
+            b"close" => {
+                if let Some(prevspan) = *closespan {
+                    self.error(attrib.span, m::MultipleCloseAttribs {})
+                        .note(prevspan, m::PreviousCloseAttrib {})
+                        .done()?;
+                }
+                *closespan = Some(attrib.span);
+                MM::Close
+            }
+            _ => {
+                self.error(attrib.span, m::UnknownLocalAttrib { name: &attrib.base }).done()?;
+                return Ok(modf);
+            }
+        };
+
+        match modf {
+            MM::None | MM::Const => Ok(attribmodf),
+            MM::Close => Ok(MM::Close),
+            MM::Module => {
+                self.error(attrib.span, m::LocalAttribWithModule { name: &attrib.base }).done()?;
+                Ok(modf)
+            }
+        }
+    }
+
     fn try_name_or_keyword(&mut self) -> Result<Spanned<Name>> {
         match_next! { self;
             Tok::Name(name) in span => Ok(name.with_loc(span));
@@ -958,6 +1088,7 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // the caller is responsible for restoring `label_blocks` on error
     fn _parse_block(&mut self) -> Result<Spanned<Block>> {
         trace!("parsing block");
 
@@ -965,14 +1096,13 @@ impl<'a> Parser<'a> {
         let mut stmts = Vec::new();
         let mut pastlast = false;
         let mut excessspan = None;
+        self.label_blocks.push(LabelBlock::new());
         while let Some(stmt) = self.try_parse_stmt()? {
             self.may_expect(Punct::Semicolon);
+            self.count_stmt_in_label_block(&stmt.base);
 
             // if the statement is the final one, further parsing is an error
-            let last = match &*stmt.base {
-                &St::Return(..) | &St::Break => true,
-                _ => false,
-            };
+            let last = self.is_last_stmt(&stmt.base);
 
             if pastlast {
                 excessspan = Some(excessspan.unwrap_or(Span::dummy()) | stmt.span);
@@ -984,19 +1114,73 @@ impl<'a> Parser<'a> {
         if let Some(span) = excessspan {
             self.error(span, m::StmtAfterReturnOrBreak {}).done()?;
         }
+        self.close_label_block()?;
         Ok(stmts.with_loc(begin..self.last_pos()))
     }
 
     // will reset the scope after the end of the block
     fn parse_block(&mut self) -> Result<Spanned<Block>> {
         let nscopes = self.scope_stack.len();
+        let nlabelblocks = self.label_blocks.len();
         self.block_depth += 1;
         let block = self._parse_block();
         self.block_depth -= 1;
         self.pop_scope_upto(nscopes);
+        self.label_blocks.truncate(nlabelblocks);
         block
     }
 
+    // `return` is always the final statement in the block; `break` is so only in Lua 5.1
+    fn is_last_stmt(&self, stmt: &St) -> bool {
+        match *stmt {
+            St::Return(..) => true,
+            St::Break => !self.supports_goto(),
+            _ => false,
+        }
+    }
+
+    fn count_stmt_in_label_block(&mut self, stmt: &St) {
+        if let St::Label(..) = *stmt {
+            return;
+        }
+        if let Some(block) = self.label_blocks.last_mut() {
+            block.nstmts += 1;
+        }
+    }
+
+    fn add_local_to_label_block(&mut self, name: Spanned<Name>) {
+        if let Some(block) = self.label_blocks.last_mut() {
+            block.locals.push(name);
+        }
+    }
+
+    // resolves pending `goto`s in the innermost block with its labels,
+    // and moves remaining `goto`s to the outer block (if any) or reports them.
+    // a `goto` cannot jump into the scope of a local variable,
+    // unless the label is at the end of the block (followed only by void statements).
+    fn close_label_block(&mut self) -> Result<()> {
+        let block = self.label_blocks.pop().expect("no label block to close");
+        for (goto, nlocals) in block.gotos {
+            let label = block.labels.iter().find(|&&(ref label, _, _)| label.base == goto.base);
+            if let Some(&(_, labelnlocals, labelnstmts)) = label {
+                if labelnlocals > nlocals && labelnstmts < block.nstmts {
+                    let local = &block.locals[nlocals];
+                    self.error(goto.span, m::GotoIntoLocalScope { name: &goto.base,
+                                                                  local: &local.base })
+                        .done()?;
+                }
+                continue;
+            }
+            if let Some(outer) = self.label_blocks.last_mut() {
+                let nlocals = outer.locals.len();
+                outer.gotos.push((goto, nlocals));
+                continue;
+            }
+            self.error(goto.span, m::NoVisibleLabel { name: &goto.base }).done()?;
+        }
+        Ok(())
+    }
+
     // same to `self.parse_block()` followed by `self.expect(Keyword::End)`,
     // but will also set the scope in the block and recover from the error.
     // in order to keep the lexical order of scoped ids (purely for cosmetic & debugging reasons),
@@ -1006,6 +1190,7 @@ impl<'a> Parser<'a> {
         where F: FnOnce(&mut Parser<'a>, Scope) -> Result<X>
     {
         let nscopes = self.scope_stack.len();
+        let nlabelblocks = self.label_blocks.len();
         let scope = self.generate_sibling_scope();
         self.push_scope(scope);
         let preret = preblock(self, scope)?;
@@ -1013,6 +1198,7 @@ impl<'a> Parser<'a> {
         let block = self._parse_block();
         self.block_depth -= 1;
         self.pop_scope_upto(nscopes);
+        self.label_blocks.truncate(nlabelblocks);
         let block = self.recover(|_| block, Keyword::End)?;
         Ok((preret, scope, block))
     }
@@ -1026,6 +1212,7 @@ impl<'a> Parser<'a> {
         let mut stmts = Vec::new();
         let mut pastlast = false;
         let mut excessspan = None;
+        self.label_blocks = vec![LabelBlock::new()];
         loop {
             let stmt = match self.try_parse_stmt() {
                 Ok(Some(stmt)) => stmt,
@@ -1047,12 +1234,10 @@ impl<'a> Parser<'a> {
                 }
             };
             self.may_expect(Punct::Semicolon);
+            self.count_stmt_in_label_block(&stmt.base);
 
             // if the statement is the final one, further parsing is an error
-            let last = match &*stmt.base {
-                &St::Return(..) | &St::Break => true,
-                _ => false,
-            };
+            let last = self.is_last_stmt(&stmt.base);
 
             if pastlast {
                 excessspan = Some(excessspan.unwrap_or(Span::dummy()) | stmt.span);
@@ -1064,6 +1249,8 @@ impl<'a> Parser<'a> {
         if let Some(span) = excessspan {
             self.error(span, m::StmtAfterReturnOrBreak {}).done()?;
         }
+        self.label_blocks.truncate(1);
+        self.close_label_block()?;
         Ok(stmts.with_loc(begin..self.last_pos()))
     }
 
@@ -1224,11 +1411,22 @@ impl<'a> Parser<'a> {
             Tok::Keyword(Keyword::Function) => {
                 let namesbegin = self.pos();
                 let rootname = self.parse_name()?.map(|name| self.resolve_name(name));
-                if let NameRef::Global(ref name) = rootname.base {
-                    // this will assign a global name, and is handled like a global assignment
-                    self.global_scope.entry(name.clone()).or_insert(rootname.span);
-                }
                 let mut names = Vec::new();
+                let rootname = match rootname.base {
+                    NameRef::Global(name) => {
+                        if let Some(env) = self.resolve_local_env() {
+                            // `function NAME` is same to `function _ENV.NAME` here
+                            names.push(name.with_loc(rootname.span));
+                            NameRef::Local(env).with_loc(rootname.span)
+                        } else {
+                            // this will assign a global name,
+                            // and is handled like a global assignment
+                            self.global_scope.entry(name.clone()).or_insert(rootname.span);
+                            NameRef::Global(name).with_loc(rootname.span)
+                        }
+                    }
+                    nameref => nameref.with_loc(rootname.span),
+                };
                 while self.may_expect(Punct::Dot) {
                     let name = self.parse_name()?;
                     names.push(name.map(|n| n.name));
@@ -1279,6 +1477,7 @@ impl<'a> Parser<'a> {
                                                                                   funcspec)? {
                             let sibling_scope = self.generate_sibling_scope();
                             self.push_scope(sibling_scope);
+                            self.add_local_to_label_block(name.clone().map(|n| n.name));
                             let name = self.add_spanned_local_name(sibling_scope, name)?;
                             let name = name.map(NameRef::Local);
                             Box::new(St::FuncDecl(name, sig, scope, body, Some(sibling_scope)))
@@ -1295,9 +1494,14 @@ impl<'a> Parser<'a> {
                         }
 
                         let mut names = Vec::new();
+                        let mut attribs = Vec::new();
                         let (span, eq) =
-                            self.scan_list_with_spec(Self::parse_name,
-                                                     |namespec| names.push(namespec))?;
+                            self.scan_list_with_spec(Self::parse_name_with_attrib, |namespec| {
+                                let (name, attrib) = namespec.base;
+                                attribs.push(attrib);
+                                names.push(TypeSpec { base: name, modf: namespec.modf,
+                                                      kind: namespec.kind });
+                            })?;
                         let mut names = names.with_loc(span);
 
                         let exps = if eq {
@@ -1318,11 +1522,17 @@ impl<'a> Parser<'a> {
 
                         let sibling_scope = self.generate_sibling_scope();
                         self.push_scope(sibling_scope);
+                        if let Some(namespec) = names.base.first() {
+                            self.add_local_to_label_block(namespec.base.clone().map(|n| n.name));
+                        }
                         // XXX should also mention all excess arguments
                         let mut namerefs = Vec::new().with_loc(names.span);
-                        for namespec in names.base {
+                        let mut closespan = None;
+                        for (namespec, attrib) in names.base.into_iter().zip(attribs) {
+                            let modf = self.apply_local_attrib(namespec.modf, attrib,
+                                                               &mut closespan)?;
                             let name = self.add_spanned_local_name(sibling_scope, namespec.base)?;
-                            namerefs.push(TypeSpec { base: name, modf: namespec.modf,
+                            namerefs.push(TypeSpec { base: name, modf: modf,
                                                      kind: namespec.kind });
                         }
                         Box::new(St::Local(namerefs, exps, sibling_scope))
@@ -1340,6 +1550,42 @@ impl<'a> Parser<'a> {
 
             Tok::Keyword(Keyword::Break) => Box::new(St::Break);
 
+            // goto NAME (the keyword only appears in Lua 5.2 or later)
+            Tok::Keyword(Keyword::Goto) => {
+                let name = self.parse_name()?.map(|n| n.name);
+                if let Some(block) = self.label_blocks.last_mut() {
+                    let nlocals = block.locals.len();
+                    block.gotos.push((name.clone(), nlocals));
+                }
+                Box::new(St::Goto(name))
+            };
+
+            // "::" NAME "::"
+            Tok::Punct(Punct::ColonColon) in span => {
+                let lua = self.language.lua();
+                if !self.supports_goto() {
+                    self.error(span, m::FutureToken { read: &Tok::Punct(Punct::ColonColon),
+                                                      current: lua, future: Lua::Lua52 })
+                        .done()?;
+                }
+                let name = self.parse_name()?.map(|n| n.name);
+                self.expect(Punct::ColonColon)?;
+
+                let prevspan = self.label_blocks.last().and_then(|block| {
+                    block.labels.iter().find(|&&(ref label, _, _)| label.base == name.base)
+                                       .map(|&(ref label, _, _)| label.span)
+                });
+                if let Some(prevspan) = prevspan {
+                    self.error(name.span, m::DuplicateLabel { name: &name.base })
+                        .note(prevspan, m::PreviousLabel {})
+                        .done()?;
+                } else if let Some(block) = self.label_blocks.last_mut() {
+                    let (nlocals, nstmts) = (block.locals.len(), block.nstmts);
+                    block.labels.push((name.clone(), nlocals, nstmts));
+                }
+                Box::new(St::Label(name))
+            };
+
             'unread: _ => {
                 // only prefixexp can appear at this position, but it is very common that
                 // incomplete expression results in a (partial) non-prefix expression.
@@ -1646,9 +1892,11 @@ impl<'a> Parser<'a> {
             }.with_loc(begin..end)
         });
 
+        // labels are not visible across function boundaries
+        let outer_label_blocks = mem::replace(&mut self.label_blocks, Vec::new());
+
         // resolve every parameter (including self)
-        let ((selfparam, args), scope, block) = self.parse_block_end_with_scope(move |parser,
-                                                                                      scope| {
+        let ret = self.parse_block_end_with_scope(move |parser, scope| {
             // attach all arguments to the function body scope
             // XXX should also mention all excess arguments
             // TODO should we add varargs?
@@ -1693,7 +1941,9 @@ impl<'a> Parser<'a> {
 
             let args = Seq { head: head, tail: tail }.with_loc(args.span);
             Ok((selfparam, args))
-        })?;
+        });
+        self.label_blocks = outer_label_blocks;
+        let ((selfparam, args), scope, block) = ret?;
 
         let sig = Sig { attrs: attrs, args: args, returns: returns };
         Ok(Some((selfparam, sig, scope, block)))
@@ -1805,7 +2055,7 @@ impl<'a> Parser<'a> {
             Tok::Name(name) in span => {
                 let name = self.indexed_name_from(name, span);
                 let nameref = self.resolve_name(name.base);
-                Box::new(Ex::Var(nameref.with_loc(span))).with_loc(span)
+                self.exp_from_nameref(nameref.with_loc(span))
             };
 
             'unread: _ => return Ok(None);
@@ -1920,6 +2170,14 @@ impl<'a> Parser<'a> {
             Tok::Keyword(Keyword::False) in span => Ok(Some(Box::new(Ex::False).with_loc(span)));
             Tok::Keyword(Keyword::True) in span => Ok(Some(Box::new(Ex::True).with_loc(span)));
             Tok::Num(v) in span => Ok(Some(Box::new(Ex::Num(v)).with_loc(span)));
+            Tok::SuffixedNum(v, suffix) in span => {
+                if !self.language.luajit() {
+                    self.error(span, m::LuaJITOnlyToken { read: &Tok::SuffixedNum(v, suffix),
+                                                          current: self.language.lua() })
+                        .done()?;
+                }
+                Ok(Some(Box::new(Ex::SuffixedNum(v, suffix)).with_loc(span)))
+            };
             Tok::Str(s) in span => Ok(Some(Box::new(Ex::Str(s)).with_loc(span)));
             Tok::Punct(Punct::DotDotDot) in span => Ok(Some(Box::new(Ex::Varargs).with_loc(span)));
 
@@ -1973,6 +2231,7 @@ impl<'a> Parser<'a> {
             Tok::Punct(Punct::Dash) => Some(UnOp::Neg),
             Tok::Keyword(Keyword::Not) => Some(UnOp::Not),
             Tok::Punct(Punct::Hash) => Some(UnOp::Len),
+            Tok::Punct(Punct::Tilde) => Some(UnOp::BNot),
             _ => None,
         };
         op.map(|op| op.with_loc(tok))
@@ -1985,9 +2244,15 @@ impl<'a> Parser<'a> {
             Tok::Punct(Punct::Dash) => Some(BinOp::Sub),
             Tok::Punct(Punct::Star) => Some(BinOp::Mul),
             Tok::Punct(Punct::Slash) => Some(BinOp::Div),
+            Tok::Punct(Punct::SlashSlash) => Some(BinOp::IDiv),
             Tok::Punct(Punct::Caret) => Some(BinOp::Pow),
             Tok::Punct(Punct::Percent) => Some(BinOp::Mod),
             Tok::Punct(Punct::DotDot) => Some(BinOp::Cat),
+            Tok::Punct(Punct::Amp) => Some(BinOp::BAnd),
+            Tok::Punct(Punct::Pipe) => Some(BinOp::BOr),
+            Tok::Punct(Punct::Tilde) => Some(BinOp::BXor),
+            Tok::Punct(Punct::LtLt) => Some(BinOp::Shl),
+            Tok::Punct(Punct::GtGt) => Some(BinOp::Shr),
             Tok::Punct(Punct::Lt) => Some(BinOp::Lt),
             Tok::Punct(Punct::LtEq) => Some(BinOp::Le),
             Tok::Punct(Punct::Gt) => Some(BinOp::Gt),
@@ -2001,6 +2266,16 @@ impl<'a> Parser<'a> {
         op.map(|op| op.with_loc(tok))
     }
 
+    // reports an operator not available in the current language but otherwise accepts it
+    fn check_future_op(&self, tok: &Spanned<Tok>, future: Lua) -> Result<()> {
+        let lua = self.language.lua();
+        if lua < future {
+            self.error(tok.span, m::FutureToken { read: &tok.base, current: lua, future: future })
+                .done()?;
+        }
+        Ok(())
+    }
+
     // the precedence level 0 is reserved and used at the top level
     fn try_parse_partial_exp(&mut self, minprec: u8) -> Result<Option<Spanned<Exp>>> {
         trace!("parsing exp with min prec {}", minprec);
@@ -2018,7 +2293,7 @@ impl<'a> Parser<'a> {
         fn unary_prec(op: UnOp) -> /*recursion*/ u8 {
             match op {
                 // binary ^ operator here
-                UnOp::Neg | UnOp::Not | UnOp::Len => 8,
+                UnOp::Neg | UnOp::Not | UnOp::Len | UnOp::BNot => 12,
                 // other binary operators here
             }
         }
@@ -2026,7 +2301,10 @@ impl<'a> Parser<'a> {
         let begin = self.pos();
         if let Some(op) = self.try_peek_unary_op() {
             // unop exp ...
-            self.read();
+            let (_, tok) = self.read();
+            if op.base == UnOp::BNot {
+                self.check_future_op(&tok, Lua::Lua53)?;
+            }
             let rprec = unary_prec(op.base);
             let exp = self.parse_partial_exp(rprec)?;
             Ok(Some(Box::new(Ex::Un(op, exp)).with_loc(begin..self.last_pos())))
@@ -2040,11 +2318,15 @@ impl<'a> Parser<'a> {
                                 mut exp: Spanned<Exp>) -> Result<Spanned<Exp>> {
         fn binary_prec(op: BinOp) -> (/*comparison*/ u8, /*recursion*/ u8) {
             match op {
-                BinOp::Pow => (10, 9),
+                BinOp::Pow => (14, 13),
                 // unary operators here
-                BinOp::Mul | BinOp::Div | BinOp::Mod => (7, 7),
-                BinOp::Add | BinOp::Sub => (6, 6),
-                BinOp::Cat => (5, 4),
+                BinOp::Mul | BinOp::Div | BinOp::IDiv | BinOp::Mod => (11, 11),
+                BinOp::Add | BinOp::Sub => (10, 10),
+                BinOp::Cat => (9, 8),
+                BinOp::Shl | BinOp::Shr => (7, 7),
+                BinOp::BAnd => (6, 6),
+                BinOp::BXor => (5, 5),
+                BinOp::BOr => (4, 4),
                 BinOp::Lt | BinOp::Le | BinOp::Gt | BinOp::Ge | BinOp::Eq | BinOp::Ne => (3, 3),
                 BinOp::And => (2, 2),
                 BinOp::Or => (1, 1),
@@ -2055,7 +2337,13 @@ impl<'a> Parser<'a> {
         while let Some(op) = self.try_peek_binary_op() {
             let (cprec, rprec) = binary_prec(op.base);
             if cprec <= minprec { break; }
-            self.read();
+            let (_, tok) = self.read();
+            match op.base {
+                BinOp::IDiv | BinOp::BAnd | BinOp::BOr | BinOp::BXor | BinOp::Shl | BinOp::Shr => {
+                    self.check_future_op(&tok, Lua::Lua53)?;
+                }
+                _ => {}
+            }
             let exp2 = self.parse_partial_exp(rprec)?;
             exp = Box::new(Ex::Bin(exp, op, exp2)).with_loc(begin..self.last_pos());
         }
@@ -2089,7 +2377,7 @@ impl<'a> Parser<'a> {
 
         let begin = self.pos();
         let nameref = self.resolve_name(name.base);
-        let exp = Box::new(Ex::Var(nameref.with_loc(name.span))).with_loc(name.span);
+        let exp = self.exp_from_nameref(nameref.with_loc(name.span));
         let exp = self.parse_prefix_exp_suffix(begin, exp)?;
         self.parse_partial_binary_exp(0, begin, exp)
     }
@@ -2211,6 +2499,18 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // zero or more attributes, as in `[ATTR] [ATTR] ...`
+    fn try_parse_kailua_attrs(&mut self) -> Result<Vec<Spanned<Attr>>> {
+        let mut attrs = Vec::new();
+        loop {
+            match self.try_parse_kailua_attr() {
+                Ok(Some(attr)) => attrs.push(attr),
+                Ok(None) | Err(Stop::Recover) => return Ok(attrs),
+                Err(Stop::Fatal) => return Err(Stop::Fatal),
+            }
+        }
+    }
+
     fn try_parse_kailua_attr_value(&mut self) -> Result<Option<Spanned<AttrValue>>> {
         match_next! { self;
             Tok::Name(name) in span => {
@@ -2426,9 +2726,39 @@ impl<'a> Parser<'a> {
         Ok(Seq { head: specs, tail: tail }.with_loc(begin..end))
     }
 
+    // parses [`<` NAME {`,` NAME} `>`]. names should be unique.
+    // used for generic function types and generic type aliases.
+    fn try_parse_kailua_type_params(&mut self) -> Result<Vec<Spanned<Name>>> {
+        let mut tparams = Vec::new();
+        if self.may_expect(Punct::Lt) {
+            let mut seen = HashMap::new(); // value denotes the first span
+            loop {
+                let name = self.parse_name()?.map(|n| n.name);
+                match seen.entry(name.base.clone()) {
+                    hash_map::Entry::Occupied(e) => {
+                        self.error(name.span, m::DuplicateTypeParam { name: &name })
+                            .note(*e.get(), m::FirstTypeParam {})
+                            .done()?;
+                    }
+                    hash_map::Entry::Vacant(e) => {
+                        e.insert(name.span);
+                        tparams.push(name);
+                    }
+                }
+                if !self.may_expect(Punct::Comma) {
+                    break;
+                }
+            }
+            self.expect(Punct::Gt)?;
+        }
+        Ok(tparams)
+    }
+
     fn parse_kailua_funckind(&mut self) -> Result<Spanned<FuncKind>> {
         let begin = self.pos();
 
+        let tparams = self.try_parse_kailua_type_params()?;
+
         self.expect(Punct::LParen)?;
         let args = self.parse_kailua_kindlist(true)?;
         self.expect(Punct::RParen)?;
@@ -2449,7 +2779,7 @@ impl<'a> Parser<'a> {
         };
 
         let span = begin..self.last_pos();
-        Ok(FuncKind { args: args, returns: returns }.with_loc(span))
+        Ok(FuncKind { tparams: tparams, args: args, returns: returns }.with_loc(span))
     }
 
     fn try_parse_kailua_kind_params(&mut self)
@@ -2465,18 +2795,7 @@ impl<'a> Parser<'a> {
                 let kind = self.recover_upto(Self::parse_kailua_kind)?;
                 kinds.push((modf, kind));
             }
-            if !self.may_expect(Punct::Gt) {
-                // try to match against `>>` as well (Lua 5.2+)
-                // for now, we intentionally put an edited token (`>`) back;
-                // this can be done in a better way, though.
-                let tok = self.read();
-                if let (side, Spanned { base: Tok::Punct(Punct::GtGt), span }) = tok {
-                    // XXX this span is bad, but we are unlikely to use this span anyway...
-                    self.unread((side, Tok::Punct(Punct::Gt).with_loc(span)));
-                } else {
-                    error_with!(self, tok, m::NoKindParamsClose);
-                }
-            }
+            self.expect_kind_params_close()?;
             let end = self.last_pos();
             Ok(Some(kinds.with_loc(begin..end)))
         } else {
@@ -2484,6 +2803,44 @@ impl<'a> Parser<'a> {
         }
     }
 
+    fn expect_kind_params_close(&mut self) -> Result<()> {
+        if !self.may_expect(Punct::Gt) {
+            // try to match against `>>` as well (Lua 5.2+)
+            // for now, we intentionally put an edited token (`>`) back;
+            // this can be done in a better way, though.
+            let tok = self.read();
+            if let (side, Spanned { base: Tok::Punct(Punct::GtGt), span }) = tok {
+                // XXX this span is bad, but we are unlikely to use this span anyway...
+                self.unread((side, Tok::Punct(Punct::Gt).with_loc(span)));
+            } else {
+                error_with!(self, tok, m::NoKindParamsClose);
+            }
+        }
+        Ok(())
+    }
+
+    // `thread` is already read and the lookahead is `<`
+    fn parse_kailua_thread_params(&mut self) -> Result<K> {
+        // `<` KINDSEQ `,` KINDSEQ `,` KINDSEQ `>`
+        let begin = self.pos();
+        self.expect(Punct::Lt)?;
+        let mut seqs = vec![self.parse_kailua_kind_seq()?];
+        while self.may_expect(Punct::Comma) {
+            seqs.push(self.parse_kailua_kind_seq()?);
+        }
+        self.expect_kind_params_close()?;
+
+        if seqs.len() != 3 {
+            self.error(begin..self.last_pos(), m::WrongThreadParamsArity {}).done()?;
+            return Ok(K::Oops);
+        }
+        let mut it = seqs.into_iter();
+        let args = it.next().unwrap();
+        let yields = it.next().unwrap();
+        let returns = it.next().unwrap();
+        Ok(K::ThreadOf(ThreadKind { args: args, yields: yields, returns: returns }))
+    }
+
     fn parse_kailua_kind_params(&mut self)
             -> Result<Spanned<Vec<(Spanned<M>, Spanned<Kind>)>>> {
         if let Some(params) = self.try_parse_kailua_kind_params()? {
@@ -2508,6 +2865,13 @@ impl<'a> Parser<'a> {
 
     fn parse_kailua_kind_after_name(&mut self, begin: Pos,
                                     name: Spanned<IndexedName>) -> Result<Spanned<Kind>> {
+        let kind = self.parse_kailua_atomic_kind_after_name(begin, name)?;
+        let kind = self.parse_kailua_kind_after_kind(begin, kind)?; // handle `| KIND ...`
+        Ok(kind)
+    }
+
+    fn parse_kailua_atomic_kind_after_name(&mut self, begin: Pos,
+                                           name: Spanned<IndexedName>) -> Result<Spanned<Kind>> {
         let kind = if *name.base.name == b"error"[..] {
             // may follow an error reason
             let reason = match_next! { self;
@@ -2518,35 +2882,72 @@ impl<'a> Parser<'a> {
         } else {
             let namespan = name.span;
             let kind = match self.builtin_kind(&name.base.name) {
+                // `thread<A, Y, R>`
+                Some(Some(K::Thread)) if self.lookahead(Punct::Lt) => {
+                    self.parse_kailua_thread_params()?
+                },
                 Some(Some(kind)) => kind,
                 Some(None) => {
                     self.error(&name, m::ReservedKindName { name: &name }).done()?;
                     K::Oops
                 },
                 None => {
-                    K::Named(name.map(|n| n.name))
+                    // NAME [`<` KIND {`,` KIND} `>`]
+                    let args = match self.try_parse_kailua_kind_params()? {
+                        Some(params) => {
+                            let mut args = Vec::new();
+                            for (m, kind) in params.base {
+                                if m.base != M::None {
+                                    self.error(&m, m::TypeArgsModf {}).done()?;
+                                }
+                                args.push(kind);
+                            }
+                            args
+                        },
+                        None => Vec::new(),
+                    };
+                    K::Named(name.map(|n| n.name), args)
                 },
             };
-            Box::new(kind).with_loc(namespan)
+            Box::new(kind).with_loc(namespan | Span::from(self.last_pos()))
         };
 
         let kind = self.parse_kailua_kind_suffix(begin, kind); // handle ? or !
-        let kind = self.parse_kailua_kind_after_kind(begin, kind)?; // handle `| KIND ...`
         Ok(kind)
     }
 
     // returns true if it can be followed by postfix operators
     fn try_parse_kailua_atomic_kind_seq(&mut self) -> Result<Option<AtomicKind>> {
+        // any nested kind is no longer at the top level of return types
+        let in_func_returns = mem::replace(&mut self.in_func_returns, false);
+        let kind = self.try_parse_kailua_atomic_kind_seq_inner();
+        self.in_func_returns = in_func_returns;
+        kind
+    }
+
+    fn try_parse_kailua_atomic_kind_seq_inner(&mut self) -> Result<Option<AtomicKind>> {
         let begin = self.pos();
 
         let kind = match_next! { self;
             Tok::Keyword(Keyword::Function) in span => {
                 // either a "function" type or a function signature
-                if self.lookahead(Punct::LParen) {
-                    // function `(` ... `)` [`-->` ...]
+                if self.lookahead(Punct::LParen) || self.lookahead(Punct::Lt) {
+                    // function [`<` ... `>`] `(` ... `)` [`-->` ...]
+                    //     {`&` function [`<` ... `>`] `(` ... `)` [`-->` ...]}
                     let func = self.parse_kailua_funckind()?;
+                    let mut kind = Box::new(K::Func(func)).with_loc(begin..self.last_pos());
+                    if self.lookahead_overload(Keyword::Function) {
+                        let mut kinds = vec![kind];
+                        while self.lookahead_overload(Keyword::Function) {
+                            self.expect(Punct::Amp)?;
+                            let begin = self.pos();
+                            self.expect(Keyword::Function)?;
+                            let func = self.parse_kailua_funckind()?;
+                            kinds.push(Box::new(K::Func(func)).with_loc(begin..self.last_pos()));
+                        }
+                        kind = Box::new(K::Intersection(kinds)).with_loc(begin..self.last_pos());
+                    }
                     // cannot be followed by postfix operators
-                    let kind = Box::new(K::Func(func)).with_loc(begin..self.last_pos());
                     return Ok(Some(AtomicKind::Seq(Seq { head: vec![kind], tail: None })));
                 } else {
                     Box::new(K::Function).with_loc(span)
@@ -2654,6 +3055,24 @@ impl<'a> Parser<'a> {
                 Box::new(kind).with_loc(begin..self.last_pos())
             };
 
+            Tok::Keyword(Keyword::Readonly) => {
+                // `readonly` applies to the following atomic type only
+                let kbegin = self.pos();
+                match self.try_parse_kailua_atomic_kind_seq()? {
+                    Some(AtomicKind::One(kind)) => {
+                        Box::new(K::Readonly(kind)).with_loc(begin..self.last_pos())
+                    }
+                    Some(AtomicKind::Seq(..)) => {
+                        self.error(kbegin..self.last_pos(), m::NoTypeSeqInReadonly {}).done()?;
+                        Recover::recover()
+                    }
+                    None => {
+                        error_with!(self, m::NoType);
+                        Recover::recover()
+                    }
+                }
+            };
+
             Tok::Keyword(Keyword::Nil) in span => Box::new(K::Nil).with_loc(span);
             Tok::Keyword(Keyword::True) in span => Box::new(K::BooleanLit(true)).with_loc(span);
             Tok::Keyword(Keyword::False) in span => Box::new(K::BooleanLit(false)).with_loc(span);
@@ -2696,7 +3115,7 @@ impl<'a> Parser<'a> {
 
             Tok::Name(name) in span => {
                 let name = self.indexed_name_from(name, span);
-                let kind = self.parse_kailua_kind_after_name(begin, name)?;
+                let kind = self.parse_kailua_atomic_kind_after_name(begin, name)?;
                 return Ok(Some(AtomicKind::One(kind)));
             };
 
@@ -2721,19 +3140,23 @@ impl<'a> Parser<'a> {
     fn try_parse_kailua_prefixed_kind_seq(&mut self) -> Result<Option<AtomicKind>> {
         let begin = self.pos();
 
-        let attr = match self.try_parse_kailua_attr() {
-            Ok(attr) => attr,
-            Err(Stop::Recover) => None,
-            Err(Stop::Fatal) => return Err(Stop::Fatal),
-        };
+        let attrs = self.try_parse_kailua_attrs()?;
+        self.try_parse_kailua_kind_seq_after_attrs(begin, attrs)
+    }
+
+    // same to `try_parse_kailua_prefixed_kind_seq` but attributes (if any) have been read
+    fn try_parse_kailua_kind_seq_after_attrs(&mut self, begin: Pos,
+                                             attrs: Vec<Spanned<Attr>>)
+            -> Result<Option<AtomicKind>> {
         if let Some(kindseq) = self.try_parse_kailua_atomic_kind_seq()? {
             let end = self.last_pos();
 
-            // apply an attribute if any
-            if let Some(attr) = attr {
+            // apply attributes if any, the first attribute being the outermost
+            if !attrs.is_empty() {
                 let apply_attr = |kind| {
-                    let kind = Box::new(K::Attr(kind, attr));
-                    kind.with_loc(begin..end)
+                    attrs.into_iter().rev().fold(kind, |kind, attr| {
+                        Box::new(K::Attr(kind, attr)).with_loc(begin..end)
+                    })
                 };
 
                 match kindseq {
@@ -2763,6 +3186,7 @@ impl<'a> Parser<'a> {
 
     fn parse_kailua_kind_after_kind(&mut self, begin: Pos,
                                     kind: Spanned<Kind>) -> Result<Spanned<Kind>> {
+        let kind = self.parse_kailua_intersection_after_kind(begin, kind)?;
         if self.lookahead(Punct::Pipe) { // A | B | ...
             // TODO the current parser is massively ambiguous about pipes in atomic types
             let mut kinds = vec![kind];
@@ -2770,6 +3194,7 @@ impl<'a> Parser<'a> {
                 let begin = self.pos();
                 match self.try_parse_kailua_prefixed_kind_seq()? {
                     Some(AtomicKind::One(kind2)) => {
+                        let kind2 = self.parse_kailua_intersection_after_kind(begin, kind2)?;
                         kinds.push(kind2);
                     }
                     Some(AtomicKind::Seq(..)) => {
@@ -2789,9 +3214,72 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // true if the next tokens are `&` followed by given keyword (`function` or `method`),
+    // which continues the overloaded function type
+    fn lookahead_overload(&mut self, keyword: Keyword) -> bool {
+        if !self.lookahead(Punct::Amp) {
+            return false;
+        }
+        // `unread` cannot fully restore the last span otherwise
+        let (last_idx2, last_span2) = (self.last_idx2, self.last_span2);
+        let amp = self.read();
+        let overload = self.lookahead(keyword);
+        self.unread(amp);
+        self.last_idx2 = last_idx2;
+        self.last_span2 = last_span2;
+        overload
+    }
+
+    fn lookahead_intersection(&mut self) -> bool {
+        if self.in_func_returns {
+            // `function() --> A & function() --> B` is an overloaded function,
+            // not a function returning an intersection
+            self.lookahead(Punct::Amp) &&
+                !self.lookahead_overload(Keyword::Function) &&
+                !self.lookahead_overload(Keyword::Method)
+        } else {
+            self.lookahead(Punct::Amp)
+        }
+    }
+
+    fn parse_kailua_intersection_after_kind(&mut self, begin: Pos,
+                                            kind: Spanned<Kind>) -> Result<Spanned<Kind>> {
+        if self.lookahead_intersection() { // A & B & ...
+            let mut kinds = vec![kind];
+            while self.lookahead_intersection() {
+                self.expect(Punct::Amp)?;
+                let begin = self.pos();
+                match self.try_parse_kailua_prefixed_kind_seq()? {
+                    Some(AtomicKind::One(kind2)) => {
+                        kinds.push(kind2);
+                    }
+                    Some(AtomicKind::Seq(..)) => {
+                        self.error(begin..self.last_pos(), m::NoTypeSeqInIntersection {})
+                            .done()?;
+                        kinds.push(Recover::recover());
+                    }
+                    None => {
+                        error_with!(self, m::NoType);
+                        break;
+                    }
+                }
+            }
+            Ok(Box::new(K::Intersection(kinds)).with_loc(begin..self.last_pos()))
+        } else {
+            Ok(kind)
+        }
+    }
+
     fn try_parse_kailua_kind_seq(&mut self) -> Result<Option<Spanned<Seq<Spanned<Kind>>>>> {
         let begin = self.pos();
-        match self.try_parse_kailua_prefixed_kind_seq()? {
+        let kindseq = self.try_parse_kailua_prefixed_kind_seq()?;
+        self.try_parse_kailua_kind_seq_after_prefixed(begin, kindseq)
+    }
+
+    fn try_parse_kailua_kind_seq_after_prefixed(&mut self, begin: Pos,
+                                                kindseq: Option<AtomicKind>)
+            -> Result<Option<Spanned<Seq<Spanned<Kind>>>>> {
+        match kindseq {
             None => Ok(None),
             Some(AtomicKind::Seq(kindseq)) => {
                 Ok(Some(kindseq.with_loc(begin..self.last_pos())))
@@ -2805,7 +3293,14 @@ impl<'a> Parser<'a> {
     }
 
     fn try_parse_kailua_kind(&mut self) -> Result<Option<Spanned<Kind>>> {
-        if let Some(mut kindseq) = self.try_parse_kailua_kind_seq()? {
+        let begin = self.pos();
+        let kindseq = self.try_parse_kailua_prefixed_kind_seq()?;
+        self.try_parse_kailua_kind_after_prefixed(begin, kindseq)
+    }
+
+    fn try_parse_kailua_kind_after_prefixed(&mut self, begin: Pos, kindseq: Option<AtomicKind>)
+            -> Result<Option<Spanned<Kind>>> {
+        if let Some(mut kindseq) = self.try_parse_kailua_kind_seq_after_prefixed(begin, kindseq)? {
             if kindseq.base.head.len() == 1 && kindseq.base.tail.is_none() {
                 let first = kindseq.base.head.pop().unwrap();
                 let span = kindseq.span | first.span; // overwrite the span
@@ -2841,8 +3336,10 @@ impl<'a> Parser<'a> {
         match_next! { self;
             Tok::Punct(Punct::Bang) in span => Ok(Returns::Never(span));
             'unread: _ => {
-                let seq = self.parse_kailua_kind_seq()?;
-                Ok(Returns::Seq(seq))
+                let in_func_returns = mem::replace(&mut self.in_func_returns, true);
+                let seq = self.parse_kailua_kind_seq();
+                self.in_func_returns = in_func_returns;
+                Ok(Returns::Seq(seq?))
             };
         }
     }
@@ -3075,8 +3572,8 @@ impl<'a> Parser<'a> {
 
     // assume [global] NAME ":" MODF KIND
     // assume [static] NAME {"." NAME} ":" MODF KIND
-    // assume NAME {"." NAME} ":" MODF "method" ...
-    // assume [global] class ["(" NAME ")"] NAME [":" NAME] ["=" MODF]
+    // assume NAME {"." NAME} ":" MODF [ATTR] "method" ...
+    // assume [global] class ["(" NAME ")"] NAME [":" NAME {"," NAME}]
     //
     // returns a sibling scope if created.
     fn try_parse_kailua_assume(&mut self) -> Result<(Stmt, Option<Scope>)> {
@@ -3086,7 +3583,7 @@ impl<'a> Parser<'a> {
         #[derive(Clone, Debug)]
         enum Kindlike {
             Kind(Spanned<Kind>),
-            Method(Span, Option<Spanned<FuncKind>>),
+            Method(Span, Vec<Spanned<Attr>>, Option<Vec<Spanned<FuncKind>>>),
         }
 
         let scopebegin = self.pos();
@@ -3108,13 +3605,16 @@ impl<'a> Parser<'a> {
                 None
             };
             let classname = self.parse_name()?;
-            let parenttype = if self.may_expect(Punct::Colon) {
+            let mut parenttypes = Vec::new();
+            if self.may_expect(Punct::Colon) {
                 self.recover_upto_with(|parser| {
-                    parser.parse_name().map(|n| Some(n.map(|n| n.name)))
-                }, || None)?
-            } else {
-                None
-            };
+                    parenttypes.push(parser.parse_name()?.map(|n| n.name));
+                    while parser.may_expect(Punct::Comma) {
+                        parenttypes.push(parser.parse_name()?.map(|n| n.name));
+                    }
+                    Ok(())
+                }, || ())?;
+            }
 
             // ignore `static`
             if scope.base == Scope::Static {
@@ -3124,7 +3624,7 @@ impl<'a> Parser<'a> {
             let (renameref, sibling_scope) =
                 self.resolve_kailua_assume_rename(scope.base == Scope::Global,
                                                   scope.span, classname, false)?;
-            Ok((Box::new(St::KailuaAssumeClass(system, renameref, parenttype, sibling_scope)),
+            Ok((Box::new(St::KailuaAssumeClass(system, renameref, parenttypes, sibling_scope)),
                 sibling_scope))
         } else {
             let namesbegin = self.pos();
@@ -3139,14 +3639,32 @@ impl<'a> Parser<'a> {
             self.expect(Punct::Colon)?;
             let modf = self.parse_kailua_modf()?.base;
             let kindbegin = self.pos();
+            let attrs = self.try_parse_kailua_attrs()?;
             let kind = if self.may_expect(Keyword::Method) {
+                // method(...) --> ... {`&` method(...) --> ...}
                 let funckind = self.recover_upto_with(|p| {
-                    p.parse_kailua_funckind().map(Some)
+                    let mut funckinds = vec![p.parse_kailua_funckind()?];
+                    while p.lookahead_overload(Keyword::Method) {
+                        p.expect(Punct::Amp)?;
+                        p.expect(Keyword::Method)?;
+                        funckinds.push(p.parse_kailua_funckind()?);
+                    }
+                    Ok(Some(funckinds))
                 }, || None)?;
                 // if the parsing fails later, we need a span to construct K::Func
-                Kindlike::Method(Span::new(kindbegin, self.last_pos()), funckind)
+                Kindlike::Method(Span::new(kindbegin, self.last_pos()), attrs, funckind)
             } else {
-                Kindlike::Kind(self.recover_upto(Self::parse_kailua_kind)?)
+                // attributes are not for `method`, so they should be a part of the type
+                Kindlike::Kind(self.recover_upto(|p| {
+                    let kindseq = p.try_parse_kailua_kind_seq_after_attrs(kindbegin, attrs)?;
+                    if let Some(kind) = p.try_parse_kailua_kind_after_prefixed(kindbegin,
+                                                                               kindseq)? {
+                        Ok(kind)
+                    } else {
+                        error_with!(p, m::NoSingleType);
+                        Err(Stop::Recover)
+                    }
+                })?)
             };
 
             if names.is_empty() {
@@ -3154,11 +3672,20 @@ impl<'a> Parser<'a> {
                 // assume that it is a typo of function()
                 let kind = match kind {
                     Kindlike::Kind(kind) => kind,
-                    Kindlike::Method(kindspan, funckind) => {
+                    Kindlike::Method(kindspan, _attrs, funckind) => {
                         self.error(kindspan, m::AssumeMethodToNonInstanceField {})
                               .done()?;
-                        if let Some(funckind) = funckind {
-                            Box::new(K::Func(funckind)).with_loc(kindspan)
+                        if let Some(mut funckinds) = funckind {
+                            if funckinds.len() == 1 {
+                                let funckind = funckinds.pop().unwrap();
+                                Box::new(K::Func(funckind)).with_loc(kindspan)
+                            } else {
+                                let kinds = funckinds.into_iter().map(|funckind| {
+                                    let span = funckind.span;
+                                    Box::new(K::Func(funckind)).with_loc(span)
+                                }).collect();
+                                Box::new(K::Intersection(kinds)).with_loc(kindspan)
+                            }
                         } else {
                             Kind::recover().with_loc(kindspan)
                         }
@@ -3194,12 +3721,12 @@ impl<'a> Parser<'a> {
                 let names = (rootname, names).with_loc(namesbegin..namesend);
                 let st = match kind {
                     Kindlike::Kind(kind) => St::KailuaAssumeField(is_static, names, modf, kind),
-                    Kindlike::Method(kindspan, funckind) =>{
+                    Kindlike::Method(kindspan, attrs, funckind) =>{
                         if scope.base != Scope::Implied {
                             self.error(kindspan, m::AssumeMethodToNonInstanceField {}).done()?;
                         }
-                        if let Some(funckind) = funckind {
-                            St::KailuaAssumeMethod(names, modf, funckind)
+                        if let Some(funckinds) = funckind {
+                            St::KailuaAssumeMethod(names, modf, attrs, funckinds)
                         } else {
                             St::KailuaAssumeField(is_static, names, modf,
                                                   Kind::recover().without_loc())
@@ -3211,6 +3738,76 @@ impl<'a> Parser<'a> {
         }
     }
 
+    // we have already read up to the opening brace
+    fn parse_kailua_interface_body(&mut self)
+        -> Result<Vec<(Spanned<Str>, Spanned<MemberKind>)>>
+    {
+        let mut seen = HashMap::new(); // value denotes the first span
+        let (_, members) = self.scan_tabular_body(false, |parser| {
+            let name = parser.parse_name()?;
+            match seen.entry(name.base.name.clone()) {
+                hash_map::Entry::Occupied(e) => {
+                    parser.error(name.span,
+                                 m::DuplicateMemberNameInInterface { name: &name.base })
+                          .note(*e.get(), m::FirstMemberNameInInterface {})
+                          .done()?;
+                }
+                hash_map::Entry::Vacant(e) => {
+                    e.insert(name.span);
+                }
+            }
+            let name = Str::from(name.base.name).with_loc(name.span);
+            parser.expect(Punct::Colon)?;
+
+            let begin = parser.pos();
+            let member = if parser.may_expect(Keyword::Method) {
+                let funckind = parser.parse_kailua_funckind()?;
+                MemberKind::Method(funckind.base)
+            } else {
+                let slotkind = parser.parse_kailua_slotkind()?;
+                MemberKind::Field(slotkind.base)
+            };
+            Ok((name, member.with_loc(begin..parser.last_pos())))
+        })?;
+        Ok(members)
+    }
+
+    // STRING {"|" STRING}, which is converted to a union of string literals
+    fn parse_kailua_enum_body(&mut self) -> Result<Spanned<Kind>> {
+        let begin = self.pos();
+        let mut seen = HashMap::new(); // value denotes the first span
+        let mut kinds = Vec::new();
+        loop {
+            let s = match_next! { self;
+                Tok::Str(s) in span => s.with_loc(span);
+                'unread: _ => {
+                    error_with!(self, m::NoStringInEnum);
+                    return Err(Stop::Recover);
+                };
+            };
+            match seen.entry(s.base.clone()) {
+                hash_map::Entry::Occupied(e) => {
+                    self.error(s.span, m::DuplicateStringInEnum { s: &s.base })
+                        .note(*e.get(), m::FirstStringInEnum {})
+                        .done()?;
+                }
+                hash_map::Entry::Vacant(e) => {
+                    e.insert(s.span);
+                }
+            }
+            kinds.push(Box::new(K::StringLit(s.base)).with_loc(s.span));
+            if !self.may_expect(Punct::Pipe) {
+                break;
+            }
+        }
+
+        if kinds.len() == 1 {
+            Ok(kinds.pop().unwrap())
+        } else {
+            Ok(Box::new(K::Union(kinds)).with_loc(begin..self.last_pos()))
+        }
+    }
+
     fn try_parse_kailua_spec(&mut self) -> Result<Option<Option<Spanned<Stmt>>>> {
         trace!("parsing kailua spec");
         let begin = self.pos();
@@ -3228,20 +3825,40 @@ impl<'a> Parser<'a> {
                         Some(stmt)
                     };
 
-                    // class system ...
+                    // class system NAME {ATTR}
                     Tok::Keyword(Keyword::Class) => {
                         parser.expect(FixedName("system"))?;
                         let name = parser.parse_name()?;
-                        Some(Box::new(St::KailuaClassSystem(name.map(|n| n.name))))
+                        let mut attrs = Vec::new();
+                        while let Some(attr) = parser.try_parse_kailua_attr()? {
+                            attrs.push(attr);
+                        }
+                        Some(Box::new(St::KailuaClassSystem(name.map(|n| n.name), attrs)))
                     };
 
                     // open NAME
                     Tok::Keyword(Keyword::Open) => {
                         let name = parser.parse_name()?;
-                        // TODO also set the parser option
+                        // opening a whole standard library also switches the language
+                        // (this should be done before reading any further token)
+                        if let Some(language) = parser.language.with_lua_name(&name.name) {
+                            parser.language = language;
+                        }
                         Some(Box::new(St::KailuaOpen(name.map(|n| n.name))))
                     };
 
+                    // lang NAME
+                    Tok::Keyword(Keyword::Lang) => {
+                        let name = parser.parse_name()?;
+                        // same to `open` but only switches the language (thus no statement)
+                        if let Some(language) = parser.language.with_lua_name(&name.name) {
+                            parser.language = language;
+                        } else {
+                            parser.error(&name, m::UnknownLanguage { name: &name.name }).done()?;
+                        }
+                        None
+                    };
+
                     // type [local | global] NAME = KIND
                     Tok::Keyword(Keyword::Type) => {
                         let typescope = if parser.may_expect(Keyword::Local) {
@@ -3253,6 +3870,7 @@ impl<'a> Parser<'a> {
                         };
 
                         let name = parser.parse_name()?;
+                        let tparams = parser.try_parse_kailua_type_params()?;
                         parser.expect(Punct::Eq)?;
                         let kind = parser.recover_upto(Self::parse_kailua_kind)?;
 
@@ -3275,7 +3893,128 @@ impl<'a> Parser<'a> {
                             }
                         }
 
-                        Some(Box::new(St::KailuaType(typescope, name.map(|n| n.name), kind)))
+                        Some(Box::new(St::KailuaType(typescope, name.map(|n| n.name),
+                                                     tparams, kind)))
+                    };
+
+                    // enum [local | global] [ATTR] NAME = STRING {"|" STRING}
+                    Tok::Keyword(Keyword::Enum) => {
+                        let typescope = if parser.may_expect(Keyword::Local) {
+                            TypeScope::Local
+                        } else if parser.may_expect(Keyword::Global) {
+                            TypeScope::Global
+                        } else {
+                            TypeScope::Exported
+                        };
+
+                        let attr = parser.try_parse_kailua_attr()?;
+                        let name = parser.parse_name()?;
+                        parser.expect(Punct::Eq)?;
+                        let mut kind = parser.recover_upto(Self::parse_kailua_enum_body)?;
+                        if let Some(attr) = attr {
+                            let span = attr.span | kind.span;
+                            kind = Box::new(K::Attr(kind, attr)).with_loc(span);
+                        }
+
+                        // forbid overriding builtin types
+                        if parser.builtin_kind(&*name.base.name).is_some() {
+                            parser.error(name.span, m::CannotRedefineBuiltin {}).done()?;
+                        }
+
+                        // error on module-level enum definitions in the local scope
+                        let end = parser.last_pos();
+                        if parser.block_depth != 0 {
+                            match typescope {
+                                TypeScope::Local => {}
+                                TypeScope::Global => {
+                                    parser.error(begin..end, m::EnumGlobalInLocalScope {}).done()?;
+                                }
+                                TypeScope::Exported => {
+                                    parser.error(begin..end, m::EnumExportInLocalScope {}).done()?;
+                                }
+                            }
+                        }
+
+                        // an enum is a type alias to the union of string literals
+                        Some(Box::new(St::KailuaType(typescope, name.map(|n| n.name),
+                                                     Vec::new(), kind)))
+                    };
+
+                    // newtype [local | global] NAME = KIND
+                    Tok::Keyword(Keyword::Newtype) => {
+                        let typescope = if parser.may_expect(Keyword::Local) {
+                            TypeScope::Local
+                        } else if parser.may_expect(Keyword::Global) {
+                            TypeScope::Global
+                        } else {
+                            TypeScope::Exported
+                        };
+
+                        let name = parser.parse_name()?;
+                        parser.expect(Punct::Eq)?;
+                        let kind = parser.recover_upto(Self::parse_kailua_kind)?;
+
+                        // forbid overriding builtin types
+                        if parser.builtin_kind(&*name.base.name).is_some() {
+                            parser.error(name.span, m::CannotRedefineBuiltin {}).done()?;
+                        }
+
+                        // error on module-level newtype definitions in the local scope
+                        let end = parser.last_pos();
+                        if parser.block_depth != 0 {
+                            match typescope {
+                                TypeScope::Local => {}
+                                TypeScope::Global => {
+                                    parser.error(begin..end, m::NewtypeGlobalInLocalScope {})
+                                          .done()?;
+                                }
+                                TypeScope::Exported => {
+                                    parser.error(begin..end, m::NewtypeExportInLocalScope {})
+                                          .done()?;
+                                }
+                            }
+                        }
+
+                        Some(Box::new(St::KailuaNewtype(typescope, name.map(|n| n.name), kind)))
+                    };
+
+                    // interface [local | global] NAME "{" NAME ":" MEMBER {"," ...} "}"
+                    Tok::Keyword(Keyword::Interface) => {
+                        let typescope = if parser.may_expect(Keyword::Local) {
+                            TypeScope::Local
+                        } else if parser.may_expect(Keyword::Global) {
+                            TypeScope::Global
+                        } else {
+                            TypeScope::Exported
+                        };
+
+                        let name = parser.parse_name()?;
+                        parser.expect(Punct::LBrace)?;
+                        let members = parser.parse_kailua_interface_body()?;
+
+                        // forbid overriding builtin types
+                        if parser.builtin_kind(&*name.base.name).is_some() {
+                            parser.error(name.span, m::CannotRedefineBuiltin {}).done()?;
+                        }
+
+                        // error on module-level interface definitions in the local scope
+                        let end = parser.last_pos();
+                        if parser.block_depth != 0 {
+                            match typescope {
+                                TypeScope::Local => {}
+                                TypeScope::Global => {
+                                    parser.error(begin..end, m::InterfaceGlobalInLocalScope {})
+                                          .done()?;
+                                }
+                                TypeScope::Exported => {
+                                    parser.error(begin..end, m::InterfaceExportInLocalScope {})
+                                          .done()?;
+                                }
+                            }
+                        }
+
+                        Some(Box::new(St::KailuaInterface(typescope, name.map(|n| n.name),
+                                                          members)))
                     };
 
                     'unread: _ => None; // empty `--#` is valid
@@ -3313,7 +4052,7 @@ impl<'a> Parser<'a> {
         // (unlike normal cases of `pop_scope_upto`, as this might be past EOF)
         let end = self.last_pos();
         while let Some((scope, scopebegin)) = self.scope_stack.pop() {
-            self.scope_map.set_span(scope.with_loc(scopebegin..end));
+            self.set_scope_span(scope, Span::from(scopebegin..end));
         }
 
         if let Ok(block) = ret {
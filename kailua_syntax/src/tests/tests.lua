@@ -816,9 +816,31 @@ local c = a
 --! [KailuaAssumeField(false, (`a`_.`b`), _, Oops)]
 
 --8<-- assume-field-method-recover-2
---# assume a.b: [strange] method() --@< Error: Expected a single type, got a keyword `method`
+--# assume a.b: [strange] method(x: string --@<-v Error: Expected `)`, got a newline
 --! [KailuaAssumeField(false, (`a`_.`b`), _, Oops)]
 
+--8<-- assume-field-method-attr
+--# assume a.b: [abstract] method(x: string) --> boolean
+--# assume a.c: const [abstract] method(WHATEVER)
+--! [KailuaAssumeMethod((`a`_.`b`), _, [`abstract`] (`x`: String) --> Boolean), \
+--!  KailuaAssumeMethod((`a`_.`c`), Const, [`abstract`] (Dynamic) --> ())]
+
+--8<-- assume-field-method-multi-attr
+--# assume a.b: [abstract] [protected] method()
+--! [KailuaAssumeMethod((`a`_.`b`), _, [`abstract`] [`protected`] () --> ())]
+
+--8<-- assume-field-attr
+--# assume a.b: [type] function(any) --> string
+--# assume a.c: [exhaustive] string | integer
+--# assume d: [exhaustive] string | integer
+--! [KailuaAssumeField(false, (`a`_.`b`), _, [`type`] Func((Any) --> String)), \
+--!  KailuaAssumeField(false, (`a`_.`c`), _, Union([[`exhaustive`] String, Integer])), \
+--!  KailuaAssume(`d`_ => `d`$1, _, Union([[`exhaustive`] String, Integer]))$1]
+
+--8<-- assume-field-method-overload
+--# assume a.b: method(x: string) --> boolean & method(x: integer) --> string
+--! [KailuaAssumeMethod((`a`_.`b`), _, (`x`: String) --> Boolean & (`x`: Integer) --> String)]
+
 --8<-- assume-field-method-static
 --# assume static a.b: method(x: string, y: integer?) --> boolean
 --@^ Error: `method(...) --> ...` type is only available when using `--# assume` to a non-static field
@@ -849,41 +871,41 @@ local c = a
 local A
 --# assume class A
 --! [Local([`A`$1], [])$1, \
---!  KailuaAssumeClass(None, `A`$1 => `A`$2, None)$2]
+--!  KailuaAssumeClass(None, `A`$1 => `A`$2, [])$2]
 
 --8<-- assume-class-local-shadowing
 --# assume class A --@< Error: `--# assume` tried to shadow a globally defined variable `A`
---! [KailuaAssumeClass(None, `A`_ => `A`$1, None)$1]
+--! [KailuaAssumeClass(None, `A`_ => `A`$1, [])$1]
 
 --8<-- assume-class-global
 local A
 --# assume global class A --@< Error: `--# assume` directive tried to set a global variable `A`, but it was shadowed by a local variable of the same name
 --! [Local([`A`$1], [])$1, \
---!  KailuaAssumeClass(None, `A`_ => `A`_, None)]
+--!  KailuaAssumeClass(None, `A`_ => `A`_, [])]
 
 --8<-- assume-class-global-shadowing
 --# assume global class A
---! [KailuaAssumeClass(None, `A`_ => `A`_, None)]
+--! [KailuaAssumeClass(None, `A`_ => `A`_, [])]
 
 --8<-- assume-class-for
 local A
 --# assume class(something) A
 --# assume global class(another) B
 --! [Local([`A`$1], [])$1, \
---!  KailuaAssumeClass(Some(`something`), `A`$1 => `A`$2, None)$2, \
---!  KailuaAssumeClass(Some(`another`), `B`_ => `B`_, None)]
+--!  KailuaAssumeClass(Some(`something`), `A`$1 => `A`$2, [])$2, \
+--!  KailuaAssumeClass(Some(`another`), `B`_ => `B`_, [])]
 
 --8<-- assume-class-for-recover-1
 --# assume global class() A --@< Error: Expected a name, got `)`
---! [KailuaAssumeClass(None, `A`_ => `A`_, None)]
+--! [KailuaAssumeClass(None, `A`_ => `A`_, [])]
 
 --8<-- assume-class-for-recover-2
 --# assume global class(not a name) A --@< Error: Expected a name, got a keyword `not`
---! [KailuaAssumeClass(None, `A`_ => `A`_, None)]
+--! [KailuaAssumeClass(None, `A`_ => `A`_, [])]
 
 --8<-- assume-class-for-recover-3
 --# assume global class(really?) A --@< Error: Expected `)`, got `?`
---! [KailuaAssumeClass(None, `A`_ => `A`_, None)]
+--! [KailuaAssumeClass(None, `A`_ => `A`_, [])]
 
 --8<-- assume-class-for-recover-4
                          --@v-vv Error: Expected a name, got a newline
@@ -893,12 +915,22 @@ x = 42
 
 --8<-- assume-class-inherit
 --# assume global class A: B
---! [KailuaAssumeClass(None, `A`_ => `A`_, Some(`B`))]
+--! [KailuaAssumeClass(None, `A`_ => `A`_, [`B`])]
+
+--8<-- assume-class-inherit-multi
+--# assume global class A: B, C
+--! [KailuaAssumeClass(None, `A`_ => `A`_, [`B`, `C`])]
+
+--8<-- assume-class-inherit-multi-recover
+--# assume global class A: B, --@<-v Error: Expected a name, got a newline
+x = y
+--! [KailuaAssumeClass(None, `A`_ => `A`_, [`B`]), \
+--!  Assign([`x`_], [`y`_])]
 
 --8<-- assume-class-inherit-recover
 --# assume global class A: --@<-v Error: Expected a name, got a newline
 x = 42
---! [KailuaAssumeClass(None, `A`_ => `A`_, None), \
+--! [KailuaAssumeClass(None, `A`_ => `A`_, []), \
 --!  Assign([`x`_], [42])]
 
 --8<-- class-system
@@ -907,6 +939,10 @@ x = 42
 --! [KailuaClassSystem(`foo`), \
 --!  KailuaClassSystem(`bar`)]
 
+--8<-- class-system-rules
+--# class system foo [constructor(create)] [inheritance(multiple)] [bar]
+--! [KailuaClassSystem(`foo`, [`constructor`(`create`)], [`inheritance`(`multiple`)], [`bar`])]
+
 --8<-- class-system-missing-name
 --# class system --@<-v Error: Expected a name, got a newline
 x = 42
@@ -930,6 +966,26 @@ local x --: {b:string, a:integer, c:const {const {}}}
 --! [Local([`x`$1: _ Record(["b": _ String, "a": _ Integer, \
 --!                          "c": Const Tuple([Const EmptyTable])])], [])$1]
 
+--8<-- kind-readonly-table
+local x --: readonly {a: integer}
+--! [Local([`x`$1: _ Readonly(Record(["a": _ Integer]))], [])$1]
+
+--8<-- kind-readonly-vector
+local x --: readonly vector<integer>
+--! [Local([`x`$1: _ Readonly(Array(_ Integer))], [])$1]
+
+--8<-- kind-readonly-opt
+local x --: readonly {a: integer}?
+--! [Local([`x`$1: _ Readonly(Record(["a": _ Integer])?)], [])$1]
+
+--8<-- kind-readonly-seq
+local x --: readonly (integer, string) --@< Error: A sequence of types cannot be `readonly`
+--! [Local([`x`$1: _ Oops], [])$1]
+
+--8<-- kind-readonly-recover
+local x --: readonly --@<-v Error: Expected a type, got a newline
+--! [Local([`x`$1: _ Oops], [])$1]
+
 --8<-- kind-table-old-recover
 local x --: {b=string, a=integer, c=const {const {}}} --@< Error: Expected `,`, `;` or `}`, got `=`
 --! [Local([`x`$1: _ Tuple([_ `b`])], [])$1]
@@ -1017,6 +1073,31 @@ local x --: function(x: string, --@< Note: The first duplicate appeared here
 local x --: function(`some arg here`: any)
 --! [Local([`x`$1: _ Func((`some arg here`: Any) --> ())], [])$1]
 
+--8<-- kind-func-generic
+local x --: function<T>(vector<T>) --> T
+--! [Local([`x`$1: _ Func(<`T`>(Array(_ `T`)) --> `T`)], [])$1]
+
+--8<-- kind-func-generic-2
+local x --: function<K, V>(t: map<K, V>, k: K) --> V?
+--! [Local([`x`$1: _ Func(<`K`, `V`>(`t`: Map(`K`, _ `V`), `k`: `K`) --> `V`?)], [])$1]
+
+--8<-- kind-func-generic-nested
+local x --: function<T>(function<U>(U) --> T) --> T
+--! [Local([`x`$1: _ Func(<`T`>(Func(<`U`>(`U`) --> `T`)) --> `T`)], [])$1]
+
+--8<-- kind-func-generic-dup
+local x --: function<T, --@< Note: The first duplicate appeared here
+        --:          T>(T) --@< Error: Duplicate type parameter `T` in the type specification
+--! [Local([`x`$1: _ Func(<`T`>(`T`) --> ())], [])$1]
+
+--8<-- kind-func-generic-empty
+local x --: function<>() --@< Error: Expected a name, got `>`
+--! [Local([`x`$1: _ Oops], [])$1]
+
+--8<-- kind-func-generic-no-args
+local x --: function<T> --@<-v Error: Expected `(`, got a newline
+--! [Local([`x`$1: _ Oops], [])$1]
+
 --8<-- kind-func-named-args-varargs
 local x --: function(a: any, any...)
 local y --: function(a: any...) --@< Error: Variadic arguments cannot have a name
@@ -1280,6 +1361,50 @@ local z --: map<integer, string>
 local x --: map<const integer, string> --@< Error: The first type parameter of `map` type cannot have modifiers
 --! [Local([`x`$1: _ Map(Integer, _ String)], [])$1]
 
+--8<-- kind-thread-params
+local x --: thread<integer, string, boolean>
+--! [Local([`x`$1: _ Thread<Integer, String, Boolean>], [])$1]
+
+--8<-- kind-thread-params-seq
+local x --: thread<(), (integer, string...), vector<integer>>
+--! [Local([`x`$1: _ Thread<(), (Integer, String...), Array(_ Integer)>], [])$1]
+
+--8<-- kind-thread-params-union
+local x --: thread<integer|string, string?, ()>
+--! [Local([`x`$1: _ Thread<Union([Integer, String]), String?, ()>], [])$1]
+
+--8<-- kind-thread-params-recover
+local x --: thread<integer, string> --@< Error: `thread` type with type parameters needs three of them (argument, yield and return types)
+local y --: thread<integer, string, boolean>
+--! [Local([`x`$1: _ Oops], [])$1, \
+--!  Local([`y`$2: _ Thread<Integer, String, Boolean>], [])$2]
+
+--8<-- kind-intersection
+local x --: A & B & C
+--! [Local([`x`$1: _ Intersection([`A`, `B`, `C`])], [])$1]
+
+--8<-- kind-intersection-union
+local x --: A & B | C
+local y --: A | B & C?
+--! [Local([`x`$1: _ Union([Intersection([`A`, `B`]), `C`])], [])$1, \
+--!  Local([`y`$2: _ Union([`A`, Intersection([`B`, `C`?])])], [])$2]
+
+--8<-- kind-intersection-seq
+local x --: A & (B, C) --@< Error: A sequence of types cannot be inside an intersection
+--! [Local([`x`$1: _ Intersection([`A`, Oops])], [])$1]
+
+--8<-- kind-func-overload
+local x --: function(string) --> integer & function(integer) --> string
+--! [Local([`x`$1: _ Intersection([Func((String) --> Integer), Func((Integer) --> String)])], [])$1]
+
+--8<-- kind-func-overload-returns-intersection
+local x --: function() --> A & B & function(integer)
+--! [Local([`x`$1: _ Intersection([Func(() --> Intersection([`A`, `B`])), Func((Integer) --> ())])], [])$1]
+
+--8<-- kind-func-overload-paren
+local x --: (function() --> A) & B
+--! [Local([`x`$1: _ Intersection([Func(() --> `A`), `B`])], [])$1]
+
 --8<-- kind-nested-table
 local x --: map<integer, const vector<map<string, {integer, integer}?>>>
 --! [Local([`x`$1: _ Map(Integer, \
@@ -1339,9 +1464,9 @@ local w --: [builtin(wtf, omg, bbq,)] string --@< Error: Expected a parameter va
 local x --: [type] function(any)
 --! [Local([`x`$1: _ [`type`] Func((Any) --> ())], [])$1]
 
---8<-- kind-attr-dup
-local x --: [builtin] [builtin] string --@< Error: Expected a newline, got `[`
---! [Local([`x`$1: _ Oops], [])$1]
+--8<-- kind-attr-multi
+local x --: [builtin] [builtin] string
+--! [Local([`x`$1: _ [`builtin`] [`builtin`] String], [])$1]
 
 --8<-- kind-attr-seq
 local x --: function() --> [builtin] (string, string)
@@ -2238,6 +2363,137 @@ do
 end
 --! [Do([KailuaType(Local, `Int`, Integer)])]
 
+--8<-- alias-generic
+--# type Result<T> = {ok: boolean, value: T?}
+--# type local Pair<A, B> = {A, B}
+--# assume x: Result<vector<integer>>
+--# assume y: Pair<string, Result<integer>>
+--! [KailuaType(Exported, `Result`<`T`>, Record(["ok": _ Boolean, "value": _ `T`?])), \
+--!  KailuaType(Local, `Pair`<`A`, `B`>, Tuple([_ `A`, _ `B`])), \
+--!  KailuaAssume(`x`_ => `x`$1, _, `Result`<Array(_ Integer)>)$1, \
+--!  KailuaAssume(`y`_ => `y`$2, _, `Pair`<String, `Result`<Integer>>)$2]
+
+--8<-- alias-generic-dup
+--# type Pair<A, --@< Note: The first duplicate appeared here
+--#           A> = {A, A} --@< Error: Duplicate type parameter `A` in the type specification
+--! [KailuaType(Exported, `Pair`<`A`>, Tuple([_ `A`, _ `A`]))]
+
+--8<-- alias-generic-no-params
+--# type Result<> = {} --@< Error: Expected a name, got `>`
+--! [Oops]
+
+--8<-- alias-generic-args-modf
+--# assume x: Result<const integer> --@< Error: Type arguments to the generic type cannot have modifiers
+--! [KailuaAssume(`x`_ => `x`$1, _, `Result`<Integer>)$1]
+
+--8<-- alias-generic-args-optional
+--# assume x: Result<integer>?
+--! [KailuaAssume(`x`_ => `x`$1, _, `Result`<Integer>?)$1]
+
+--8<-- interface
+--# interface Drawable { pos: {x: number, y: number}, draw: method() }
+--# interface local Named { name: const string, rename: method(string) --> boolean }
+--# interface global Empty {}
+--! [KailuaInterface(Exported, `Drawable`, \
+--!                  ["pos": _ Record(["x": _ Number, "y": _ Number]), \
+--!                   "draw": Method(() --> ())]), \
+--!  KailuaInterface(Local, `Named`, \
+--!                  ["name": Const String, "rename": Method((String) --> Boolean)]), \
+--!  KailuaInterface(Global, `Empty`, [])]
+
+--8<-- interface-multiline
+--# interface Shape {
+--#     area: method() --> number,
+--#     name: string,
+--# }
+--! [KailuaInterface(Exported, `Shape`, ["area": Method(() --> Number), "name": _ String])]
+
+--8<-- interface-duplicate-name
+--# interface Shape { area: number, --@< Note: The first duplicate appeared here
+--#                   area: method() --> number } --@< Error: Duplicate interface member `area`
+--! [KailuaInterface(Exported, `Shape`, ["area": _ Number, "area": Method(() --> Number)])]
+
+--8<-- interface-builtin
+--# interface string { len: integer } --@< Error: Cannot redefine a builtin type
+--! [KailuaInterface(Exported, `string`, ["len": _ Integer])]
+
+--8<-- interface-export-in-local-scope
+do
+    --# interface Shape { name: string } --@< Error: `--# interface` with an exported type should be in the top-level scope
+end
+--! [Do([KailuaInterface(Exported, `Shape`, ["name": _ String])])]
+
+--8<-- interface-global-in-local-scope
+do
+    --# interface global Shape { name: string } --@< Error: `--# interface global` should be in the top-level scope
+end
+--! [Do([KailuaInterface(Global, `Shape`, ["name": _ String])])]
+
+--8<-- enum
+--# enum Dir = "north" | "south" | 'east' | 'west'
+--# enum local One = "one"
+--! [KailuaType(Exported, `Dir`, \
+--!             Union([String("north"), String("south"), String("east"), String("west")])), \
+--!  KailuaType(Local, `One`, String("one"))]
+
+--8<-- enum-attr
+--# enum global [exhaustive] Dir = "north" | "south"
+--! [KailuaType(Global, `Dir`, [`exhaustive`] Union([String("north"), String("south")]))]
+
+--8<-- enum-non-string
+--# enum Dir = "north" | south --@< Error: Expected a string literal in the enum, got a name
+--! [KailuaType(Exported, `Dir`, Oops)]
+
+--8<-- enum-duplicate
+--# enum Dir = "north" | "south" | "north"
+--@^ Error: Duplicate enum string "north"
+--@^^ Note: The first duplicate appeared here
+--! [KailuaType(Exported, `Dir`, Union([String("north"), String("south"), String("north")]))]
+
+--8<-- enum-builtin
+--# enum string = "a" | "b" --@< Error: Cannot redefine a builtin type
+--! [KailuaType(Exported, `string`, Union([String("a"), String("b")]))]
+
+--8<-- enum-export-in-local-scope
+do
+    --# enum Dir = "north" | "south" --@< Error: `--# enum` with an exported type should be in the top-level scope
+end
+--! [Do([KailuaType(Exported, `Dir`, Union([String("north"), String("south")]))])]
+
+--8<-- enum-global-in-local-scope
+do
+    --# enum global Dir = "north" --@< Error: `--# enum global` should be in the top-level scope
+end
+--! [Do([KailuaType(Global, `Dir`, String("north"))])]
+
+--8<-- newtype
+--# newtype UserId = integer
+--# newtype local Tag = string
+--# newtype global Flag = boolean
+--! [KailuaNewtype(Exported, `UserId`, Integer), \
+--!  KailuaNewtype(Local, `Tag`, String), \
+--!  KailuaNewtype(Global, `Flag`, Boolean)]
+
+--8<-- newtype-builtin
+--# newtype integer = number --@< Error: Cannot redefine a builtin type
+--! [KailuaNewtype(Exported, `integer`, Number)]
+
+--8<-- newtype-no-type
+--# newtype UserId = --@<-v Error: Expected a single type, got a newline
+--! [KailuaNewtype(Exported, `UserId`, Oops)]
+
+--8<-- newtype-export-in-local-scope
+do
+    --# newtype UserId = integer --@< Error: `--# newtype` with an exported type should be in the top-level scope
+end
+--! [Do([KailuaNewtype(Exported, `UserId`, Integer)])]
+
+--8<-- newtype-global-in-local-scope
+do
+    --# newtype global UserId = integer --@< Error: `--# newtype global` should be in the top-level scope
+end
+--! [Do([KailuaNewtype(Global, `UserId`, Integer)])]
+
 --8<-- kind-error
 --# type x = error
 --! [KailuaType(Exported, `x`, Error)]
@@ -2266,6 +2522,307 @@ f()
 --# type `goto` = integer
 --! [KailuaOpen(`lua51`), KailuaType(Exported, `goto`, Integer)]
 
+--8<-- lua52-goto-continue
+--# open lua52
+for i in f() do
+    if g(i) then goto continue end
+    h(i)
+    ::continue::
+end
+--! [KailuaOpen(`lua52`), ForIn([`i`$1], [`f`_()], $1[If((`g`_(`i`$1) => [Goto(`continue`)])), Void(`h`_(`i`$1)), Label(`continue`)])]
+
+--8<-- lua52-goto-backward
+--# open lua52
+::again::
+f()
+goto again
+--! [KailuaOpen(`lua52`), Label(`again`), Void(`f`_()), Goto(`again`)]
+
+--8<-- lua52-goto-outer-block
+--# open lua52
+do
+    do goto done end
+end
+::done::
+--! [KailuaOpen(`lua52`), Do([Do([Goto(`done`)])]), Label(`done`)]
+
+--8<-- lua52-goto-no-label
+--# open lua52
+goto nowhere --@< Error: No visible label `nowhere` for `goto`
+--! [KailuaOpen(`lua52`), Goto(`nowhere`)]
+
+--8<-- lua52-goto-inner-label
+--# open lua52
+goto inner --@< Error: No visible label `inner` for `goto`
+do ::inner:: end
+--! [KailuaOpen(`lua52`), Goto(`inner`), Do([Label(`inner`)])]
+
+--8<-- lua52-goto-across-function
+--# open lua52
+::outer::
+local function f()
+    goto outer --@< Error: No visible label `outer` for `goto`
+end
+--! [KailuaOpen(`lua52`), Label(`outer`), FuncDecl(`f`$2, [] --> _, $1[Goto(`outer`)])$2]
+
+--8<-- lua52-duplicate-label
+--# open lua52
+::a::
+::a:: --@< Error: The label `a` has been already defined in the same block
+--@^^ Note: The previous label was defined here
+--! [KailuaOpen(`lua52`), Label(`a`), Label(`a`)]
+
+--8<-- lua52-same-label-in-nested-block
+--# open lua52
+::a::
+do ::a:: end
+--! [KailuaOpen(`lua52`), Label(`a`), Do([Label(`a`)])]
+
+--8<-- lua52-goto-into-local-scope
+--# open lua52
+goto skip --@< Error: `goto` to the label `skip` jumps into the scope of local `x`
+local x = f()
+::skip::
+print(x)
+--! [KailuaOpen(`lua52`), Goto(`skip`), Local([`x`$1], [`f`_()])$1, Label(`skip`), Void(`print`_(`x`$1))]
+
+--8<-- lua52-goto-into-local-scope-from-inner-block
+--# open lua52
+do goto skip end --@< Error: `goto` to the label `skip` jumps into the scope of local `f`
+local function f() end
+::skip::
+f()
+--! [KailuaOpen(`lua52`), Do([Goto(`skip`)]), FuncDecl(`f`$2, [] --> _, $1[])$2, Label(`skip`), Void(`f`$2())]
+
+--8<-- lua52-goto-into-local-scope-at-end-of-block
+--# open lua52
+do
+    goto skip
+    local x = f()
+    ::skip::
+end
+--! [KailuaOpen(`lua52`), Do([Goto(`skip`), Local([`x`$1], [`f`_()])$1, Label(`skip`)])]
+
+--8<-- lua52-goto-past-local-scope
+--# open lua52
+do
+    local x = f()
+    goto skip
+    local y = g()
+end
+::skip::
+h()
+--! [KailuaOpen(`lua52`), Do([Local([`x`$1], [`f`_()])$1, Goto(`skip`), Local([`y`$2], [`g`_()])$2]), \
+--!     Label(`skip`), Void(`h`_())]
+
+--8<-- lua51-label
+--# open lua51
+::a:: --@< Error: `::` cannot be used in Lua 5.1, it is only available since Lua 5.2
+--! [KailuaOpen(`lua51`), Label(`a`)]
+
+--8<-- lua52-stmt-after-break
+--# open lua52
+while true do
+    break
+    f()
+end
+--! [KailuaOpen(`lua52`), While(true, [Break, Void(`f`_())])]
+
+--8<-- lua51-stmt-after-break
+--# open lua51
+while true do
+    break
+    f() --@< Error: `return` or `break` cannot be followed by other statements
+end
+--! [KailuaOpen(`lua51`), While(true, [Break, Void(`f`_())])]
+
+--8<-- lua52-local-env
+--# open lua52
+local _ENV = {}
+x = y
+function f() end
+--! [KailuaOpen(`lua52`), Local([`_ENV`$1], [{}])$1, Assign([`_ENV`$1.`x`], [`_ENV`$1.`y`]), MethodDecl((`_ENV`$1.`f`), None, [] --> _, $2[])]
+
+--8<-- lua51-local-env
+--# open lua51
+local _ENV = {}
+x = y
+--! [KailuaOpen(`lua51`), Local([`_ENV`$1], [{}])$1, Assign([`x`_], [`y`_])]
+
+--8<-- lua53-idiv
+--# open lua53
+x = a // b
+--! [KailuaOpen(`lua53`), Assign([`x`_], [(`a`_ // `b`_)])]
+
+--8<-- lua53-bitwise-ops
+--# open lua53
+x = a & b | c ~ d
+--! [KailuaOpen(`lua53`), Assign([`x`_], [((`a`_ & `b`_) | (`c`_ ~ `d`_))])]
+
+--8<-- lua53-bitwise-prec-1
+--# open lua53
+x = a | b ~ c & d << e .. f
+--! [KailuaOpen(`lua53`), Assign([`x`_], [(`a`_ | (`b`_ ~ (`c`_ & (`d`_ << (`e`_ .. `f`_)))))])]
+
+--8<-- lua53-bitwise-prec-2
+--# open lua53
+x = a < b | c
+--! [KailuaOpen(`lua53`), Assign([`x`_], [(`a`_ < (`b`_ | `c`_))])]
+
+--8<-- lua53-shifts
+--# open lua53
+x = a << b >> c
+--! [KailuaOpen(`lua53`), Assign([`x`_], [((`a`_ << `b`_) >> `c`_)])]
+
+--8<-- lua53-bnot
+--# open lua53
+x = ~a ~ ~b
+--! [KailuaOpen(`lua53`), Assign([`x`_], [((~ `a`_) ~ (~ `b`_))])]
+
+--8<-- lua52-idiv
+--# open lua52
+x = a // b --@< Error: `//` cannot be used in Lua 5.2, it is only available since Lua 5.3
+--! [KailuaOpen(`lua52`), Assign([`x`_], [(`a`_ // `b`_)])]
+
+--8<-- lua51-bnot
+--# open lua51
+x = ~a --@< Error: `~` cannot be used in Lua 5.1, it is only available since Lua 5.3
+--! [KailuaOpen(`lua51`), Assign([`x`_], [(~ `a`_)])]
+
+--8<-- lua51-bitwise-ops
+--# open lua51
+x = a & b --@< Error: `&` cannot be used in Lua 5.1, it is only available since Lua 5.3
+y = a | b --@< Error: `|` cannot be used in Lua 5.1, it is only available since Lua 5.3
+--! [KailuaOpen(`lua51`), Assign([`x`_], [(`a`_ & `b`_)]), Assign([`y`_], [(`a`_ | `b`_)])]
+
+--8<-- lua54-local-const
+--# open lua54
+local x <const> = y
+--! [KailuaOpen(`lua54`), Local([`x`$1: Const], [`y`_])$1]
+
+--8<-- lua54-local-close
+--# open lua54
+local x <close>, y <const> = a, b
+--! [KailuaOpen(`lua54`), Local([`x`$1: Close, `y`$1: Const], [`a`_, `b`_])$1]
+
+--8<-- lua54-local-attrib-with-type-spec
+--# open lua54
+local x <const> --: integer
+= y
+--! [KailuaOpen(`lua54`), Local([`x`$1: Const Integer], [`y`_])$1]
+
+--8<-- lua54-local-attrib-with-const-type-spec
+--# open lua54
+local x <close> --: const
+= y
+--! [KailuaOpen(`lua54`), Local([`x`$1: Close], [`y`_])$1]
+
+--8<-- lua54-local-attrib-with-module
+--# open lua54
+local x <const> --: module --@< Error: The attribute `const` cannot be used with the `module` type specification
+= y
+--! [KailuaOpen(`lua54`), Local([`x`$1: Module], [`y`_])$1]
+
+--8<-- lua54-local-unknown-attrib
+--# open lua54
+local x <foo> = y --@< Error: Unknown attribute `foo` for a local variable
+--! [KailuaOpen(`lua54`), Local([`x`$1], [`y`_])$1]
+
+--8<-- lua54-local-multiple-close
+--# open lua54
+local x <close>, y <close> = a, b
+--@^ Error: Only one variable can have the <close> attribute in a single `local` declaration
+--@^^ Note: The previous <close> attribute was given here
+--! [KailuaOpen(`lua54`), Local([`x`$1: Close, `y`$1: Close], [`a`_, `b`_])$1]
+
+--8<-- lua53-local-attrib
+--# open lua53
+local x <const> = y --@< Error: `<` cannot be used in Lua 5.3, it is only available since Lua 5.4
+--! [KailuaOpen(`lua53`), Local([`x`$1: Const], [`y`_])$1]
+
+--8<-- luajit-goto
+--# open luajit
+goto a
+::a::
+--! [KailuaOpen(`luajit`), Goto(`a`), Label(`a`)]
+
+--8<-- luajit-stmt-after-break
+--# open luajit
+while x do
+    break
+    f()
+end
+--! [KailuaOpen(`luajit`), While(`x`_, [Break, Void(`f`_())])]
+
+--8<-- luajit-no-local-env
+--# open luajit
+local _ENV = {}
+x = y
+--! [KailuaOpen(`luajit`), Local([`_ENV`$1], [{}])$1, Assign([`x`_], [`y`_])]
+
+--8<-- luajit-imaginary
+--# open luajit
+x = 0.5i
+--! [KailuaOpen(`luajit`), Assign([`x`_], [0.5i])]
+
+--8<-- luajit-suffix-case
+--# open luajit
+x = 0.5I
+--! [KailuaOpen(`luajit`), Assign([`x`_], [0.5i])]
+
+--8<-- luajit-bad-suffix-1
+--# open luajit
+f(0.5LL) --@< Error: Invalid number
+--! [KailuaOpen(`luajit`), Void(`f`_())]
+
+--8<-- luajit-bad-suffix-2
+--# open luajit
+f(0.5u) --@< Error: Invalid number
+--! [KailuaOpen(`luajit`), Void(`f`_())]
+
+--8<-- lua51-suffixed-num
+--# open lua51
+x = 0.5i --@< Error: Cannot use a number with a suffix `i` in Lua 5.1, it is only available in LuaJIT
+--! [KailuaOpen(`lua51`), Assign([`x`_], [0.5i])]
+
+--8<-- luajit-reset-by-lua51
+--# open luajit
+--# open lua51
+x = 0.5i --@< Error: Cannot use a number with a suffix `i` in Lua 5.1, it is only available in LuaJIT
+--! [KailuaOpen(`luajit`), KailuaOpen(`lua51`), Assign([`x`_], [0.5i])]
+
+--8<-- lang-lua53
+--# lang lua53
+x = a // b
+--! [Assign([`x`_], [(`a`_ // `b`_)])]
+
+--8<-- lang-luajit
+--# lang luajit
+x = 0.5i
+--! [Assign([`x`_], [0.5i])]
+
+--8<-- lang-lua51-after-lua53
+--# lang lua53
+--# lang lua51
+x = a // b --@< Error: `//` cannot be used in Lua 5.1, it is only available since Lua 5.3
+--! [Assign([`x`_], [(`a`_ // `b`_)])]
+
+--8<-- lang-then-open
+--# lang lua53
+--# open lua51
+x = a // b --@< Error: `//` cannot be used in Lua 5.1, it is only available since Lua 5.3
+--! [KailuaOpen(`lua51`), Assign([`x`_], [(`a`_ // `b`_)])]
+
+--8<-- lang-unknown
+--# lang lua99 --@< Error: Unknown language `lua99`, should be one of `lua51`, `lua52`, `lua53`, `lua54` or `luajit`
+x = a // b --@< Error: `//` cannot be used in Lua 5.1, it is only available since Lua 5.3
+--! [Assign([`x`_], [(`a`_ // `b`_)])]
+
+--8<-- lang-no-name
+--# lang --@<-v Error: Expected a name, got a newline
+x = a
+--! [Oops, Assign([`x`_], [`a`_])]
+
 --8<-- type-spec-recover-negative-span
 local a = {} --: var { var { } } --@< Error: Expected a newline, got a keyword `var`
 local b --: var { var { } }      --@< Error: Expected a newline, got a keyword `var`
@@ -46,6 +46,8 @@ pub enum Origin {
     Strings = 0x12,
     /// From an operation with `Functions`.
     Functions = 0x13,
+    /// From an operation with `Threads`.
+    Threads = 0x14,
 
     /// From an operation with `Union`.
     Union = 0x20,
@@ -100,6 +102,7 @@ enum ReportItem {
     RecCannotHaveKey(Span, Spanned<Key>),
     RecShouldHaveKeys(Span, Spanned<Vec<Key>>),
     RecExtendedWithNonNil(Span, Spanned<Key>, Spanned<String>),
+    IfaceMissingMember(Span, Spanned<Key>),
 }
 
 impl TypeReport {
@@ -306,6 +309,12 @@ impl TypeReport {
         self.messages.push(ReportItem::RecExtendedWithNonNil(Span::dummy(), k, v)); // TODO span
         self
     }
+
+    pub fn interface_missing_member(mut self, k: &Key) -> TypeReport {
+        let k = k.clone().without_loc(); // TODO span
+        self.messages.push(ReportItem::IfaceMissingMember(Span::dummy(), k)); // TODO span
+        self
+    }
 }
 
 /// A hint about the context where given type operation occurred.
@@ -533,6 +542,11 @@ impl<'a, T> TypeReportMore for ReportMore<'a, T> {
                     // TODO do something with key.span and value.span
                     self = self.cause(recspan, m::RecExtendedWithNonNil { key: key, slot: value });
                 }
+
+                ReportItem::IfaceMissingMember(ifacespan, ref key) => {
+                    // TODO do something with key.span
+                    self = self.cause(ifacespan, m::IfaceMissingMember { key: key });
+                }
             }
         }
 
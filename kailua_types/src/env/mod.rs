@@ -8,10 +8,12 @@ use vec_map::{self, VecMap};
 use atomic::Atomic;
 use atomic::Ordering::Relaxed;
 
+use kailua_env::Spanned;
 use kailua_diag::Locale;
+use kailua_syntax::Name;
 use diag::{Origin, TypeReport, TypeResult};
 use ty::{Ty, T, Slot, TVar, RVar, Lattice, Key};
-use ty::{TypeContext, ClassId, ClassSystemId, DisplayState};
+use ty::{TypeContext, Class, ClassId, ClassSystemId, MetatableId, NamedId, DisplayState};
 use ty::flags::*;
 use self::partitions::{Partition, Partitions};
 
@@ -24,8 +26,15 @@ struct Bound {
     bound: Option<Ty>,
 }
 
+impl Clone for Bound {
+    fn clone(&self) -> Bound {
+        Bound { parent: Atomic::new(self.parent.load(Relaxed)), rank: self.rank,
+                bound: self.bound.clone() }
+    }
+}
+
 // a set of constraints that can be organized as a tree
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct Constraints {
     op: &'static str,
     bounds: Partitions<Box<Bound>>,
@@ -128,7 +137,7 @@ impl Constraints {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 struct RowInfo {
     // the hashmap being None indicates that it is currently recursing;
     // the value can be Some(slot) for "positive" fields, which the row variable contains that key,
@@ -160,6 +169,11 @@ pub trait ClassProvider: Send + Sync {
     /// Should return true if the nominal identifier `lhs` is
     /// a subtype of another nominal identifier `rhs`.
     fn is_subclass_of(&self, lhs: ClassId, rhs: ClassId) -> bool;
+
+    /// Should iterate over a list of keys and corresponding types available to given nominal type.
+    /// The closure can stop the iteration by returning `Err`.
+    fn list_class_fields(&self, cls: Class,
+                         f: &mut FnMut(&Key, &Slot) -> Result<(), ()>) -> Result<(), ()>;
 }
 
 impl<'a, T: ClassProvider + ?Sized> ClassProvider for &'a T {
@@ -174,6 +188,10 @@ impl<'a, T: ClassProvider + ?Sized> ClassProvider for &'a T {
     fn is_subclass_of(&self, lhs: ClassId, rhs: ClassId) -> bool {
         (**self).is_subclass_of(lhs, rhs)
     }
+    fn list_class_fields(&self, cls: Class,
+                         f: &mut FnMut(&Key, &Slot) -> Result<(), ()>) -> Result<(), ()> {
+        (**self).list_class_fields(cls, f)
+    }
 }
 
 impl<T: ClassProvider + ?Sized> ClassProvider for Box<T> {
@@ -188,6 +206,10 @@ impl<T: ClassProvider + ?Sized> ClassProvider for Box<T> {
     fn is_subclass_of(&self, lhs: ClassId, rhs: ClassId) -> bool {
         (**self).is_subclass_of(lhs, rhs)
     }
+    fn list_class_fields(&self, cls: Class,
+                         f: &mut FnMut(&Key, &Slot) -> Result<(), ()>) -> Result<(), ()> {
+        (**self).list_class_fields(cls, f)
+    }
 }
 
 /// A dummy `ClassProvider` which allows no nominal types.
@@ -206,6 +228,19 @@ impl ClassProvider for DummyClassProvider {
     fn is_subclass_of(&self, _lhs: ClassId, _rhs: ClassId) -> bool {
         false
     }
+    fn list_class_fields(&self, _cls: Class,
+                         _f: &mut FnMut(&Key, &Slot) -> Result<(), ()>) -> Result<(), ()> {
+        Ok(())
+    }
+}
+
+/// Saved constraints to type and row variables, made by `Types::snapshot`.
+pub struct TypesSnapshot {
+    tvar_sub: Constraints,
+    tvar_sup: Constraints,
+    tvar_eq: Constraints,
+    row_infos: VecMap<Box<RowInfo>>,
+    named_relations: HashSet<(NamedId, NamedId, bool)>,
 }
 
 /// The type environment.
@@ -224,6 +259,15 @@ pub struct Types {
 
     // classes and class systems are handled in a separate subsystem, encapsulated as ClassProvider
     classes: Box<ClassProvider>,
+
+    // metatable types, indexed by MetatableId
+    metatables: Vec<Ty>,
+
+    // recursive named types and their definitions, indexed by NamedId
+    named_types: Vec<(Spanned<Name>, Ty)>,
+
+    // relations between recursive named types being asserted (the last is true for equality)
+    named_relations: HashSet<(NamedId, NamedId, bool)>,
 }
 
 impl Types {
@@ -238,6 +282,9 @@ impl Types {
             next_rvar: RVar::new(1), // RVar::new(0) == RVar::empty()
             row_infos: VecMap::new(),
             classes: classes,
+            metatables: Vec::new(),
+            named_types: Vec::new(),
+            named_relations: HashSet::new(),
         }
     }
 
@@ -251,6 +298,29 @@ impl Types {
         self.message_locale = locale;
     }
 
+    /// Saves constraints to type and row variables, so that they can be later restored.
+    ///
+    /// Used to tentatively check types (e.g. each overload of a function) without side effects.
+    /// Slots are shared and not saved, and type and row variables are never reused.
+    pub fn snapshot(&self) -> TypesSnapshot {
+        TypesSnapshot {
+            tvar_sub: self.tvar_sub.clone(),
+            tvar_sup: self.tvar_sup.clone(),
+            tvar_eq: self.tvar_eq.clone(),
+            row_infos: self.row_infos.clone(),
+            named_relations: self.named_relations.clone(),
+        }
+    }
+
+    /// Restores constraints to type and row variables saved by `Types::snapshot`.
+    pub fn restore(&mut self, snapshot: TypesSnapshot) {
+        self.tvar_sub = snapshot.tvar_sub;
+        self.tvar_sup = snapshot.tvar_sup;
+        self.tvar_eq = snapshot.tvar_eq;
+        self.row_infos = snapshot.row_infos;
+        self.named_relations = snapshot.named_relations;
+    }
+
     fn assert_rvar_rel(&mut self, lhs: RVar, rhs: RVar, is_sub: bool) -> TypeResult<()> {
         trace!("{:?} should be {} {:?}", lhs, if is_sub { "<:" } else { "=" }, rhs);
 
@@ -611,6 +681,10 @@ impl TypeContext for Types {
         self.tvar_eq.get_bound(tvar).and_then(|b| b.bound.as_ref()).cloned()
     }
 
+    fn get_tvar_lower_bound(&self, tvar: TVar) -> Option<Ty> {
+        self.tvar_sup.get_bound(tvar).and_then(|b| b.bound.as_ref()).cloned()
+    }
+
     fn gen_rvar(&mut self) -> RVar {
         let rvar = self.next_rvar.clone();
         self.next_rvar = RVar::new(rvar.to_usize() + 1);
@@ -757,6 +831,49 @@ impl TypeContext for Types {
     fn is_subclass_of(&self, lhs: ClassId, rhs: ClassId) -> bool {
         self.classes.is_subclass_of(lhs, rhs)
     }
+
+    fn list_class_fields(&self, cls: Class,
+                         f: &mut FnMut(&Key, &Slot) -> Result<(), ()>) -> Result<(), ()> {
+        self.classes.list_class_fields(cls, f)
+    }
+
+    fn gen_metatable(&mut self, ty: &Ty) -> MetatableId {
+        if let Some(i) = self.metatables.iter().position(|mt| mt == ty) {
+            MetatableId(i as u32)
+        } else {
+            self.metatables.push(ty.clone());
+            MetatableId(self.metatables.len() as u32 - 1)
+        }
+    }
+
+    fn get_metatable(&self, mtid: MetatableId) -> Ty {
+        self.metatables[mtid.0 as usize].clone()
+    }
+
+    fn gen_named_type(&mut self, name: &Spanned<Name>) -> NamedId {
+        self.named_types.push((name.clone(), Ty::dummy()));
+        NamedId(self.named_types.len() as u32 - 1)
+    }
+
+    fn set_named_type(&mut self, id: NamedId, ty: Ty) {
+        self.named_types[id.0 as usize].1 = ty;
+    }
+
+    fn get_named_type(&self, id: NamedId) -> Ty {
+        self.named_types[id.0 as usize].1.clone()
+    }
+
+    fn get_named_type_name(&self, id: NamedId) -> &Spanned<Name> {
+        &self.named_types[id.0 as usize].0
+    }
+
+    fn mark_named_types(&mut self, lhs: NamedId, rhs: NamedId, eq: bool) -> bool {
+        self.named_relations.insert((lhs, rhs, eq))
+    }
+
+    fn unmark_named_types(&mut self, lhs: NamedId, rhs: NamedId, eq: bool) {
+        self.named_relations.remove(&(lhs, rhs, eq));
+    }
 }
 
 #[test]
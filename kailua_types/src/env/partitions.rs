@@ -8,7 +8,7 @@ pub trait Partition {
     fn increment_rank(&mut self);
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Partitions<T> {
     map: VecMap<T>,
 }
@@ -162,6 +162,12 @@ define_msg! { pub RecExtendedWithNonNil<'a> { key: &'a Key, slot: &'a str }:
              the value type `{slot}` that is not explicitly nilable",
 }
 
+// TODO should point to the correct span
+define_msg! { pub IfaceMissingMember<'a> { key: &'a Key }:
+    "ko" => "인터페이스에 필요한 `{key}` 멤버가 없습니다",
+    _    => "The member `{key}` required by the interface is missing",
+}
+
 // should be same to kailua_check's version
 define_msg! { pub CannotUpdate<'a> { tab: &'a str }:
     "ko" => "변경할 수 없는 `{tab}` 타입을 인덱싱해서 갱신할 수 없습니다",
@@ -199,11 +205,32 @@ define_msg! { pub DuplicateAttr<'a> { ty: Ty<'a> }:
     _    => "Cannot add an attribute to a type `{ty}` with an existing attribute",
 }
 
+define_msg! { pub DuplicateModifier<'a> { name: &'a Name }:
+    "ko" => "{name} 속성이 이미 붙어 있으므로 무시합니다",
+    _    => "The attribute {name} has been already given and ignored",
+}
+
+define_msg! { pub ConflictingVisibility:
+    "ko" => "[private]와 [protected] 속성은 함께 붙일 수 없으므로 [private]로 간주합니다",
+    _    => "[private] and [protected] attributes cannot be given together, \
+             [private] is assumed",
+}
+
+define_msg! { pub NonTableReadonly<'a> { ty: Ty<'a> }:
+    "ko" => "테이블이 아닌 `{ty}` 타입은 `readonly`가 될 수 없습니다",
+    _    => "A non-table type `{ty}` cannot be `readonly`",
+}
+
 define_msg! { pub UnsupportedErrorType:
     "ko" => "`error \"메시지\"` 타입은 아직 지원되지 않습니다",
     _    => "`error \"message\"` type is not yet supported",
 }
 
+define_msg! { pub WrongTypeArgsArity<'a> { name: &'a Name, expected: usize, actual: usize }:
+    "ko" => "{name} 타입에는 {expected}개의 타입 인자가 필요하지만 {actual}개가 주어졌습니다",
+    _    => "The type {name} requires {expected} type argument(s) but {actual} were given",
+}
+
 define_msg! { pub DuplicateFieldNameInRec<'a> { name: &'a Name }:
     "ko" => "타입에서 레코드 이름 {name}이 중복됩니다",
     _    => "Duplicate record field {name} in the type specification",
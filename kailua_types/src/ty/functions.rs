@@ -1,15 +1,23 @@
 use std::fmt;
+use std::collections::HashMap;
 use kailua_env::Spanned;
 use kailua_diag::Result;
 use kailua_syntax::Name;
 use kailua_syntax::ast::{FuncKind, Returns};
 
 use diag::{Origin, TypeReport, TypeResult};
-use super::{Display, DisplayState, Ty, TySeq, TypeContext, TypeResolver, Lattice};
+use super::{Display, DisplayState, DisplayName, T, Ty, TySeq, TVar};
+use super::{TypeContext, TypeResolver, TypeParamResolver, Lattice};
 
 /// A function type.
 #[derive(Clone, PartialEq)]
 pub struct Function {
+    /// Type parameters and corresponding type variables, empty if the function is not generic.
+    ///
+    /// These type variables are never bound; they are instead replaced with
+    /// fresh type variables whenever the function gets called (see `Function::instantiate`).
+    pub tparams: Vec<(Spanned<Name>, TVar)>,
+
     /// Argument types.
     pub args: TySeq,
 
@@ -22,6 +30,23 @@ pub struct Function {
 
 impl Function {
     pub fn from_kind(func: &Spanned<FuncKind>, resolv: &mut TypeResolver) -> Result<Function> {
+        if !func.tparams.is_empty() {
+            let tparams: Vec<_> = func.tparams.iter().map(|name| {
+                (name.clone(), resolv.context_mut().gen_tvar())
+            }).collect();
+            let mut func = {
+                let mut resolv = TypeParamResolver::new(resolv, &tparams);
+                Function::from_kind_without_tparams(func, &mut resolv)?
+            };
+            func.tparams = tparams;
+            Ok(func)
+        } else {
+            Function::from_kind_without_tparams(func, resolv)
+        }
+    }
+
+    fn from_kind_without_tparams(func: &Spanned<FuncKind>,
+                                 resolv: &mut TypeResolver) -> Result<Function> {
         let args = TySeq::from_kind_seq(&func.args, |namekind| &namekind.1, resolv)?;
         let mut argnames = Vec::new();
         for (i, &(ref name, _)) in func.args.head.iter().enumerate() {
@@ -34,10 +59,56 @@ impl Function {
             Returns::Seq(ref seq) => Some(TySeq::from_kind_seq(seq, |kind| kind, resolv)?),
             Returns::Never(_span) => None,
         };
-        Ok(Function { args: args, argnames: argnames, returns: returns })
+        Ok(Function { tparams: Vec::new(), args: args, argnames: argnames, returns: returns })
+    }
+
+    /// Returns true if the function has type parameters.
+    pub fn is_generic(&self) -> bool {
+        !self.tparams.is_empty()
+    }
+
+    /// Replaces each type parameter with a fresh type variable,
+    /// returning a non-generic function type and the list of those type variables
+    /// (in the order of type parameters).
+    ///
+    /// The caller may resolve the returned type variables after the function has been used
+    /// and substitute them once again, so that the resulting type has no type variables.
+    /// Until then they are displayed as the names of type parameters.
+    pub fn instantiate(&self, ctx: &mut TypeContext) -> (Function, Vec<TVar>) {
+        let tvars: Vec<_> = self.tparams.iter().map(|_| ctx.gen_tvar()).collect();
+        let subst = self.tparams.iter().zip(tvars.iter()).map(|(&(ref name, tparam), &tvar)| {
+            let ty = Ty::new(T::TVar(tvar)).and_display(DisplayName::Type(name.clone()));
+            (tparam, ty)
+        }).collect();
+        let mut func = self.substitute(&subst, ctx);
+        func.tparams = Vec::new();
+        (func, tvars)
+    }
+
+    pub fn substitute(&self, subst: &HashMap<TVar, Ty>, ctx: &mut TypeContext) -> Function {
+        Function {
+            tparams: self.tparams.clone(),
+            args: self.args.substitute(subst, ctx),
+            argnames: self.argnames.clone(),
+            returns: self.returns.as_ref().map(|returns| returns.substitute(subst, ctx)),
+        }
     }
 
     fn assert_sub(&self, other: &Self, ctx: &mut TypeContext) -> TypeResult<()> {
+        // a generic function is a subtype of any of its instantiations.
+        // the other way (a non-generic function being used as a generic one) is unsound,
+        // as the generic function can be called with any type for its type parameters.
+        if !self.is_generic() && other.is_generic() {
+            return Err(ctx.gen_report());
+        }
+        // when both are generic, we don't have rigid type variables and
+        // instantiate both type parameters in the same way.
+        if self.is_generic() || other.is_generic() {
+            let lhs = if self.is_generic() { self.instantiate(ctx).0 } else { self.clone() };
+            let rhs = if other.is_generic() { other.instantiate(ctx).0 } else { other.clone() };
+            return lhs.assert_sub(&rhs, ctx);
+        }
+
         // contravariant
         other.args.assert_sub(&self.args, ctx)?;
 
@@ -52,6 +123,20 @@ impl Function {
     }
 
     fn assert_eq(&self, other: &Self, ctx: &mut TypeContext) -> TypeResult<()> {
+        // generic functions are equal when they are equal after renaming type parameters
+        if self.is_generic() || other.is_generic() {
+            if self.tparams.len() != other.tparams.len() {
+                return Err(ctx.gen_report());
+            }
+            let (lhs, tvars) = self.instantiate(ctx);
+            let subst = other.tparams.iter().zip(tvars.into_iter()).map(|(&(_, tparam), tvar)| {
+                (tparam, Ty::new(T::TVar(tvar)))
+            }).collect();
+            let mut rhs = other.substitute(&subst, ctx);
+            rhs.tparams = Vec::new();
+            return lhs.assert_eq(&rhs, ctx);
+        }
+
         self.args.assert_eq(&other.args, ctx)?;
 
         match (&self.returns, &other.returns) {
@@ -66,13 +151,46 @@ impl Function {
         }
     }
 
-    fn fmt_generic<WriteTy, WriteTySeq>(&self, f: &mut fmt::Formatter,
+    /// Same to the usual display but prints `method` instead of `function`.
+    /// Used for displaying interface members.
+    pub fn fmt_as_method(&self, f: &mut fmt::Formatter, st: Option<&DisplayState>) -> fmt::Result {
+        if let Some(st) = st {
+            self.fmt_generic(
+                f, "method",
+                |t, f, without_nil| {
+                    let t = t.display(st);
+                    if without_nil { write!(f, "{:#}", t) } else { write!(f, "{}", t) }
+                },
+                |s, f| fmt::Display::fmt(&s.display(st), f),
+            )
+        } else {
+            self.fmt_generic(
+                f, "method",
+                |t, f, without_nil| {
+                    if without_nil { write!(f, "{:#?}", t) } else { write!(f, "{:?}", t) }
+                },
+                fmt::Debug::fmt,
+            )
+        }
+    }
+
+    fn fmt_generic<WriteTy, WriteTySeq>(&self, f: &mut fmt::Formatter, keyword: &str,
                                         mut write_ty: WriteTy,
                                         mut write_tyseq: WriteTySeq) -> fmt::Result
             where WriteTy: FnMut(&Ty, &mut fmt::Formatter, bool) -> fmt::Result,
                   WriteTySeq: FnMut(&TySeq, &mut fmt::Formatter) -> fmt::Result {
         // we cannot directly print self.args as they should be interleaved with self.argnames
-        write!(f, "function(")?;
+        write!(f, "{}", keyword)?;
+        if !self.tparams.is_empty() {
+            write!(f, "<")?;
+            let mut first = true;
+            for &(ref name, _) in &self.tparams {
+                if first { first = false; } else { write!(f, ", ")?; }
+                write!(f, "{:+}", name.base)?;
+            }
+            write!(f, ">")?;
+        }
+        write!(f, "(")?;
         let mut first = true;
         let mut names = self.argnames.iter();
         for t in &self.args.head {
@@ -111,7 +229,7 @@ impl Function {
 impl Display for Function {
     fn fmt_displayed(&self, f: &mut fmt::Formatter, st: &DisplayState) -> fmt::Result {
         self.fmt_generic(
-            f,
+            f, "function",
             |t, f, without_nil| {
                 let t = t.display(st);
                 if without_nil { write!(f, "{:#}", t) } else { write!(f, "{}", t) }
@@ -124,7 +242,7 @@ impl Display for Function {
 impl fmt::Debug for Function {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         self.fmt_generic(
-            f,
+            f, "function",
             |t, f, without_nil| {
                 if without_nil { write!(f, "{:#?}", t) } else { write!(f, "{:?}", t) }
             },
@@ -144,6 +262,13 @@ pub enum Functions {
 }
 
 impl Functions {
+    pub fn substitute(&self, subst: &HashMap<TVar, Ty>, ctx: &mut TypeContext) -> Functions {
+        match *self {
+            Functions::Simple(ref f) => Functions::Simple(f.substitute(subst, ctx)),
+            Functions::All => Functions::All,
+        }
+    }
+
     fn fmt_generic<WriteFunc>(&self, f: &mut fmt::Formatter,
                               mut write_func: WriteFunc) -> fmt::Result
             where WriteFunc: FnMut(&Function, &mut fmt::Formatter) -> fmt::Result {
@@ -0,0 +1,229 @@
+use std::fmt;
+use std::collections::{BTreeMap, HashMap};
+use kailua_env::{Spanned, WithLoc};
+use kailua_diag::Result;
+use kailua_syntax::{Str, Name};
+use kailua_syntax::ast::MemberKind;
+
+use diag::{Origin, TypeReport, TypeResult};
+use super::{Display, DisplayState, T, Ty, Nil, F, Slot, Key, Function, Functions};
+use super::{TypeContext, TypeResolver, Lattice, TVar};
+
+/// A member of the interface type.
+#[derive(Clone, PartialEq)]
+pub enum Member {
+    /// A field `name: type`, which should be present in the conforming type.
+    Field(Slot),
+
+    /// A method `name: method(...) --> ...`,
+    /// which should be a function receiving the conforming type as the first argument.
+    ///
+    /// The function type here does not include the `self` argument.
+    Method(Function),
+}
+
+/// A structural interface type, declared by `--# interface`.
+///
+/// Unlike records, the interface is not a type of any particular table;
+/// any tabular type (including nominal types) having all members is a subtype of the interface.
+#[derive(Clone, PartialEq)]
+pub struct Interface {
+    pub members: BTreeMap<Key, Member>,
+}
+
+impl Interface {
+    pub fn from_kind(members: &[(Spanned<Str>, Spanned<MemberKind>)],
+                     resolv: &mut TypeResolver) -> Result<Interface> {
+        // the parser has already reported duplicates, so we only keep the first one
+        let mut newmembers = BTreeMap::new();
+        for &(ref name, ref kind) in members {
+            let key = Key::from(&name.base);
+            if newmembers.contains_key(&key) {
+                continue;
+            }
+            let member = match kind.base {
+                MemberKind::Field(ref slotkind) => {
+                    let ty = Ty::from_kind(&slotkind.kind, resolv)?;
+                    Member::Field(Slot::new(F::from(slotkind.modf), ty))
+                }
+                MemberKind::Method(ref func) => {
+                    let func = func.clone().with_loc(kind);
+                    Member::Method(Function::from_kind(&func, resolv)?)
+                }
+            };
+            newmembers.insert(key, member);
+        }
+        Ok(Interface { members: newmembers })
+    }
+
+    pub fn generalize(self, ctx: &mut TypeContext) -> Interface {
+        let members = self.members.into_iter().map(|(k, m)| {
+            let m = match m {
+                Member::Field(slot) => Member::Field(slot.generalize(ctx)),
+                // functions are not generalized (see `T::generalize`)
+                Member::Method(func) => Member::Method(func),
+            };
+            (k, m)
+        }).collect();
+        Interface { members: members }
+    }
+
+    pub fn substitute(&self, subst: &HashMap<TVar, Ty>, ctx: &mut TypeContext) -> Interface {
+        let members = self.members.iter().map(|(k, m)| {
+            let m = match *m {
+                Member::Field(ref slot) => Member::Field(slot.substitute(subst, ctx)),
+                Member::Method(ref func) => Member::Method(func.substitute(subst, ctx)),
+            };
+            (k.clone(), m)
+        }).collect();
+        Interface { members: members }
+    }
+
+    /// Returns a slot for given member when the interface is used as `selfty`,
+    /// or `None` if there is no such member.
+    ///
+    /// Methods are converted to functions with an explicit `self` argument.
+    pub fn member_slot(&self, key: &Key, selfty: &Ty) -> Option<Slot> {
+        self.members.get(key).map(|m| {
+            match *m {
+                Member::Field(ref slot) => slot.clone(),
+                Member::Method(ref func) => {
+                    let mut func = func.clone();
+                    func.args.head.insert(0, selfty.clone());
+                    func.argnames.insert(0, Some(Name::from(&b"self"[..]).without_loc()));
+                    Slot::new(F::Const, Ty::new(T::func(func)))
+                }
+            }
+        })
+    }
+
+    /// Returns a list of all members as if the interface is used as `selfty`.
+    pub fn member_slots(&self, selfty: &Ty) -> Vec<(Key, Slot)> {
+        self.members.keys().map(|k| {
+            (k.clone(), self.member_slot(k, selfty).expect("missing interface member"))
+        }).collect()
+    }
+
+    /// Asserts that a type with given fields conforms to this interface.
+    ///
+    /// The first occurrence of each key wins, so the caller can list shadowing fields first.
+    /// Methods are compared without the first (`self`) argument,
+    /// as it is satisfied by the conforming type itself.
+    pub fn assert_conform(&self, fields: &[(Key, Slot)],
+                          ctx: &mut TypeContext) -> TypeResult<()> {
+        let mut lookup = HashMap::new();
+        for &(ref k, ref v) in fields {
+            lookup.entry(k).or_insert(v);
+        }
+
+        for (key, member) in &self.members {
+            let lhs = match lookup.get(key) {
+                Some(lhs) => *lhs,
+                None => return Err(ctx.gen_report().interface_missing_member(key)),
+            };
+            match *member {
+                Member::Field(ref slot) => lhs.assert_sub(slot, ctx)?,
+                Member::Method(ref method) => assert_method_sub(lhs, method, ctx)?,
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn assert_eq(&self, other: &Interface, ctx: &mut TypeContext) -> TypeResult<()> {
+        if self.members.len() != other.members.len() {
+            return Err(ctx.gen_report());
+        }
+        for ((lk, lm), (rk, rm)) in self.members.iter().zip(other.members.iter()) {
+            if *lk != *rk {
+                return Err(ctx.gen_report());
+            }
+            match (lm, rm) {
+                (&Member::Field(ref a), &Member::Field(ref b)) => a.assert_eq(b, ctx)?,
+                (&Member::Method(ref a), &Member::Method(ref b)) => {
+                    let a = Functions::Simple(a.clone());
+                    let b = Functions::Simple(b.clone());
+                    a.assert_eq(&b, ctx)?;
+                }
+                (_, _) => return Err(ctx.gen_report()),
+            }
+        }
+        Ok(())
+    }
+
+    fn fmt_generic<WriteSlot, WriteMethod>(&self, f: &mut fmt::Formatter,
+                                           mut write_slot: WriteSlot,
+                                           mut write_method: WriteMethod) -> fmt::Result
+            where WriteSlot: FnMut(&Slot, &mut fmt::Formatter) -> fmt::Result,
+                  WriteMethod: FnMut(&Function, &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "interface {{")?;
+        let mut first = true;
+        for (key, member) in &self.members {
+            if first { first = false; } else { write!(f, ", ")?; }
+            write!(f, "{}: ", key)?;
+            match *member {
+                Member::Field(ref slot) => write_slot(slot, f)?,
+                Member::Method(ref func) => write_method(func, f)?,
+            }
+        }
+        write!(f, "}}")
+    }
+}
+
+// lhs.method <: function(<self>, method args...) --> method returns
+fn assert_method_sub(lhs: &Slot, method: &Function, ctx: &mut TypeContext) -> TypeResult<()> {
+    // the slot should be cloned, as the constraint may require updating itself
+    let mut lhs = lhs.unlift().clone();
+
+    // fields in the nominal types are often bound to type variables
+    while let T::TVar(tv) = *lhs {
+        match ctx.get_tvar_exact_type(tv) {
+            Some(ty) => { lhs = ty; }
+            None => break,
+        }
+    }
+    let rhs = Functions::Simple(method.clone());
+
+    (|| {
+        if lhs.nil() == Nil::Noisy {
+            return Err(ctx.gen_report());
+        }
+        match *lhs {
+            T::Dynamic(_) => Ok(()),
+            T::Functions(ref func) => match **func {
+                Functions::Simple(ref func) => {
+                    let mut func = func.clone();
+                    if !func.args.head.is_empty() {
+                        func.args.head.remove(0);
+                        if !func.argnames.is_empty() {
+                            func.argnames.remove(0);
+                        }
+                    }
+                    Functions::Simple(func).assert_sub(&rhs, ctx)
+                }
+                Functions::All => Err(ctx.gen_report()),
+            },
+            _ => Err(ctx.gen_report()),
+        }
+    })().map_err(|r: TypeReport| r.not_sub(Origin::Functions, &lhs, &rhs, ctx))
+}
+
+impl Display for Interface {
+    fn fmt_displayed(&self, f: &mut fmt::Formatter, st: &DisplayState) -> fmt::Result {
+        self.fmt_generic(
+            f,
+            |s, f| fmt::Display::fmt(&s.display(st), f),
+            |func, f| func.fmt_as_method(f, Some(st)),
+        )
+    }
+}
+
+impl fmt::Debug for Interface {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_generic(
+            f,
+            |s, f| fmt::Debug::fmt(s, f),
+            |func, f| func.fmt_as_method(f, None),
+        )
+    }
+}
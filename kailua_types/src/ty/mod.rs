@@ -3,24 +3,28 @@
 use std::fmt;
 use std::result;
 use diag::{TypeReport, TypeResult};
-use kailua_env::Spanned;
-use kailua_diag::{Result, Locale, Report};
+use kailua_env::{Span, Spanned};
+use kailua_diag::{Result, Kind, Locale, Localize, Report};
 use kailua_syntax::Name;
 
 pub use self::display::{Display, Displayed, DisplayState, DisplayName};
 pub use self::literals::{Numbers, Strings};
 pub use self::tables::{Key, Tables};
 pub use self::functions::{Function, Functions};
+pub use self::threads::{Thread, Threads};
+pub use self::interface::{Interface, Member};
 pub use self::union::Unioned;
 pub use self::value::{Dyn, Nil, T, Ty};
 pub use self::slot::{F, S, Slot};
 pub use self::seq::{SeqIter, TySeq, SpannedTySeq, SlotSeq, SpannedSlotSeq};
-pub use self::tag::Tag;
+pub use self::tag::{Tag, Modifiers, Visibility};
 
 mod display;
 mod literals;
 mod tables;
 mod functions;
+mod threads;
+mod interface;
 mod union;
 mod value;
 mod slot;
@@ -87,6 +91,33 @@ impl fmt::Debug for ClassSystemId {
     }
 }
 
+/// Identifiers for metatables attached to tables via `setmetatable`.
+///
+/// The metatable type itself is kept in the type context (see `TypeContext::get_metatable`).
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct MetatableId(pub u32);
+
+/// In the debugging output the metatable identifier is denoted <code>&lt;mt #<i>id</i>&gt;</code>.
+impl fmt::Debug for MetatableId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<mt #{}>", self.0)
+    }
+}
+
+/// Identifiers for recursive named types, i.e. type aliases referring to themselves.
+///
+/// The definition itself is kept in the type context (see `TypeContext::get_named_type`).
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct NamedId(pub u32);
+
+/// In the debugging output the named type identifier is denoted
+/// <code>&lt;type #<i>id</i>&gt;</code>.
+impl fmt::Debug for NamedId {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "<type #{}>", self.0)
+    }
+}
+
 /// Identifiers for nominal types (currently only used for instantiable classes).
 #[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ClassId(pub ClassSystemId, pub u32);
@@ -156,6 +187,13 @@ pub trait TypeResolver: Report {
 
     /// Resolves a class system name to an identifier if any. The span is used for error reporting.
     fn class_system_from_name(&self, name: &Spanned<Name>) -> Result<Option<ClassSystemId>>;
+
+    /// Returns type parameters of the named type, empty if the type is not generic.
+    /// Should not report anything as `ty_from_name` will report an unresolved name.
+    ///
+    /// Type parameters appear in the type returned by `ty_from_name` as type variables,
+    /// which should be substituted with type arguments.
+    fn type_params_from_name(&self, name: &Spanned<Name>) -> Vec<TVar>;
 }
 
 impl<'a, R: TypeResolver + ?Sized> TypeResolver for &'a mut R {
@@ -171,6 +209,65 @@ impl<'a, R: TypeResolver + ?Sized> TypeResolver for &'a mut R {
     fn class_system_from_name(&self, name: &Spanned<Name>) -> Result<Option<ClassSystemId>> {
         (**self).class_system_from_name(name)
     }
+    fn type_params_from_name(&self, name: &Spanned<Name>) -> Vec<TVar> {
+        (**self).type_params_from_name(name)
+    }
+}
+
+/// A type resolver that resolves given type parameters to type variables
+/// before delegating to the parent resolver.
+///
+/// Used for generic function types and generic type aliases.
+pub struct TypeParamResolver<'a> {
+    parent: &'a mut TypeResolver,
+    tparams: &'a [(Spanned<Name>, TVar)],
+}
+
+impl<'a> TypeParamResolver<'a> {
+    pub fn new(parent: &'a mut TypeResolver,
+               tparams: &'a [(Spanned<Name>, TVar)]) -> TypeParamResolver<'a> {
+        TypeParamResolver { parent: parent, tparams: tparams }
+    }
+}
+
+impl<'a> Report for TypeParamResolver<'a> {
+    fn message_locale(&self) -> Locale {
+        self.parent.message_locale()
+    }
+
+    fn add_span(&self, kind: Kind, span: Span, msg: &Localize) -> Result<()> {
+        self.parent.add_span(kind, span, msg)
+    }
+}
+
+impl<'a> TypeResolver for TypeParamResolver<'a> {
+    fn context(&self) -> &TypeContext {
+        self.parent.context()
+    }
+
+    fn context_mut(&mut self) -> &mut TypeContext {
+        self.parent.context_mut()
+    }
+
+    fn ty_from_name(&self, name: &Spanned<Name>) -> Result<Ty> {
+        if let Some(&(ref tparam, tvar)) = self.tparams.iter().find(|p| p.0.base == name.base) {
+            Ok(Ty::new(T::TVar(tvar)).and_display(DisplayName::Type(tparam.clone())))
+        } else {
+            self.parent.ty_from_name(name)
+        }
+    }
+
+    fn class_system_from_name(&self, name: &Spanned<Name>) -> Result<Option<ClassSystemId>> {
+        self.parent.class_system_from_name(name)
+    }
+
+    fn type_params_from_name(&self, name: &Spanned<Name>) -> Vec<TVar> {
+        if self.tparams.iter().any(|p| p.0.base == name.base) {
+            Vec::new()
+        } else {
+            self.parent.type_params_from_name(name)
+        }
+    }
 }
 
 /// A trait that provides every type-related operations.
@@ -214,6 +311,11 @@ pub trait TypeContext {
     /// Resolves a given type variable if there is a tight bound.
     fn get_tvar_exact_type(&self, tvar: TVar) -> Option<Ty>;
 
+    /// Returns a lower bound of given type variable if any.
+    ///
+    /// Mainly used to resolve type parameters of generic functions after the call.
+    fn get_tvar_lower_bound(&self, tvar: TVar) -> Option<Ty>;
+
     /// Generates a new fresh row variable.
     fn gen_rvar(&mut self) -> RVar;
 
@@ -273,6 +375,54 @@ pub trait TypeContext {
     /// Returns true if given nominal instance type is a subtype of another nominal instance type.
     fn is_subclass_of(&self, lhs: ClassId, rhs: ClassId) -> bool;
 
+    /// Iterates over a list of keys and corresponding types available to given nominal type.
+    /// The closure can stop the iteration by returning `Err`.
+    fn list_class_fields(
+        &self, cls: Class, f: &mut FnMut(&Key, &Slot) -> result::Result<(), ()>
+    ) -> result::Result<(), ()>;
+
+    /// Collects and returns a list of all known fields in given nominal type.
+    fn get_class_fields(&self, cls: Class) -> Vec<(Key, Slot)> {
+        let mut fields = Vec::new();
+        self.list_class_fields(cls, &mut |k, v| {
+            fields.push((k.clone(), v.clone()));
+            Ok(())
+        }).expect("list_class_fields exited early while we haven't break");
+        fields
+    }
+
+    /// Registers a metatable type and returns its identifier.
+    /// Equal metatable types are given the same identifier.
+    fn gen_metatable(&mut self, ty: &Ty) -> MetatableId;
+
+    /// Returns a metatable type for given identifier.
+    fn get_metatable(&self, mtid: MetatableId) -> Ty;
+
+    /// Registers a recursive named type and returns its identifier.
+    ///
+    /// The definition is initially unknown and should be set later with `set_named_type`,
+    /// so that the definition itself can refer to the identifier.
+    fn gen_named_type(&mut self, name: &Spanned<Name>) -> NamedId;
+
+    /// Sets the definition of given recursive named type.
+    fn set_named_type(&mut self, id: NamedId, ty: Ty);
+
+    /// Returns the definition of given recursive named type.
+    fn get_named_type(&self, id: NamedId) -> Ty;
+
+    /// Returns the name of given recursive named type.
+    fn get_named_type_name(&self, id: NamedId) -> &Spanned<Name>;
+
+    /// Marks that the subtyping (or equivalence if `eq` is true) relation between
+    /// two recursive named types is being asserted.
+    /// Returns false if it has been already marked, in which case the relation is assumed to hold.
+    ///
+    /// This is required for asserting relations between recursive types without looping forever.
+    fn mark_named_types(&mut self, lhs: NamedId, rhs: NamedId, eq: bool) -> bool;
+
+    /// Unmarks the relation between two recursive named types marked with `mark_named_types`.
+    fn unmark_named_types(&mut self, lhs: NamedId, rhs: NamedId, eq: bool);
+
     /// Returns a pair of type flags that is an exact lower and upper bound for that type.
     ///
     /// Used as an approximate type bound testing like arithmetics.
@@ -287,16 +437,28 @@ pub trait TypeContext {
     /// Exactly resolves the type variable inside `ty` if possible.
     ///
     /// This is a requirement for table indexing and function calls.
+    ///
+    /// Recursive named types are also unfolded to their definitions.
     fn resolve_exact_type(&self, ty: &Ty) -> Option<Ty> {
-        if let T::TVar(tv) = **ty {
+        let ty = if let T::TVar(tv) = **ty {
             if let Some(ty2) = self.get_tvar_exact_type(tv) {
                 let tag = ty.tag().or(ty2.tag());
-                Some(ty2.union_nil(ty.nil()).with_tag(tag))
+                let modifiers = ty.modifiers().or(ty2.modifiers());
+                ty2.union_nil(ty.nil()).with_tag(tag).with_modifiers(modifiers)
             } else {
-                None
+                return None;
             }
         } else {
-            Some(ty.clone())
+            ty.clone()
+        };
+
+        if let T::Named(id, _) = *ty {
+            let ty2 = self.get_named_type(id);
+            let tag = ty.tag().or(ty2.tag());
+            let modifiers = ty.modifiers().or(ty2.modifiers());
+            Some(ty2.union_nil(ty.nil()).with_tag(tag).with_modifiers(modifiers))
+        } else {
+            Some(ty)
         }
     }
 }
@@ -419,6 +581,9 @@ impl TypeContext for NoTypeContext {
     fn get_tvar_exact_type(&self, tvar: TVar) -> Option<Ty> {
         panic!("get_tvar_exact_type({:?}) is not supposed to be called here", tvar);
     }
+    fn get_tvar_lower_bound(&self, tvar: TVar) -> Option<Ty> {
+        panic!("get_tvar_lower_bound({:?}) is not supposed to be called here", tvar);
+    }
 
     fn gen_rvar(&mut self) -> RVar {
         panic!("gen_rvar is not supposed to be called here");
@@ -455,6 +620,37 @@ impl TypeContext for NoTypeContext {
     fn is_subclass_of(&self, lhs: ClassId, rhs: ClassId) -> bool {
         panic!("is_subclass_of({:?}, {:?}) is not supposed to be called here", lhs, rhs);
     }
+    fn list_class_fields(
+        &self, cls: Class, _f: &mut FnMut(&Key, &Slot) -> result::Result<(), ()>
+    ) -> result::Result<(), ()> {
+        panic!("list_class_fields({:?}, ...) is not supposed to be called here", cls);
+    }
+    fn gen_metatable(&mut self, ty: &Ty) -> MetatableId {
+        panic!("gen_metatable({:?}) is not supposed to be called here", ty);
+    }
+    fn get_metatable(&self, mtid: MetatableId) -> Ty {
+        panic!("get_metatable({:?}) is not supposed to be called here", mtid);
+    }
+    fn gen_named_type(&mut self, name: &Spanned<Name>) -> NamedId {
+        panic!("gen_named_type({:?}) is not supposed to be called here", name);
+    }
+    fn set_named_type(&mut self, id: NamedId, ty: Ty) {
+        panic!("set_named_type({:?}, {:?}) is not supposed to be called here", id, ty);
+    }
+    fn get_named_type(&self, id: NamedId) -> Ty {
+        panic!("get_named_type({:?}) is not supposed to be called here", id);
+    }
+    fn get_named_type_name(&self, id: NamedId) -> &Spanned<Name> {
+        panic!("get_named_type_name({:?}) is not supposed to be called here", id);
+    }
+    fn mark_named_types(&mut self, lhs: NamedId, rhs: NamedId, eq: bool) -> bool {
+        panic!("mark_named_types({:?}, {:?}, {:?}) is not supposed to be called here",
+               lhs, rhs, eq);
+    }
+    fn unmark_named_types(&mut self, lhs: NamedId, rhs: NamedId, eq: bool) {
+        panic!("unmark_named_types({:?}, {:?}, {:?}) is not supposed to be called here",
+               lhs, rhs, eq);
+    }
 }
 
 impl Lattice for TVar {
@@ -619,9 +815,6 @@ pub mod flags {
             /// `boolean` and its subtype.
             const U_BOOLEAN = T_BOOLEAN.bits,
 
-            /// `thread`.
-            const U_THREAD = T_THREAD.bits,
-
             /// `userdata`.
             const U_USERDATA = T_USERDATA.bits,
         }
@@ -3,12 +3,13 @@ use std::fmt;
 use std::vec;
 use std::usize;
 use std::iter;
+use std::collections::HashMap;
 
 use kailua_env::{Span, Spanned, WithLoc};
 use kailua_diag;
 use kailua_syntax::ast::{Seq, Kind};
 use diag::{Origin, TypeReport, TypeResult};
-use super::{T, Ty, Slot, Lattice, Union, Dummy};
+use super::{T, Ty, Slot, Lattice, Union, Dummy, TVar};
 use super::{Display, DisplayState, TypeContext, TypeResolver};
 
 /// Yields each element type.
@@ -671,6 +672,12 @@ define_slotseq! {
 }
 
 impl TySeq {
+    pub fn substitute(&self, subst: &HashMap<TVar, Ty>, ctx: &mut TypeContext) -> TySeq {
+        let head = self.head.iter().map(|t| t.substitute(subst, ctx)).collect();
+        let tail = self.tail.as_ref().map(|t| t.substitute(subst, ctx));
+        TySeq { head: head, tail: tail }
+    }
+
     pub fn all_with_loc<Loc: Into<Span>>(self, loc: Loc) -> SpannedTySeq {
         let span: Span = loc.into();
         SpannedTySeq { head: self.head.into_iter().map(|t| t.with_loc(span)).collect(),
@@ -2,6 +2,7 @@ use std::fmt;
 use std::mem;
 use std::ops::Deref;
 use std::sync::Arc;
+use std::collections::HashMap;
 use std::sync::atomic::{Ordering, AtomicUsize};
 use take_mut::take;
 use parking_lot::{RwLock, RwLockReadGuard};
@@ -56,7 +57,7 @@ impl From<MM> for F {
     fn from(modf: MM) -> F {
         match modf {
             MM::None => F::Var,
-            MM::Const => F::Const,
+            MM::Const | MM::Close => F::Const,
             MM::Module => F::Module,
         }
     }
@@ -576,6 +577,11 @@ impl Slot {
         Slot::from((*self.0).clone().generalize(ctx))
     }
 
+    pub fn substitute(&self, subst: &HashMap<TVar, Ty>, ctx: &mut TypeContext) -> Slot {
+        let ty = self.unlift().clone();
+        Slot::new(self.flex(), ty.substitute(subst, ctx))
+    }
+
     // should *not* create a new slot! (the resulting slot is not a different type,
     // but a same type with a display hint; the hint *should* be global.)
     pub fn set_display(self, disp: DisplayName) -> Slot {
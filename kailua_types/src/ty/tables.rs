@@ -1,11 +1,12 @@
 use std::fmt;
 use std::i32;
 use std::borrow::Cow;
-use std::collections::BTreeMap;
+use std::collections::{BTreeMap, HashMap};
 
 use kailua_syntax::Str;
 use diag::{Origin, TypeReport, TypeResult};
-use super::{Display, DisplayState, T, Ty, Slot, TypeContext, Union, Lattice, RVar};
+use super::{Display, DisplayState, T, Ty, Slot, TypeContext, Union, Lattice, TVar, RVar};
+use super::Interface;
 
 /// A key allowed in the row variable.
 #[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -92,6 +93,11 @@ pub enum Tables {
     /// This type should be explicit (as `map<integer, U>` is indistinguishable from `vector<U>`).
     Map(Ty, Slot),
 
+    /// A structural interface type declared by `--# interface`.
+    ///
+    /// Records and nominal types having all members of the interface are its subtypes.
+    Interface(Interface),
+
     /// Any table type.
     All,
 }
@@ -112,6 +118,46 @@ impl Tables {
                 let v = v.generalize(ctx);
                 Tables::Map(k, v)
             },
+            Tables::Interface(iface) => Tables::Interface(iface.generalize(ctx)),
+            Tables::All => Tables::All,
+        }
+    }
+
+    pub fn substitute(&self, subst: &HashMap<TVar, Ty>, ctx: &mut TypeContext) -> Tables {
+        match *self {
+            Tables::Fields(ref r) => {
+                if *r == RVar::empty() {
+                    return Tables::Fields(r.clone());
+                }
+
+                let mut fields = Vec::new();
+                let last = ctx.list_rvar_fields(r.clone(), &mut |k, v| {
+                    fields.push((k.clone(), v.clone()));
+                    Ok(())
+                }).expect("list_rvar_fields exited early while we haven't break");
+                let fields: Vec<_> = fields.into_iter().map(|(k, v)| {
+                    (k, v.substitute(subst, ctx))
+                }).collect();
+
+                let rvar = ctx.gen_rvar();
+                ctx.assert_rvar_includes(rvar.clone(), &fields).expect(
+                    "cannot insert disjoint fields into a fresh row variable"
+                );
+                if last == RVar::empty() {
+                    ctx.assert_rvar_closed(rvar.clone()).expect(
+                        "cannot make a fresh row variable not extensible"
+                    );
+                }
+                Tables::Fields(rvar)
+            },
+            Tables::Array(ref v) => Tables::Array(v.substitute(subst, ctx)),
+            Tables::ArrayN(ref v) => Tables::ArrayN(v.substitute(subst, ctx)),
+            Tables::Map(ref k, ref v) => {
+                let k = k.substitute(subst, ctx);
+                let v = v.substitute(subst, ctx);
+                Tables::Map(k, v)
+            },
+            Tables::Interface(ref iface) => Tables::Interface(iface.substitute(subst, ctx)),
             Tables::All => Tables::All,
         }
     }
@@ -239,6 +285,14 @@ impl Tables {
                 write!(f, ">")?;
                 Ok(())
             }
+
+            Tables::Interface(ref iface) => {
+                if let Some(st) = st {
+                    fmt::Display::fmt(&iface.display(st), f)
+                } else {
+                    fmt::Debug::fmt(iface, f)
+                }
+            }
         }
     }
 }
@@ -272,6 +326,10 @@ impl Union for Tables {
                     ar.assert_eq(&br, ctx)?;
                     Ok(Tables::Fields(ar.clone()))
                 },
+                (&Tables::Interface(ref a), &Tables::Interface(ref b)) => {
+                    a.assert_eq(b, ctx)?;
+                    Ok(Tables::Interface(a.clone()))
+                },
 
                 // for the records and non-records, records should be a subtype of non-records
                 // (and should be no longer extensible)
@@ -301,6 +359,20 @@ impl Lattice for Tables {
                     return ar.assert_sub(&br, ctx);
                 },
 
+                // interfaces are structurally checked, and records remain extensible
+                // since adding more fields cannot break the conformance
+                (&Tables::Fields(ref rvar), &Tables::Interface(ref iface)) => {
+                    let fields = ctx.get_rvar_fields(rvar.clone());
+                    return iface.assert_conform(&fields, ctx);
+                },
+                (&Tables::Interface(ref a), &Tables::Interface(ref b)) => {
+                    let selfty = Ty::new(T::Tables(Cow::Borrowed(self)).into_send());
+                    let fields = a.member_slots(&selfty);
+                    return b.assert_conform(&fields, ctx);
+                },
+                (&Tables::Interface(..), _) => false,
+                (_, &Tables::Interface(..)) => false,
+
                 (&Tables::Fields(ref rvar), &Tables::Map(ref key, ref value)) => {
                     // subtyping should hold for existing fields
                     for (k, v) in ctx.get_rvar_fields(rvar.clone()) {
@@ -401,6 +473,9 @@ impl Lattice for Tables {
                     true
                 }
                 (&Tables::Fields(ref ar), &Tables::Fields(ref br)) => return ar.assert_eq(&br, ctx),
+                (&Tables::Interface(ref a), &Tables::Interface(ref b)) => {
+                    return a.assert_eq(b, ctx);
+                }
                 (_, _) => false,
             };
 
@@ -418,6 +493,7 @@ impl PartialEq for Tables {
             (&Tables::Map(ref ak, ref av), &Tables::Map(ref bk, ref bv)) =>
                 *ak == *bk && *av == *bv,
             (&Tables::Fields(ref ar), &Tables::Fields(ref br)) => *ar == *br,
+            (&Tables::Interface(ref a), &Tables::Interface(ref b)) => *a == *b,
             (_, _) => false,
         }
     }
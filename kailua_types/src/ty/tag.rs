@@ -1,8 +1,9 @@
 use std::fmt;
+use std::cmp;
 use kailua_env::Spanned;
 use kailua_diag::{Result, Reporter};
 use kailua_syntax::ast::{Attr, AttrValue};
-use super::{Display, DisplayState, TypeResolver, ClassSystemId};
+use super::{Display, DisplayState, TypeResolver, ClassSystemId, MetatableId, NamedId};
 use message as m;
 
 /// A type tag for giving a type special meanings.
@@ -55,16 +56,19 @@ pub enum Tag {
 
     /// `function(table, ...) -> (function(table, any) -> (any?, any), table, any, ...)`
     ///
-    /// A hack for supporting generic `pairs` and `ipairs` functions.
+    /// A hack for supporting the generic `pairs` function.
     /// The first argument is exactly resolved first and the return values are updated
-    /// to fit the following generic signature if possible (not yet directly supported):
+    /// to fit the following generic signature if possible:
     ///
     /// ```text
     /// ({T => U}, ...) -> (function({T => U}, T) -> (T?, U), {T => U}, T, ...)
     /// ```
     ///
     /// ...except when the third return type is not any, in which case T is replaced with
-    /// *that* parameter so that `ipairs` (which T should be integer) works for any table types.
+    /// *that* parameter so that `ipairs`-like functions (which T should be integer) work.
+    ///
+    /// Unlike type parameters (`function<T>(...)`), this also accepts vectors and
+    /// vectors with `n` as maps, which cannot be expressed with a single signature.
     GenericPairs,
 
     /// `table`
@@ -106,6 +110,88 @@ pub enum Tag {
     /// there is no other valid way to get a table with such a type.
     StringMeta,
 
+    /// `userdata`
+    ///
+    /// A LuaJIT FFI data object (cdata). C declarations are not parsed,
+    /// so any indexing or call to this type is allowed and results in a dynamic type.
+    /// Arithmetic and comparison operators also accept numbers and other cdata.
+    CData,
+
+    /// `function(table, table?) -> table`
+    ///
+    /// Sets the metatable of the first argument, which is returned with a `Metatable` tag
+    /// pointing to the second argument's type. When the metatable is `nil`,
+    /// the first argument is returned without the tag.
+    SetMetatable,
+
+    /// `function(function) -> thread`
+    ///
+    /// Creates a typed thread (`thread<A, Y, R>`) from the function type `function(A) -> R`.
+    /// When the argument is a function literal, its body is checked as a coroutine body
+    /// and the types given to `coroutine.yield` inside it become `Y`.
+    /// Otherwise `Y` is unknown and becomes `WHATEVER...`.
+    CoroutineCreate,
+
+    /// `function(function) -> function`
+    ///
+    /// Same to `CoroutineCreate`, but returns a function `function(A) -> Y|R` instead.
+    CoroutineWrap,
+
+    /// `function(thread, ...) -> (boolean, ...)`
+    ///
+    /// When the first argument is a typed thread `thread<A, Y, R>`,
+    /// remaining arguments are checked against `A` and the return type is `(boolean, Y|R)`.
+    CoroutineResume,
+
+    /// `function(...) -> (...)`
+    ///
+    /// When called directly from a coroutine body created by `CoroutineCreate`
+    /// or `CoroutineWrap`, the arguments are collected into the yield types and
+    /// the argument types of the coroutine body are returned.
+    CoroutineYield,
+
+    /// `function(string, string, ...) -> (integer, integer, string...)`
+    ///
+    /// When the pattern (the second argument) is a string literal and the fourth argument
+    /// is not `true`, the return types are derived from captures in the pattern:
+    /// `(integer, integer)` followed by `string` for each capture and `integer` for
+    /// each position capture (`()`). A malformed pattern is reported as a warning.
+    StringFind,
+
+    /// `function(string, string, ...) -> (string...)`
+    ///
+    /// Same to `StringFind`, but the return types only contain captures
+    /// (or a single `string` for the entire match if there are no captures).
+    StringMatch,
+
+    /// `function(string, string) -> function() -> string?`
+    ///
+    /// Same to `StringMatch`, but the return types are those of the returned iterator.
+    /// The first of them is always nilable to signal the end of matches.
+    StringGMatch,
+
+    /// `function(..., string, any...) -> ...`
+    ///
+    /// The first argument with a type of `string` in the signature (including `self`)
+    /// is a format string for `string.format`. When it is a string literal,
+    /// following arguments are checked against directives in the format string.
+    /// This can be also used for user-defined functions calling `string.format`.
+    StringFormat,
+
+    /// `table`
+    ///
+    /// A table with a known metatable. The following metatable fields are recognized:
+    ///
+    /// - `__index` is consulted when a missing field is read from the table.
+    ///   It can be a table, in which case the field is looked up from it,
+    ///   or a function, in which case it is called with the table and the key.
+    /// - `__call` makes the table callable, receiving the table as the first argument.
+    /// - Arithmetic, concatenation and comparison metamethods (`__add`, `__concat`, `__eq`,
+    ///   `__lt` and so on) are used when one of operands has them.
+    ///
+    /// This cannot be written in the type specification, only `setmetatable` can make it.
+    Metatable(MetatableId),
+
     /// `function(<class prototype type>?) -> <class prototype type>`
     ///
     /// A function that makes a class prototype. If the argument is given, it should be
@@ -117,6 +203,55 @@ pub enum Tag {
     /// There may be additional behaviors depending on the class system used.
     MakeClass(ClassSystemId),
 
+    /// `function(any, <class prototype type>) -> boolean`
+    ///
+    /// Tests if the first argument is an instance of the class given as the second argument
+    /// (or its subclass). When the call is used as a condition and the first argument
+    /// (or `self` for method calls) is a local variable, the variable is narrowed to
+    /// the instance type of that class if possible.
+    IsInstanceOf,
+
+    /// `function(any, <class prototype type>) -> boolean`
+    ///
+    /// Same to `IsInstanceOf`, but tests if the first argument is a subclass of the class
+    /// and narrows the variable to the class prototype instead.
+    IsSubclassOf,
+
+    /// `function(<class prototype type>, table...) -> <class prototype type>`
+    ///
+    /// Includes given mixin tables to the class given as the first argument
+    /// (or `self` for method calls). What is included depends on the class system.
+    ///
+    /// This cannot be written as an attribute, only class systems can make it.
+    IncludeMixin,
+
+    /// `table`
+    ///
+    /// A table type made by `readonly T`. Every value slot in the table is `const`,
+    /// and no new key can be added to the table. Other table types can be freely assigned
+    /// to this type as long as their values are subtypes of values in this type.
+    ///
+    /// This cannot be written as an attribute, only `readonly` can make it.
+    Readonly,
+
+    /// `boolean`, `number`, `string` or their subtypes
+    ///
+    /// A distinct nominal type made by `--# newtype`, identified by the named type
+    /// that holds the original type. This type is neither a subtype nor a supertype of
+    /// the original type (or any other newtype), so the conversion should be explicit.
+    ///
+    /// This cannot be written as an attribute, only `--# newtype` can make it.
+    Newtype(NamedId),
+
+    /// `string`
+    ///
+    /// A union of string literals (usually declared by `--# enum`) which `if` chains
+    /// comparing against it should handle every case. An `if` chain without the final `else`
+    /// that leaves any string literal unhandled will result in a warning.
+    ///
+    /// Plain string literals can be freely assigned to this type.
+    Exhaustive,
+
     /// `function() -> any`
     ///
     /// Issues a fresh type variable for each use. The return type is ignored.
@@ -175,6 +310,18 @@ impl Tag {
             b"package_path"  => no_values(resolv, Tag::PackagePath),
             b"package_cpath" => no_values(resolv, Tag::PackageCpath),
             b"string_meta"   => no_values(resolv, Tag::StringMeta),
+            b"cdata"         => no_values(resolv, Tag::CData),
+            b"setmetatable"  => no_values(resolv, Tag::SetMetatable),
+
+            b"coroutine_create" => no_values(resolv, Tag::CoroutineCreate),
+            b"coroutine_wrap"   => no_values(resolv, Tag::CoroutineWrap),
+            b"coroutine_resume" => no_values(resolv, Tag::CoroutineResume),
+            b"coroutine_yield"  => no_values(resolv, Tag::CoroutineYield),
+
+            b"string_find"   => no_values(resolv, Tag::StringFind),
+            b"string_match"  => no_values(resolv, Tag::StringMatch),
+            b"string_gmatch" => no_values(resolv, Tag::StringGMatch),
+            b"string_format" => no_values(resolv, Tag::StringFormat),
 
             b"make_class" => {
                 let values = values(resolv, 1)?;
@@ -186,6 +333,11 @@ impl Tag {
                 Ok(None)
             },
 
+            b"is_instance_of" => no_values(resolv, Tag::IsInstanceOf),
+            b"is_subclass_of" => no_values(resolv, Tag::IsSubclassOf),
+
+            b"exhaustive" => no_values(resolv, Tag::Exhaustive),
+
             b"internal kailua_gen_tvar"    => no_values(resolv, Tag::KailuaGenTvar),
             b"internal kailua_assert_tvar" => no_values(resolv, Tag::KailuaAssertTvar),
 
@@ -210,7 +362,24 @@ impl Tag {
             Tag::PackagePath  => "package_path",
             Tag::PackageCpath => "package_cpath",
             Tag::StringMeta   => "string_meta",
+            Tag::CData        => "cdata",
+            Tag::SetMetatable => "setmetatable",
+            Tag::CoroutineCreate => "coroutine_create",
+            Tag::CoroutineWrap   => "coroutine_wrap",
+            Tag::CoroutineResume => "coroutine_resume",
+            Tag::CoroutineYield  => "coroutine_yield",
+            Tag::StringFind   => "string_find",
+            Tag::StringMatch  => "string_match",
+            Tag::StringGMatch => "string_gmatch",
+            Tag::StringFormat => "string_format",
+            Tag::Metatable(_) => "metatable",
             Tag::MakeClass(_) => "make_class",
+            Tag::IsInstanceOf => "is_instance_of",
+            Tag::IsSubclassOf => "is_subclass_of",
+            Tag::IncludeMixin => "include_mixin",
+            Tag::Readonly     => "readonly",
+            Tag::Newtype(_)   => "newtype",
+            Tag::Exhaustive   => "exhaustive",
 
             Tag::_Subtype         => "internal subtype",
             Tag::_NoSubtype       => "internal no_subtype",
@@ -232,13 +401,34 @@ impl Tag {
             Tag::AssertNot |
             Tag::AssertType |
             Tag::GenericPairs |
+            Tag::CoroutineCreate |
+            Tag::CoroutineWrap |
+            Tag::CoroutineResume |
+            Tag::CoroutineYield |
+            Tag::StringFind |
+            Tag::StringMatch |
+            Tag::StringGMatch |
+            Tag::StringFormat |
             Tag::MakeClass(_) |
+            Tag::IsInstanceOf |
+            Tag::IsSubclassOf |
+            Tag::IncludeMixin |
             Tag::KailuaGenTvar |
             Tag::KailuaAssertTvar => true,
             _ => false,
         }
     }
 
+    /// Returns true if the tagged type is not a subtype of the original type.
+    ///
+    /// Such tag should also need the strict subtyping rule.
+    pub fn is_nominal(&self) -> bool {
+        match *self {
+            Tag::Newtype(_) => true,
+            _ => false,
+        }
+    }
+
     /// Returns true if this tag needs the strict subtyping rule.
     ///
     /// For example, it is NOT possible to update `[type] function(any) -> string` with
@@ -252,7 +442,9 @@ impl Tag {
             Tag::_NoSubtype2 => false,
 
             Tag::PackagePath |
-            Tag::PackageCpath => false,
+            Tag::PackageCpath |
+            Tag::Readonly |
+            Tag::Exhaustive => false,
             _ => true,
         }
     }
@@ -266,6 +458,12 @@ impl fmt::Debug for Tag {
             Tag::MakeClass(csid) => {
                 write!(f, "({:?})", csid)?;
             }
+            Tag::Metatable(mtid) => {
+                write!(f, "({:?})", mtid)?;
+            }
+            Tag::Newtype(id) => {
+                write!(f, "({:?})", id)?;
+            }
             _ => {}
         }
 
@@ -290,3 +488,112 @@ impl Display for Tag {
     }
 }
 
+
+/// The visibility of class fields, from the least restrictive to the most restrictive.
+#[derive(Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Debug)]
+pub enum Visibility {
+    /// The field can be accessed anywhere.
+    Public,
+
+    /// The field can be only accessed from functions defined in the class defining the field
+    /// or its subclasses (`[protected]`).
+    Protected,
+
+    /// The field can be only accessed from functions defined in the class defining the field
+    /// (`[private]`).
+    Private,
+}
+
+/// Modifiers for class fields and methods.
+///
+/// They are given as type attributes like tags, but they can be freely combined
+/// with each other and with a tag (e.g. `[abstract] [protected] method()`).
+/// They never affect the subtyping, so plain values can be assigned to modified types.
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Modifiers {
+    /// True if this is an abstract method (`[abstract]`).
+    ///
+    /// Abstract methods should be overriden before the class is instantiated.
+    /// Class systems with a constructor report every abstract method left unimplemented
+    /// along the inheritance chain when the constructor is used.
+    /// Overriding methods do not have to be abstract themselves.
+    pub abstract_: bool,
+
+    /// The visibility of the field. Any access outside of the allowed scope results in an error.
+    pub visibility: Visibility,
+}
+
+impl Modifiers {
+    pub fn none() -> Modifiers {
+        Modifiers { abstract_: false, visibility: Visibility::Public }
+    }
+
+    pub fn is_none(&self) -> bool {
+        *self == Modifiers::none()
+    }
+
+    /// Returns `self` if it has any modifier, `other` otherwise. Analogous to `Option::or`.
+    pub fn or(self, other: Modifiers) -> Modifiers {
+        if self.is_none() { other } else { self }
+    }
+
+    /// Returns true if the attribute denotes a modifier instead of a tag.
+    pub fn is_modifier_attr(attr: &Attr) -> bool {
+        match &attr.name.base[..] {
+            b"abstract" | b"private" | b"protected" => true,
+            _ => false,
+        }
+    }
+
+    /// Adds a modifier from the attribute, which should satisfy `Modifiers::is_modifier_attr`.
+    ///
+    /// Duplicate or conflicting modifiers are reported but ignored,
+    /// except that the more restrictive visibility is used.
+    pub fn add_attr(&mut self, attr: &Attr, resolv: &mut TypeResolver) -> Result<()> {
+        if let Some(ref values) = attr.values {
+            resolv.error(values, m::AttrCannotHaveAnyValues { name: &attr.name }).done()?;
+        }
+
+        let visibility = match &attr.name.base[..] {
+            b"abstract" => {
+                if self.abstract_ {
+                    resolv.warn(&attr.name, m::DuplicateModifier { name: &attr.name }).done()?;
+                }
+                self.abstract_ = true;
+                return Ok(());
+            }
+            b"private" => Visibility::Private,
+            b"protected" => Visibility::Protected,
+            _ => panic!("Modifiers::add_attr given a non-modifier attribute"),
+        };
+
+        if self.visibility == visibility {
+            resolv.warn(&attr.name, m::DuplicateModifier { name: &attr.name }).done()?;
+        } else if self.visibility != Visibility::Public {
+            resolv.warn(&attr.name, m::ConflictingVisibility {}).done()?;
+        }
+        self.visibility = cmp::max(self.visibility, visibility);
+        Ok(())
+    }
+}
+
+// modifiers are displayed as attributes, each followed by a space (so that it can be
+// directly followed by the type)
+impl fmt::Debug for Modifiers {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.abstract_ {
+            write!(f, "[abstract] ")?;
+        }
+        match self.visibility {
+            Visibility::Public => Ok(()),
+            Visibility::Protected => write!(f, "[protected] "),
+            Visibility::Private => write!(f, "[private] "),
+        }
+    }
+}
+
+impl Display for Modifiers {
+    fn fmt_displayed(&self, f: &mut fmt::Formatter, _st: &DisplayState) -> fmt::Result {
+        fmt::Debug::fmt(self, f)
+    }
+}
@@ -0,0 +1,170 @@
+use std::fmt;
+use std::collections::HashMap;
+use kailua_diag::Result;
+use kailua_syntax::ast::ThreadKind;
+
+use diag::{Origin, TypeReport, TypeResult};
+use super::{Display, DisplayState, Ty, TySeq, TVar};
+use super::{TypeContext, TypeResolver, Lattice, Union};
+
+/// A typed thread (coroutine) type.
+#[derive(Clone, PartialEq)]
+pub struct Thread {
+    /// Types of arguments to `coroutine.resume`.
+    ///
+    /// The first `resume` passes them to the coroutine body,
+    /// and subsequent `resume`s make them returned from `coroutine.yield`.
+    pub args: TySeq,
+
+    /// Types of values given to `coroutine.yield`,
+    /// which become the return values of the corresponding `coroutine.resume`.
+    pub yields: TySeq,
+
+    /// Types of values returned from the coroutine body.
+    pub returns: TySeq,
+}
+
+impl Thread {
+    pub fn from_kind(th: &ThreadKind, resolv: &mut TypeResolver) -> Result<Thread> {
+        let args = TySeq::from_kind_seq(&th.args, |kind| kind, resolv)?;
+        let yields = TySeq::from_kind_seq(&th.yields, |kind| kind, resolv)?;
+        let returns = TySeq::from_kind_seq(&th.returns, |kind| kind, resolv)?;
+        Ok(Thread { args: args, yields: yields, returns: returns })
+    }
+
+    /// Returns the types of values that `coroutine.resume` can receive after `true`,
+    /// i.e. a union of yield and return types.
+    pub fn resumed(&self, ctx: &mut TypeContext) -> TySeq {
+        self.yields.union(&self.returns, false, ctx).unwrap_or_else(|_| TySeq::dummy())
+    }
+
+    pub fn substitute(&self, subst: &HashMap<TVar, Ty>, ctx: &mut TypeContext) -> Thread {
+        Thread {
+            args: self.args.substitute(subst, ctx),
+            yields: self.yields.substitute(subst, ctx),
+            returns: self.returns.substitute(subst, ctx),
+        }
+    }
+
+    fn assert_sub(&self, other: &Self, ctx: &mut TypeContext) -> TypeResult<()> {
+        // contravariant
+        other.args.assert_sub(&self.args, ctx)?;
+
+        // covariant
+        self.yields.assert_sub(&other.yields, ctx)?;
+        self.returns.assert_sub(&other.returns, ctx)
+    }
+
+    fn assert_eq(&self, other: &Self, ctx: &mut TypeContext) -> TypeResult<()> {
+        self.args.assert_eq(&other.args, ctx)?;
+        self.yields.assert_eq(&other.yields, ctx)?;
+        self.returns.assert_eq(&other.returns, ctx)
+    }
+
+    fn fmt_generic<WriteTy, WriteTySeq>(&self, f: &mut fmt::Formatter,
+                                        mut write_ty: WriteTy,
+                                        mut write_tyseq: WriteTySeq) -> fmt::Result
+            where WriteTy: FnMut(&Ty, &mut fmt::Formatter) -> fmt::Result,
+                  WriteTySeq: FnMut(&TySeq, &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "thread<")?;
+        let mut first = true;
+        for seq in &[&self.args, &self.yields, &self.returns] {
+            if first { first = false; } else { write!(f, ", ")?; }
+            if seq.head.len() == 1 && seq.tail.is_none() {
+                write_ty(&seq.head[0], f)?;
+            } else {
+                write_tyseq(seq, f)?;
+            }
+        }
+        write!(f, ">")
+    }
+}
+
+impl Display for Thread {
+    fn fmt_displayed(&self, f: &mut fmt::Formatter, st: &DisplayState) -> fmt::Result {
+        self.fmt_generic(
+            f,
+            |t, f| fmt::Display::fmt(&t.display(st), f),
+            |s, f| fmt::Display::fmt(&s.display(st), f),
+        )
+    }
+}
+
+impl fmt::Debug for Thread {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_generic(f, fmt::Debug::fmt, fmt::Debug::fmt)
+    }
+}
+
+/// Thread types.
+#[derive(Clone)]
+pub enum Threads {
+    /// A thread with known argument, yield and return types.
+    Simple(Thread),
+
+    /// Any thread.
+    All,
+}
+
+impl Threads {
+    pub fn substitute(&self, subst: &HashMap<TVar, Ty>, ctx: &mut TypeContext) -> Threads {
+        match *self {
+            Threads::Simple(ref th) => Threads::Simple(th.substitute(subst, ctx)),
+            Threads::All => Threads::All,
+        }
+    }
+
+    fn fmt_generic<WriteThread>(&self, f: &mut fmt::Formatter,
+                                mut write_thread: WriteThread) -> fmt::Result
+            where WriteThread: FnMut(&Thread, &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Threads::All => write!(f, "thread"),
+            Threads::Simple(ref th) => write_thread(th, f),
+        }
+    }
+}
+
+impl Lattice for Threads {
+    fn assert_sub(&self, other: &Self, ctx: &mut TypeContext) -> TypeResult<()> {
+        (|| {
+            match (self, other) {
+                (_, &Threads::All) => Ok(()),
+                (&Threads::All, _) => Err(ctx.gen_report()),
+
+                (&Threads::Simple(ref a), &Threads::Simple(ref b)) => a.assert_sub(b, ctx),
+            }
+        })().map_err(|r: TypeReport| r.not_sub(Origin::Threads, self, other, ctx))
+    }
+
+    fn assert_eq(&self, other: &Self, ctx: &mut TypeContext) -> TypeResult<()> {
+        (|| {
+            match (self, other) {
+                (&Threads::All, &Threads::All) => Ok(()),
+                (&Threads::Simple(ref a), &Threads::Simple(ref b)) => a.assert_eq(b, ctx),
+                (_, _) => Err(ctx.gen_report()),
+            }
+        })().map_err(|r: TypeReport| r.not_eq(Origin::Threads, self, other, ctx))
+    }
+}
+
+impl PartialEq for Threads {
+    fn eq(&self, other: &Threads) -> bool {
+        match (self, other) {
+            (&Threads::All, &Threads::All) => true,
+            (&Threads::Simple(ref a), &Threads::Simple(ref b)) => *a == *b,
+            (_, _) => false,
+        }
+    }
+}
+
+impl Display for Threads {
+    fn fmt_displayed(&self, f: &mut fmt::Formatter, st: &DisplayState) -> fmt::Result {
+        self.fmt_generic(f, |t, f| fmt::Display::fmt(&t.display(st), f))
+    }
+}
+
+impl fmt::Debug for Threads {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.fmt_generic(f, fmt::Debug::fmt)
+    }
+}
@@ -6,7 +6,7 @@ use kailua_syntax::Str;
 use diag::{TypeReport, TypeResult, Origin};
 use super::display::{Display, DisplayState, DisplayName};
 use super::{T, TypeContext, Lattice, Union};
-use super::{Numbers, Strings, Tables, Functions, Class};
+use super::{Numbers, Strings, Tables, Functions, Threads, Class};
 use super::flags::*;
 
 /// An expanded value type for unions.
@@ -33,6 +33,9 @@ pub struct Unioned {
     /// Function types, if any.
     pub functions: Option<Functions>,
 
+    /// Thread types, if any.
+    pub threads: Option<Threads>,
+
     /// A set of nominal types.
     pub classes: BTreeSet<Class>,
 
@@ -54,7 +57,8 @@ impl Unioned {
     pub fn empty() -> Unioned {
         Unioned {
             simple: U_NONE, numbers: None, strings: None, tables: None,
-            functions: None, classes: BTreeSet::new(), display_hints: Vec::new(),
+            functions: None, threads: None, classes: BTreeSet::new(),
+            display_hints: Vec::new(),
         }
     }
 
@@ -62,21 +66,24 @@ impl Unioned {
         let simple = if b { U_TRUE } else { U_FALSE };
         Unioned {
             simple: simple, numbers: None, strings: None, tables: None,
-            functions: None, classes: BTreeSet::new(), display_hints: Vec::new(),
+            functions: None, threads: None, classes: BTreeSet::new(),
+            display_hints: Vec::new(),
         }
     }
 
     pub fn explicit_int(v: i32) -> Unioned {
         Unioned {
             simple: U_NONE, numbers: Some(Numbers::One(v)), strings: None, tables: None,
-            functions: None, classes: BTreeSet::new(), display_hints: Vec::new(),
+            functions: None, threads: None, classes: BTreeSet::new(),
+            display_hints: Vec::new(),
         }
     }
 
     pub fn explicit_str(s: Str) -> Unioned {
         Unioned {
             simple: U_NONE, numbers: None, strings: Some(Strings::One(s)), tables: None,
-            functions: None, classes: BTreeSet::new(), display_hints: Vec::new(),
+            functions: None, threads: None, classes: BTreeSet::new(),
+            display_hints: Vec::new(),
         }
     }
 
@@ -87,15 +94,18 @@ impl Unioned {
             &T::Dynamic(_) | &T::All => {
                 panic!("Unioned::from called with T::Dynamic or T::All");
             }
-            &T::TVar(_) => {
+            &T::TVar(_) | &T::Intersection(_) => {
                 return Err(ctx.gen_report().cannot_union_single(ty, ctx));
             }
+            &T::Named(id, _) => {
+                let ty = ctx.get_named_type(id);
+                return Unioned::from(&ty, ctx);
+            }
 
             &T::None     => {}
             &T::Boolean  => { u.simple = U_BOOLEAN; }
             &T::True     => { u.simple = U_TRUE; }
             &T::False    => { u.simple = U_FALSE; }
-            &T::Thread   => { u.simple = U_THREAD; }
             &T::UserData => { u.simple = U_USERDATA; }
 
             &T::Number     => { u.numbers = Some(Numbers::All); }
@@ -106,6 +116,7 @@ impl Unioned {
 
             &T::Tables(ref tab)     => { u.tables = Some(tab.clone().into_owned()); }
             &T::Functions(ref func) => { u.functions = Some(func.clone().into_owned()); }
+            &T::Threads(ref th)     => { u.threads = Some(th.clone().into_owned()); }
             &T::Class(c)            => { u.classes.insert(c); }
 
             &T::Union(ref u) => return Ok(u.clone().into_owned()), // ignore `u` above
@@ -124,6 +135,7 @@ impl Unioned {
         if self.strings.is_some()   { flags.insert(T_STRING); }
         if self.tables.is_some()    { flags.insert(T_TABLE); }
         if self.functions.is_some() { flags.insert(T_FUNCTION); }
+        if self.threads.is_some()   { flags.insert(T_THREAD); }
         if !self.classes.is_empty() { flags.insert(T_TABLE); }
         flags
     }
@@ -137,7 +149,9 @@ impl Unioned {
         } else if simple.contains(U_FALSE) {
             f(T::False)?;
         }
-        if simple.contains(U_THREAD) { f(T::Thread)?; }
+        if mask.contains(T_THREAD) {
+            if let Some(ref th) = self.threads { f(T::Threads(Cow::Borrowed(th)))?; }
+        }
         if simple.contains(U_USERDATA) { f(T::UserData)?; }
 
         match self.numbers {
@@ -328,12 +342,23 @@ impl Union for Unioned {
                 lhs.clone()
             });
 
+            // typed threads are absorbed by `thread`, otherwise they should be equal
+            let threads = union_options!(&self.threads, &other.threads, |lhs, rhs| {
+                match (lhs, rhs) {
+                    (&Threads::All, _) | (_, &Threads::All) => Threads::All,
+                    (_, _) => {
+                        lhs.assert_eq(rhs, ctx)?;
+                        lhs.clone()
+                    }
+                }
+            });
+
             let mut classes = self.classes.clone();
             classes.extend(other.classes.iter().cloned());
 
             let mut u = Unioned {
                 simple: simple, numbers: numbers, strings: strings,
-                tables: tables, functions: functions, classes: classes,
+                tables: tables, functions: functions, threads: threads, classes: classes,
                 display_hints: self.display_hints.clone(),
             };
             for &(flags, ref name) in &other.display_hints {
@@ -368,6 +393,7 @@ impl Lattice for Unioned {
             assert_sub_options!(&self.strings, &other.strings);
             assert_sub_options!(&self.tables, &other.tables);
             assert_sub_options!(&self.functions, &other.functions);
+            assert_sub_options!(&self.threads, &other.threads);
 
             if !self.classes.is_subset(&other.classes) {
                 return Err(ctx.gen_report());
@@ -399,6 +425,7 @@ impl Lattice for Unioned {
             assert_eq_options!(&self.strings, &other.strings);
             assert_eq_options!(&self.tables, &other.tables);
             assert_eq_options!(&self.functions, &other.functions);
+            assert_eq_options!(&self.threads, &other.threads);
 
             if self.classes != other.classes {
                 return Err(ctx.gen_report());
@@ -13,7 +13,8 @@ use diag::{Origin, TypeReport, TypeResult, TypeReportHint, TypeReportMore};
 use super::display::{Display, DisplayState, DisplayName};
 use super::{TypeContext, NoTypeContext, TypeResolver};
 use super::{F, Slot, Lattice, Union, Dummy};
-use super::{Numbers, Strings, Key, Tables, Function, Functions, Unioned, TVar, Tag, Class};
+use super::{Numbers, Strings, Key, Tables, Function, Functions, Thread, Threads};
+use super::{Unioned, TVar, RVar, Tag, Modifiers, Class, NamedId};
 use super::flags::*;
 use message as m;
 
@@ -88,9 +89,6 @@ pub enum T<'a> {
     /// `string`.
     String,
 
-    /// `thread`.
-    Thread,
-
     /// `userdata`.
     UserData,
 
@@ -106,6 +104,9 @@ pub enum T<'a> {
     /// Function types.
     Functions(Cow<'a, Functions>),
 
+    /// Thread types.
+    Threads(Cow<'a, Threads>),
+
     /// Nominal types.
     Class(Class),
 
@@ -114,6 +115,18 @@ pub enum T<'a> {
 
     /// Union types, or explicit literal types.
     Union(Cow<'a, Unioned>),
+
+    /// Intersection types, which can be generated only from the type specification.
+    ///
+    /// A value of the intersection type should satisfy all of its parts at once.
+    /// Nested intersections are flattened and there are at least two parts.
+    Intersection(Cow<'a, [Ty]>),
+
+    /// A reference to the recursive named type, which only occurs inside its own definition.
+    ///
+    /// The definition is kept in the type context and should be unfolded when needed.
+    /// Flags of the definition are copied here, as `T::flags` cannot access the type context.
+    Named(NamedId, Flags),
 }
 
 impl<'a> T<'a> {
@@ -125,6 +138,8 @@ impl<'a> T<'a> {
     */
     pub fn function()        -> T<'a> { T::Functions(Cow::Owned(Functions::All)) }
     pub fn func(f: Function) -> T<'a> { T::Functions(Cow::Owned(Functions::Simple(f))) }
+    pub fn thread()          -> T<'a> { T::Threads(Cow::Owned(Threads::All)) }
+    pub fn coroutine(th: Thread) -> T<'a> { T::Threads(Cow::Owned(Threads::Simple(th))) }
 
     pub fn ints<I: IntoIterator<Item=i32>>(i: I) -> T<'a> {
         let mut u = Unioned::empty();
@@ -165,7 +180,6 @@ impl<'a> T<'a> {
             T::Boolean  => T_BOOLEAN,
             T::True     => T_TRUE,
             T::False    => T_FALSE,
-            T::Thread   => T_THREAD,
             T::UserData => T_USERDATA,
 
             T::Number   => T_NUMBER,
@@ -176,10 +190,13 @@ impl<'a> T<'a> {
 
             T::Tables(..) => T_TABLE,
             T::Functions(..) => T_FUNCTION,
+            T::Threads(..) => T_THREAD,
             T::Class(..) => T_TABLE,
 
             T::TVar(..) => T_NONE,
             T::Union(ref u) => u.flags(),
+            T::Intersection(ref tys) => tys.iter().fold(T_ALL, |flags, ty| flags & (**ty).flags()),
+            T::Named(_, flags) => flags,
         }
     }
 
@@ -192,7 +209,6 @@ impl<'a> T<'a> {
             T::Boolean  => T::Boolean,
             T::True     => T::True,
             T::False    => T::False,
-            T::Thread   => T::Thread,
             T::UserData => T::UserData,
 
             T::Number     => T::Number,
@@ -203,9 +219,12 @@ impl<'a> T<'a> {
 
             T::Tables(ref tab) => T::Tables(Cow::Borrowed(&**tab)),
             T::Functions(ref func) => T::Functions(Cow::Borrowed(&**func)),
+            T::Threads(ref th) => T::Threads(Cow::Borrowed(&**th)),
             T::Class(c) => T::Class(c),
             T::TVar(v) => T::TVar(v),
             T::Union(ref u) => T::Union(Cow::Borrowed(&**u)),
+            T::Intersection(ref tys) => T::Intersection(Cow::Borrowed(&**tys)),
+            T::Named(id, flags) => T::Named(id, flags),
         }
     }
 
@@ -235,6 +254,56 @@ impl<'a> T<'a> {
         }
     }
 
+    // returns false if the string part of the type is a set of literals not including `s`.
+    // other types (including `string`) may always be equal to `s`.
+    pub fn may_equal_str(&self, s: &Str) -> bool {
+        match *self {
+            T::Str(ref s_) => **s_ == *s,
+            T::Union(ref u) => match u.strings {
+                Some(Strings::One(ref s_)) => *s_ == *s,
+                Some(Strings::Some(ref set)) => set.contains(s),
+                _ => true,
+            },
+            _ => true,
+        }
+    }
+
+    // used for narrowing a union of string literals (e.g. enums) by comparison.
+    // returns `None` when the comparison cannot narrow the type, including plain `string`.
+    pub fn narrow_str(&self, s: &Str, eq: bool) -> Option<T<'static>> {
+        let mut u = match *self {
+            T::Str(ref s) => Unioned::explicit_str((**s).to_owned()),
+            T::Union(ref u) => u.clone().into_owned(),
+            _ => return None,
+        };
+
+        let contains = match u.strings {
+            Some(Strings::Some(ref set)) => set.contains(s),
+            Some(Strings::One(ref s_)) => *s_ == *s,
+            Some(Strings::All) | None => false,
+        };
+        if !contains {
+            return None;
+        }
+        if eq {
+            return Some(T::Str(Cow::Owned(s.clone())));
+        }
+
+        u.strings = match u.strings.take() {
+            Some(Strings::Some(mut set)) => {
+                set.remove(s);
+                if set.len() == 1 {
+                    Some(Strings::One(set.into_iter().next().unwrap()))
+                } else {
+                    Some(Strings::Some(set))
+                }
+            }
+            _ => None,
+        };
+        u.filter_display_hints(T_STRING);
+        Some(u.simplify())
+    }
+
     pub fn is_dynamic(&self)  -> bool { self.flags().is_dynamic() }
     pub fn is_integral(&self) -> bool { self.flags().is_integral() }
     pub fn is_numeric(&self)  -> bool { self.flags().is_numeric() }
@@ -267,6 +336,14 @@ impl<'a> T<'a> {
         }
     }
 
+    pub fn get_threads(&self) -> Option<&Threads> {
+        match *self {
+            T::Threads(ref th) => Some(th),
+            T::Union(ref u) => u.threads.as_ref(),
+            _ => None,
+        }
+    }
+
     pub fn get_tvar(&self) -> Option<TVar> {
         match *self {
             T::TVar(tv) => Some(tv),
@@ -338,7 +415,6 @@ impl<'a> T<'a> {
             T::Boolean    => T::Boolean,
             T::True       => T::True,
             T::False      => T::False,
-            T::Thread     => T::Thread,
             T::UserData   => T::UserData,
 
             T::Number     => T::Number,
@@ -352,6 +428,7 @@ impl<'a> T<'a> {
 
             // functions are _not_ recursively altered (will be generalized at call site)
             T::Functions(func) => T::Functions(Cow::Owned(func.into_owned())),
+            T::Threads(th) => T::Threads(Cow::Owned(th.into_owned())),
 
             T::Class(c) => T::Class(c),
             T::TVar(tv) => T::TVar(ctx.copy_tvar(tv)),
@@ -362,6 +439,41 @@ impl<'a> T<'a> {
                 u.tables = u.tables.take().map(|tab| tab.generalize(ctx));
                 T::Union(Cow::Owned(u))
             },
+
+            // so are intersections
+            T::Intersection(tys) => {
+                let tys = tys.into_owned().into_iter().map(|ty| ty.generalize(ctx)).collect();
+                T::Intersection(Cow::Owned(tys))
+            },
+
+            // recursive named types are never altered, their definitions have no variables
+            T::Named(id, flags) => T::Named(id, flags),
+        }
+    }
+
+    // replaces type variables in `subst` recursively, including ones inside functions.
+    // type variables at the top level are handled by `Ty::substitute`,
+    // as they have to retain the nilability and tag of the original type.
+    pub fn substitute(&self, subst: &HashMap<TVar, Ty>, ctx: &mut TypeContext) -> T<'static> {
+        match *self {
+            T::Tables(ref tab) => T::Tables(Cow::Owned(tab.substitute(subst, ctx))),
+            T::Functions(ref func) => T::Functions(Cow::Owned(func.substitute(subst, ctx))),
+            T::Threads(ref th) => T::Threads(Cow::Owned(th.substitute(subst, ctx))),
+
+            T::Union(ref u) => {
+                let mut u = u.clone().into_owned();
+                u.tables = u.tables.take().map(|tab| tab.substitute(subst, ctx));
+                u.functions = u.functions.take().map(|func| func.substitute(subst, ctx));
+                u.threads = u.threads.take().map(|th| th.substitute(subst, ctx));
+                T::Union(Cow::Owned(u))
+            },
+
+            T::Intersection(ref tys) => {
+                let tys = tys.iter().map(|ty| ty.substitute(subst, ctx)).collect();
+                T::Intersection(Cow::Owned(tys))
+            },
+
+            ref t => t.clone().into_send(),
         }
     }
 
@@ -374,7 +486,6 @@ impl<'a> T<'a> {
             T::Boolean    => T::Boolean,
             T::True       => T::True,
             T::False      => T::False,
-            T::Thread     => T::Thread,
             T::UserData   => T::UserData,
 
             T::Number     => T::Number,
@@ -385,10 +496,13 @@ impl<'a> T<'a> {
 
             T::Tables(tab)     => T::Tables(Cow::Owned(tab.into_owned())),
             T::Functions(func) => T::Functions(Cow::Owned(func.into_owned())),
+            T::Threads(th)     => T::Threads(Cow::Owned(th.into_owned())),
             T::Class(c)        => T::Class(c),
             T::TVar(tv)        => T::TVar(tv),
 
             T::Union(u) => T::Union(Cow::Owned(u.into_owned())),
+            T::Intersection(tys) => T::Intersection(Cow::Owned(tys.into_owned())),
+            T::Named(id, flags) => T::Named(id, flags),
         }
     }
 
@@ -405,7 +519,7 @@ impl<'a> T<'a> {
             if flags.contains(T_STRING)     { t = t | T::String; }
             if flags.contains(T_TABLE)      { t = t | T::table(); }
             if flags.contains(T_FUNCTION)   { t = t | T::function(); }
-            if flags.contains(T_THREAD)     { t = t | T::Thread; }
+            if flags.contains(T_THREAD)     { t = t | T::thread(); }
             if flags.contains(T_USERDATA)   { t = t | T::UserData; }
             t
         }
@@ -455,17 +569,37 @@ impl<'a> T<'a> {
             T::Int(_)   => Ok(flags_or_none(T_INTEGER)),
             T::True     => Ok(flags_or_none(T_TRUE)),
             T::False    => Ok(flags_or_none(T_FALSE)),
-            T::Thread   => Ok(flags_or_none(T_THREAD)),
             T::UserData => Ok(flags_or_none(T_USERDATA)),
             T::String   => Ok(flags_or_none(T_STRING)),
 
             T::Str(_)       => Ok(flags_or_none(T_STRING)),
             T::Tables(_)    => Ok(flags_or_none(T_TABLE)),
             T::Functions(_) => Ok(flags_or_none(T_FUNCTION)),
+            T::Threads(_)   => Ok(flags_or_none(T_THREAD)),
             T::Class(_)     => Ok(flags_or_none(T_TABLE)),
 
             T::TVar(tv) => Ok(Cow::Owned(T::TVar(narrow_tvar(tv, flags, ctx)?))),
 
+            // recursive named types are kept as is unless they are partially removed
+            T::Named(id, namedflags) => {
+                if (namedflags & !flags).is_empty() {
+                    Ok(Cow::Borrowed(self))
+                } else {
+                    let t = ctx.get_named_type(id).unwrap();
+                    let t = t.filter_by_flags(flags, ctx)?.into_owned().into_send();
+                    Ok(Cow::Owned(t))
+                }
+            },
+
+            // intersections are kept as is unless they are entirely removed
+            T::Intersection(_) => {
+                if self.flags().intersects(flags) {
+                    Ok(Cow::Borrowed(self))
+                } else {
+                    Ok(Cow::Owned(T::None))
+                }
+            },
+
             T::Union(ref u) => {
                 // compile a list of flags to remove, and only alter if there is any removal
                 let removed = !flags & u.flags();
@@ -483,6 +617,7 @@ impl<'a> T<'a> {
                 if removed.contains(T_STRING)   { u.strings   = None; }
                 if removed.contains(T_TABLE)    { u.tables    = None; }
                 if removed.contains(T_FUNCTION) { u.functions = None; }
+                if removed.contains(T_THREAD)   { u.threads   = None; }
                 u.filter_display_hints(removed);
                 Ok(Cow::Owned(u.simplify()))
             },
@@ -520,7 +655,6 @@ impl<'a> Lattice<Unioned> for T<'a> {
                 T::Boolean  => other.simple.contains(U_BOOLEAN),
                 T::True     => other.simple.contains(U_TRUE),
                 T::False    => other.simple.contains(U_FALSE),
-                T::Thread   => other.simple.contains(U_THREAD),
                 T::UserData => other.simple.contains(U_USERDATA),
 
                 T::Number => match other.numbers {
@@ -561,7 +695,22 @@ impl<'a> Lattice<Unioned> for T<'a> {
                     }
                     false
                 },
-                T::Class(c) => other.classes.contains(&c),
+                T::Threads(ref lhs) => {
+                    if let Some(ref th) = other.threads {
+                        return lhs.assert_sub(th, ctx);
+                    }
+                    false
+                },
+                T::Class(c) => {
+                    if other.classes.contains(&c) {
+                        true
+                    } else if let Some(ref tab @ Tables::Interface(_)) = other.tables {
+                        // nominal types can structurally conform to interfaces
+                        return self.assert_sub(&T::Tables(Cow::Borrowed(tab)), ctx);
+                    } else {
+                        false
+                    }
+                },
 
                 T::TVar(lhs) => {
                     let otherty = &Ty::new(T::Union(Cow::Owned(other.clone())));
@@ -572,6 +721,17 @@ impl<'a> Lattice<Unioned> for T<'a> {
                     return lhs.assert_sub(other, ctx);
                 },
 
+                // a1 /\ a2 <: b === a1 <: b OR a2 <: b
+                T::Intersection(ref lhs) => {
+                    let otherty = T::Union(Cow::Borrowed(other));
+                    lhs.iter().any(|ty| ty.assert_sub(&otherty, ctx).is_ok())
+                },
+
+                T::Named(id, _) => {
+                    let lhs = ctx.get_named_type(id);
+                    return lhs.unwrap().assert_sub(other, ctx);
+                },
+
                 _ => false,
             };
 
@@ -609,13 +769,16 @@ impl<'a> T<'a> {
         fn resolve<'t, 'u>(t: &'t T<'u>, ctx: &mut TypeContext)
             -> (Cow<'t, T<'u>>, Option<(Nil, Option<Tag>)>, Option<DisplayHint>)
         {
-            if let &T::TVar(tv) = t {
-                if let Some(ty) = ctx.get_tvar_exact_type(tv) {
-                    let nil = ty.nil();
-                    let tag = ty.tag();
-                    let hint = ty.display_hint().cloned();
-                    return (Cow::Owned(ty.unwrap()), Some((nil, tag)), hint);
-                }
+            let ty = match *t {
+                T::TVar(tv) => ctx.get_tvar_exact_type(tv),
+                T::Named(id, _) => Some(ctx.get_named_type(id)),
+                _ => None,
+            };
+            if let Some(ty) = ty {
+                let nil = ty.nil();
+                let tag = ty.tag();
+                let hint = ty.display_hint().cloned();
+                return (Cow::Owned(ty.unwrap()), Some((nil, tag)), hint);
             }
             (Cow::Borrowed(t), None, None)
         }
@@ -658,7 +821,6 @@ impl<'a> T<'a> {
                 (&T::True,     &T::False)    if !explicit => (T::Boolean, NONE),
                 (&T::False,    &T::True)     if !explicit => (T::Boolean, NONE),
                 (&T::False,    &T::False)    => (T::False, BOTH),
-                (&T::Thread,   &T::Thread)   => (T::Thread, BOTH),
                 (&T::UserData, &T::UserData) => (T::UserData, BOTH),
 
                 (&T::Number,  &T::Number)  => (T::Number, BOTH),
@@ -699,6 +861,25 @@ impl<'a> T<'a> {
                     (T::Functions(Cow::Owned(a.clone().into_owned())), BOTH)
                 },
 
+                // typed threads are absorbed by `thread`, otherwise they should be equal
+                (&T::Threads(ref a), &T::Threads(ref b)) => {
+                    match (&**a, &**b) {
+                        (&Threads::All, &Threads::All) => (T::thread(), BOTH),
+                        (&Threads::All, _) => (T::thread(), LEFT),
+                        (_, &Threads::All) => (T::thread(), RIGHT),
+                        (_, _) => {
+                            a.assert_eq(b, ctx)?;
+                            (T::Threads(Cow::Owned(a.clone().into_owned())), BOTH)
+                        },
+                    }
+                },
+
+                // intersections cannot be unioned unless they are equal
+                (&T::Intersection(_), &T::Intersection(_)) => {
+                    t1.assert_eq(&*t2, ctx)?;
+                    ((*t1).clone().into_send(), BOTH)
+                },
+
                 // unresolved type variables should be equal to each other to be unioned
                 (&T::TVar(a), &T::TVar(b)) => {
                     ctx.assert_tvar_eq_tvar(a, b)?;
@@ -807,7 +988,6 @@ impl<'a, 'b> Lattice<T<'b>> for T<'a> {
                 (&T::True,     &T::True)     => true,
                 (&T::False,    &T::Boolean)  => true,
                 (&T::False,    &T::False)    => true,
-                (&T::Thread,   &T::Thread)   => true,
                 (&T::UserData, &T::UserData) => true,
 
                 (&T::Number,     &T::Number)     => true,
@@ -822,6 +1002,7 @@ impl<'a, 'b> Lattice<T<'b>> for T<'a> {
 
                 (&T::Tables(ref a),    &T::Tables(ref b))    => return a.assert_sub(b, ctx),
                 (&T::Functions(ref a), &T::Functions(ref b)) => return a.assert_sub(b, ctx),
+                (&T::Threads(ref a),   &T::Threads(ref b))   => return a.assert_sub(b, ctx),
 
                 (&T::Class(Class::Prototype(a)), &T::Class(Class::Prototype(b))) => {
                     a == b // prototypes are NOT compatible to each other!
@@ -830,6 +1011,35 @@ impl<'a, 'b> Lattice<T<'b>> for T<'a> {
                     ctx.is_subclass_of(a, b)
                 },
 
+                // nominal types can structurally conform to interfaces
+                (&T::Class(c), &T::Tables(ref b)) => match **b {
+                    Tables::Interface(ref iface) => {
+                        let fields = ctx.get_class_fields(c);
+                        return iface.assert_conform(&fields, ctx);
+                    },
+                    _ => false,
+                },
+
+                // recursive named types are unfolded, assuming that the relation being asserted
+                // holds when the same relation is encountered again
+                (&T::Named(a, _), &T::Named(b, _)) => return assert_named_rel(a, b, false, ctx),
+                (&T::Named(a, _), b) => {
+                    let a = ctx.get_named_type(a);
+                    return a.unwrap().assert_sub(b, ctx);
+                },
+                (a, &T::Named(b, _)) => {
+                    let b = ctx.get_named_type(b);
+                    return a.assert_sub(&b.unwrap(), ctx);
+                },
+
+                // a <: b1 /\ b2 === a <: b1 AND a <: b2
+                (a, &T::Intersection(ref b)) => {
+                    for ty in b.iter() {
+                        a.assert_sub(ty, ctx)?;
+                    }
+                    return Ok(());
+                },
+
                 (&T::Union(ref a), &T::Union(ref b)) => return a.assert_sub(b, ctx),
                 (&T::Union(_), &T::TVar(b)) => {
                     // do NOT try to split `T|U <: x` into `T <: x AND U <: x` if possible
@@ -846,6 +1056,8 @@ impl<'a, 'b> Lattice<T<'b>> for T<'a> {
                 (a, &T::TVar(b)) => return ctx.assert_tvar_sup(b, &Ty::new(a.clone().into_send())),
                 (&T::TVar(a), b) => return ctx.assert_tvar_sub(a, &Ty::new(b.clone().into_send())),
 
+                (&T::Intersection(ref a), b) => assert_intersection_sub(self, a, b, ctx)?,
+
                 (_, _) => false,
             };
 
@@ -866,7 +1078,6 @@ impl<'a, 'b> Lattice<T<'b>> for T<'a> {
                 (&T::Boolean,  &T::Boolean)  => true,
                 (&T::True,     &T::True)     => true,
                 (&T::False,    &T::False)    => true,
-                (&T::Thread,   &T::Thread)   => true,
                 (&T::UserData, &T::UserData) => true,
 
                 (&T::Number,     &T::Number)     => true,
@@ -877,8 +1088,30 @@ impl<'a, 'b> Lattice<T<'b>> for T<'a> {
 
                 (&T::Tables(ref a),    &T::Tables(ref b))    => return a.assert_eq(b, ctx),
                 (&T::Functions(ref a), &T::Functions(ref b)) => return a.assert_eq(b, ctx),
+                (&T::Threads(ref a),   &T::Threads(ref b))   => return a.assert_eq(b, ctx),
                 (&T::Class(a),         &T::Class(b))         => a == b,
 
+                (&T::Intersection(ref a), &T::Intersection(ref b)) => {
+                    if a.len() != b.len() {
+                        false
+                    } else {
+                        for (a, b) in a.iter().zip(b.iter()) {
+                            a.assert_eq(b, ctx)?;
+                        }
+                        true
+                    }
+                },
+
+                (&T::Named(a, _), &T::Named(b, _)) => return assert_named_rel(a, b, true, ctx),
+                (&T::Named(a, _), b) => {
+                    let a = ctx.get_named_type(a);
+                    return a.unwrap().assert_eq(b, ctx);
+                },
+                (a, &T::Named(b, _)) => {
+                    let b = ctx.get_named_type(b);
+                    return a.assert_eq(&b.unwrap(), ctx);
+                },
+
                 (&T::TVar(a), &T::TVar(b)) => return a.assert_eq(&b, ctx),
                 (a, &T::TVar(b)) => return ctx.assert_tvar_eq(b, &Ty::new(a.clone().into_send())),
                 (&T::TVar(a), b) => return ctx.assert_tvar_eq(a, &Ty::new(b.clone().into_send())),
@@ -894,6 +1127,53 @@ impl<'a, 'b> Lattice<T<'b>> for T<'a> {
     }
 }
 
+// asserts a relation between two recursive named types by comparing their definitions.
+// the relation is assumed to hold while it is being asserted, so that it can be coinductively
+// proved even when definitions are unfolded indefinitely.
+fn assert_named_rel(lhs: NamedId, rhs: NamedId, eq: bool,
+                    ctx: &mut TypeContext) -> TypeResult<()> {
+    if lhs == rhs || !ctx.mark_named_types(lhs, rhs, eq) {
+        return Ok(());
+    }
+
+    let lhsty = ctx.get_named_type(lhs);
+    let rhsty = ctx.get_named_type(rhs);
+    let ret = if eq { lhsty.assert_eq(&rhsty, ctx) } else { lhsty.assert_sub(&rhsty, ctx) };
+    ctx.unmark_named_types(lhs, rhs, eq);
+    ret
+}
+
+// a1 /\ a2 <: b === a1 <: b OR a2 <: b, except when b is an interface;
+// the interface can be satisfied by members spread across multiple parts.
+fn assert_intersection_sub(lhs: &T, parts: &[Ty], rhs: &T,
+                           ctx: &mut TypeContext) -> TypeResult<bool> {
+    if let T::Tables(ref tab) = *rhs {
+        if let Tables::Interface(ref iface) = **tab {
+            let selfty = Ty::new(lhs.clone().into_send());
+            let mut fields = Vec::new();
+            for ty in parts {
+                match **ty {
+                    T::Tables(ref tab) => match **tab {
+                        Tables::Fields(ref rvar) => {
+                            fields.extend(ctx.get_rvar_fields(rvar.clone()));
+                        },
+                        Tables::Interface(ref iface) => {
+                            fields.extend(iface.member_slots(&selfty));
+                        },
+                        _ => {},
+                    },
+                    T::Class(c) => fields.extend(ctx.get_class_fields(c)),
+                    _ => {},
+                }
+            }
+            iface.assert_conform(&fields, ctx)?;
+            return Ok(true);
+        }
+    }
+
+    Ok(parts.iter().any(|ty| ty.assert_sub(rhs, ctx).is_ok()))
+}
+
 impl<'a, 'b> ops::BitOr<T<'b>> for T<'a> {
     type Output = T<'static>;
     fn bitor(self, rhs: T<'b>) -> T<'static> {
@@ -914,7 +1194,6 @@ impl<'a, 'b> PartialEq<T<'b>> for T<'a> {
             (&T::Boolean,  &T::Boolean)  => true,
             (&T::True,     &T::True)     => true,
             (&T::False,    &T::False)    => true,
-            (&T::Thread,   &T::Thread)   => true,
             (&T::UserData, &T::UserData) => true,
 
             (&T::Number,     &T::Number)     => true,
@@ -925,9 +1204,12 @@ impl<'a, 'b> PartialEq<T<'b>> for T<'a> {
 
             (&T::Tables(ref a),    &T::Tables(ref b))    => *a == *b,
             (&T::Functions(ref a), &T::Functions(ref b)) => *a == *b,
+            (&T::Threads(ref a),   &T::Threads(ref b))   => *a == *b,
             (&T::Class(a),         &T::Class(b))         => a == b,
             (&T::TVar(a),          &T::TVar(b))          => a == b,
             (&T::Union(ref a),     &T::Union(ref b))     => a == b,
+            (&T::Intersection(ref a), &T::Intersection(ref b)) => a == b,
+            (&T::Named(a, _),         &T::Named(b, _))         => a == b,
 
             (_, _) => false,
         }
@@ -955,7 +1237,6 @@ impl<'a> Display for T<'a> {
             T::Boolean  => write!(f, "boolean"),
             T::True     => write!(f, "true"),
             T::False    => write!(f, "false"),
-            T::Thread   => write!(f, "thread"),
             T::UserData => write!(f, "userdata"),
 
             T::Number     => write!(f, "number"),
@@ -983,8 +1264,24 @@ impl<'a> Display for T<'a> {
 
             T::Tables(ref tab)      => fmt::Display::fmt(&tab.display(st), f),
             T::Functions(ref func)  => fmt::Display::fmt(&func.display(st), f),
+            T::Threads(ref th)      => fmt::Display::fmt(&th.display(st), f),
             T::Class(c)             => fmt::Display::fmt(&c.display(st), f),
             T::Union(ref u)         => fmt::Display::fmt(&u.display(st), f),
+
+            T::Intersection(ref tys) => {
+                let mut first = true;
+                for ty in tys.iter() {
+                    if first { first = false; } else { write!(f, " & ")?; }
+                    fmt::Display::fmt(&ty.display(st), f)?;
+                }
+                Ok(())
+            },
+
+            // recursive named types are always displayed by names, so they never loop
+            T::Named(id, _) => {
+                let name = DisplayName::Type(st.context.get_named_type_name(id).clone());
+                fmt::Display::fmt(&name.display(st), f)
+            },
         }
     }
 }
@@ -1000,7 +1297,6 @@ impl<'a> fmt::Debug for T<'a> {
             T::Boolean  => write!(f, "boolean"),
             T::True     => write!(f, "true"),
             T::False    => write!(f, "false"),
-            T::Thread   => write!(f, "thread"),
             T::UserData => write!(f, "userdata"),
 
             T::Number     => write!(f, "number"),
@@ -1011,9 +1307,21 @@ impl<'a> fmt::Debug for T<'a> {
 
             T::Tables(ref tab)     => fmt::Debug::fmt(tab, f),
             T::Functions(ref func) => fmt::Debug::fmt(func, f),
+            T::Threads(ref th)     => fmt::Debug::fmt(th, f),
             T::Class(ref c)        => fmt::Debug::fmt(c, f),
             T::TVar(ref tv)        => fmt::Debug::fmt(tv, f),
             T::Union(ref u)        => fmt::Debug::fmt(u, f),
+
+            T::Intersection(ref tys) => {
+                let mut first = true;
+                for ty in tys.iter() {
+                    if first { first = false; } else { write!(f, " & ")?; }
+                    fmt::Debug::fmt(ty, f)?;
+                }
+                Ok(())
+            },
+
+            T::Named(ref id, _) => fmt::Debug::fmt(id, f),
         }
     }
 }
@@ -1089,12 +1397,13 @@ struct TyInner {
     ty: T<'static>,
     nil: Nil,
     tag: Option<Tag>,
+    modifiers: Modifiers,
     display_hint: Option<Box<DisplayHint>>,
 }
 
 impl TyInner {
     fn new(ty: T<'static>, nil: Nil) -> TyInner {
-        TyInner { ty: ty, nil: nil, tag: None, display_hint: None }
+        TyInner { ty: ty, nil: nil, tag: None, modifiers: Modifiers::none(), display_hint: None }
     }
 
     fn nil(&self) -> Nil { self.nil }
@@ -1132,6 +1441,9 @@ impl TyInner {
     fn tag(&self) -> Option<Tag> { self.tag }
     fn set_tag(&mut self, tag: Option<Tag>) { self.tag = tag; }
 
+    fn modifiers(&self) -> Modifiers { self.modifiers }
+    fn set_modifiers(&mut self, modifiers: Modifiers) { self.modifiers = modifiers; }
+
     fn display_hint(&self) -> Option<&DisplayHint> {
         self.display_hint.as_ref().map(|hint| &**hint)
     }
@@ -1202,9 +1514,27 @@ impl Ty {
             K::String            => Ty::new(T::String),
             K::Table             => Ty::new(T::Tables(Cow::Owned(Tables::All))),
             K::Function          => Ty::new(T::Functions(Cow::Owned(Functions::All))),
-            K::Thread            => Ty::new(T::Thread),
+            K::Thread            => Ty::new(T::thread()),
+            K::ThreadOf(ref th)  => Ty::new(T::coroutine(Thread::from_kind(th, resolv)?)),
             K::UserData          => Ty::new(T::UserData),
-            K::Named(ref name)   => resolv.ty_from_name(name)?,
+            K::Named(ref name, ref args) => {
+                let ty = resolv.ty_from_name(name)?;
+                let tparams = resolv.type_params_from_name(name);
+                if tparams.len() != args.len() {
+                    resolv.error(kind, m::WrongTypeArgsArity { name: name, expected: tparams.len(),
+                                                               actual: args.len() })
+                          .done()?;
+                    Ty::dummy()
+                } else if tparams.is_empty() {
+                    ty
+                } else {
+                    let mut subst = HashMap::new();
+                    for (&tvar, arg) in tparams.iter().zip(args.iter()) {
+                        subst.insert(tvar, Ty::from_kind(arg, resolv)?);
+                    }
+                    ty.substitute(&subst, resolv.context_mut())
+                }
+            },
             K::WithNil(ref k)    => Ty::from_kind(k, resolv)?.or_nil(Nil::Noisy),
             K::WithoutNil(ref k) => Ty::from_kind(k, resolv)?.or_nil(Nil::Absent),
             // XXX think about the possibility of nil? and nil! more
@@ -1320,10 +1650,44 @@ impl Ty {
                 ty.base
             }
 
+            K::Intersection(ref kinds) => {
+                assert!(!kinds.is_empty());
+                let mut tys = Vec::new();
+                for k in kinds {
+                    let ty = Ty::from_kind(k, resolv)?;
+                    // intersections from named types are flattened
+                    if let T::Intersection(ref parts) = *ty {
+                        tys.extend(parts.iter().cloned());
+                        continue;
+                    }
+                    tys.push(ty);
+                }
+                if tys.len() == 1 {
+                    tys.pop().unwrap()
+                } else {
+                    Ty::new(T::Intersection(Cow::Owned(tys)))
+                }
+            }
+
+            K::Readonly(ref k) => {
+                let ty = Ty::from_kind(k, resolv)?;
+                if let Some(ty) = ty.to_readonly(resolv.context_mut()) {
+                    ty
+                } else {
+                    resolv.error(kind, m::NonTableReadonly { ty: ty.display(resolv.context()) })
+                          .done()?;
+                    ty
+                }
+            }
+
             K::Attr(ref kind, ref attr) => {
                 let mut ty = Ty::from_kind(kind, resolv)?;
-                // None is simply ignored, `Tag::from` has already reported the error
-                if let Some(tag) = Tag::from(attr, resolv)? {
+                if Modifiers::is_modifier_attr(attr) {
+                    let mut modifiers = ty.inner.modifiers();
+                    modifiers.add_attr(attr, resolv)?;
+                    ty.inner.set_modifiers(modifiers);
+                } else if let Some(tag) = Tag::from(attr, resolv)? {
+                    // None is simply ignored, `Tag::from` has already reported the error
                     if ty.inner.tag().is_some() {
                         resolv.warn(attr, m::DuplicateAttr { ty: ty.display(resolv.context()) })
                               .done()?;
@@ -1338,6 +1702,56 @@ impl Ty {
         Ok(ty)
     }
 
+    /// Returns a readonly view of given table type, or `None` if it is not a table type.
+    ///
+    /// Every value slot becomes `const` (so that values are covariant) and
+    /// the resulting type is tagged with `Tag::Readonly` (so that no key can be added).
+    pub fn to_readonly(&self, ctx: &mut TypeContext) -> Option<Ty> {
+        let cnst = |slot: &Slot| {
+            if let F::Dynamic(_) = slot.flex() {
+                slot.clone()
+            } else {
+                Slot::new(F::Const, slot.unlift().clone())
+            }
+        };
+
+        let resolved = ctx.resolve_exact_type(self)?;
+        let tables = match *resolved {
+            T::Tables(ref tab) => match **tab {
+                Tables::All => Tables::All,
+                Tables::Array(ref v) => Tables::Array(cnst(v)),
+                Tables::ArrayN(ref v) => Tables::ArrayN(cnst(v)),
+                Tables::Map(ref k, ref v) => Tables::Map(k.clone(), cnst(v)),
+                Tables::Fields(ref rvar) => {
+                    let mut fields = Vec::new();
+                    let last = ctx.list_rvar_fields(rvar.clone(), &mut |k, v| {
+                        fields.push((k.clone(), cnst(v)));
+                        Ok(())
+                    }).expect("list_rvar_fields exited early while we haven't break");
+                    let rvar = ctx.gen_rvar();
+                    ctx.assert_rvar_includes(rvar.clone(), &fields).expect(
+                        "cannot insert disjoint fields into a fresh row variable"
+                    );
+                    if last == RVar::empty() {
+                        ctx.assert_rvar_closed(rvar.clone()).expect(
+                            "cannot make a fresh row variable not extensible"
+                        );
+                    }
+                    Tables::Fields(rvar)
+                }
+                Tables::Interface(_) => return None,
+            },
+            _ => return None,
+        };
+
+        // the display hint (if any) is retained, so `readonly Foo` is displayed as is
+        let mut ty = self.clone();
+        *ty.inner.ty_mut() = T::Tables(Cow::Owned(tables));
+        ty.inner.set_nil(resolved.nil());
+        ty.inner.set_tag(Some(Tag::Readonly));
+        Some(ty)
+    }
+
     pub fn nil(&self) -> Nil {
         self.inner.nil()
     }
@@ -1379,6 +1793,15 @@ impl Ty {
         self
     }
 
+    pub fn modifiers(&self) -> Modifiers {
+        self.inner.modifiers()
+    }
+
+    pub fn with_modifiers(mut self, modifiers: Modifiers) -> Ty {
+        self.inner.set_modifiers(modifiers);
+        self
+    }
+
     pub fn and_display(mut self, disp: DisplayName) -> Ty {
         // update if there is no hint already set, or the new display can override the old hint
         if self.inner.display_hint().map_or(true, |hint| disp.can_override(&hint.name)) {
@@ -1436,6 +1859,24 @@ impl Ty {
         self
     }
 
+    /// Replaces type variables in `subst` with corresponding types.
+    ///
+    /// Unlike `generalize`, this also alters types inside functions.
+    /// Row variables are copied so that the original record types are left intact.
+    pub fn substitute(&self, subst: &HashMap<TVar, Ty>, ctx: &mut TypeContext) -> Ty {
+        if let T::TVar(tv) = *self.inner.ty() {
+            if let Some(ty) = subst.get(&tv) {
+                let tag = self.tag().or(ty.tag());
+                return ty.clone().union_nil(self.nil()).with_tag(tag)
+                                 .with_modifiers(self.modifiers());
+            }
+        }
+
+        let mut ty = self.clone();
+        ty.inner.remap_ty(|t| t.substitute(subst, ctx));
+        ty
+    }
+
     pub fn flags(&self) -> Flags {
         let mut flags = self.inner.ty().flags();
         if self.inner.nil() == Nil::Noisy {
@@ -1532,8 +1973,23 @@ fn tag_is_sub(lhs: Option<Tag>, rhs: Option<Tag>) -> bool {
 
         (None, Some(rtag)) => !rtag.needs_subtype(),
 
-        // every tagged types are subtypes of the original type
-        (_, None) => true,
+        // every tagged types are subtypes of the original type, except for nominal tags
+        (Some(ltag), None) => !ltag.is_nominal(),
+
+        (None, None) => true,
+    }
+}
+
+// nominal tags do not apply to dynamic types, `any` and type variables,
+// so that the nominal type can be passed to the untyped code or a generic function
+fn tag_is_exempt(lhs: Option<Tag>, rhs: Option<Tag>, lty: &T, rty: &T) -> bool {
+    if !(lhs.map_or(false, |tag| tag.is_nominal()) || rhs.map_or(false, |tag| tag.is_nominal())) {
+        return false;
+    }
+    match (lty, rty) {
+        (&T::Dynamic(_), _) | (_, &T::Dynamic(_)) | (_, &T::All) => true,
+        (&T::TVar(_), _) | (_, &T::TVar(_)) => true,
+        (_, _) => false,
     }
 }
 
@@ -1562,6 +2018,7 @@ macro_rules! define_ty_impls {
         as_is = $lhs_as_is:expr, $rhs_as_is:expr;
         without_nil = $lhs_without_nil:expr, _;
         union_tag = $union_tag:expr;
+        union_modifiers = $union_modifiers:expr;
         union_nil = $union_nil:expr;
     })*) => ($(
         impl<$($param)*> Union<$rhs> for $lhs {
@@ -1582,6 +2039,7 @@ macro_rules! define_ty_impls {
                 };
                 ty.inner.union_nil($union_nil);
                 ty.inner.union_tag($union_tag);
+                ty.inner.set_modifiers($union_modifiers);
                 Ok(ty)
             }
         }
@@ -1595,7 +2053,7 @@ macro_rules! define_ty_impls {
                     let $l = self;
                     let $r = other;
 
-                    if !tag_is_sub($ltag, $rtag) {
+                    if !tag_is_sub($ltag, $rtag) && !tag_is_exempt($ltag, $rtag, $lty, $rty) {
                         return Err(ctx.gen_report());
                     }
 
@@ -1757,6 +2215,7 @@ define_ty_impls! {
         as_is       = &Ty::new(lhs.clone().into_send()).or_nil(Nil::Absent), rhs;
         without_nil = &Ty::new(lhs.clone().into_send()).or_nil(Nil::Absent), _;
         union_tag = None;
+        union_modifiers = Modifiers::none();
         union_nil = rhs.inner.nil();
     }
 
@@ -1770,6 +2229,7 @@ define_ty_impls! {
         as_is       = lhs, &Ty::new(rhs.clone().into_send()).or_nil(Nil::Absent);
         without_nil = &lhs.clone().without_nil(), _;
         union_tag = None;
+        union_modifiers = Modifiers::none();
         union_nil = lhs.inner.nil();
     }
 
@@ -1786,6 +2246,10 @@ define_ty_impls! {
             let (ltag, rtag) = (lhs.inner.tag(), rhs.inner.tag());
             if ltag == rtag { ltag } else { None }
         };
+        union_modifiers = {
+            let (lmods, rmods) = (lhs.inner.modifiers(), rhs.inner.modifiers());
+            if lmods == rmods { lmods } else { Modifiers::none() }
+        };
         union_nil = lhs.inner.nil().union(rhs.inner.nil());
     }
 }
@@ -1796,8 +2260,25 @@ impl Display for Ty {
         let (nil, tag, name) = self.display_repr(st, f);
         let nil = if f.alternate() { nil.with_nil() } else { nil };
 
-        if let Some(tag) = tag {
-            write!(f, "[{}] ", tag.display(st))?;
+        self.inner.modifiers().fmt_displayed(f, st)?;
+
+        match tag {
+            // `readonly` is not an attribute
+            Some(Tag::Readonly) => write!(f, "readonly ")?,
+
+            // newtypes are always displayed by names, even when the display hint is lost
+            Some(Tag::Newtype(id)) => {
+                let name = DisplayName::Type(st.context.get_named_type_name(id).clone());
+                write!(f, "{}", name.display(st))?;
+                return match nil {
+                    Nil::Silent => Ok(()),
+                    Nil::Noisy => write!(f, "?"),
+                    Nil::Absent => write!(f, "!"),
+                };
+            }
+
+            Some(tag) => write!(f, "[{}] ", tag.display(st))?,
+            None => {}
         }
 
         if let Some(name) = name {
@@ -1827,6 +2308,7 @@ impl fmt::Debug for Ty {
         let tag = self.inner.tag();
         let nil = if f.alternate() { nil.with_nil() } else { nil };
 
+        write!(f, "{:?}", self.inner.modifiers())?;
         if let Some(tag) = tag {
             write!(f, "[{:?}] ", tag)?;
         }
@@ -2097,10 +2579,10 @@ mod tests {
                T::array(just(T::Integer)));
 
         // others
-        check!(T::Thread, T::Thread; T::Thread);
+        check!(T::thread(), T::thread(); T::thread());
         check!(T::UserData, T::UserData; T::UserData);
         check!(T::All, T::UserData; T::All);
-        check!(T::Thread, T::Dynamic(Dyn::User); T::Dynamic(Dyn::User));
+        check!(T::thread(), T::Dynamic(Dyn::User); T::Dynamic(Dyn::User));
 
         // general unions
         check!(T::True, T::True; T::True);
@@ -2130,9 +2612,9 @@ mod tests {
         check!(nil(T::Int(3)), nil(T::Int(4));
                explicit=nil(T::ints(vec![3, 4])),
                implicit=nil(T::Integer));
-        check!(nil(T::Int(3) | T::UserData), nil(T::Thread | T::Int(4));
-               explicit=nil(T::Thread | T::ints(vec![3, 4]) | T::UserData),
-               implicit=nil(T::Thread | T::Integer | T::UserData));
+        check!(nil(T::Int(3) | T::UserData), nil(T::thread() | T::Int(4));
+               explicit=nil(T::thread() | T::ints(vec![3, 4]) | T::UserData),
+               implicit=nil(T::thread() | T::Integer | T::UserData));
         check!(nil(T::ints(vec![3, 5])), T::Int(4) | T::String;
                explicit=nil(T::String | T::ints(vec![3, 4, 5])),
                implicit=nil(T::String | T::Integer));
@@ -16,14 +16,15 @@ extern crate kailua_syntax;
 extern crate kailua_check;
 
 use std::error::Error;
+use std::collections::HashMap;
 use std::io::{self, Read};
 use std::fs::File;
 use std::path::{Path, PathBuf};
 use kailua_env::{Spanned, WithLoc};
 use kailua_diag::{Report, NoReport, Reporter, Stop, Locale};
-use kailua_syntax::Chunk;
+use kailua_syntax::{Chunk, Language};
 use kailua_check::Preload;
-use kailua_check::options::{Options, FsSource, FsOptions};
+use kailua_check::options::{Options, FsSource, FsOptions, ClassSystemRules};
 
 mod message;
 
@@ -61,8 +62,16 @@ pub struct Config {
     /// Preloading options.
     pub preload: Preload,
 
+    /// The default language for every source file, if any.
+    ///
+    /// Each file can still override this with `--# lang` or `--# open`.
+    pub language: Option<Language>,
+
     /// A preferred message locale, if any.
     pub message_locale: Option<Locale>,
+
+    /// Rules for user-defined class systems, keyed by the class system name.
+    pub class_systems: HashMap<String, ClassSystemRules>,
 }
 
 impl Config {
@@ -75,7 +84,9 @@ impl Config {
             package_path: None,
             package_cpath: None,
             preload: Preload::default(),
+            language: None,
             message_locale: None,
+            class_systems: HashMap::new(),
         }
     }
 
@@ -87,7 +98,9 @@ impl Config {
             package_path: None,
             package_cpath: None,
             preload: Preload::default(),
+            language: None,
             message_locale: None,
+            class_systems: HashMap::new(),
         }
     }
 
@@ -106,7 +119,9 @@ impl Config {
             package_path: Option<String>,
             package_cpath: Option<String>,
             message_lang: Option<String>,
+            language: Option<String>,
             preload: Option<Preload>,
+            class_systems: Option<HashMap<String, HashMap<String, String>>>,
         }
 
         #[derive(Deserialize, Clone, Debug)]
@@ -173,6 +188,15 @@ impl Config {
         } else {
             None
         };
+        self.language = if let Some(lang) = data.language {
+            if let Some(language) = Language::from_name(&lang) {
+                Some(language)
+            } else {
+                return Err(invalid_data("invalid language"));
+            }
+        } else {
+            None
+        };
         if let Some(preload) = data.preload {
             self.preload.open = preload.open.into_iter().map(|s| {
                 s.into_bytes().without_loc()
@@ -181,6 +205,18 @@ impl Config {
                 s.into_bytes().without_loc()
             }).collect();
         }
+        if let Some(class_systems) = data.class_systems {
+            for (name, ruledata) in class_systems {
+                let mut rules = ClassSystemRules::default();
+                for (rule, value) in ruledata {
+                    if rules.set(&rule, &value).is_err() {
+                        return Err(invalid_data(format!("invalid rule `{}` for \
+                                                         the class system `{}`", rule, name)));
+                    }
+                }
+                self.class_systems.insert(name, rules);
+            }
+        }
 
         Ok(true)
     }
@@ -205,7 +241,9 @@ pub struct Workspace {
     package_path: Option<Vec<u8>>,
     package_cpath: Option<Vec<u8>>,
     preload: Preload,
+    language: Language,
     message_locale: Locale,
+    class_systems: HashMap<String, ClassSystemRules>,
 }
 
 impl Workspace {
@@ -221,7 +259,9 @@ impl Workspace {
             package_path: config.package_path.clone(),
             package_cpath: config.package_cpath.clone(),
             preload: config.preload.clone(),
+            language: config.language.unwrap_or_default(),
             message_locale: config.message_locale.unwrap_or(default_locale),
+            class_systems: config.class_systems.clone(),
         })
     }
 
@@ -241,9 +281,19 @@ impl Workspace {
         &self.preload
     }
 
+    /// Returns the default language for every source file.
+    pub fn language(&self) -> Language {
+        self.language
+    }
+
     pub fn message_locale(&self) -> Locale {
         self.message_locale
     }
+
+    /// Returns rules for user-defined class systems, keyed by the class system name.
+    pub fn class_systems(&self) -> &HashMap<String, ClassSystemRules> {
+        &self.class_systems
+    }
 }
 
 /// An extension to `FsOptions` that is initialized from an workspace.
@@ -251,6 +301,7 @@ pub struct WorkspaceOptions<S> {
     options: FsOptions<S>,
     can_update_package_path: bool,
     can_update_package_cpath: bool,
+    class_systems: HashMap<String, ClassSystemRules>,
 }
 
 impl<S: FsSource> WorkspaceOptions<S> {
@@ -273,6 +324,7 @@ impl<S: FsSource> WorkspaceOptions<S> {
             options: options,
             can_update_package_path: workspace.package_path.is_none(),
             can_update_package_cpath: workspace.package_cpath.is_none(),
+            class_systems: workspace.class_systems.clone(),
         }
     }
 }
@@ -302,6 +354,10 @@ impl<S: FsSource> Options for WorkspaceOptions<S> {
                      report: &Report) -> Result<Chunk, Option<Stop>> {
         self.options.require_chunk(path, report)
     }
+
+    fn class_system_rules(&self, name: &str) -> Option<ClassSystemRules> {
+        self.class_systems.get(name).cloned()
+    }
 }
 
 // serde-json does not allow comments that we really need to...
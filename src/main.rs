@@ -24,7 +24,7 @@ fn parse_and_check(workspace: &Workspace, quiet: bool) -> Result<(), String> {
     use kailua_env::{Span, Spanned, Source, SourceFile, WithLoc};
     use kailua_diag::message::{Locale, Localize};
     use kailua_diag::report::{Stop, Kind, Report, ConsoleReport, TrackMaxKind};
-    use kailua_syntax::{parse_chunk, Chunk};
+    use kailua_syntax::{parse_chunk_with_language, Chunk, Language};
     use kailua_check::check_from_chunk_with_preloading;
     use kailua_check::env::Context;
     use kailua_check::options::FsSource;
@@ -32,6 +32,7 @@ fn parse_and_check(workspace: &Workspace, quiet: bool) -> Result<(), String> {
 
     struct LocalFsSource {
         source: Rc<RefCell<Source>>,
+        language: Language,
     }
 
     impl FsSource for LocalFsSource {
@@ -40,7 +41,8 @@ fn parse_and_check(workspace: &Workspace, quiet: bool) -> Result<(), String> {
             match SourceFile::from_file(&resolved_path) {
                 Ok(file) => {
                     let span = self.source.borrow_mut().add(file);
-                    if let Ok(chunk) = parse_chunk(&self.source.borrow(), span, report) {
+                    if let Ok(chunk) = parse_chunk_with_language(&self.source.borrow(), span,
+                                                                 self.language, report) {
                         Ok(Some(chunk))
                     } else {
                         Err(Some(Stop)) // we have already reported parsing errors
@@ -86,7 +88,10 @@ fn parse_and_check(workspace: &Workspace, quiet: bool) -> Result<(), String> {
     for start_path in workspace.start_paths() {
         let mut context = Context::new(report.clone());
 
-        let fssource = LocalFsSource { source: source.clone() };
+        let fssource = LocalFsSource {
+            source: source.clone(),
+            language: workspace.language(),
+        };
         let filechunk = match fssource.chunk_from_path((**start_path).without_loc(), &report) {
             Ok(Some(chunk)) => chunk,
             _ => {